@@ -82,6 +82,17 @@ rec {
     #   inject test dependencies into the build
 
     crates = {
+      "ahash" = rec {
+        crateName = "ahash";
+        version = "0.4.7";
+        edition = "2018";
+        sha256 = "0vm13qaz4nzj0gd397d36wpyav05bflg6m2nbhs82nb0ns6lm7vk";
+        authors = [ "Tom Kaitchuck <Tom.Kaitchuck@gmail.com>" ];
+        features = {
+          "compile-time-rng" = [ "const-random" ];
+          "default" = [ "compile-time-rng" "std" ];
+        };
+      };
       "ansi_term 0.11.0" = rec {
         crateName = "ansi_term";
         version = "0.11.0";
@@ -426,6 +437,64 @@ rec {
         resolvedDefaultFeatures =
           [ "clock" "default" "libc" "oldtime" "serde" "std" "time" "winapi" ];
       };
+      "chrono-tz" = rec {
+        crateName = "chrono-tz";
+        version = "0.6.1";
+        edition = "2015";
+        sha256 = "0lim4lvdras2wqc7ix6l8g47nk4mpkah4880cg780c6s88c9ym2q";
+        authors = [ "Djzin" ];
+        dependencies = [
+          {
+            name = "chrono";
+            packageId = "chrono";
+            usesDefaultFeatures = false;
+          }
+          {
+            name = "phf";
+            packageId = "phf";
+            usesDefaultFeatures = false;
+            features = [ "uncased" ];
+          }
+        ];
+        buildDependencies = [{
+          name = "chrono-tz-build";
+          packageId = "chrono-tz-build";
+        }];
+        features = {
+          "case-insensitive" = [ "uncased" "chrono-tz-build/case-insensitive" ];
+          "default" = [ "std" ];
+          "filter-by-regex" = [ "chrono-tz-build/filter-by-regex" ];
+        };
+        resolvedDefaultFeatures = [ "default" "std" ];
+      };
+      "chrono-tz-build" = rec {
+        crateName = "chrono-tz-build";
+        version = "0.0.2";
+        edition = "2018";
+        sha256 = "0schy3z03psvmc6734hgkx52cdb3zvixgzhvhr0mzxmj7x4qs1fv";
+        authors = [ "Brandon W Maister <quodlibetor@gmail.com>" ];
+        dependencies = [
+          {
+            name = "parse-zoneinfo";
+            packageId = "parse-zoneinfo";
+          }
+          {
+            name = "phf";
+            packageId = "phf";
+            usesDefaultFeatures = false;
+            features = [ "uncased" ];
+          }
+          {
+            name = "phf_codegen";
+            packageId = "phf_codegen";
+            usesDefaultFeatures = false;
+          }
+        ];
+        features = {
+          "case-insensitive" = [ "uncased" ];
+          "filter-by-regex" = [ "regex" ];
+        };
+      };
       "clap" = rec {
         crateName = "clap";
         version = "2.33.3";
@@ -1029,6 +1098,23 @@ rec {
           features = [ "derive" ];
         }];
 
+      };
+      "fallible-iterator" = rec {
+        crateName = "fallible-iterator";
+        version = "0.2.0";
+        edition = "2018";
+        sha256 = "1xq759lsr8gqss7hva42azn3whgrbrs2sd9xpn92c5ickxm1fhs4";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+        features = { "default" = [ "std" ]; };
+        resolvedDefaultFeatures = [ "default" "std" ];
+      };
+      "fallible-streaming-iterator" = rec {
+        crateName = "fallible-streaming-iterator";
+        version = "0.1.9";
+        edition = "2015";
+        sha256 = "0nj6j26p71bjy8h42x6jahx1hn0ng6mc2miwpgwnp8vnwqf4jq3k";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+
       };
       "features" = rec {
         crateName = "features";
@@ -1573,7 +1659,32 @@ rec {
         }];
         features = { };
       };
-      "hashbrown" = rec {
+      "hashbrown 0.9.1" = rec {
+        crateName = "hashbrown";
+        version = "0.9.1";
+        edition = "2018";
+        sha256 = "016dsm9s4xmxlkw2jfikm54qlz6vyk0qr280gab7kzp342jf9byp";
+        authors = [ "Amanieu d'Antras <amanieu@gmail.com>" ];
+        dependencies = [{
+          name = "ahash";
+          packageId = "ahash";
+          optional = true;
+          usesDefaultFeatures = false;
+        }];
+        features = {
+          "ahash-compile-time-rng" = [ "ahash/compile-time-rng" ];
+          "default" = [ "ahash" "inline-more" ];
+          "rustc-dep-of-std" = [
+            "nightly"
+            "core"
+            "compiler_builtins"
+            "alloc"
+            "rustc-internal-api"
+          ];
+        };
+        resolvedDefaultFeatures = [ "ahash" "default" "inline-more" ];
+      };
+      "hashbrown 0.11.2" = rec {
         crateName = "hashbrown";
         version = "0.11.2";
         edition = "2018";
@@ -1592,6 +1703,18 @@ rec {
         };
         resolvedDefaultFeatures = [ "raw" ];
       };
+      "hashlink" = rec {
+        crateName = "hashlink";
+        version = "0.6.0";
+        edition = "2018";
+        sha256 = "1a2gi4737lmqq1i48b9w13gvbkh4g3gc7gj6d3974hywy21gg76r";
+        authors = [ "kyren <kerriganw@gmail.com>" ];
+        dependencies = [{
+          name = "hashbrown";
+          packageId = "hashbrown 0.9.1";
+        }];
+        features = { "serde_impl" = [ "serde" ]; };
+      };
       "heck" = rec {
         crateName = "heck";
         version = "0.3.3";
@@ -1803,8 +1926,16 @@ rec {
           "runtime" = [ "tcp" "tokio/rt" ];
           "tcp" = [ "socket2" "tokio/net" "tokio/rt" "tokio/time" ];
         };
-        resolvedDefaultFeatures =
-          [ "client" "h2" "http1" "http2" "runtime" "socket2" "tcp" ];
+        resolvedDefaultFeatures = [
+          "client"
+          "h2"
+          "http1"
+          "http2"
+          "runtime"
+          "server"
+          "socket2"
+          "tcp"
+        ];
       };
       "hyper-tls" = rec {
         crateName = "hyper-tls";
@@ -1881,7 +2012,7 @@ rec {
         authors = [ "bluss" "Josh Stone <cuviper@gmail.com>" ];
         dependencies = [{
           name = "hashbrown";
-          packageId = "hashbrown";
+          packageId = "hashbrown 0.11.2";
           usesDefaultFeatures = false;
           features = [ "raw" ];
         }];
@@ -1986,6 +2117,7 @@ rec {
           filter = sourceFilter;
           src = ./.;
         };
+        libPath = "src/lectev.rs";
         authors = [ "Eric B. Merritt <eric@merritt.tech>" ];
         dependencies = [
           {
@@ -2010,6 +2142,10 @@ rec {
             packageId = "chrono";
             features = [ "serde" ];
           }
+          {
+            name = "chrono-tz";
+            packageId = "chrono-tz";
+          }
           {
             name = "colored";
             packageId = "colored";
@@ -2035,11 +2171,25 @@ rec {
             name = "futures";
             packageId = "futures 0.3.17";
           }
+          {
+            name = "hyper";
+            packageId = "hyper";
+            features = [ "server" "http1" "tcp" ];
+          }
+          {
+            name = "openssl";
+            packageId = "openssl";
+          }
           {
             name = "reqwest";
             packageId = "reqwest";
             features = [ "json" ];
           }
+          {
+            name = "rusqlite";
+            packageId = "rusqlite";
+            features = [ "bundled" "chrono" ];
+          }
           {
             name = "serde";
             packageId = "serde";
@@ -2049,6 +2199,10 @@ rec {
             name = "serde_json";
             packageId = "serde_json";
           }
+          {
+            name = "serde_path_to_error";
+            packageId = "serde_path_to_error";
+          }
           {
             name = "serde_yaml";
             packageId = "serde_yaml";
@@ -2070,6 +2224,10 @@ rec {
             packageId = "tokio 1.12.0";
             features = [ "full" "process" ];
           }
+          {
+            name = "tokio-openssl";
+            packageId = "tokio-openssl";
+          }
           {
             name = "tracing";
             packageId = "tracing";
@@ -2118,6 +2276,54 @@ rec {
         };
         resolvedDefaultFeatures = [ "default" "std" ];
       };
+      "libsqlite3-sys" = rec {
+        crateName = "libsqlite3-sys";
+        version = "0.20.1";
+        edition = "2018";
+        sha256 = "1g9gbjjpm9phhs991abkzmacszibp94m5nrh331ycd99y9ci1lv4";
+        links = "sqlite3";
+        authors = [ "The rusqlite developers" ];
+        buildDependencies = [
+          {
+            name = "cc";
+            packageId = "cc";
+            optional = true;
+          }
+          {
+            name = "pkg-config";
+            packageId = "pkg-config";
+            optional = true;
+          }
+          {
+            name = "vcpkg";
+            packageId = "vcpkg";
+            optional = true;
+            target = { target, features }: (target."env" == "msvc");
+          }
+        ];
+        features = {
+          "buildtime_bindgen" = [ "bindgen" "pkg-config" "vcpkg" ];
+          "bundled" = [ "cc" "bundled_bindings" ];
+          "bundled-windows" = [ "cc" "bundled_bindings" ];
+          "default" = [ "min_sqlite_version_3_6_8" ];
+          "min_sqlite_version_3_6_23" = [ "pkg-config" "vcpkg" ];
+          "min_sqlite_version_3_6_8" = [ "pkg-config" "vcpkg" ];
+          "min_sqlite_version_3_7_16" = [ "pkg-config" "vcpkg" ];
+          "min_sqlite_version_3_7_7" = [ "pkg-config" "vcpkg" ];
+          "preupdate_hook" = [ "buildtime_bindgen" ];
+          "session" = [ "preupdate_hook" "buildtime_bindgen" ];
+          "winsqlite3" = [ "min_sqlite_version_3_7_16" ];
+        };
+        resolvedDefaultFeatures = [
+          "bundled"
+          "bundled_bindings"
+          "cc"
+          "default"
+          "min_sqlite_version_3_6_8"
+          "pkg-config"
+          "vcpkg"
+        ];
+      };
       "linked-hash-map" = rec {
         crateName = "linked-hash-map";
         version = "0.5.4";
@@ -2868,6 +3074,19 @@ rec {
           "deadlock_detection" = [ "petgraph" "thread-id" "backtrace" ];
         };
       };
+      "parse-zoneinfo" = rec {
+        crateName = "parse-zoneinfo";
+        version = "0.3.0";
+        edition = "2015";
+        sha256 = "0h8g6jy4kckn2gk8sd5adaws180n1ip65xhzw5jxlq4w8ibg41f7";
+        authors = [ "Djzin <djzin@users.noreply.github.com>" ];
+        dependencies = [{
+          name = "regex";
+          packageId = "regex";
+          usesDefaultFeatures = false;
+          features = [ "std" "unicode-perl" ];
+        }];
+      };
       "percent-encoding" = rec {
         crateName = "percent-encoding";
         version = "2.1.0";
@@ -2889,6 +3108,83 @@ rec {
         }];
         features = { "pretty-print" = [ "serde" "serde_json" ]; };
       };
+      "phf" = rec {
+        crateName = "phf";
+        version = "0.10.1";
+        edition = "2018";
+        sha256 = "0naj8n5nasv5hj5ldlva3cl6y3sv7zp3kfgqylhbrg55v3mg3fzs";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+        dependencies = [{
+          name = "phf_shared";
+          packageId = "phf_shared";
+          usesDefaultFeatures = false;
+        }];
+        features = {
+          "default" = [ "std" ];
+          "macros" = [ "phf_macros" "proc-macro-hack" ];
+          "std" = [ "phf_shared/std" ];
+          "uncased" = [ "phf_shared/uncased" ];
+          "unicase" = [ "phf_shared/unicase" ];
+        };
+        resolvedDefaultFeatures = [ "uncased" ];
+      };
+      "phf_codegen" = rec {
+        crateName = "phf_codegen";
+        version = "0.10.0";
+        edition = "2018";
+        sha256 = "1k8kdad9wk2d5972k6jmjki2xpdy2ky4zd19rv7ybm2dpjlc7cag";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+        dependencies = [
+          {
+            name = "phf_generator";
+            packageId = "phf_generator";
+          }
+          {
+            name = "phf_shared";
+            packageId = "phf_shared";
+          }
+        ];
+      };
+      "phf_generator" = rec {
+        crateName = "phf_generator";
+        version = "0.10.0";
+        edition = "2018";
+        sha256 = "1mlq6hlajsvlsx6rhw49g9ricsm017lrxmgmmbk85sxm7f4qaljx";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+        dependencies = [
+          {
+            name = "phf_shared";
+            packageId = "phf_shared";
+            usesDefaultFeatures = false;
+          }
+          {
+            name = "rand";
+            packageId = "rand";
+            features = [ "small_rng" ];
+          }
+        ];
+      };
+      "phf_shared" = rec {
+        crateName = "phf_shared";
+        version = "0.10.0";
+        edition = "2018";
+        sha256 = "15n02nc8yqpd8hbxngblar2g53p3nllc93d8s8ih3p5cf7bnlydn";
+        authors = [ "Steven Fackler <sfackler@gmail.com>" ];
+        dependencies = [
+          {
+            name = "siphasher";
+            packageId = "siphasher";
+          }
+          {
+            name = "uncased";
+            packageId = "uncased";
+            optional = true;
+            usesDefaultFeatures = false;
+          }
+        ];
+        features = { "default" = [ "std" ]; };
+        resolvedDefaultFeatures = [ "default" "std" "uncased" ];
+      };
       "pin-project" = rec {
         crateName = "pin-project";
         version = "1.0.8";
@@ -3122,6 +3418,7 @@ rec {
           "libc"
           "rand_chacha"
           "rand_hc"
+          "small_rng"
           "std"
           "std_rng"
         ];
@@ -3267,7 +3564,7 @@ rec {
           "unstable" = [ "pattern" ];
           "use_std" = [ "std" ];
         };
-        resolvedDefaultFeatures = [ "std" ];
+        resolvedDefaultFeatures = [ "std" "unicode-perl" ];
       };
       "regex-automata" = rec {
         crateName = "regex-automata";
@@ -3563,6 +3860,96 @@ rec {
           "tokio-native-tls"
         ];
       };
+      "rusqlite" = rec {
+        crateName = "rusqlite";
+        version = "0.24.2";
+        edition = "2018";
+        sha256 = "04jiqa9y7pk7byk0kicjxqy75jifz1v4xhhaxhkwicms3kkqxwym";
+        authors = [ "The rusqlite developers" ];
+        dependencies = [
+          {
+            name = "bitflags";
+            packageId = "bitflags";
+          }
+          {
+            name = "chrono";
+            packageId = "chrono";
+            optional = true;
+          }
+          {
+            name = "fallible-iterator";
+            packageId = "fallible-iterator";
+          }
+          {
+            name = "fallible-streaming-iterator";
+            packageId = "fallible-streaming-iterator";
+          }
+          {
+            name = "hashlink";
+            packageId = "hashlink";
+          }
+          {
+            name = "libsqlite3-sys";
+            packageId = "libsqlite3-sys";
+          }
+          {
+            name = "memchr";
+            packageId = "memchr";
+          }
+          {
+            name = "smallvec";
+            packageId = "smallvec 1.7.0";
+          }
+        ];
+        features = {
+          "array" = [ "vtab" ];
+          "backup" = [ "libsqlite3-sys/min_sqlite_version_3_6_23" ];
+          "blob" = [ "libsqlite3-sys/min_sqlite_version_3_7_7" ];
+          "buildtime_bindgen" = [ "libsqlite3-sys/buildtime_bindgen" ];
+          "bundled" = [ "libsqlite3-sys/bundled" "modern_sqlite" ];
+          "bundled-full" = [
+            "array"
+            "backup"
+            "blob"
+            "bundled"
+            "chrono"
+            "collation"
+            "column_decltype"
+            "csvtab"
+            "extra_check"
+            "functions"
+            "hooks"
+            "i128_blob"
+            "limits"
+            "load_extension"
+            "serde_json"
+            "series"
+            "trace"
+            "unlock_notify"
+            "url"
+            "uuid"
+            "vtab"
+            "window"
+          ];
+          "bundled-windows" = [ "libsqlite3-sys/bundled-windows" ];
+          "csvtab" = [ "csv" "vtab" ];
+          "functions" = [ "libsqlite3-sys/min_sqlite_version_3_7_7" ];
+          "i128_blob" = [ "byteorder" ];
+          "in_gecko" = [ "modern_sqlite" "libsqlite3-sys/in_gecko" ];
+          "modern_sqlite" = [ "libsqlite3-sys/bundled_bindings" ];
+          "series" = [ "vtab" ];
+          "session" = [ "libsqlite3-sys/session" "hooks" ];
+          "sqlcipher" = [ "libsqlite3-sys/sqlcipher" ];
+          "trace" = [ "libsqlite3-sys/min_sqlite_version_3_6_23" ];
+          "unlock_notify" = [ "libsqlite3-sys/unlock_notify" ];
+          "vtab" = [ "libsqlite3-sys/min_sqlite_version_3_7_7" "lazy_static" ];
+          "wasm32-wasi-vfs" = [ "libsqlite3-sys/wasm32-wasi-vfs" ];
+          "window" = [ "functions" ];
+          "winsqlite3" = [ "libsqlite3-sys/winsqlite3" ];
+          "with-asan" = [ "libsqlite3-sys/with-asan" ];
+        };
+        resolvedDefaultFeatures = [ "bundled" "chrono" "modern_sqlite" ];
+      };
       "rustc_version 0.2.3" = rec {
         crateName = "rustc_version";
         version = "0.2.3";
@@ -3852,6 +4239,17 @@ rec {
         };
         resolvedDefaultFeatures = [ "default" "std" ];
       };
+      "serde_path_to_error" = rec {
+        crateName = "serde_path_to_error";
+        version = "0.1.4";
+        edition = "2018";
+        sha256 = "0n5ilbsxvi174m2fd506ivd43kws0yh523li1xz0zqh60ngi1xj2";
+        authors = [ "David Tolnay <dtolnay@gmail.com>" ];
+        dependencies = [{
+          name = "serde";
+          packageId = "serde";
+        }];
+      };
       "serde_urlencoded" = rec {
         crateName = "serde_urlencoded";
         version = "0.7.0";
@@ -3943,6 +4341,19 @@ rec {
         }];
 
       };
+      "siphasher" = rec {
+        crateName = "siphasher";
+        version = "0.3.7";
+        edition = "2018";
+        sha256 = "12wkdsvxq8ljckpsh92ri52w8z0gh32cclxb4lvd695pz6l98d2k";
+        authors = [ "Frank Denis <github@pureftpd.org>" ];
+        features = {
+          "default" = [ "std" ];
+          "serde_no_std" = [ "serde/alloc" ];
+          "serde_std" = [ "std" "serde/std" ];
+        };
+        resolvedDefaultFeatures = [ "default" "std" ];
+      };
       "slab" = rec {
         crateName = "slab";
         version = "0.4.4";
@@ -4753,6 +5164,32 @@ rec {
         }];
 
       };
+      "tokio-openssl" = rec {
+        crateName = "tokio-openssl";
+        version = "0.6.3";
+        edition = "2018";
+        sha256 = "12l7a01sid095zmdkcmjnds9hwfcyjn9539r3c6b5w89g3xrz3y0";
+        authors = [ "Alex Crichton <alex@alexcrichton.com>" ];
+        dependencies = [
+          {
+            name = "futures-util";
+            packageId = "futures-util";
+            usesDefaultFeatures = false;
+          }
+          {
+            name = "openssl";
+            packageId = "openssl";
+          }
+          {
+            name = "openssl-sys";
+            packageId = "openssl-sys";
+          }
+          {
+            name = "tokio";
+            packageId = "tokio 1.12.0";
+          }
+        ];
+      };
       "tokio-reactor" = rec {
         crateName = "tokio-reactor";
         version = "0.1.12";
@@ -5492,6 +5929,22 @@ rec {
         features = { "default" = [ "std" ]; };
         resolvedDefaultFeatures = [ "default" "std" ];
       };
+      "uncased" = rec {
+        crateName = "uncased";
+        version = "0.9.6";
+        edition = "2018";
+        sha256 = "1l3flz044hfdnsddahj08dflqprfydszkm4vkf458l724xryvbjv";
+        authors = [ "Sergio Benitez <sb@sergio.bz>" ];
+        buildDependencies = [{
+          name = "version_check";
+          packageId = "version_check";
+        }];
+        features = {
+          "default" = [ "alloc" ];
+          "with-serde" = [ "serde" ];
+          "with-serde-alloc" = [ "serde" "serde/alloc" "alloc" ];
+        };
+      };
       "unicode-bidi" = rec {
         crateName = "unicode-bidi";
         version = "0.3.7";
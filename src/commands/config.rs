@@ -0,0 +1,105 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Config Validation
+//!
+//! Checks every profile of a config file and prints the problems found, so that mistakes are
+//! caught before running a report.
+use crate::configs::jira as jira_config;
+use crate::error_output::{Category, Coded};
+use snafu::{ensure, ResultExt, Snafu};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use tracing::instrument;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not read config: {}", source))]
+    ReadConfig { source: jira_config::Error },
+    #[snafu(display("No profile named {} in config {}", profile, path.display()))]
+    UnknownProfile { profile: String, path: PathBuf },
+    #[snafu(display("Config {} has {} errors", path.display(), errors))]
+    InvalidConfig { path: PathBuf, errors: usize },
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::ReadConfig { .. } => "config-read",
+            Error::UnknownProfile { .. } => "config-unknown-profile",
+            Error::InvalidConfig { .. } => "config-invalid",
+        }
+    }
+
+    fn category(&self) -> Category {
+        Category::Config
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ValidateArgs {
+    // Optional config path for the jira functionality. If not provided the default configuration
+    // will be used.
+    #[structopt(short, long, parse(from_os_str))]
+    pub config_path: Option<PathBuf>,
+    /// Only validate this profile, rather than every profile in the config
+    #[structopt(short, long)]
+    pub profile: Option<String>,
+}
+
+/// Print every problem found in the config. Fails when any of them is an error, warnings alone
+/// don't fail.
+#[instrument]
+pub async fn do_validate(args: &ValidateArgs) -> Result<(), Error> {
+    let validated = jira_config::read_all(&args.config_path)
+        .await
+        .context(ReadConfig {})?;
+    if let Some(profile) = &args.profile {
+        ensure!(
+            validated.profiles.contains_key(profile),
+            UnknownProfile {
+                profile,
+                path: validated.path
+            }
+        );
+    }
+
+    let problems: Vec<&jira_config::Problem> = validated
+        .problems
+        .iter()
+        .filter(|problem| {
+            args.profile
+                .as_ref()
+                .map_or(true, |profile| *profile == problem.profile)
+        })
+        .collect();
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.severity == jira_config::Severity::Error)
+        .count();
+    if problems.is_empty() {
+        println!("{} is valid", validated.path.display());
+    }
+    ensure!(
+        errors == 0,
+        InvalidConfig {
+            path: validated.path,
+            errors
+        }
+    );
+    Ok(())
+}
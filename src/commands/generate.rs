@@ -0,0 +1,136 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Sample Data
+//!
+//! Writes sample data so lectev can be tried without a jira instance, and so the reports have
+//! fixtures to be run against.
+use crate::error_output::{Category, Coded};
+use crate::lib::jira::{api, sample};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use tracing::instrument;
+use url::Url;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not generate the sample issues: {}", source))]
+    CouldNotGenerate { source: url::ParseError },
+    #[snafu(display("Could not convert the sample issues to json: {}", source))]
+    FailedToSerializeDump { source: serde_json::Error },
+    #[snafu(display("Could not convert the sample config to yaml: {}", source))]
+    FailedToSerializeConfig { source: serde_yaml::Error },
+    #[snafu(display("Unable to write file {}: {}", path, source))]
+    FailedToWriteFile {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::CouldNotGenerate { .. } => "generate-sample",
+            Error::FailedToSerializeDump { .. } => "generate-serialize-dump",
+            Error::FailedToSerializeConfig { .. } => "generate-serialize-config",
+            Error::FailedToWriteFile { .. } => "generate-write-file",
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            Error::CouldNotGenerate { .. }
+            | Error::FailedToSerializeDump { .. }
+            | Error::FailedToSerializeConfig { .. } => Category::Data,
+            Error::FailedToWriteFile { .. } => Category::Io,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SampleJiraDumpArgs {
+    /// Where the sample issues are written, in the format of a 'debug-jira-file'
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Also write a jira config that maps the statuses, issue types and resolutions of the
+    /// sample issues
+    #[structopt(long, parse(from_os_str))]
+    pub config_output: Option<PathBuf>,
+    /// The number of issues to generate
+    #[structopt(long, default_value = "200")]
+    pub issues: usize,
+    /// The number of people the issues are assigned to
+    #[structopt(long, default_value = "5")]
+    pub people: usize,
+    /// The issues are created over this many days, up to now
+    #[structopt(long, default_value = "90")]
+    pub days: u32,
+    /// The same seed always generates the same issues
+    #[structopt(long, default_value = "1")]
+    pub seed: u64,
+}
+
+/// The layout of a 'debug-jira-file', as read by `--load-from-jira-file`
+#[derive(Debug, Serialize)]
+struct SampleDump<'a> {
+    fetched_at: DateTime<Utc>,
+    issues: &'a [api::IssueDetail],
+}
+
+async fn write(path: &PathBuf, contents: &str) -> Result<(), Error> {
+    tokio::fs::write(path, contents)
+        .await
+        .context(FailedToWriteFile {
+            path: path.to_string_lossy(),
+        })
+}
+
+#[instrument]
+pub async fn do_sample_jira_dump(args: &SampleJiraDumpArgs) -> Result<(), Error> {
+    let instance = Url::parse(sample::INSTANCE).context(CouldNotGenerate {})?;
+    let fetched_at = Utc::now();
+    let issues = sample::issues(
+        sample::Options {
+            issues: args.issues,
+            people: args.people,
+            days: args.days,
+            seed: args.seed,
+        },
+        &instance,
+        fetched_at,
+    )
+    .context(CouldNotGenerate {})?;
+    let dump = serde_json::to_string(&SampleDump {
+        fetched_at,
+        issues: &issues,
+    })
+    .context(FailedToSerializeDump {})?;
+    write(&args.output_path, &dump).await?;
+
+    if let Some(config_path) = &args.config_output {
+        let config = serde_yaml::to_string(&sample::config(&instance))
+            .context(FailedToSerializeConfig {})?;
+        write(config_path, &config).await?;
+        println!(
+            "Try the report with: lectev jira -c {} time-in-status-wip -j 'project = SAMPLE' \
+             --debug-jira-file {} --load-from-jira-file -o report.csv",
+            config_path.display(),
+            args.output_path.display()
+        );
+    }
+    Ok(())
+}
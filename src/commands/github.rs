@@ -0,0 +1,160 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # GitHub Commands
+//!
+//! Reports on GitHub issues. The issues are pulled through the GitHub [`Tracker`], so the reports
+//! are the same as those run against jira.
+use crate::configs::github as github_config;
+use crate::error_output::{Category, Coded};
+use crate::feature_flags;
+use crate::lib::calendar::Calendar;
+use crate::lib::cancel;
+use crate::lib::github::tracker::GithubTracker;
+use crate::lib::jira::times_in_flight;
+use crate::lib::rest;
+use crate::lib::tracker::{self, Tracker};
+use crate::utils;
+use futures::stream::TryStreamExt;
+use snafu::{ResultExt, Snafu};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use tokio::fs::File;
+use tracing::{error, instrument, warn};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not get config: {}", source))]
+    GetConfig { source: github_config::Error },
+    #[snafu(display("Could not build rest client {}", source))]
+    FailedToBuildClient { source: rest::Error },
+    #[snafu(display("Could not get issues from github {}", source))]
+    FailedToGetItems { source: tracker::Error },
+    #[snafu(display("Failed to create csv output file {}", source))]
+    FailedToCreateCSVFile { source: std::io::Error },
+    #[snafu(display("Failed to write csv output to file {}", source))]
+    FailedToWriteToCSVFile { source: csv_async::Error },
+    #[snafu(display("Unable to write file to {}: {}", path, source))]
+    FailedToWriteFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Feature flag 'github-time-in-status' is not enabled"))]
+    FeatureFlagNotEnabled,
+    #[snafu(display("Interrupted before all of the issues were pulled"))]
+    Cancelled,
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::GetConfig { .. } => "github-get-config",
+            Error::FailedToBuildClient { .. } => "github-build-client",
+            Error::FailedToGetItems { .. } => "github-get-items",
+            Error::FailedToCreateCSVFile { .. } => "github-create-csv",
+            Error::FailedToWriteToCSVFile { .. } => "github-write-csv",
+            Error::FailedToWriteFile { .. } => "github-write-file",
+            Error::FeatureFlagNotEnabled => "github-feature-flag-not-enabled",
+            Error::Cancelled => "github-cancelled",
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            Error::GetConfig { .. } | Error::FailedToBuildClient { .. } => Category::Config,
+            Error::FailedToGetItems { .. } => Category::Remote,
+            Error::FailedToCreateCSVFile { .. }
+            | Error::FailedToWriteToCSVFile { .. }
+            | Error::FailedToWriteFile { .. } => Category::Io,
+            Error::FeatureFlagNotEnabled => Category::Usage,
+            Error::Cancelled => Category::Cancelled,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TimeInStatusArgs {
+    /// The GitHub search query for the issues to report on, e.g.
+    /// `repo:octocat/hello-world is:issue closed:>2021-01-01`
+    #[structopt(short, long)]
+    pub query: String,
+    /// The path of the csv report
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+}
+
+/// Write the time each issue matching the query spent in each status. When interrupted, the
+/// issues already pulled are kept and the report is moved to `<name>-partial`.
+#[instrument(skip(cancel))]
+pub async fn do_time_in_status(
+    config_path: &Option<PathBuf>,
+    args: &TimeInStatusArgs,
+    cancel: &cancel::Token,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::GithubTimeInStatus) {
+        let conf = github_config::read(config_path)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(&conf.api_url, &conf.username, &conf.token, &conf.retry)
+            .await
+            .context(FailedToBuildClient {})?;
+        let tracker = GithubTracker::new(&conf, client).with_cancellation(cancel.clone());
+
+        let mut writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut pages = tracker.items(&args.query);
+        let pulled = loop {
+            let items = match pages.try_next().await {
+                Ok(Some(items)) => items,
+                Ok(None) => break Ok(()),
+                Err(tracker::Error::Cancelled) => break Cancelled.fail(),
+                Err(source) => return Err(source).context(FailedToGetItems {}),
+            };
+            for (mut entry, item) in
+                times_in_flight::calculate(&conf.api_url, &Calendar::default(), &items)
+                    .into_iter()
+                    .zip(&items)
+            {
+                // The entry links to jira's browse page, github issues link to themselves
+                entry.url = item.native_url.to_string();
+                writer
+                    .serialize(&entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        };
+        writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+        if let Err(Error::Cancelled) = pulled {
+            let partial_path = utils::sibling_path(&args.output_path, "partial");
+            tokio::fs::rename(&args.output_path, &partial_path)
+                .await
+                .context(FailedToWriteFile {
+                    path: partial_path.to_string_lossy(),
+                })?;
+            warn!(
+                "The report is missing issues, it was written to {}",
+                partial_path.display()
+            );
+        }
+        pulled
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        FeatureFlagNotEnabled.fail()
+    }
+}
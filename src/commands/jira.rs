@@ -12,19 +12,54 @@
 //
 //  You should have received a copy of the GNU General Public License
 //  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+mod html;
+
+use crate::command;
 use crate::configs::jira as jira_config;
+use crate::configs::workspace;
+use crate::error_output::{Category, Coded};
 use crate::feature_flags;
+use crate::lib::calendar::Calendar;
+use crate::lib::cancel;
 use crate::lib::jira::api;
+use crate::lib::jira::checkpoint;
 use crate::lib::jira::core;
+use crate::lib::jira::dump::{self, JiraDump};
+use crate::lib::jira::dump_diff;
+use crate::lib::jira::epics;
+use crate::lib::jira::estimate_accuracy;
+use crate::lib::jira::field_history;
+use crate::lib::jira::filters;
+use crate::lib::jira::flow;
+use crate::lib::jira::metrics;
+use crate::lib::jira::native;
 use crate::lib::jira::nativetocore;
+use crate::lib::jira::responsiveness;
+use crate::lib::jira::rollup;
+use crate::lib::jira::suggest;
+use crate::lib::jira::summary;
+use crate::lib::jira::thresholds;
+use crate::lib::jira::time_in_status;
 use crate::lib::jira::times_in_flight;
+use crate::lib::jira::tracker::{DumpTracker, JiraTracker, Recording};
+use crate::lib::jira::transitions;
+use crate::lib::notify;
 use crate::lib::rest;
-use snafu::{ResultExt, Snafu};
+use crate::lib::sink::{self, Error as SinkError};
+use crate::lib::store;
+use crate::lib::tracker::{self, InMemory, Tracker};
+use crate::utils;
+use chrono::{Duration, NaiveDate, Utc};
+use futures::stream::{BoxStream, TryStreamExt};
+use serde::Deserialize;
+use snafu::{ensure, ResultExt, Snafu};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::StructOpt;
 use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use tracing::{error, instrument};
+use tracing::{error, info, instrument, warn};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -34,6 +69,8 @@ pub enum Error {
     FailedToBuildClient { source: rest::Error },
     #[snafu(display("Could not get data from jira {}", source))]
     FailedToGetData { source: api::Error },
+    #[snafu(display("Could not get items {}", source))]
+    FailedToGetItems { source: tracker::Error },
     #[snafu(display("Failed to transform jira data to internal model {}", source))]
     FailedToTransformData { source: nativetocore::Error },
     #[snafu(display("Failed to create raw dump file {}", source))]
@@ -62,105 +99,2217 @@ pub enum Error {
     FailedToWriteToCSVFile { source: csv_async::Error },
     #[snafu(display("Feature flag 'JIRA_TIME_IN_STATUS' is not enabled"))]
     FeatureFlagNotEnabled,
+    #[snafu(display("Report thresholds breached: {}", breaches))]
+    ThresholdsBreached { breaches: String },
+    #[snafu(display("A jql query or board name is required when not reading from the store"))]
+    MissingJqlQuery {},
+    #[snafu(display("Failed to use the issue store {}", source))]
+    FailedToUseStore { source: store::Error },
+    #[snafu(display("Feature flag 'jira-store' is not enabled"))]
+    StoreFeatureFlagNotEnabled,
+    #[snafu(display("Failed to write html report {}", source))]
+    FailedToWriteHtmlReport { source: html::Error },
+    #[snafu(display(
+        "The changelogs of {} issues could not be pulled, they are missing from the report: {}",
+        count,
+        issue_keys
+    ))]
+    IncompleteReport { count: usize, issue_keys: String },
+    #[snafu(display("Failed to print the summary of the report {}", source))]
+    FailedToWriteSummary { source: command::Error },
+    #[snafu(display("Feature flag 'jira-epic-report' is not enabled"))]
+    EpicReportFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-estimate-accuracy' is not enabled"))]
+    EstimateAccuracyFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-field-history' is not enabled"))]
+    FieldHistoryFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-rollup' is not enabled"))]
+    RollupFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-transitions' is not enabled"))]
+    TransitionsFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-diff-dumps' is not enabled"))]
+    DiffDumpsFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-suggest-config' is not enabled"))]
+    SuggestConfigFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-flow-metrics' is not enabled"))]
+    FlowMetricsFeatureFlagNotEnabled,
+    #[snafu(display("Feature flag 'jira-responsiveness' is not enabled"))]
+    ResponsivenessFeatureFlagNotEnabled,
+    #[snafu(display("No fields to export, pass --field or set history-fields in the config"))]
+    NoHistoryFields,
+    #[snafu(display("Interrupted before all of the issues were pulled"))]
+    Cancelled,
+    #[snafu(display("Failed to write the report output {}", source))]
+    FailedToUseOutput { source: SinkError },
+    #[snafu(display("Failed to use the pull checkpoint {}", source))]
+    FailedToUseCheckpoint { source: checkpoint::Error },
+    #[snafu(display("An output path is required when not running a batch"))]
+    MissingOutputPath {},
+    #[snafu(display("--since {} is not before --until {}", since, until))]
+    EmptyTimeWindow { since: NaiveDate, until: NaiveDate },
+    #[snafu(display("Could not read batch file {}: {}", path, source))]
+    FailedToReadBatchFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Batch file {} is not valid: {}", path, source))]
+    InvalidBatchFile {
+        path: String,
+        source: serde_yaml::Error,
+    },
 }
 
-#[instrument]
-async fn load_jira_from_file(load_file: &Path) -> Result<Vec<api::IssueDetail>, Error> {
-    let contents = tokio::fs::read_to_string(load_file)
-        .await
-        .context(FailedToReadFromFile {})?;
-    serde_json::from_str(&contents).context(FailedToConvertJsonToInternalStructure {})
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::GetConfig { .. } => "jira-get-config",
+            Error::FailedToBuildClient { .. } => "jira-build-client",
+            Error::FailedToGetData { .. } => "jira-get-data",
+            Error::FailedToGetItems { .. } => "jira-get-items",
+            Error::FailedToTransformData { .. } => "jira-transform-data",
+            Error::FailedToCreateRawDumpFile { .. } => "jira-create-raw-dump",
+            Error::FailedToConvertInternalStructureToJson { .. } => "jira-serialize-items",
+            Error::FailedToWriteFile { .. } => "jira-write-file",
+            Error::FailedToWriteRawDumpFile { .. } => "jira-write-raw-dump",
+            Error::FailedToReadFromFile { .. } => "jira-read-file",
+            Error::FailedToConvertJsonToInternalStructure { .. } => "jira-deserialize-items",
+            Error::UnableToLoadFromJiraFile { .. } => "jira-missing-jira-file",
+            Error::FailedToCreateCSVFile { .. } => "jira-create-csv",
+            Error::FailedToWriteToCSVFile { .. } => "jira-write-csv",
+            Error::FeatureFlagNotEnabled => "jira-feature-flag-not-enabled",
+            Error::ThresholdsBreached { .. } => "jira-thresholds-breached",
+            Error::MissingJqlQuery { .. } => "jira-missing-jql-query",
+            Error::FailedToUseStore { .. } => "jira-use-store",
+            Error::StoreFeatureFlagNotEnabled => "jira-store-feature-flag-not-enabled",
+            Error::FailedToWriteHtmlReport { .. } => "jira-write-html",
+            Error::FailedToWriteSummary { .. } => "jira-write-summary",
+            Error::IncompleteReport { .. } => "jira-incomplete-report",
+            Error::EpicReportFeatureFlagNotEnabled => "jira-epic-report-feature-flag-not-enabled",
+            Error::EstimateAccuracyFeatureFlagNotEnabled => {
+                "jira-estimate-accuracy-feature-flag-not-enabled"
+            }
+            Error::FieldHistoryFeatureFlagNotEnabled => {
+                "jira-field-history-feature-flag-not-enabled"
+            }
+            Error::RollupFeatureFlagNotEnabled => "jira-rollup-feature-flag-not-enabled",
+            Error::TransitionsFeatureFlagNotEnabled => "jira-transitions-feature-flag-not-enabled",
+            Error::DiffDumpsFeatureFlagNotEnabled => "jira-diff-dumps-feature-flag-not-enabled",
+            Error::SuggestConfigFeatureFlagNotEnabled => {
+                "jira-suggest-config-feature-flag-not-enabled"
+            }
+            Error::FlowMetricsFeatureFlagNotEnabled => "jira-flow-metrics-feature-flag-not-enabled",
+            Error::ResponsivenessFeatureFlagNotEnabled => {
+                "jira-responsiveness-feature-flag-not-enabled"
+            }
+            Error::NoHistoryFields => "jira-no-history-fields",
+            Error::Cancelled => "jira-cancelled",
+            Error::FailedToUseOutput { .. } => "jira-use-output",
+            Error::FailedToUseCheckpoint { .. } => "jira-use-checkpoint",
+            Error::MissingOutputPath { .. } => "jira-missing-output-path",
+            Error::EmptyTimeWindow { .. } => "jira-empty-time-window",
+            Error::FailedToReadBatchFile { .. } => "jira-read-batch-file",
+            Error::InvalidBatchFile { .. } => "jira-invalid-batch-file",
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            Error::GetConfig { .. }
+            | Error::FailedToBuildClient { .. }
+            | Error::FailedToUseOutput {
+                source: SinkError::MissingCredentials { .. } | SinkError::InvalidEndpoint { .. },
+            } => Category::Config,
+            Error::FailedToGetItems {
+                source: tracker::Error::JiraTranslate { .. },
+            }
+            | Error::FailedToTransformData { .. }
+            | Error::FailedToConvertInternalStructureToJson { .. }
+            | Error::FailedToConvertJsonToInternalStructure { .. } => Category::Data,
+            Error::UnableToLoadFromJiraFile { .. }
+            | Error::FeatureFlagNotEnabled
+            | Error::MissingJqlQuery { .. }
+            | Error::MissingOutputPath { .. }
+            | Error::EmptyTimeWindow { .. }
+            | Error::FailedToUseOutput {
+                source: SinkError::InvalidUri { .. },
+            }
+            | Error::InvalidBatchFile { .. }
+            | Error::StoreFeatureFlagNotEnabled
+            | Error::EpicReportFeatureFlagNotEnabled
+            | Error::EstimateAccuracyFeatureFlagNotEnabled
+            | Error::FieldHistoryFeatureFlagNotEnabled
+            | Error::RollupFeatureFlagNotEnabled
+            | Error::TransitionsFeatureFlagNotEnabled
+            | Error::DiffDumpsFeatureFlagNotEnabled
+            | Error::SuggestConfigFeatureFlagNotEnabled
+            | Error::FlowMetricsFeatureFlagNotEnabled
+            | Error::ResponsivenessFeatureFlagNotEnabled
+            | Error::NoHistoryFields
+            | Error::FailedToGetData {
+                source: api::Error::TooManyIssues { .. },
+            }
+            | Error::FailedToUseCheckpoint {
+                source:
+                    checkpoint::Error::NoCheckpoint { .. }
+                    | checkpoint::Error::CheckpointMismatch { .. }
+                    | checkpoint::Error::ResultsChanged { .. },
+            } => Category::Usage,
+            Error::FailedToGetData { .. }
+            | Error::FailedToGetItems { .. }
+            | Error::FailedToUseOutput {
+                source: SinkError::CouldNotSign { .. } | SinkError::CouldNotUpload { .. },
+            } => Category::Remote,
+            Error::FailedToCreateRawDumpFile { .. }
+            | Error::FailedToWriteFile { .. }
+            | Error::FailedToWriteRawDumpFile { .. }
+            | Error::FailedToReadFromFile { .. }
+            | Error::FailedToCreateCSVFile { .. }
+            | Error::FailedToWriteToCSVFile { .. }
+            | Error::FailedToUseStore { .. }
+            | Error::FailedToWriteHtmlReport { .. }
+            | Error::FailedToWriteSummary { .. }
+            | Error::FailedToReadBatchFile { .. }
+            | Error::FailedToUseCheckpoint { .. }
+            | Error::FailedToUseOutput {
+                source: SinkError::CouldNotStage { .. },
+            } => Category::Io,
+            Error::ThresholdsBreached { .. } => Category::Threshold,
+            Error::Cancelled => Category::Cancelled,
+            Error::IncompleteReport { .. } => Category::Partial,
+        }
+    }
+}
+
+/// The formats the time in status report can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Csv,
+    Html,
+}
+
+/// The values accepted for [`OutputFormat`] on the command line
+pub const OUTPUT_FORMATS: [&str; 2] = ["csv", "html"];
+
+/// How the days in each status are laid out in the csv report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// A row per issue with a column per status
+    Wide,
+    /// A row per issue and status
+    Long,
+}
+
+/// The values accepted for [`Layout`] on the command line
+pub const LAYOUTS: [&str; 2] = ["wide", "long"];
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "wide" => Ok(Layout::Wide),
+            "long" => Ok(Layout::Long),
+            _ => Err(format!("Unknown layout '{}'", value)),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(OutputFormat::Csv),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(format!("Unknown output format '{}'", value)),
+        }
+    }
+}
+
+/// The formats the change log of `diff-dumps` can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeLogFormat {
+    Csv,
+    Json,
+}
+
+/// The values accepted for [`ChangeLogFormat`] on the command line
+pub const CHANGE_LOG_FORMATS: [&str; 2] = ["csv", "json"];
+
+impl FromStr for ChangeLogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(ChangeLogFormat::Csv),
+            "json" => Ok(ChangeLogFormat::Json),
+            _ => Err(format!("Unknown change log format '{}'", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TimeInStatusArgs {
+    /// Raw api dump file. This dumps the response from jira
+    #[structopt(long, parse(from_os_str))]
+    pub debug_jira_file: Option<PathBuf>,
+
+    /// If specified will load from the jira data file specified in the 'debug-jira-file' argument,
+    /// and *will not* pull from jira.
+    #[structopt(long)]
+    pub load_from_jira_file: bool,
+    /// Refresh the 'debug-jira-file' rather than replacing it. Only the issues updated since the
+    /// file was last fetched are pulled from jira, they are merged into the file and the report
+    /// is run against the result.
+    #[structopt(
+        long,
+        requires = "debug-jira-file",
+        conflicts_with = "load-from-jira-file"
+    )]
+    pub merge_jira_file: bool,
+    /// Controls the output of the report. You can provide the path and filename + extension here,
+    /// or an `s3://bucket/key` or `gs://bucket/key` uri to upload the report when it is finished
+    #[structopt(short, long, parse(from_os_str), required_unless = "batch")]
+    pub output_path: Option<PathBuf>,
+    /// The format of the report, `csv` or `html`. The html report is a standalone page with a
+    /// sortable table.
+    #[structopt(long, default_value = "csv", possible_values = &OUTPUT_FORMATS)]
+    pub output_format: OutputFormat,
+    /// The layout of the csv report. `wide` has a row per issue with a column per status, `long`
+    /// has a row per issue and status with the columns `issue`, `status` and `business_days`.
+    #[structopt(long, default_value = "wide", possible_values = &LAYOUTS)]
+    pub layout: Layout,
+    /// Provides the JQL query that the command uses to gather the Issues which are analyzed
+    /// for the Time in Status report.
+    #[structopt(short, long, required_unless_one = &["from-store", "board-name", "batch"])]
+    pub jql_query: Option<String>,
+    /// Report on the issues of the agile board with this name, rather than those matching a jql
+    /// query
+    #[structopt(long, conflicts_with = "jql-query")]
+    pub board_name: Option<String>,
+    /// Run the report against the items in a local issue store, created by `jira sync-wip`,
+    /// rather than pulling from jira.
+    #[structopt(long, parse(from_os_str))]
+    pub from_store: Option<PathBuf>,
+    /// Ask Jira for the HTML rendered descriptions and comments of each issue. These are
+    /// stored in the 'debug-jira-file' alongside the raw fields, which is useful for archival
+    /// exports.
+    #[structopt(long)]
+    pub rendered_fields: bool,
+    /// Ask Jira for the changelog of each issue in the search itself, rather than pulling each
+    /// changelog separately. Jira truncates long changelogs, those are still pulled separately.
+    /// This is much faster for queries with few issues.
+    #[structopt(long)]
+    pub embed_changelogs: bool,
+    /// Remove the names, email addresses, avatars and time zones of people from the
+    /// 'debug-jira-file'
+    #[structopt(long)]
+    pub redact_pii: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `p85_cycle_time>20d`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse))]
+    pub fail_on: Vec<thresholds::Threshold>,
+    /// Only report on the entries that pass the filter, e.g. `in_test>10d` or `team==Platform`.
+    /// May be given multiple times, an entry must pass every filter. Filtered out entries are not
+    /// counted by `fail-on` or `group-by` either.
+    #[structopt(long, parse(try_from_str = filters::parse))]
+    pub filter: Vec<filters::Filter>,
+    /// Only report on issues with this label. May be given multiple times, an issue with any of
+    /// the labels is reported.
+    #[structopt(long)]
+    pub include_label: Vec<String>,
+    /// Don't report on issues with this label. May be given multiple times.
+    #[structopt(long)]
+    pub exclude_label: Vec<String>,
+    /// Only report on issues in this component. May be given multiple times, an issue in any of
+    /// the components is reported.
+    #[structopt(long)]
+    pub component: Vec<String>,
+    /// Only report on issues with this resolution, as mapped by the `resolution-mapping`. May be
+    /// given multiple times. Replaces the `exclude-resolutions` of the config.
+    #[structopt(long, possible_values = &core::RESOLUTIONS)]
+    pub include_resolution: Vec<core::Resolution>,
+    /// Don't report on issues with this resolution, e.g. `Rejected`. May be given multiple times.
+    /// Replaces the `exclude-resolutions` of the config.
+    #[structopt(long, possible_values = &core::RESOLUTIONS)]
+    pub exclude_resolution: Vec<core::Resolution>,
+    /// Also write the average days in each status per assignee or team. They are written to a
+    /// second csv next to the output, with `-by-assignee` or `-by-team` added to its name.
+    #[structopt(long, possible_values = &times_in_flight::GROUP_BY)]
+    pub group_by: Option<times_in_flight::GroupBy>,
+    /// Add the description of each issue to the csv report, in the `body-format` of the config
+    #[structopt(long)]
+    pub include_body: bool,
+    /// Also write the issues that moved backwards through the statuses at least once. They are
+    /// written to a second csv next to the output, with `-ping-pong` added to its name.
+    #[structopt(long)]
+    pub ping_pong: bool,
+    /// Also write when each issue entered and left each status, one row per status with the
+    /// columns `issue`, `status`, `start`, `end` and `state`. May be an object storage uri like
+    /// the output path.
+    #[structopt(long, parse(from_os_str))]
+    pub timeline_output: Option<PathBuf>,
+    /// Also write the aggregates of the report, the average days in each status, the issues in
+    /// each status and the resolved issues, in the Prometheus text format. Point the node
+    /// exporter's textfile collector at it to scrape the trend of a scheduled run. May be an
+    /// object storage uri like the output path.
+    #[structopt(long, parse(from_os_str))]
+    pub metrics_output: Option<PathBuf>,
+    /// Print a summary of the report once it is written, the slowest issues by cycle time and the
+    /// average days in each status. This is the default.
+    #[structopt(long, overrides_with = "no-summary")]
+    pub summary: bool,
+    /// Don't print the summary of the report
+    #[structopt(long, overrides_with = "summary")]
+    pub no_summary: bool,
+    /// The number of slowest issues the summary lists
+    #[structopt(long, default_value = "10")]
+    pub summary_top: usize,
+    /// Print what the report would do, including the number of issues the query matches, and
+    /// exit without pulling any issues or writing the report
+    #[structopt(long, visible_alias = "explain")]
+    pub dry_run: bool,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Refuse to pull the issues when the query matches more than this many, replacing the
+    /// `max-issues` of the config
+    #[structopt(long)]
+    pub max_issues: Option<u64>,
+    /// Skip the issues pulled from jira that can't be read, e.g. because a field has an
+    /// unexpected shape, rather than failing the command. The skipped issues, and where in each
+    /// the problem is, are logged as a warning at the end.
+    #[structopt(long)]
+    pub skip_invalid_issues: bool,
+    /// Leave out the issues whose changelog still can't be pulled after retrying, rather than
+    /// failing the command. The rest of the report is written, the failed issues are listed in
+    /// the `errors` of the 'debug-jira-file' and the command exits with the `partial` exit code.
+    #[structopt(long)]
+    pub skip_failed_changelogs: bool,
+    /// Spool each page of the pull as it is pulled, so that a pull that fails or is interrupted
+    /// can be continued with `--resume`
+    #[structopt(
+        long,
+        conflicts_with_all = &["batch", "from-store", "load-from-jira-file", "merge-jira-file"]
+    )]
+    pub checkpoint: bool,
+    /// Continue a pull that failed or was interrupted from its last checkpoint, rather than
+    /// starting over. The query, or board, and the jira instance must be the same as before, and
+    /// the query must still match as many issues as when the pull was started.
+    #[structopt(
+        long,
+        conflicts_with_all = &["batch", "from-store", "load-from-jira-file", "merge-jira-file"]
+    )]
+    pub resume: bool,
+    /// Where the pages of a checkpointed pull are spooled until it is finished, each query in its
+    /// own directory. Defaults to `lectev-spool` in the temporary directory.
+    #[structopt(long, parse(from_os_str))]
+    pub spool_dir: Option<PathBuf>,
+    /// Only report on issues resolved on or after this date in the reporting timezone, e.g.
+    /// `2021-01-01`
+    #[structopt(long)]
+    pub resolved_after: Option<NaiveDate>,
+    /// Only report on issues resolved before this date in the reporting timezone, e.g.
+    /// `2021-04-01`
+    #[structopt(long)]
+    pub resolved_before: Option<NaiveDate>,
+    /// Only count the time in each status from the start of this date, e.g. `2021-04-01`. Time
+    /// in a status that started earlier is counted from the start of the day.
+    #[structopt(long)]
+    pub since: Option<NaiveDate>,
+    /// Only count the time in each status up to the start of this date, e.g. `2021-07-01`. Time
+    /// in a status that ended later is counted up to the start of the day.
+    #[structopt(long)]
+    pub until: Option<NaiveDate>,
+    /// Run the report once for each of the named queries in a batch file, writing each to its own
+    /// output path. Every other option applies to all of the reports.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &[
+            "jql-query",
+            "board-name",
+            "from-store",
+            "output-path",
+            "debug-jira-file",
+            "load-from-jira-file",
+            "merge-jira-file",
+            "timeline-output",
+            "metrics-output",
+        ]
+    )]
+    pub batch: Option<PathBuf>,
+}
+
+impl TimeInStatusArgs {
+    /// The summary is printed by default, `--summary` only undoes an earlier `--no-summary`
+    fn prints_summary(&self) -> bool {
+        self.summary || !self.no_summary
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SyncArgs {
+    /// The JQL query for the issues to keep in the store. Only issues updated since the last sync
+    /// of the same query are pulled.
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path to the store. It is created if it does not exist.
+    #[structopt(short, long, parse(from_os_str))]
+    pub store_path: PathBuf,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Ask Jira for the HTML rendered description of each issue, which is kept with its item in
+    /// the store
+    #[structopt(long)]
+    pub rendered_fields: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct EpicReportArgs {
+    /// The JQL query for the epics to report on, e.g. `project = ABC AND issuetype = Epic`
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the csv report
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Warn about and skip children with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Ask Jira for the HTML rendered description of each epic, which is written to the
+    /// `rendered_description` column
+    #[structopt(long)]
+    pub rendered_fields: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `min_percent_completed<50`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse_for::<epics::Entry>))]
+    pub fail_on: Vec<thresholds::Threshold>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct EstimateAccuracyArgs {
+    /// The JQL query for the issues to compare, e.g. `project = ABC AND resolved >= -30d`
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the per issue csv report. The aggregate accuracy is written next to it, so
+    /// `report.csv` has its summary written to `report-summary.csv`.
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `p85_accuracy_ratio>2`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse_for::<estimate_accuracy::Entry>))]
+    pub fail_on: Vec<thresholds::Threshold>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FieldHistoryArgs {
+    /// The JQL query for the issues to export the history of
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the csv export
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// A field to export, by name, e.g. `Story Points`, or by id, e.g. `customfield_10002`. May be
+    /// given more than once. Replaces the `history-fields` of the config.
+    #[structopt(short, long)]
+    pub field: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RollupArgs {
+    /// The JQL query for the issues to roll up, e.g. `project = ABC AND created >= -90d`. Include
+    /// the sub-tasks to have them counted towards the epic of their story.
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the per epic csv report
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `max_blocked_days>10d`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse_for::<rollup::Entry>))]
+    pub fail_on: Vec<thresholds::Threshold>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TransitionsArgs {
+    /// The JQL query for the issues to list the transitions of
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the csv export
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SuggestConfigArgs {
+    /// The JQL query for the issues whose statuses, resolutions and issue types are suggested,
+    /// e.g. `project = ABC AND updated >= -90d`
+    #[structopt(short, long)]
+    pub jql_query: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FlowMetricsArgs {
+    /// The JQL query for the issues whose flow is measured
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the csv export, one row per week
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Flag the weeks where the average work in progress differs from what Little's Law
+    /// predicts by more than this share of it
+    #[structopt(long, default_value = "0.25")]
+    pub max_deviation: f64,
+    /// Warn about and skip issues with a status that is not mapped in the config, rather than
+    /// failing the command
+    #[structopt(long)]
+    pub skip_unmapped_statuses: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `avg_throughput<2`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse_for::<flow::Week>))]
+    pub fail_on: Vec<thresholds::Threshold>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ResponsivenessArgs {
+    /// The JQL query for the issues to measure, e.g. `project = HELP AND created >= -30d`
+    #[structopt(short, long)]
+    pub jql_query: String,
+    /// The path of the per issue csv report. The summary is written next to it, so `report.csv`
+    /// has its summary written to `report-summary.csv`.
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// Ask Jira for the HTML rendered description of each issue, which is written to the
+    /// `rendered_description` column
+    #[structopt(long)]
+    pub rendered_fields: bool,
+    /// Fail the command when the aggregate results breach the threshold, e.g.
+    /// `p85_working_days_to_first_response>2`. May be given multiple times. The report is still written.
+    #[structopt(long, parse(try_from_str = thresholds::parse_for::<responsiveness::Entry>))]
+    pub fail_on: Vec<thresholds::Threshold>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DiffDumpsArgs {
+    /// The older 'debug-jira-file'
+    #[structopt(parse(from_os_str))]
+    pub old: PathBuf,
+    /// The newer 'debug-jira-file', of the same query
+    #[structopt(parse(from_os_str))]
+    pub new: PathBuf,
+    /// The path of the change log
+    #[structopt(short, long, parse(from_os_str))]
+    pub output_path: PathBuf,
+    /// The format of the change log
+    #[structopt(long, default_value = "csv", possible_values = &CHANGE_LOG_FORMATS)]
+    pub output_format: ChangeLogFormat,
 }
 
 #[instrument]
-async fn write_json_file(dump_path: &Path, data: &[api::IssueDetail]) -> Result<(), Error> {
-    let mut dump_file = File::create(dump_path)
-        .await
-        .context(FailedToCreateRawDumpFile {})?;
-    dump_file
-        .write_all(
-            serde_json::to_string(&data)
-                .context(FailedToConvertInternalStructureToJson {})?
-                .as_bytes(),
-        )
+async fn load_jira_from_file(load_file: &Path) -> Result<JiraDump, Error> {
+    dump::read(load_file).await.map_err(from_dump)
+}
+
+/// The error of a report that could not use a jira dump
+fn from_dump(source: dump::Error) -> Error {
+    match source {
+        dump::Error::CouldNotRead { source, .. } => Error::FailedToReadFromFile { source },
+        dump::Error::InvalidDump { source, .. } => {
+            Error::FailedToConvertJsonToInternalStructure { source }
+        }
+        dump::Error::CouldNotCreate { source, .. } => Error::FailedToCreateRawDumpFile { source },
+        dump::Error::UnableToSerialize { source } => {
+            Error::FailedToConvertInternalStructureToJson { source }
+        }
+        dump::Error::CouldNotWrite { path, source } => Error::FailedToWriteFile {
+            path: path.to_string_lossy().into_owned(),
+            source,
+        },
+    }
+}
+
+/// The error of a report whose items could not be pulled, with the same code as when the report
+/// pulled the issues itself
+fn from_tracker(source: tracker::Error) -> Error {
+    match source {
+        tracker::Error::Jira { source } => Error::FailedToGetData { source },
+        tracker::Error::JiraTranslate { source } => Error::FailedToTransformData { source },
+        tracker::Error::JiraDump { source } => from_dump(source),
+        tracker::Error::JiraCheckpoint { source } => Error::FailedToUseCheckpoint { source },
+        tracker::Error::Incomplete { count, issue_keys } => {
+            Error::IncompleteReport { count, issue_keys }
+        }
+        tracker::Error::Cancelled => Error::Cancelled,
+        source => Error::FailedToGetItems { source },
+    }
+}
+
+/// Pull every item matching `query`, for the reports that need all of them at once
+async fn all_items(tracker: &dyn Tracker, query: &str) -> Result<Vec<core::Item>, Error> {
+    let mut items = Vec::new();
+    let mut pages = tracker.items(query);
+    while let Some(page) = pages.try_next().await.map_err(from_tracker)? {
+        items.extend(page);
+    }
+    Ok(items)
+}
+
+/// The rest client, built the first time it is needed. Reports that don't pull from jira never
+/// build it, and the reports of a batch share it.
+#[derive(Debug)]
+struct LazyClient<'a> {
+    conf: &'a jira_config::Config,
+    client: Option<rest::Client>,
+}
+
+impl<'a> LazyClient<'a> {
+    fn new(conf: &'a jira_config::Config) -> Self {
+        LazyClient { conf, client: None }
+    }
+
+    async fn get(&mut self) -> Result<&rest::Client, Error> {
+        let client = match self.client.take() {
+            Some(client) => client,
+            None => rest::new(
+                &self.conf.jira_instance,
+                &self.conf.username,
+                &self.conf.token,
+                &self.conf.retry,
+            )
+            .await
+            .context(FailedToBuildClient {})?,
+        };
+        Ok(self.client.get_or_insert(client))
+    }
+}
+
+/// Where the issues of a time in status report are pulled from
+#[derive(Debug)]
+enum IssueSource {
+    Jql(String),
+    /// Every issue of a board
+    Board {
+        id: native::BoardId,
+        name: String,
+    },
+}
+
+impl IssueSource {
+    async fn resolve(jira: &JiraTracker<'_>, args: &TimeInStatusArgs) -> Result<Self, Error> {
+        match (&args.board_name, &args.jql_query) {
+            (Some(board_name), _) => {
+                let board = jira.board(board_name).await.map_err(from_tracker)?;
+                Ok(IssueSource::Board {
+                    id: board.id,
+                    name: board.name,
+                })
+            }
+            (None, Some(jql)) => Ok(IssueSource::Jql(jql.clone())),
+            (None, None) => MissingJqlQuery {}.fail(),
+        }
+    }
+
+    /// The query the issues are pulled with, which only narrows down those of a board
+    fn query(&self) -> &str {
+        match self {
+            IssueSource::Jql(jql) => jql,
+            IssueSource::Board { .. } => "",
+        }
+    }
+
+    /// Identifies the issues pulled, so a pull is only resumed for the same issues
+    fn describe(&self) -> String {
+        match self {
+            IssueSource::Jql(jql) => format!("jql {}", jql),
+            IssueSource::Board { id, .. } => format!("board {}", id),
+        }
+    }
+}
+
+/// The tracker the issues of the report are pulled from jira with, and where from
+async fn jira_tracker<'a>(
+    conf: &'a jira_config::Config,
+    client: &'a rest::Client,
+    args: &TimeInStatusArgs,
+    cancel: &cancel::Token,
+) -> Result<(JiraTracker<'a>, IssueSource), Error> {
+    let mut jira = JiraTracker::new(conf, client, unmapped_status(args.skip_unmapped_statuses))
+        .with_cancellation(cancel.clone())
+        .with_expand(expand(args));
+    if let Some(max_issues) = args.max_issues {
+        jira = jira.with_max_issues(max_issues);
+    }
+    if args.skip_invalid_issues {
+        jira = jira.skip_invalid_issues();
+    }
+    if args.skip_failed_changelogs {
+        jira = jira.skip_failed_changelogs();
+    }
+    let source = IssueSource::resolve(&jira, args).await?;
+    let jira = match &source {
+        IssueSource::Board { id, .. } => jira.on_board(*id),
+        IssueSource::Jql(_) => jira,
+    };
+    Ok((jira, source))
+}
+
+fn expand(args: &TimeInStatusArgs) -> api::Expand {
+    api::Expand {
+        rendered_fields: args.rendered_fields,
+        changelog: args.embed_changelogs,
+    }
+}
+
+/// Ask for the rendered fields of each issue when `rendered_fields` is set
+fn rendered(rendered_fields: bool) -> api::Expand {
+    api::Expand {
+        rendered_fields,
+        ..api::Expand::default()
+    }
+}
+
+fn unmapped_status(skip_unmapped_statuses: bool) -> nativetocore::UnmappedStatus {
+    if skip_unmapped_statuses {
+        nativetocore::UnmappedStatus::Skip
+    } else {
+        nativetocore::UnmappedStatus::Fail
+    }
+}
+
+/// The items the report selects by their labels, components and resolutions. The resolutions
+/// given on the command line replace the `exclude-resolutions` of the config.
+fn selection(conf: &jira_config::Config, args: &TimeInStatusArgs) -> time_in_status::Selection {
+    let (include_resolutions, exclude_resolutions) =
+        if args.include_resolution.is_empty() && args.exclude_resolution.is_empty() {
+            (Vec::new(), conf.exclude_resolutions.clone())
+        } else {
+            (
+                args.include_resolution.clone(),
+                args.exclude_resolution.clone(),
+            )
+        };
+    time_in_status::Selection {
+        include_labels: args.include_label.clone(),
+        exclude_labels: args.exclude_label.clone(),
+        components: args.component.clone(),
+        include_resolutions,
+        exclude_resolutions,
+    }
+}
+
+/// The writer for the chosen output format
+#[derive(Debug)]
+enum ReportWriter {
+    Csv(csv_async::AsyncSerializer<File>),
+    Html(html::Writer),
+}
+
+/// Where a report and its exports end up, each may be local or in object storage
+#[derive(Debug)]
+struct Outputs {
+    report: sink::Output,
+    timeline: Option<sink::Output>,
+    metrics: Option<sink::Output>,
+}
+
+impl Outputs {
+    async fn prepare(output_path: &Path, args: &TimeInStatusArgs) -> Result<Outputs, Error> {
+        Ok(Outputs {
+            report: sink::Output::prepare(output_path)
+                .await
+                .context(FailedToUseOutput {})?,
+            timeline: match &args.timeline_output {
+                Some(path) => Some(
+                    sink::Output::prepare(path)
+                        .await
+                        .context(FailedToUseOutput {})?,
+                ),
+                None => None,
+            },
+            metrics: match &args.metrics_output {
+                Some(path) => Some(
+                    sink::Output::prepare(path)
+                        .await
+                        .context(FailedToUseOutput {})?,
+                ),
+                None => None,
+            },
+        })
+    }
+
+    /// Upload what was written to object storage. The staging directories are removed either
+    /// way, also when the report failed and this is never called.
+    async fn finish(self) -> Result<(), Error> {
+        self.report.finish().await.context(FailedToUseOutput {})?;
+        for output in self.timeline.into_iter().chain(self.metrics) {
+            output.finish().await.context(FailedToUseOutput {})?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the report as the entries are produced
+#[derive(Debug)]
+struct Report {
+    path: PathBuf,
+    writer: ReportWriter,
+    layout: Layout,
+    observations: thresholds::Observations,
+    groups: Option<times_in_flight::Groups>,
+    /// The writer for the issues that moved backwards, when they are wanted
+    ping_pong: Option<csv_async::AsyncSerializer<File>>,
+    /// The path and writer of the status timeline export, when it is wanted
+    timeline: Option<(PathBuf, csv_async::AsyncSerializer<File>)>,
+    /// The path of the metrics export and its totals, when it is wanted
+    metrics: Option<(PathBuf, metrics::Metrics)>,
+    /// The summary printed once the report is written, unless it was turned off
+    summary: Option<summary::Summary>,
+    /// What is reported on and the period the time in each status is counted over
+    options: time_in_status::Options,
+}
+
+impl Report {
+    async fn create(
+        conf: &jira_config::Config,
+        outputs: &Outputs,
+        args: &TimeInStatusArgs,
+    ) -> Result<Report, Error> {
+        if let (Some(since), Some(until)) = (args.since, args.until) {
+            ensure!(since < until, EmptyTimeWindow { since, until });
+        }
+        let out_file = &outputs.report.local_path();
+        Ok(Report {
+            path: out_file.clone(),
+            writer: match args.output_format {
+                OutputFormat::Csv => ReportWriter::Csv(csv_async::AsyncSerializer::from_writer(
+                    File::create(out_file)
+                        .await
+                        .context(FailedToCreateCSVFile {})?,
+                )),
+                OutputFormat::Html => ReportWriter::Html(
+                    html::create(out_file)
+                        .await
+                        .context(FailedToWriteHtmlReport {})?,
+                ),
+            },
+            layout: args.layout,
+            observations: thresholds::observations(&args.fail_on),
+            groups: args.group_by.map(times_in_flight::groups),
+            ping_pong: if args.ping_pong {
+                Some(csv_async::AsyncSerializer::from_writer(
+                    File::create(utils::sibling_path(out_file, "ping-pong"))
+                        .await
+                        .context(FailedToCreateCSVFile {})?,
+                ))
+            } else {
+                None
+            },
+            timeline: match &outputs.timeline {
+                Some(timeline) => {
+                    let timeline_path = timeline.local_path();
+                    let writer = csv_async::AsyncSerializer::from_writer(
+                        File::create(&timeline_path)
+                            .await
+                            .context(FailedToCreateCSVFile {})?,
+                    );
+                    Some((timeline_path, writer))
+                }
+                None => None,
+            },
+            metrics: outputs
+                .metrics
+                .as_ref()
+                .map(|metrics| (metrics.local_path(), metrics::Metrics::default())),
+            summary: if args.prints_summary() {
+                Some(summary::new(
+                    format!(
+                        "Summary of {}",
+                        args.output_path.as_deref().unwrap_or(out_file).display()
+                    ),
+                    args.summary_top,
+                ))
+            } else {
+                None
+            },
+            options: time_in_status::Options {
+                selection: selection(conf, args),
+                filters: args.filter.clone(),
+                resolved_after: args.resolved_after,
+                resolved_before: args.resolved_before,
+                since: args.since,
+                until: args.until,
+                include_body: args.include_body,
+            },
+        })
+    }
+
+    /// Run the report on the items `tracker` has for `query`, writing them a page at a time so
+    /// that only a single page of items is in memory at once
+    async fn write_from(
+        &mut self,
+        conf: &jira_config::Config,
+        tracker: &dyn Tracker,
+        query: &str,
+    ) -> Result<(), Error> {
+        let mut report = time_in_status::time_in_status(conf, tracker, query, self.options.clone());
+        while let Some(page) = report.next_page().await.map_err(from_tracker)? {
+            for (entry, item) in page.entries() {
+                self.write_entry(&entry, item).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_entry(
+        &mut self,
+        entry: &times_in_flight::Entry<'_>,
+        item: &core::Item,
+    ) -> Result<(), Error> {
+        self.observations.observe(entry);
+        if let Some((_, metrics)) = &mut self.metrics {
+            metrics.observe(entry);
+        }
+        if let Some(summary) = &mut self.summary {
+            summary.observe(entry);
+        }
+        if let Some(groups) = &mut self.groups {
+            groups.add(entry);
+        }
+        if let Some(ping_pong_writer) = &mut self.ping_pong {
+            if entry.backward_transitions > 0 {
+                ping_pong_writer
+                    .serialize(entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        if let Some((_, timeline_writer)) = &mut self.timeline {
+            for timeline_entry in times_in_flight::timeline_entries(item) {
+                timeline_writer
+                    .serialize(&timeline_entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        match &mut self.writer {
+            ReportWriter::Csv(csv_writer) if self.layout == Layout::Long => {
+                for long_entry in &entry.long_entries() {
+                    csv_writer
+                        .serialize(long_entry)
+                        .await
+                        .context(FailedToWriteToCSVFile {})?;
+                }
+            }
+            ReportWriter::Csv(csv_writer) => csv_writer
+                .serialize(entry)
+                .await
+                .context(FailedToWriteToCSVFile {})?,
+            ReportWriter::Html(html_writer) => html_writer
+                .write(entry)
+                .await
+                .context(FailedToWriteHtmlReport {})?,
+        }
+        Ok(())
+    }
+
+    /// Flush the report and write the group averages. A partial report, one where pulling the
+    /// issues was interrupted, is moved to `<name>-partial` so it can't be mistaken for a
+    /// complete one.
+    async fn finish(self, partial: bool) -> Result<thresholds::Observations, Error> {
+        match self.writer {
+            ReportWriter::Csv(mut csv_writer) => {
+                csv_writer.flush().await.context(FailedToWriteFile {
+                    path: self.path.to_string_lossy(),
+                })?;
+            }
+            ReportWriter::Html(html_writer) => html_writer
+                .finish()
+                .await
+                .context(FailedToWriteHtmlReport {})?,
+        }
+        let has_ping_pong = self.ping_pong.is_some();
+        if let Some(mut ping_pong_writer) = self.ping_pong {
+            ping_pong_writer.flush().await.context(FailedToWriteFile {
+                path: utils::sibling_path(&self.path, "ping-pong").to_string_lossy(),
+            })?;
+        }
+        if let Some((timeline_path, mut timeline_writer)) = self.timeline {
+            timeline_writer.flush().await.context(FailedToWriteFile {
+                path: timeline_path.to_string_lossy(),
+            })?;
+            if partial {
+                let partial_path = utils::sibling_path(&timeline_path, "partial");
+                tokio::fs::rename(&timeline_path, &partial_path)
+                    .await
+                    .context(FailedToWriteFile {
+                        path: partial_path.to_string_lossy(),
+                    })?;
+            }
+        }
+        if let Some((metrics_path, metrics)) = &self.metrics {
+            let metrics_path = if partial {
+                utils::sibling_path(metrics_path, "partial")
+            } else {
+                metrics_path.clone()
+            };
+            tokio::fs::write(&metrics_path, metrics.to_string())
+                .await
+                .context(FailedToWriteFile {
+                    path: metrics_path.to_string_lossy(),
+                })?;
+        }
+        let path = if partial {
+            let partial_path = utils::sibling_path(&self.path, "partial");
+            tokio::fs::rename(&self.path, &partial_path)
+                .await
+                .context(FailedToWriteFile {
+                    path: partial_path.to_string_lossy(),
+                })?;
+            if has_ping_pong {
+                let ping_pong_path = utils::sibling_path(&partial_path, "ping-pong");
+                tokio::fs::rename(
+                    utils::sibling_path(&self.path, "ping-pong"),
+                    &ping_pong_path,
+                )
+                .await
+                .context(FailedToWriteFile {
+                    path: ping_pong_path.to_string_lossy(),
+                })?;
+            }
+            warn!(
+                "The report is missing issues, it was written to {}",
+                partial_path.display()
+            );
+            partial_path
+        } else {
+            self.path
+        };
+        if let Some(groups) = &self.groups {
+            write_groups(&path, groups).await?;
+        }
+        if let Some(summary) = &self.summary {
+            command::write(summary.to_string().trim_end())
+                .await
+                .context(FailedToWriteSummary {})?;
+        }
+        Ok(self.observations)
+    }
+}
+
+#[instrument(skip(groups))]
+async fn write_groups(out_file: &Path, groups: &times_in_flight::Groups) -> Result<(), Error> {
+    let path = utils::sibling_path(out_file, &format!("by-{}", groups.group_by().name()));
+    let mut group_writer = csv_async::AsyncSerializer::from_writer(
+        File::create(&path)
+            .await
+            .context(FailedToCreateCSVFile {})?,
+    );
+    for group in groups.averages() {
+        group_writer
+            .serialize(&group)
+            .await
+            .context(FailedToWriteToCSVFile {})?;
+    }
+    group_writer.flush().await.context(FailedToWriteFile {
+        path: path.to_string_lossy(),
+    })
+}
+
+/// Pull the next page of items. Being interrupted is reported as [`Error::Cancelled`] rather
+/// than as a failure to get the items.
+async fn next_items(
+    items: &mut BoxStream<'_, Result<Vec<core::Item>, tracker::Error>>,
+) -> Result<Option<Vec<core::Item>>, Error> {
+    match items.try_next().await {
+        Err(tracker::Error::Cancelled) => Cancelled.fail(),
+        result => result.context(FailedToGetItems {}),
+    }
+}
+
+/// Pull the issues from jira, or the debug file, and write them to the report a page at a time,
+/// so that only a single page of issues is in memory at once. When `cancel` is cancelled the
+/// pages already pulled are kept, the dump is marked as partial and [`Error::Cancelled`] is
+/// returned.
+#[instrument(skip(client, cancel, report))]
+async fn report_from_jira(
+    conf: &jira_config::Config,
+    client: &mut LazyClient<'_>,
+    args: &TimeInStatusArgs,
+    cancel: &cancel::Token,
+    report: &mut Report,
+) -> Result<(), Error> {
+    let unmapped_status = unmapped_status(args.skip_unmapped_statuses);
+    match (
+        args.load_from_jira_file,
+        args.merge_jira_file,
+        &args.debug_jira_file,
+    ) {
+        (true, _, Some(load_path)) => {
+            let dump = DumpTracker::new(conf, load_path, unmapped_status);
+            report.write_from(conf, &dump, "").await
+        }
+        (true, _, None) | (false, true, None) => UnableToLoadFromJiraFile {}.fail(),
+        (false, true, Some(dump_path)) => {
+            let client = client.get().await?;
+            let (jira, source) = jira_tracker(conf, client, args, cancel).await?;
+            let dump = DumpTracker::new(conf, dump_path, unmapped_status)
+                .refreshed_from(&jira, args.redact_pii);
+            report.write_from(conf, &dump, source.query()).await
+        }
+        (false, false, dump_path) => {
+            let client = client.get().await?;
+            let (jira, source) = jira_tracker(conf, client, args, cancel).await?;
+            let spool = open_spool(conf, &jira, args, &source).await?;
+            let recording = Recording::new(spool, dump_path.as_deref(), args.redact_pii)
+                .await
+                .map_err(from_tracker)?;
+            report
+                .write_from(conf, &jira.recording(recording), source.query())
+                .await
+        }
+    }
+}
+
+/// The spool of the pull of `source`, when the pull is checkpointed or resumed. With
+/// `--checkpoint` or `--resume` each page is spooled so that a pull that fails or is interrupted
+/// can be continued with `--resume`, see [`Recording`]. The issues `source` matches are counted
+/// first, a resume is refused when they are no longer the number the pull started with.
+async fn open_spool(
+    conf: &jira_config::Config,
+    jira: &JiraTracker<'_>,
+    args: &TimeInStatusArgs,
+    source: &IssueSource,
+) -> Result<Option<checkpoint::Spool>, Error> {
+    if !args.checkpoint && !args.resume {
+        return Ok(None);
+    }
+    let base = args
+        .spool_dir
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("lectev-spool"));
+    let description = source.describe();
+    let spool_dir = checkpoint::spool_dir(&base, &conf.jira_instance, &description);
+    let total = jira.count(source.query()).await.map_err(from_tracker)?;
+    let spool = if args.resume {
+        checkpoint::Spool::resume(&spool_dir, &conf.jira_instance, &description, total).await
+    } else {
+        checkpoint::Spool::create(&spool_dir, &conf.jira_instance, &description, total).await
+    };
+    spool.map(Some).context(FailedToUseCheckpoint {})
+}
+
+fn print_mapping<V: std::fmt::Display>(title: &str, mapping: &HashMap<String, V>) {
+    println!("{}:", title);
+    let sorted: BTreeMap<&String, &V> = mapping.iter().collect();
+    for (from, to) in sorted {
+        println!("  {} -> {}", from, to);
+    }
+}
+
+/// Print how the config translates jira issues into items
+fn join_resolutions(resolutions: &[core::Resolution]) -> String {
+    resolutions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Print the dates the issues are resolved in and the time in each status is counted over
+fn print_dates(args: &TimeInStatusArgs) {
+    if let Some(resolved_after) = args.resolved_after {
+        println!("Resolved on or after: {}", resolved_after);
+    }
+    if let Some(resolved_before) = args.resolved_before {
+        println!("Resolved before: {}", resolved_before);
+    }
+    if let Some(since) = args.since {
+        println!("Counting time since: {}", since);
+    }
+    if let Some(until) = args.until {
+        println!("Counting time until: {}", until);
+    }
+}
+
+fn print_config(conf: &jira_config::Config) {
+    println!(
+        "Resolution field: {}",
+        conf.resolution_field
+            .as_ref()
+            .map_or("(jira resolution)", |field| field.0.as_str())
+    );
+    if let Some(team_field) = &conf.team_field {
+        println!("Team field: {}", team_field);
+    }
+    if let Some(epic_link_field) = &conf.epic_link_field {
+        println!("Epic link field: {}", epic_link_field);
+    }
+    println!(
+        "Feature issue types: {}",
+        conf.issue_types.features.join(", ")
+    );
+    println!(
+        "Operational issue types: {}",
+        conf.issue_types.operational.join(", ")
+    );
+    for (project, issue_types) in &conf.project_issue_types {
+        println!(
+            "  {} feature: {}, operational: {}",
+            project,
+            issue_types.features.join(", "),
+            issue_types.operational.join(", ")
+        );
+    }
+    print_mapping("Status mapping", &conf.status_mapping);
+    for status_pattern in &conf.status_patterns {
+        println!("  {} -> {}", status_pattern.pattern, status_pattern.status);
+    }
+    if conf.infer_status_categories {
+        for (category, status) in &nativetocore::STATUS_CATEGORIES {
+            println!("  category {} -> {}", category, status);
+        }
+    }
+    if let Some(default_status) = &conf.default_status {
+        println!("  * -> {}", default_status);
+    }
+    print_mapping("Resolution mapping", &conf.resolution_mapping);
+    if !conf.exclude_resolutions.is_empty() {
+        println!(
+            "Exclude resolutions: {}",
+            join_resolutions(&conf.exclude_resolutions)
+        );
+    }
+    print_mapping("Link mapping", &conf.link_mapping);
+    match &conf.working_week {
+        Some(working_week) => {
+            let days: Vec<String> = working_week
+                .days
+                .iter()
+                .map(|weekday| format!("{:?}", weekday))
+                .collect();
+            print!("Working week: {}", days.join(", "));
+            if let Some(hours) = working_week.working_hours {
+                print!(", {}:00 to {}:00", hours.start, hours.end);
+                if conf.reporting_timezone.is_none() {
+                    print!(" at UTC{:+}", working_week.utc_offset_hours);
+                }
+            }
+            println!();
+        }
+        None => println!("Working week: US settlement calendar"),
+    }
+    if let Some(timezone) = &conf.reporting_timezone {
+        println!("Reporting timezone: {}", timezone);
+    }
+    if let Some(estimate_source) = &conf.estimate_source {
+        match &estimate_source.conversion {
+            jira_config::PointsConversion::Factor(factor) => println!(
+                "Estimates: {}, {} days a point",
+                estimate_source.field, factor
+            ),
+            jira_config::PointsConversion::Table(table) => {
+                let entries: Vec<String> = table
+                    .iter()
+                    .map(|entry| format!("{} -> {} days", entry.points, entry.days))
+                    .collect();
+                println!(
+                    "Estimates: {}, points {}",
+                    estimate_source.field,
+                    entries.join(", ")
+                );
+            }
+        }
+    }
+    println!("Body format: {}", conf.body_format.name());
+    println!("Max issues: {}", conf.issue_limit());
+}
+
+/// Print what the report would do without pulling any issues. The only call made to jira is to
+/// count the issues that match the query.
+#[instrument(skip(client, cancel))]
+async fn explain_time_in_status(
+    conf: &jira_config::Config,
+    client: &mut LazyClient<'_>,
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &TimeInStatusArgs,
+    output_path: &Path,
+    cancel: &cancel::Token,
+) -> Result<(), Error> {
+    let resolved_config_path = jira_config::resolve_config_path(config_path)
         .await
-        .context(FailedToWriteFile {
-            path: dump_path.to_string_lossy(),
-        })?;
+        .context(GetConfig {})?;
+    println!("Config: {}", resolved_config_path.display());
+    println!("Profile: {}", profile);
+    println!("Jira instance: {}", conf.jira_instance);
 
+    match (
+        &args.from_store,
+        args.load_from_jira_file,
+        &args.debug_jira_file,
+    ) {
+        (Some(store_path), _, _) => println!("Source: issue store {}", store_path.display()),
+        (None, true, Some(load_path)) => println!("Source: jira file {}", load_path.display()),
+        (None, true, None) => return UnableToLoadFromJiraFile {}.fail(),
+        (None, false, dump_path) => {
+            let client = client.get().await?;
+            let (jira, source) = jira_tracker(conf, client, args, cancel).await?;
+            let total = jira.count(source.query()).await.map_err(from_tracker)?;
+            match &source {
+                IssueSource::Jql(jql) => println!("JQL: {}", jql),
+                IssueSource::Board { id, name } => println!("Board: {} ({})", name, id),
+            }
+            println!("Matching issues: {}", total);
+            let max_issues = args.max_issues.unwrap_or_else(|| conf.issue_limit());
+            if total > max_issues {
+                println!(
+                    "More than the {} max issues, the report would refuse to pull them",
+                    max_issues
+                );
+            }
+            if let Some(dump_path) = dump_path {
+                println!("Raw dump: {}", dump_path.display());
+            }
+        }
+    }
+
+    println!("Output: {}", output_path.display());
+    if args.output_format == OutputFormat::Csv {
+        println!("Layout: {:?}", args.layout);
+    }
+    if let Some(group_by) = args.group_by {
+        println!("Group by: {}", group_by.name());
+    }
+    if let Some(timeline_path) = &args.timeline_output {
+        println!("Timeline: {}", timeline_path.display());
+    }
+    if let Some(metrics_path) = &args.metrics_output {
+        println!("Metrics: {}", metrics_path.display());
+    }
+    if args.prints_summary() {
+        println!("Summary: the {} slowest issues", args.summary_top);
+    }
+    if args.ping_pong {
+        println!(
+            "Ping-pong issues: {}",
+            utils::sibling_path(output_path, "ping-pong").display()
+        );
+    }
+    print_dates(args);
+    for filter in &args.filter {
+        println!("Filter: {}", filter);
+    }
+    if !args.include_label.is_empty() {
+        println!("Include labels: {}", args.include_label.join(", "));
+    }
+    if !args.exclude_label.is_empty() {
+        println!("Exclude labels: {}", args.exclude_label.join(", "));
+    }
+    if !args.component.is_empty() {
+        println!("Components: {}", args.component.join(", "));
+    }
+    let selection = selection(conf, args);
+    if !selection.include_resolutions.is_empty() {
+        println!(
+            "Include resolutions: {}",
+            join_resolutions(&selection.include_resolutions)
+        );
+    }
+    if !selection.exclude_resolutions.is_empty() {
+        println!(
+            "Exclude resolutions: {}",
+            join_resolutions(&selection.exclude_resolutions)
+        );
+    }
+    for threshold in &args.fail_on {
+        println!("Fail on: {}", threshold);
+    }
+    print_config(conf);
     Ok(())
 }
 
+/// A named query in a batch file and the path its report is written to
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BatchReport {
+    name: String,
+    jql_query: String,
+    output_path: PathBuf,
+    /// Where the raw api dump of this query is written, if anywhere
+    #[serde(default)]
+    debug_jira_file: Option<PathBuf>,
+}
+
+/// The reports run by `--batch`, a yaml file with a list of `reports`
+#[derive(Debug, Deserialize)]
+struct Batch {
+    reports: Vec<BatchReport>,
+}
+
+/// Read the batch file, with the workspace applied to the query and output path of each report
 #[instrument]
-async fn gather_from_jira(
+async fn read_batch(batch_path: &Path, workspace: &workspace::Config) -> Result<Batch, Error> {
+    let contents = tokio::fs::read_to_string(batch_path)
+        .await
+        .context(FailedToReadBatchFile {
+            path: batch_path.to_string_lossy(),
+        })?;
+    let mut batch: Batch = serde_yaml::from_str(&contents).context(InvalidBatchFile {
+        path: batch_path.to_string_lossy(),
+    })?;
+    for report in &mut batch.reports {
+        report.jql_query = workspace.query(&report.jql_query);
+        report.output_path = workspace.output_path(&report.output_path);
+    }
+    Ok(batch)
+}
+
+/// Run a single time in status report, returning what it observed and the thresholds it breached
+#[instrument(skip(client, cancel))]
+async fn run_time_in_status(
     conf: &jira_config::Config,
-    should_load_from_jira_file: bool,
-    jira_load_path: &Option<PathBuf>,
-    jql: &str,
-) -> Result<Vec<core::Item>, Error> {
-    let issues = match (should_load_from_jira_file, jira_load_path) {
-        (true, Some(load_path)) => load_jira_from_file(load_path).await?,
-        (true, None) => return UnableToLoadFromJiraFile {}.fail(),
-        _ => {
-            let client = rest::new(&conf.jira_instance, &conf.username, &conf.token)
-                .context(FailedToBuildClient {})?;
-            api::get_issues_from_jql(&client, jql)
-                .await
-                .context(FailedToGetData {})?
+    client: &mut LazyClient<'_>,
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &TimeInStatusArgs,
+    output_path: &Path,
+    cancel: &cancel::Token,
+) -> Result<(thresholds::Observations, Vec<thresholds::Breach>), Error> {
+    if args.dry_run {
+        explain_time_in_status(
+            conf,
+            client,
+            config_path,
+            profile,
+            args,
+            output_path,
+            cancel,
+        )
+        .await?;
+        return Ok((thresholds::observations(&args.fail_on), Vec::new()));
+    }
+
+    // Dropping the outputs removes their staging directories, so an error that returns early
+    // doesn't leave them behind
+    let outputs = Outputs::prepare(output_path, args).await?;
+    let mut report = Report::create(conf, &outputs, args).await?;
+    let written = match &args.from_store {
+        Some(store_path) => {
+            let items = store::open(store_path)
+                .and_then(|item_store| item_store.items())
+                .context(FailedToUseStore {})?;
+            report.write_from(conf, &InMemory::new(items), "").await
         }
+        None => report_from_jira(conf, client, args, cancel, &mut report).await,
     };
+    if let Err(Error::Cancelled) = written {
+        report.finish(true).await?;
+        outputs.finish().await?;
+        return Cancelled.fail();
+    }
+    let incomplete = match written {
+        Err(err @ Error::IncompleteReport { .. }) => Some(err),
+        written => {
+            written?;
+            None
+        }
+    };
+    let observations = report.finish(false).await?;
+    outputs.finish().await?;
+    if let Some(err) = incomplete {
+        return Err(err);
+    }
+    let breaches = thresholds::check(&args.fail_on, &observations);
+    Ok((observations, breaches))
+}
 
-    if let Some(jira_path) = jira_load_path {
-        write_json_file(jira_path, &issues).await?;
+/// Run the report for each query of the batch in turn. They share the client, so the
+/// credentials are only resolved once. The breached thresholds are prefixed with the name of the
+/// report that breached them, the observations are merged over every report.
+#[instrument(skip(client, cancel))]
+async fn run_batch(
+    conf: &jira_config::Config,
+    client: &mut LazyClient<'_>,
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &TimeInStatusArgs,
+    batch: Batch,
+    cancel: &cancel::Token,
+) -> Result<(thresholds::Observations, Vec<String>), Error> {
+    let mut observations = thresholds::observations(&args.fail_on);
+    let mut breaches = Vec::new();
+    for batch_report in batch.reports {
+        let name = batch_report.name;
+        info!("running report {}", name);
+        let report_args = TimeInStatusArgs {
+            jql_query: Some(batch_report.jql_query),
+            output_path: Some(batch_report.output_path.clone()),
+            debug_jira_file: batch_report.debug_jira_file,
+            batch: None,
+            ..args.clone()
+        };
+        let (report_observations, report_breaches) = run_time_in_status(
+            conf,
+            client,
+            config_path,
+            profile,
+            &report_args,
+            &batch_report.output_path,
+            cancel,
+        )
+        .await?;
+        observations.merge(report_observations);
+        breaches.extend(
+            report_breaches
+                .iter()
+                .map(|breach| format!("{}: {}", name, breach)),
+        );
     }
+    Ok((observations, breaches))
+}
 
-    let items = nativetocore::translate(conf, &issues).context(FailedToTransformData {})?;
+/// Run the report, or each report of the batch, and return its headline numbers. The options of
+/// the command have the workspace applied already, the reports of the batch file get it here.
+#[instrument(skip(workspace, cancel))]
+pub async fn do_time_in_status(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &TimeInStatusArgs,
+    workspace: &workspace::Config,
+    cancel: &cancel::Token,
+) -> Result<notify::Headline, Error> {
+    if feature_flags::is_enabled(feature_flags::TimeInStatus) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let mut client = LazyClient::new(&conf);
 
-    Ok(items)
+        let (observations, breaches) = match (&args.batch, &args.output_path) {
+            (Some(batch_path), _) => {
+                let batch = read_batch(batch_path, workspace).await?;
+                run_batch(
+                    &conf,
+                    &mut client,
+                    config_path,
+                    profile,
+                    args,
+                    batch,
+                    cancel,
+                )
+                .await?
+            }
+            (None, Some(output_path)) => {
+                let (observations, breaches) = run_time_in_status(
+                    &conf,
+                    &mut client,
+                    config_path,
+                    profile,
+                    args,
+                    output_path,
+                    cancel,
+                )
+                .await?;
+                (
+                    observations,
+                    breaches.iter().map(ToString::to_string).collect(),
+                )
+            }
+            (None, None) => return MissingOutputPath {}.fail(),
+        };
+
+        fail_on_breaches(&breaches)?;
+        Ok(notify::Headline {
+            issues: observations.entries(),
+            p85_cycle_time: observations.cycle_time_percentile(85),
+        })
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        FeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Fail with [`Error::ThresholdsBreached`] when any threshold was breached, logging each of them
+fn fail_on_breaches(breaches: &[String]) -> Result<(), Error> {
+    if breaches.is_empty() {
+        return Ok(());
+    }
+    for breach in breaches {
+        error!("Threshold breached: {}", breach);
+    }
+    ThresholdsBreached {
+        breaches: breaches.join(", "),
+    }
+    .fail()
+}
+
+/// Check the thresholds of a report once every entry of it was observed
+fn check_thresholds(
+    thresholds: &[thresholds::Threshold],
+    observations: &thresholds::Observations,
+) -> Result<(), Error> {
+    let breaches: Vec<_> = thresholds::check(thresholds, observations)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    fail_on_breaches(&breaches)
+}
+
+/// Pull the issues for the query that were updated since the last sync and upsert them into the
+/// store. The date used is a day before the last sync, to be certain that no updates are missed
+/// because jira interprets jql dates in the timezone of the user. When interrupted, the pages
+/// already pulled stay in the store but the sync is not recorded, so the next one starts over.
+#[instrument(skip(cancel))]
+pub async fn do_sync(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &SyncArgs,
+    cancel: &cancel::Token,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::Store) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let mut item_store = store::open(&args.store_path).context(FailedToUseStore {})?;
+
+        let sync_started = Utc::now();
+        let jql = match item_store
+            .last_sync(&args.jql_query)
+            .context(FailedToUseStore {})?
+        {
+            Some(last_sync) => format!(
+                "({}) AND updated >= \"{}\"",
+                args.jql_query,
+                (last_sync - Duration::days(1)).format("%Y-%m-%d")
+            ),
+            None => args.jql_query.clone(),
+        };
+
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses))
+                .with_cancellation(cancel.clone())
+                .with_expand(rendered(args.rendered_fields));
+        let mut pages = tracker.items(&jql);
+        while let Some(items) = next_items(&mut pages).await? {
+            item_store
+                .upsert_items(&items)
+                .context(FailedToUseStore {})?;
+        }
+
+        item_store
+            .set_last_sync(&args.jql_query, &sync_started)
+            .context(FailedToUseStore {})
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        StoreFeatureFlagNotEnabled.fail()
+    }
 }
 
+/// Write the status rollup of the children of every epic matching the query
 #[instrument]
-pub async fn write_records_to_csv(
-    out_file: &Path,
-    entries: &[times_in_flight::Entry<'_>],
+pub async fn do_epic_report(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &EpicReportArgs,
 ) -> Result<(), Error> {
-    let mut item_writer = csv_async::AsyncSerializer::from_writer(
-        File::create(out_file)
+    if feature_flags::is_enabled(feature_flags::EpicReport) {
+        let conf = jira_config::read(config_path, profile)
             .await
-            .context(FailedToCreateCSVFile {})?,
-    );
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses))
+                .with_expand(rendered(args.rendered_fields));
+
+        let mut epic_writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut observations = thresholds::observations(&args.fail_on);
+        let mut pages = tracker.items(&args.jql_query);
+        while let Some(epics) = pages.try_next().await.map_err(from_tracker)? {
+            for epic in &epics {
+                let children = tracker
+                    .children(&epic.native_id)
+                    .await
+                    .map_err(from_tracker)?;
+                let entry = epics::rollup(&conf.jira_instance, epic, &children);
+                observations.observe(&entry);
+                epic_writer
+                    .serialize(entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+
+        epic_writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+        check_thresholds(&args.fail_on, &observations)
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        EpicReportFeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Write the per issue estimate accuracy of every resolved issue matching the query, followed by
+/// the aggregate accuracy
+#[instrument]
+pub async fn do_estimate_accuracy(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &EstimateAccuracyArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::EstimateAccuracy) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses))
+                .include_worklogs();
 
-    for entry in entries {
-        item_writer
-            .serialize(&entry)
+        let mut entry_writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut totals = estimate_accuracy::Totals::default();
+        let mut observations = thresholds::observations(&args.fail_on);
+        let mut pages = tracker.items(&args.jql_query);
+        while let Some(items) = pages.try_next().await.map_err(from_tracker)? {
+            for entry in estimate_accuracy::calculate(&conf.jira_instance, &items) {
+                totals.add(&entry);
+                observations.observe(&entry);
+                entry_writer
+                    .serialize(&entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        entry_writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+
+        let summary_path = utils::sibling_path(&args.output_path, "summary");
+        let mut summary_writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&summary_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        summary_writer
+            .serialize(totals.summary())
             .await
             .context(FailedToWriteToCSVFile {})?;
+        summary_writer.flush().await.context(FailedToWriteFile {
+            path: summary_path.to_string_lossy(),
+        })?;
+        check_thresholds(&args.fail_on, &observations)
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        EstimateAccuracyFeatureFlagNotEnabled.fail()
     }
+}
 
-    Ok(())
+/// Write every change to the chosen fields of the issues matching the query, one row per change
+#[instrument]
+pub async fn do_field_history(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &FieldHistoryArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::FieldHistory) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let fields = if args.field.is_empty() {
+            &conf.history_fields
+        } else {
+            &args.field
+        };
+        if fields.is_empty() {
+            return NoHistoryFields.fail();
+        }
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker = JiraTracker::new(&conf, &client, nativetocore::UnmappedStatus::Fail);
+
+        let mut writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut pages = tracker.issues(&args.jql_query);
+        while let Some(issues) = pages.try_next().await.map_err(from_tracker)? {
+            for entry in field_history::entries(fields, &issues) {
+                writer
+                    .serialize(&entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        FieldHistoryFeatureFlagNotEnabled.fail()
+    }
 }
 
+/// Sum the time in status of the issues matching the query by their epic, one row per epic
 #[instrument]
-pub async fn do_time_in_status(
+pub async fn do_rollup(
     config_path: &Option<PathBuf>,
-    out_path: &Path,
-    should_load_jira_from_file: bool,
-    jira_load_path: &Option<PathBuf>,
-    jql: &str,
+    profile: &str,
+    args: &RollupArgs,
 ) -> Result<(), Error> {
-    if feature_flags::is_enabled(feature_flags::TimeInStatus) {
-        let conf = jira_config::read(config_path).await.context(GetConfig {})?;
+    if feature_flags::is_enabled(feature_flags::Rollup) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses));
 
-        let items =
-            gather_from_jira(&conf, should_load_jira_from_file, jira_load_path, jql).await?;
+        // The epic of a sub-task is found through its story, so every item is needed before any
+        // of them can be summed
+        let items = all_items(&tracker, &args.jql_query).await?;
 
-        let resolved_data = times_in_flight::calculate(&conf.jira_instance, &items);
+        let calendar = Calendar::new(conf.working_week.as_ref(), conf.reporting_timezone.as_ref());
+        let mut writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut observations = thresholds::observations(&args.fail_on);
+        for entry in rollup::rollup(&conf.jira_instance, &calendar, &items) {
+            observations.observe(&entry);
+            writer
+                .serialize(&entry)
+                .await
+                .context(FailedToWriteToCSVFile {})?;
+        }
+        writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+        check_thresholds(&args.fail_on, &observations)
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        RollupFeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Write every status transition of the issues matching the query, with who made it, one row per
+/// transition
+#[instrument]
+pub async fn do_transitions(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &TransitionsArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::Transitions) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses));
 
-        write_records_to_csv(out_path, &resolved_data).await?;
+        let mut writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut pages = tracker.items(&args.jql_query);
+        while let Some(items) = pages.try_next().await.map_err(from_tracker)? {
+            for entry in transitions::entries(&items) {
+                writer
+                    .serialize(&entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        TransitionsFeatureFlagNotEnabled.fail()
+    }
+}
 
+/// Write the work in progress, throughput and cycle time of the issues matching the query for
+/// each week, and whether they agree with Little's Law
+#[instrument]
+pub async fn do_flow_metrics(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &FlowMetricsArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::FlowMetrics) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker =
+            JiraTracker::new(&conf, &client, unmapped_status(args.skip_unmapped_statuses));
+
+        let mut flow = flow::Flow::default();
+        let mut pages = tracker.items(&args.jql_query);
+        while let Some(items) = pages.try_next().await.map_err(from_tracker)? {
+            for item in &items {
+                flow.observe(item);
+            }
+        }
+
+        let mut writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let calendar = Calendar::new(conf.working_week.as_ref(), conf.reporting_timezone.as_ref());
+        let mut observations = thresholds::observations(&args.fail_on);
+        for week in flow.weeks(&calendar, Utc::now(), args.max_deviation) {
+            if week.inconsistent {
+                warn!(
+                    "The week of {} does not follow Little's Law, the average wip is {:.1} but \
+                     {:.1} was predicted",
+                    week.week,
+                    week.average_wip,
+                    week.predicted_wip.unwrap_or_default()
+                );
+            }
+            observations.observe(&week);
+            writer
+                .serialize(&week)
+                .await
+                .context(FailedToWriteToCSVFile {})?;
+        }
+        writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+        check_thresholds(&args.fail_on, &observations)
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        FlowMetricsFeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Write how long each issue matching the query waited for its first response, and how many
+/// comments it has, along with a summary over all of them
+#[instrument]
+pub async fn do_responsiveness(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &ResponsivenessArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::Responsiveness) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker = JiraTracker::new(&conf, &client, nativetocore::UnmappedStatus::Fail)
+            .with_expand(rendered(args.rendered_fields))
+            .include_comments();
+        let calendar = Calendar::new(conf.working_week.as_ref(), conf.reporting_timezone.as_ref());
+
+        let mut entry_writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&args.output_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        let mut totals = responsiveness::Totals::default();
+        let mut observations = thresholds::observations(&args.fail_on);
+        let mut pages = tracker.issues(&args.jql_query);
+        while let Some(issues) = pages.try_next().await.map_err(from_tracker)? {
+            for entry in responsiveness::calculate(&conf.jira_instance, &calendar, &issues) {
+                totals.add(&entry);
+                observations.observe(&entry);
+                entry_writer
+                    .serialize(&entry)
+                    .await
+                    .context(FailedToWriteToCSVFile {})?;
+            }
+        }
+        entry_writer.flush().await.context(FailedToWriteFile {
+            path: args.output_path.to_string_lossy(),
+        })?;
+
+        let summary_path = utils::sibling_path(&args.output_path, "summary");
+        let mut summary_writer = csv_async::AsyncSerializer::from_writer(
+            File::create(&summary_path)
+                .await
+                .context(FailedToCreateCSVFile {})?,
+        );
+        summary_writer
+            .serialize(totals.summary())
+            .await
+            .context(FailedToWriteToCSVFile {})?;
+        summary_writer.flush().await.context(FailedToWriteFile {
+            path: summary_path.to_string_lossy(),
+        })?;
+        check_thresholds(&args.fail_on, &observations)
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        ResponsivenessFeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Write what changed between two 'debug-jira-file' snapshots of the same query. Nothing is
+/// pulled from jira, so neither a config nor a connection is needed.
+#[instrument]
+pub async fn do_diff_dumps(args: &DiffDumpsArgs) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::DiffDumps) {
+        let old = load_jira_from_file(&args.old).await?;
+        let new = load_jira_from_file(&args.new).await?;
+        let entries = dump_diff::diff(&old.issues, &new.issues);
+        info!(
+            "{} changes from {} to {}",
+            entries.len(),
+            args.old.display(),
+            args.new.display()
+        );
+        match args.output_format {
+            ChangeLogFormat::Csv => {
+                let mut writer = csv_async::AsyncSerializer::from_writer(
+                    File::create(&args.output_path)
+                        .await
+                        .context(FailedToCreateCSVFile {})?,
+                );
+                for entry in &entries {
+                    writer
+                        .serialize(entry)
+                        .await
+                        .context(FailedToWriteToCSVFile {})?;
+                }
+                writer.flush().await.context(FailedToWriteFile {
+                    path: args.output_path.to_string_lossy(),
+                })
+            }
+            ChangeLogFormat::Json => {
+                let contents = serde_json::to_vec_pretty(&entries)
+                    .context(FailedToConvertInternalStructureToJson {})?;
+                tokio::fs::write(&args.output_path, contents)
+                    .await
+                    .context(FailedToWriteFile {
+                        path: args.output_path.to_string_lossy(),
+                    })
+            }
+        }
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        DiffDumpsFeatureFlagNotEnabled.fail()
+    }
+}
+
+/// Print a starting `status-mapping`, `resolution-mapping` and `issue-types` for the statuses,
+/// resolutions and issue types of the issues matching the query. Only the connection settings of
+/// the config are needed.
+#[instrument]
+pub async fn do_suggest_config(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &SuggestConfigArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::SuggestConfig) {
+        let conf = jira_config::read_connection(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let tracker = JiraTracker::new(&conf, &client, nativetocore::UnmappedStatus::Fail);
+
+        let mut suggestions = suggest::Suggestions::default();
+        let mut pages = tracker.issues(&args.jql_query);
+        while let Some(issues) = pages.try_next().await.map_err(from_tracker)? {
+            for detail in &issues {
+                suggestions.observe(detail);
+            }
+        }
+        print!("{}", suggestions);
         Ok(())
     } else {
         error!("This command is a WIP, you must set the feature flag to continue");
-        FeatureFlagNotEnabled.fail()
+        SuggestConfigFeatureFlagNotEnabled.fail()
     }
 }
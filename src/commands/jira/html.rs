@@ -0,0 +1,254 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Html Time In Status Report
+//!
+//! Renders the time in status entries as a standalone html page. The page has no external
+//! dependencies, the table can be sorted by clicking a column header and each row has a bar
+//! showing how the time of the item was split between the statuses.
+//!
+//! Like the csv report the page is written a row at a time as the entries are produced.
+use crate::lib::jira::times_in_flight;
+use snafu::{ResultExt, Snafu};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not create html report {}: {}", path.display(), source))]
+    CouldNotCreateReport {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write html report {}: {}", path.display(), source))]
+    CouldNotWriteReport {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+const HEADER: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Time In Status</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 4px 8px; text-align: left; }
+th { cursor: pointer; background: #f4f4f4; position: sticky; top: 0; }
+td.number { text-align: right; }
+.bar { display: flex; width: 200px; height: 12px; background: #eee; }
+.bar span { display: block; height: 100%; }
+.todo { background: #9e9e9e; }
+.ready { background: #64b5f6; }
+.in-dev { background: #1976d2; }
+.in-test { background: #7b1fa2; }
+.waiting { background: #ffa000; }
+.completed { background: #388e3c; }
+.legend span { display: inline-block; padding: 2px 8px; margin-right: 4px; color: #fff; }
+</style>
+</head>
+<body>
+<h1>Time In Status</h1>
+<p class="legend">
+<span class="todo">To Do</span>
+<span class="ready">Ready</span>
+<span class="in-dev">In Dev</span>
+<span class="in-test">In Test</span>
+<span class="waiting">Waiting</span>
+<span class="completed">Completed</span>
+</p>
+<table id="report">
+<thead>
+<tr>
+<th>Name</th><th>Description</th>
+<th>To Do</th><th>Ready</th><th>In Dev</th><th>In Test</th><th>Waiting</th><th>Completed</th>
+<th>Blocked</th><th>First Estimate</th><th>Status</th><th>Resolution</th>
+<th>Assignee</th><th>Team</th><th>Created</th><th>Resolved</th><th>Lead Time</th>
+<th>Time In Status</th>
+</tr>
+</thead>
+<tbody>
+"#;
+
+const FOOTER: &str = r##"</tbody>
+</table>
+<script>
+document.querySelectorAll("#report th").forEach(function (header, column) {
+  var ascending = true;
+  header.addEventListener("click", function () {
+    var body = document.querySelector("#report tbody");
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function (left, right) {
+      var a = left.cells[column].innerText;
+      var b = right.cells[column].innerText;
+      var order = (a === "" || b === "" || isNaN(a) || isNaN(b))
+        ? a.localeCompare(b)
+        : parseFloat(a) - parseFloat(b);
+      return ascending ? order : -order;
+    });
+    ascending = !ascending;
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});
+</script>
+</body>
+</html>
+"##;
+
+/// Escape text so that it can be placed in an html element or attribute
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+fn days_cell(row: &mut String, days: Option<f64>) {
+    match days {
+        Some(days) => {
+            let _ = write!(row, "<td class=\"number\">{:.2}</td>", days);
+        }
+        None => row.push_str("<td class=\"number\"></td>"),
+    }
+}
+
+fn text_cell(row: &mut String, text: Option<&str>) {
+    let _ = write!(row, "<td>{}</td>", escape(text.unwrap_or("")));
+}
+
+/// A bar with one segment per status, each as wide as its share of the total days
+fn bar_cell(row: &mut String, entry: &times_in_flight::Entry) {
+    let statuses = [
+        ("todo", entry.todo),
+        ("ready", entry.ready),
+        ("in-dev", entry.in_dev),
+        ("in-test", entry.in_test),
+        ("waiting", entry.waiting),
+        ("completed", entry.completed),
+    ];
+    let total: f64 = statuses.iter().map(|(_, days)| days).sum();
+    row.push_str("<td><div class=\"bar\">");
+    if total > 0.0 {
+        for (class, days) in &statuses {
+            let _ = write!(
+                row,
+                "<span class=\"{}\" style=\"width: {:.1}%\" title=\"{:.2} days\"></span>",
+                class,
+                days / total * 100.0,
+                days
+            );
+        }
+    }
+    row.push_str("</div></td>");
+}
+
+fn render_row(entry: &times_in_flight::Entry) -> String {
+    let mut row = String::from("<tr>");
+    let _ = write!(
+        row,
+        "<td><a href=\"{}\">{}</a></td>",
+        escape(&entry.url),
+        escape(entry.name)
+    );
+    text_cell(&mut row, Some(entry.description));
+    for days in &[
+        entry.todo,
+        entry.ready,
+        entry.in_dev,
+        entry.in_test,
+        entry.waiting,
+        entry.completed,
+        entry.blocked_days,
+    ] {
+        days_cell(&mut row, Some(*days));
+    }
+    days_cell(&mut row, entry.first_estimate);
+    text_cell(&mut row, Some(&entry.status.to_string()));
+    text_cell(&mut row, Some(&entry.resolution.to_string()));
+    text_cell(&mut row, entry.assignee);
+    text_cell(&mut row, entry.team);
+    text_cell(
+        &mut row,
+        entry
+            .created
+            .map(|created| created.format("%Y-%m-%d").to_string())
+            .as_deref(),
+    );
+    text_cell(
+        &mut row,
+        entry
+            .resolved
+            .map(|resolved| resolved.format("%Y-%m-%d").to_string())
+            .as_deref(),
+    );
+    days_cell(&mut row, entry.calendar_lead_time_days);
+    bar_cell(&mut row, entry);
+    row.push_str("</tr>\n");
+    row
+}
+
+/// Writes the html report a row at a time
+#[derive(Debug)]
+pub struct Writer {
+    path: PathBuf,
+    file: File,
+}
+
+/// Create the report at `path` and write everything that comes before the rows
+pub async fn create(path: &Path) -> Result<Writer, Error> {
+    let mut file = File::create(path)
+        .await
+        .context(CouldNotCreateReport { path })?;
+    file.write_all(HEADER.as_bytes())
+        .await
+        .context(CouldNotWriteReport { path })?;
+    Ok(Writer {
+        path: path.to_path_buf(),
+        file,
+    })
+}
+
+impl Writer {
+    /// Add a row for `entry` to the table
+    pub async fn write(&mut self, entry: &times_in_flight::Entry<'_>) -> Result<(), Error> {
+        self.file
+            .write_all(render_row(entry).as_bytes())
+            .await
+            .context(CouldNotWriteReport { path: &self.path })
+    }
+
+    /// Write everything that comes after the rows
+    pub async fn finish(mut self) -> Result<(), Error> {
+        self.file
+            .write_all(FOOTER.as_bytes())
+            .await
+            .context(CouldNotWriteReport { path: &self.path })?;
+        self.file
+            .flush()
+            .await
+            .context(CouldNotWriteReport { path: &self.path })
+    }
+}
@@ -0,0 +1,256 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Webhook Listener
+//!
+//! Runs a long lived http server that accepts jira webhooks and writes the updated items to a
+//! local issue store, replacing the previous version of each item.
+//!
+//! With a `webhook-secret` in the config only webhooks signed with it are accepted, the rest are
+//! answered with 401. A webhook that can't be parsed or translated is answered with a 4xx, as
+//! sending it again won't help, while one that fails because jira or the store did is answered
+//! with a 5xx so that jira retries it.
+use crate::configs::jira as jira_config;
+use crate::error_output::{Category, Coded};
+use crate::feature_flags;
+use crate::lib::jira::{api, nativetocore, webhook};
+use crate::lib::rest;
+use crate::lib::store;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use snafu::{ensure, ResultExt, Snafu};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+use tokio::sync::Mutex;
+use tracing::{error, info, instrument, warn};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not get config: {}", source))]
+    GetConfig { source: jira_config::Error },
+    #[snafu(display("Could not build rest client {}", source))]
+    FailedToBuildClient { source: rest::Error },
+    #[snafu(display("Webhook server failed: {}", source))]
+    ServerFailed { source: hyper::Error },
+    #[snafu(display("Could not get the statuses of the instance: {}", source))]
+    CouldNotGetStatuses { source: api::Error },
+    #[snafu(display("Could not read request body: {}", source))]
+    CouldNotReadBody { source: hyper::Error },
+    #[snafu(display("Could not process webhook: {}", source))]
+    CouldNotProcessWebhook { source: webhook::Error },
+    #[snafu(display("Could not use the issue store: {}", source))]
+    CouldNotUseStore { source: store::Error },
+    #[snafu(display("Could not resolve the webhook secret: {}", source))]
+    CouldNotResolveSecret { source: rest::Error },
+    #[snafu(display("The webhook is not signed with the webhook secret"))]
+    InvalidSignature,
+    #[snafu(display("Feature flag 'serve' is not enabled"))]
+    FeatureFlagNotEnabled,
+}
+
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::GetConfig { .. } => "serve-get-config",
+            Error::FailedToBuildClient { .. } => "serve-build-client",
+            Error::ServerFailed { .. } => "serve-server-failed",
+            Error::CouldNotGetStatuses { .. } => "serve-get-statuses",
+            Error::CouldNotReadBody { .. } => "serve-read-body",
+            Error::CouldNotProcessWebhook { .. } => "serve-process-webhook",
+            Error::CouldNotUseStore { .. } => "serve-use-store",
+            Error::CouldNotResolveSecret { .. } => "serve-resolve-secret",
+            Error::InvalidSignature => "serve-invalid-signature",
+            Error::FeatureFlagNotEnabled => "serve-feature-flag-not-enabled",
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            Error::GetConfig { .. }
+            | Error::FailedToBuildClient { .. }
+            | Error::CouldNotResolveSecret { .. } => Category::Config,
+            Error::CouldNotProcessWebhook { .. } | Error::InvalidSignature => Category::Data,
+            Error::CouldNotGetStatuses { .. } => Category::Remote,
+            Error::ServerFailed { .. }
+            | Error::CouldNotReadBody { .. }
+            | Error::CouldNotUseStore { .. } => Category::Io,
+            Error::FeatureFlagNotEnabled => Category::Usage,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ServeArgs {
+    /// The address to listen for webhooks on
+    #[structopt(short, long, default_value = "127.0.0.1:8080")]
+    pub address: SocketAddr,
+    /// The issue store that updated items are written to. It is created if it does not exist.
+    #[structopt(short, long, parse(from_os_str))]
+    pub store_path: PathBuf,
+}
+
+#[derive(Debug)]
+struct State {
+    conf: jira_config::Config,
+    /// Pulled once at start up, the statuses of the instance rarely change
+    categories: nativetocore::StatusCategories,
+    client: rest::Client,
+    item_store: Mutex<store::Store>,
+    /// The secret the webhooks must be signed with, when the config has one
+    secret: Option<rest::Secret>,
+}
+
+async fn process_webhook(state: &State, request: Request<Body>) -> Result<(), Error> {
+    let signature = request
+        .headers()
+        .get(webhook::SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .context(CouldNotReadBody {})?;
+    if let Some(secret) = &state.secret {
+        let signed = signature.map_or(false, |signature| {
+            webhook::verify_signature(secret.expose().as_bytes(), &body, &signature)
+        });
+        ensure!(signed, InvalidSignature);
+    }
+    let event = webhook::parse(&body).context(CouldNotProcessWebhook {})?;
+    if let Some(item) = webhook::to_item(&state.conf, &state.categories, &state.client, event)
+        .await
+        .context(CouldNotProcessWebhook {})?
+    {
+        info!("storing update for {}", item.native_id);
+        state
+            .item_store
+            .lock()
+            .await
+            .upsert_items(&[item])
+            .context(CouldNotUseStore {})?;
+    }
+    Ok(())
+}
+
+fn respond(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// The status a webhook that could not be processed is answered with. Jira retries on a 5xx, so
+/// those are kept for failures that may pass when it does.
+fn failure_status(err: &Error) -> StatusCode {
+    match err {
+        Error::InvalidSignature => StatusCode::UNAUTHORIZED,
+        Error::CouldNotReadBody { .. }
+        | Error::CouldNotProcessWebhook {
+            source: webhook::Error::InvalidPayload { .. },
+        } => StatusCode::BAD_REQUEST,
+        Error::CouldNotProcessWebhook {
+            source: webhook::Error::CouldNotTranslateIssue { .. },
+        } => StatusCode::UNPROCESSABLE_ENTITY,
+        Error::CouldNotProcessWebhook {
+            source: webhook::Error::CouldNotGetChangelog { .. },
+        } => StatusCode::BAD_GATEWAY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn handle(state: Arc<State>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(respond(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    match process_webhook(&state, request).await {
+        Ok(()) => Ok(respond(StatusCode::OK)),
+        Err(err) => {
+            let status = failure_status(&err);
+            if status.is_client_error() {
+                error!("rejected webhook: {}", err);
+            } else {
+                error!("failed to handle webhook: {}", err);
+            }
+            Ok(respond(status))
+        }
+    }
+}
+
+#[instrument]
+pub async fn do_serve(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    args: &ServeArgs,
+) -> Result<(), Error> {
+    if feature_flags::is_enabled(feature_flags::Serve) {
+        let conf = jira_config::read(config_path, profile)
+            .await
+            .context(GetConfig {})?;
+        let client = rest::new(
+            &conf.jira_instance,
+            &conf.username,
+            &conf.token,
+            &conf.retry,
+        )
+        .await
+        .context(FailedToBuildClient {})?;
+        let categories = if conf.infer_status_categories {
+            let statuses = api::get_statuses(&client)
+                .await
+                .context(CouldNotGetStatuses {})?;
+            nativetocore::StatusCategories::new(&conf, &statuses)
+        } else {
+            nativetocore::StatusCategories::default()
+        };
+        let secret = if let Some(secret) = &conf.webhook_secret {
+            Some(
+                rest::resolve_token(secret)
+                    .await
+                    .context(CouldNotResolveSecret {})?,
+            )
+        } else {
+            warn!(
+                "There is no webhook-secret in the config, webhooks are accepted from anyone who \
+                 can reach {}",
+                args.address
+            );
+            None
+        };
+        let item_store = store::open(&args.store_path).context(CouldNotUseStore {})?;
+        let state = Arc::new(State {
+            conf,
+            categories,
+            client,
+            item_store: Mutex::new(item_store),
+            secret,
+        });
+
+        let make_service = make_service_fn(move |_connection| {
+            let state = state.clone();
+            let service = service_fn(move |request| handle(state.clone(), request));
+            async move { Ok::<_, Infallible>(service) }
+        });
+
+        info!("listening for webhooks on {}", args.address);
+        Server::bind(&args.address)
+            .serve(make_service)
+            .await
+            .context(ServerFailed {})
+    } else {
+        error!("This command is a WIP, you must set the feature flag to continue");
+        FeatureFlagNotEnabled.fail()
+    }
+}
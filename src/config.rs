@@ -12,23 +12,44 @@
 //
 //  You should have received a copy of the GNU General Public License
 //  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Config Directory
+//!
+//! Where the configs of lectev are kept, `~/.config/lectev`, which is created, readable only by
+//! its owner, the first time it is needed.
 
 use crate::utils;
 use snafu::{ResultExt, Snafu};
 use std::path::PathBuf;
 
+/// The ways finding the config directory can fail
 #[derive(Debug, Snafu)]
 pub enum Error {
+    /// The home directory could not be found
     #[snafu(display("Error expanding path to $HOME dir: {}", source))]
     FailedToGetPath {
+        /// The error of expanding `~`
         source: shellexpand::LookupError<std::env::VarError>,
     },
+    /// The config directory could not be created
     #[snafu(display("Could not create directory: {}", source))]
-    FailedToCreateDirectory { source: std::io::Error },
+    FailedToCreateDirectory {
+        /// The error of creating it
+        source: std::io::Error,
+    },
+    /// The permissions of the config directory could not be restricted
     #[snafu(display("Could set restricted permissions in directory: {}", source))]
-    CouldntSetRestrictedPermissions { source: utils::Error },
+    CouldntSetRestrictedPermissions {
+        /// The error of setting them
+        source: utils::Error,
+    },
 }
 
+/// The config directory, created if it doesn't exist yet
+///
+/// # Errors
+///
+/// Fails when the home directory can't be found, or the directory can't be created or its
+/// permissions restricted.
 pub async fn dir() -> Result<PathBuf, Error> {
     let config_dir_path = PathBuf::from(
         shellexpand::full("~/.config/lectev")
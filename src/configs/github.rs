@@ -0,0 +1,124 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Provides configuration for GitHub commands
+//!
+//! The config is a single yaml file, `github.yml` in the lectev config directory by default. For
+//! example:
+//!
+//! ```yaml
+//! username: octocat
+//! token-env: GITHUB_TOKEN
+//! label-mapping:
+//!   ready: Ready
+//!   in progress: InDev
+//!   in review: InTest
+//!   blocked: Waiting
+//! operational-labels:
+//!   - bug
+//! ```
+use crate::config;
+use crate::lib::jira::core::ItemStatus;
+use crate::lib::rest::{RetryPolicy, TokenSource};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use url::Url;
+
+/// The ways reading the github config can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The config file could not be opened
+    #[snafu(display("Could not open config from {}: {}", filename.display(), source))]
+    OpenConfig {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of opening it
+        source: std::io::Error,
+    },
+    /// The config file is not a valid github config
+    #[snafu(display("Could not parse config from {}: {}", filename.display(), source))]
+    ParseYaml {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of parsing it
+        source: serde_yaml::Error,
+    },
+    /// The directory of the configs could not be found
+    #[snafu(display("Couldn't get config dir: {}", source))]
+    CouldntGetConfigDir {
+        /// Why it could not be found
+        source: config::Error,
+    },
+}
+
+fn default_api_url() -> Url {
+    Url::parse("https://api.github.com/").expect("the default github api url is valid")
+}
+
+/// How to connect to github and how its issues map to items
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// The GitHub api, only set for GitHub Enterprise, e.g. `https://github.example.com/api/v3/`
+    #[serde(default = "default_api_url")]
+    pub api_url: Url,
+    /// The user to connect as
+    pub username: String,
+    /// One of `token`, `token-env` or `token-command`
+    #[serde(flatten)]
+    pub token: TokenSource,
+    /// Maps the name of a label to the status an open issue with that label is in
+    pub label_mapping: HashMap<String, ItemStatus>,
+    /// Issues with any of these labels are operational rather than features
+    #[serde(default)]
+    pub operational_labels: Vec<String>,
+    /// How long requests to github are retried, see [`RetryPolicy`]
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+/// The path of the github config, `config_path` when it is given, else `github.yml` in the config
+/// directory
+///
+/// # Errors
+///
+/// Fails when the config directory can't be found.
+pub async fn resolve_config_path(config_path: &Option<PathBuf>) -> Result<PathBuf, Error> {
+    match config_path {
+        Some(resolved_config_path) => Ok(resolved_config_path.clone()),
+        None => {
+            let mut resolved_config_path = config::dir().await.context(CouldntGetConfigDir {})?;
+
+            resolved_config_path.push("github");
+            resolved_config_path.set_extension("yml");
+            Ok(resolved_config_path)
+        }
+    }
+}
+
+/// Read the github config
+///
+/// # Errors
+///
+/// Fails when the config can't be found, read or parsed.
+pub async fn read(opt_config_path: &Option<PathBuf>) -> Result<Config, Error> {
+    let path = resolve_config_path(opt_config_path).await?;
+    let contents = fs::read_to_string(&path).await.context(OpenConfig {
+        filename: path.clone(),
+    })?;
+    serde_yaml::from_str(&contents).context(ParseYaml { filename: path })
+}
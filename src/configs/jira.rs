@@ -17,50 +17,316 @@
 //!
 //! This module provides for configuration of the system using serde structs and
 //! yaml
+//!
+//! The config file is a map of profile names to [`Config`]s, so that a single file can describe
+//! several Jira instances. For backwards compatibility a file that contains a single bare
+//! [`Config`] is treated as if it were the [`DEFAULT_PROFILE`].
+//!
+//! `${NAME}` in any string value of the file is replaced with the value of the environment
+//! variable `NAME`, so that a config without the username or token can be committed and those
+//! injected when it runs. `$${` is a literal `${`. The file is parsed first, so a variable can't
+//! add keys or change the structure of the config, and references in comments or keys are left
+//! alone.
+//!
+//! Every config that is read is also validated. Mistakes serde can't catch, such as misspelled
+//! keys or a status that nothing maps to, are reported as [`Problem`]s. Problems that would make
+//! the reports wrong fail the read, the rest are logged as warnings.
 use crate::config;
-use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::calendar::WorkingWeek;
+use crate::lib::jira::adf;
+use crate::lib::jira::core::{ItemStatus, LinkType, Resolution};
 use crate::lib::jira::native::CustomFieldName;
+use crate::lib::jira::nativetocore;
+use crate::lib::rest::{RetryPolicy, TokenSource};
+use crate::lib::timezone::Timezone;
 use serde::{Deserialize, Serialize};
-use snafu::{ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use tokio::fs;
+use tracing::warn;
 use url::Url;
 
+/// The ways reading the jira config can fail
 #[derive(Debug, Snafu)]
 pub enum Error {
+    /// The config file could not be opened
     #[snafu(display("Could not open config from {}: {}", filename.display(), source))]
     OpenConfig {
+        /// The path of the config
         filename: PathBuf,
+        /// The error of opening it
         source: std::io::Error,
     },
+    /// The config file is not valid yaml
     #[snafu(display("Could not parse config from {}: {}", filename.display(), source))]
     ParseYaml {
+        /// The path of the config
         filename: PathBuf,
+        /// The error of parsing it
         source: serde_yaml::Error,
     },
+    /// The directory of the configs could not be found
     #[snafu(display("Couldn't get config dir: {}", source))]
-    CouldntGetConfigDir { source: config::Error },
+    CouldntGetConfigDir {
+        /// Why it could not be found
+        source: config::Error,
+    },
+    /// The config file has no profile with the name
+    #[snafu(display("No profile named {} in config {}", profile, filename.display()))]
+    MissingProfile {
+        /// The profile that was asked for
+        profile: String,
+        /// The path of the config
+        filename: PathBuf,
+    },
+    /// A key of the config has a value of the wrong type
+    #[snafu(display("Invalid value for {} in config {}: {}", key, filename.display(), source))]
+    InvalidValue {
+        /// The key, with the keys it is nested in
+        key: String,
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of reading the value
+        source: serde_yaml::Error,
+    },
+    /// A `${NAME}` in the config names an environment variable that is not set
+    #[snafu(display(
+        "Could not read environment variable {}, referenced by {} in {}: {}",
+        name,
+        key,
+        filename.display(),
+        source
+    ))]
+    MissingEnvironmentVariable {
+        /// The name of the variable
+        name: String,
+        /// The key the variable is used in
+        key: String,
+        /// The path of the config
+        filename: PathBuf,
+        /// Why the variable could not be read
+        source: std::env::VarError,
+    },
+    /// A `${` in the config is not closed with a `}`
+    #[snafu(display("Unclosed ${{ in {} of {}", key, filename.display()))]
+    UnclosedInterpolation {
+        /// The key it is in
+        key: String,
+        /// The path of the config
+        filename: PathBuf,
+    },
+    /// A profile has problems that make it unusable
+    #[snafu(display("Invalid config {}:\n{}", filename.display(), problems))]
+    InvalidConfig {
+        /// The path of the config
+        filename: PathBuf,
+        /// The problems, one per line
+        problems: String,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The profile used when no profile is specified
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The names of the jira issue types that are reported on. Issues of any other type are skipped.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct IssueTypes {
+    /// The issue types that are features
+    #[serde(default)]
     pub features: Vec<String>,
+    /// The issue types that are operational work, e.g. incidents
+    #[serde(default)]
     pub operational: Vec<String>,
 }
 
+/// Maps every jira status whose name matches the glob `pattern` to `status`. In the pattern `*`
+/// matches any run of characters and `?` matches a single character.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusPattern {
+    /// The glob the names of the statuses are matched against
+    pub pattern: String,
+    /// The status that the matching statuses map to
+    pub status: ItemStatus,
+}
+
+/// How the points of an estimate convert to days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PointsConversion {
+    /// The days a point is worth
+    Factor(f64),
+    /// The days each number of points is worth. Points between two entries are interpolated,
+    /// points outside of the table are worth the days per point of the closest entry.
+    Table(Vec<PointsInDays>),
+}
+
+/// An entry of a [`PointsConversion::Table`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PointsInDays {
+    /// The number of points
+    pub points: f64,
+    /// The days that number of points is worth
+    pub days: f64,
+}
+
+impl PointsConversion {
+    /// The days `points` are worth
+    #[must_use]
+    pub fn days(&self, points: f64) -> f64 {
+        match self {
+            PointsConversion::Factor(factor) => points * factor,
+            PointsConversion::Table(table) => {
+                let mut table: Vec<&PointsInDays> = table.iter().collect();
+                table.sort_by(|a, b| {
+                    a.points
+                        .partial_cmp(&b.points)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                let above = table.partition_point(|entry| entry.points < points);
+                match (
+                    above.checked_sub(1).map(|below| table[below]),
+                    table.get(above).copied(),
+                ) {
+                    (Some(low), Some(high)) => {
+                        low.days
+                            + (high.days - low.days) * (points - low.points)
+                                / (high.points - low.points)
+                    }
+                    (Some(closest), None) | (None, Some(closest))
+                        if closest.points.abs() > f64::EPSILON =>
+                    {
+                        points * closest.days / closest.points
+                    }
+                    _ => 0.0,
+                }
+            }
+        }
+    }
+}
+
+/// A field that estimates are taken from in place of the `timeestimate`, such as story points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EstimateSource {
+    /// The field by name, e.g. `Story Points`, or by id, e.g. `customfield_10002`
+    pub field: String,
+    /// How the points of the field convert to days
+    pub conversion: PointsConversion,
+}
+
+impl EstimateSource {
+    /// Jira names fields in the changelog by their display name and gives custom fields an id,
+    /// so either matches, ignoring case
+    #[must_use]
+    pub fn is_field(&self, field: &str, field_id: Option<&str>) -> bool {
+        field.eq_ignore_ascii_case(&self.field)
+            || field_id.map_or(false, |field_id| field_id.eq_ignore_ascii_case(&self.field))
+    }
+}
+
+/// A profile of the jira config, what to connect to and how issues map to items
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
+    /// The url of the jira instance, e.g. `https://example.atlassian.net`
     pub jira_instance: Url,
+    /// The user to connect as, usually an email address
     pub username: String,
-    pub token: String,
+    /// One of `token`, `token-env` or `token-command`
+    #[serde(flatten)]
+    pub token: TokenSource,
+    /// The custom field that holds the resolution, in place of the `resolution` of the issues
     pub resolution_field: Option<CustomFieldName>,
+    /// The custom field that holds the team that owns an issue
+    pub team_field: Option<CustomFieldName>,
+    /// The custom field that company-managed projects use to link an issue to its epic, usually
+    /// named `Epic Link`. An issue's link is taken over its `parent` field when both are set, and
+    /// without the field only the `parent` is used.
+    pub epic_link_field: Option<CustomFieldName>,
+    /// The issue types that are reported on
+    #[serde(default)]
     pub issue_types: IssueTypes,
+    /// Replaces the `issue-types` for the issues of a project, by project key, for projects that
+    /// name their issue types differently
+    #[serde(default)]
+    pub project_issue_types: HashMap<String, IssueTypes>,
+    /// Maps the names of jira statuses to the status they mean
+    #[serde(default)]
     pub status_mapping: HashMap<String, ItemStatus>,
+    /// Checked, in order, for statuses that are not in the `status-mapping`
+    #[serde(default)]
+    pub status_patterns: Vec<StatusPattern>,
+    /// Map the statuses that are not mapped by name or pattern by their Jira status category,
+    /// `new` to `ToDo`, `indeterminate` to `InDev` and `done` to `Completed`. This is enough to
+    /// try a report before writing a `status-mapping`. The categories are pulled from the
+    /// instance's list of statuses. Reports on a dump only know the categories of the statuses
+    /// the issues are currently in, statuses only seen in their history use the `default-status`.
+    #[serde(default)]
+    pub infer_status_categories: bool,
+    /// The status used for any status that is not mapped by name, pattern or category
+    pub default_status: Option<ItemStatus>,
+    /// Maps the names of jira resolutions to the resolution they mean
+    #[serde(default)]
     pub resolution_mapping: HashMap<String, Resolution>,
+    /// The resolutions left out of the time in status report when neither
+    /// `--include-resolution` nor `--exclude-resolution` is given, e.g. `[Rejected]` to keep
+    /// rejected items out of the cycle times
+    #[serde(default)]
+    pub exclude_resolutions: Vec<Resolution>,
+    /// Maps the name of a jira link type, e.g. `Blocks`, to the relationship it describes when
+    /// read in the outward direction. Links of types that are not mapped are ignored.
+    #[serde(default)]
+    pub link_mapping: HashMap<String, LinkType>,
+    /// How long requests to jira are retried, see [`RetryPolicy`]
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// The fields exported by `field-history` when none are given on the command line, by name,
+    /// e.g. `Story Points`, or by id, e.g. `customfield_10002`
+    #[serde(default)]
+    pub history_fields: Vec<String>,
+    /// The days, and optionally hours, that count towards the time in a status. Without it the
+    /// US settlement calendar is used.
+    #[serde(default)]
+    pub working_week: Option<WorkingWeek>,
+    /// The timezone that days start and end in when counting the time in a status, as the name
+    /// of an IANA timezone such as `America/New_York`. Without it the `utc-offset-hours` of the
+    /// `working-week` is used, or UTC.
+    pub reporting_timezone: Option<Timezone>,
+    /// Take estimates from this field, converting its points to days, rather than from the
+    /// `timeestimate` of the issues
+    pub estimate_source: Option<EstimateSource>,
+    /// How the description of an issue is rendered when it is included in a report, `plain` or
+    /// `markdown`
+    #[serde(default)]
+    pub body_format: adf::Format,
+    /// The most issues a query may match before pulling them is refused, so a mistaken query
+    /// can't pull the whole instance. [`DEFAULT_MAX_ISSUES`] when it is not set.
+    pub max_issues: Option<u64>,
+    /// The secret jira signs the webhooks sent to `lectev serve` with, one of `token`,
+    /// `token-env` or `token-command`. Webhooks without a valid signature are rejected when it
+    /// is set.
+    pub webhook_secret: Option<TokenSource>,
+}
+
+/// The most issues a query may match when the config does not say otherwise
+pub const DEFAULT_MAX_ISSUES: u64 = 10_000;
+
+impl Config {
+    /// The most issues a query may match, see [`Config::max_issues`]
+    #[must_use]
+    pub fn issue_limit(&self) -> u64 {
+        self.max_issues.unwrap_or(DEFAULT_MAX_ISSUES)
+    }
 }
 
+/// The path of the jira config, `config_path` when it is given, else `jira.yml` in the config
+/// directory
+///
+/// # Errors
+///
+/// Fails when the config directory can't be found.
 pub async fn resolve_config_path(config_path: &Option<PathBuf>) -> Result<PathBuf, Error> {
     match config_path {
         Some(resolved_config_path) => Ok(resolved_config_path.clone()),
@@ -74,13 +340,591 @@ pub async fn resolve_config_path(config_path: &Option<PathBuf>) -> Result<PathBu
     }
 }
 
-pub async fn read(opt_config_path: &Option<PathBuf>) -> Result<Config, Error> {
-    let path = resolve_config_path(opt_config_path).await?;
+/// A config file that is a single bare config, rather than a map of profiles, is identified by
+/// the presence of the required `jira-instance` key at the top level.
+fn is_single_config(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => mapping.contains_key(&"jira-instance".into()),
+        _ => false,
+    }
+}
+
+/// How serious a [`Problem`] with a config is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The reports would be wrong, or fail, with this config
+    Error,
+    /// Probably a mistake, but the reports can still be run
+    Warning,
+}
+
+/// A single problem found while validating a config
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// Whether the problem makes the config unusable
+    pub severity: Severity,
+    /// The profile the problem is in
+    pub profile: String,
+    /// The key of the config the problem is in
+    pub field: String,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}: profile {}, {}: {}",
+            severity, self.profile, self.field, self.message
+        )
+    }
+}
+
+/// The keys a config may have, with the keys allowed under the keys that are maps of fixed keys
+const KNOWN_KEYS: [(&str, &[&str]); 25] = [
+    ("jira-instance", &[]),
+    ("username", &[]),
+    ("token", &[]),
+    ("token-env", &[]),
+    ("token-command", &[]),
+    ("resolution-field", &[]),
+    ("team-field", &[]),
+    ("epic-link-field", &[]),
+    ("issue-types", &["features", "operational"]),
+    ("project-issue-types", &[]),
+    ("status-mapping", &[]),
+    ("status-patterns", &[]),
+    ("infer-status-categories", &[]),
+    ("default-status", &[]),
+    ("resolution-mapping", &[]),
+    ("exclude-resolutions", &[]),
+    ("link-mapping", &[]),
+    (
+        "retry",
+        &[
+            "max-attempts",
+            "max-elapsed-seconds",
+            "circuit-breaker-failures",
+            "circuit-breaker-cooldown-seconds",
+        ],
+    ),
+    ("history-fields", &[]),
+    (
+        "working-week",
+        &["days", "working-hours", "utc-offset-hours"],
+    ),
+    ("reporting-timezone", &[]),
+    ("estimate-source", &["field", "conversion"]),
+    ("body-format", &[]),
+    ("max-issues", &[]),
+    ("webhook-secret", &["token", "token-env", "token-command"]),
+];
+
+const ITEM_STATUSES: [ItemStatus; 6] = [
+    ItemStatus::ToDo,
+    ItemStatus::Ready,
+    ItemStatus::InDev,
+    ItemStatus::InTest,
+    ItemStatus::Waiting,
+    ItemStatus::Completed,
+];
+
+fn problem(severity: Severity, profile: &str, field: &str, message: String) -> Problem {
+    Problem {
+        severity,
+        profile: profile.to_owned(),
+        field: field.to_owned(),
+        message,
+    }
+}
+
+fn mapping_keys(value: &serde_yaml::Value) -> Vec<String> {
+    value.as_mapping().map_or_else(Vec::new, |mapping| {
+        mapping
+            .iter()
+            .map(|(key, _)| {
+                key.as_str()
+                    .map_or_else(|| format!("{:?}", key), str::to_owned)
+            })
+            .collect()
+    })
+}
 
-    let contents = fs::read_to_string(path.clone()).await.context(OpenConfig {
+/// Find the keys that serde would silently ignore, such as `status_mapping` for `status-mapping`
+fn check_keys(profile: &str, value: &serde_yaml::Value) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for key in mapping_keys(value) {
+        match KNOWN_KEYS.iter().find(|(known, _)| *known == key) {
+            Some((_, known_children)) if !known_children.is_empty() => {
+                for child in mapping_keys(&value[key.as_str()]) {
+                    if !known_children.contains(&child.as_str()) {
+                        problems.push(problem(
+                            Severity::Error,
+                            profile,
+                            &format!("{}.{}", key, child),
+                            format!("unknown key, expected one of {}", known_children.join(", ")),
+                        ));
+                    }
+                }
+            }
+            Some(_) => {}
+            None => problems.push(problem(
+                Severity::Error,
+                profile,
+                &key,
+                match KNOWN_KEYS
+                    .iter()
+                    .find(|(known, _)| known.replace('-', "_") == key)
+                {
+                    Some((known, _)) => format!("unknown key, did you mean {}?", known),
+                    None => "unknown key".to_owned(),
+                },
+            )),
+        }
+    }
+    problems
+}
+
+fn check_instance_url(profile: &str, url: &Url) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if (url.scheme() != "http" && url.scheme() != "https") || url.host().is_none() {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "jira-instance",
+            format!("{} is not an http or https url", url),
+        ));
+    } else if !url.path().ends_with('/') {
+        problems.push(problem(
+            Severity::Warning,
+            profile,
+            "jira-instance",
+            format!(
+                "{} does not end with /, the last part of its path is dropped when building \
+                 links to issues",
+                url
+            ),
+        ));
+    }
+    problems
+}
+
+fn check_status_mapping(profile: &str, conf: &Config) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if conf.status_mapping.is_empty()
+        && conf.status_patterns.is_empty()
+        && !conf.infer_status_categories
+        && conf.default_status.is_none()
+    {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "status-mapping",
+            "no jira statuses are mapped".to_owned(),
+        ));
+        return problems;
+    }
+
+    if let Some(position) = conf.status_patterns.iter().position(|status_pattern| {
+        !status_pattern.pattern.is_empty() && status_pattern.pattern.chars().all(|c| c == '*')
+    }) {
+        for unreachable in &conf.status_patterns[position + 1..] {
+            problems.push(problem(
+                Severity::Warning,
+                profile,
+                "status-patterns",
+                format!(
+                    "{} can never match, the earlier pattern {} matches every status",
+                    unreachable.pattern, conf.status_patterns[position].pattern
+                ),
+            ));
+        }
+    }
+
+    for status in &ITEM_STATUSES {
+        let is_mapped = conf.status_mapping.values().any(|mapped| mapped == status)
+            || conf
+                .status_patterns
+                .iter()
+                .any(|status_pattern| status_pattern.status == *status)
+            || (conf.infer_status_categories
+                && nativetocore::STATUS_CATEGORIES
+                    .iter()
+                    .any(|(_, inferred)| inferred == status))
+            || conf.default_status.as_ref() == Some(status);
+        if !is_mapped {
+            problems.push(problem(
+                Severity::Warning,
+                profile,
+                "status-mapping",
+                format!(
+                    "no jira status maps to {}, no time will be reported in it",
+                    status
+                ),
+            ));
+        }
+    }
+    problems
+}
+
+fn check_working_week(profile: &str, working_week: &WorkingWeek) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if working_week.days.is_empty() {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "working-week",
+            "no working days are listed, no time will be reported".to_owned(),
+        ));
+    }
+    if let Some(hours) = working_week.working_hours {
+        if hours.start >= hours.end || hours.end > 24 {
+            problems.push(problem(
+                Severity::Error,
+                profile,
+                "working-week",
+                format!(
+                    "working hours {} to {} are not a range of hours within a day",
+                    hours.start, hours.end
+                ),
+            ));
+        }
+    }
+    if !(-12..=14).contains(&working_week.utc_offset_hours) {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "working-week",
+            format!(
+                "{} is not a utc offset, it must be between -12 and 14",
+                working_week.utc_offset_hours
+            ),
+        ));
+    }
+    problems
+}
+
+fn check_estimate_source(profile: &str, estimate_source: &EstimateSource) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    match &estimate_source.conversion {
+        PointsConversion::Factor(factor) if *factor <= 0.0 => problems.push(problem(
+            Severity::Error,
+            profile,
+            "estimate-source.conversion",
+            format!("a point can't be worth {} days", factor),
+        )),
+        PointsConversion::Factor(_) => {}
+        PointsConversion::Table(table) => {
+            if table.is_empty() {
+                problems.push(problem(
+                    Severity::Error,
+                    profile,
+                    "estimate-source.conversion",
+                    "the table is empty, every estimate would be zero days".to_owned(),
+                ));
+            }
+            for (index, entry) in table.iter().enumerate() {
+                if entry.points <= 0.0 || entry.days < 0.0 {
+                    problems.push(problem(
+                        Severity::Error,
+                        profile,
+                        "estimate-source.conversion",
+                        format!(
+                            "{} points worth {} days is not a positive number of points and days",
+                            entry.points, entry.days
+                        ),
+                    ));
+                }
+                if table[..index]
+                    .iter()
+                    .any(|earlier| (earlier.points - entry.points).abs() < f64::EPSILON)
+                {
+                    problems.push(problem(
+                        Severity::Error,
+                        profile,
+                        "estimate-source.conversion",
+                        format!("{} points are in the table more than once", entry.points),
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Check a config for the mistakes that parsing it does not catch
+#[must_use]
+pub fn validate(profile: &str, conf: &Config) -> Vec<Problem> {
+    let mut problems = check_instance_url(profile, &conf.jira_instance);
+    if conf.issue_types.features.is_empty()
+        && conf.issue_types.operational.is_empty()
+        && conf.project_issue_types.is_empty()
+    {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "issue-types",
+            "no issue types are listed, every issue will be skipped".to_owned(),
+        ));
+    }
+    for (project, issue_types) in &conf.project_issue_types {
+        if issue_types.features.is_empty() && issue_types.operational.is_empty() {
+            problems.push(problem(
+                Severity::Warning,
+                profile,
+                &format!("project-issue-types.{}", project),
+                format!(
+                    "no issue types are listed, every issue of {} will be skipped",
+                    project
+                ),
+            ));
+        }
+    }
+    problems.extend(check_status_mapping(profile, conf));
+    if conf.resolution_mapping.is_empty() {
+        problems.push(problem(
+            Severity::Error,
+            profile,
+            "resolution-mapping",
+            "no resolutions are mapped".to_owned(),
+        ));
+    }
+    if let Some(working_week) = &conf.working_week {
+        problems.extend(check_working_week(profile, working_week));
+        if conf.reporting_timezone.is_some() && working_week.utc_offset_hours != 0 {
+            problems.push(problem(
+                Severity::Warning,
+                profile,
+                "working-week.utc-offset-hours",
+                "ignored, the working hours are in the reporting-timezone".to_owned(),
+            ));
+        }
+    }
+    if let Some(estimate_source) = &conf.estimate_source {
+        problems.extend(check_estimate_source(profile, estimate_source));
+    }
+    problems
+}
+
+/// The parsed profiles of a config file along with the problems found in each
+#[derive(Debug)]
+pub struct ValidatedConfig {
+    /// The path of the config
+    pub path: PathBuf,
+    /// The profiles by name
+    pub profiles: HashMap<String, Config>,
+    /// The problems found, in the order of the profiles
+    pub problems: Vec<Problem>,
+}
+
+/// Replace each `${NAME}` in `text`, the string value at `key`, with the environment variable
+/// `NAME`
+fn interpolate_text(path: &Path, key: &str, text: &str) -> Result<String, Error> {
+    let mut interpolated = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        interpolated.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            interpolated.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = rest.strip_prefix("${") {
+            let end = reference.find('}').context(UnclosedInterpolation {
+                key,
+                filename: path,
+            })?;
+            let name = &reference[..end];
+            interpolated.push_str(&std::env::var(name).context(MissingEnvironmentVariable {
+                name,
+                key,
+                filename: path,
+            })?);
+            rest = &reference[end + 1..];
+        } else {
+            interpolated.push('$');
+            rest = &rest[1..];
+        }
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+/// The name of a key in the dotted path of a value, as it is written in the config
+fn key_name(parent: &str, key: &serde_yaml::Value) -> String {
+    let name = match key {
+        serde_yaml::Value::String(name) => name.clone(),
+        serde_yaml::Value::Number(number) => number.to_string(),
+        serde_yaml::Value::Bool(value) => value.to_string(),
+        _ => "?".to_owned(),
+    };
+    if parent.is_empty() {
+        name
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// Interpolate every string value under `key`. The keys are left as they are, and as the yaml has
+/// already been parsed the values of the variables are always strings, so they can't change the
+/// structure of the config.
+fn interpolate(path: &Path, key: &str, value: &mut serde_yaml::Value) -> Result<(), Error> {
+    match value {
+        serde_yaml::Value::String(text) => *text = interpolate_text(path, key, text)?,
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate(path, &format!("{}[{}]", key, index), item)?;
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (name, item) in mapping.iter_mut() {
+                interpolate(path, &key_name(key, name), item)?;
+            }
+        }
+        serde_yaml::Value::Null | serde_yaml::Value::Bool(_) | serde_yaml::Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+/// Deserialize the interpolated value of a profile, naming the key of the value that is wrong
+fn parse_profile(path: &Path, key: &str, value: serde_yaml::Value) -> Result<Config, Error> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let at = err.path().to_string();
+        Error::InvalidValue {
+            key: match (key.is_empty(), at.as_str()) {
+                (true, _) => at.clone(),
+                (false, ".") => key.to_owned(),
+                (false, _) => format!("{}.{}", key, at),
+            },
+            filename: path.to_path_buf(),
+            source: err.into_inner(),
+        }
+    })
+}
+
+async fn load(path: &Path) -> Result<ValidatedConfig, Error> {
+    let contents = fs::read_to_string(path).await.context(OpenConfig {
+        filename: path.to_path_buf(),
+    })?;
+    let mut value: serde_yaml::Value =
+        serde_yaml::from_str(&contents).context(ParseYaml { filename: path })?;
+    interpolate(path, "", &mut value)?;
+
+    let (profiles, values): (HashMap<String, Config>, Vec<(String, serde_yaml::Value)>) =
+        if is_single_config(&value) {
+            let mut profiles = HashMap::new();
+            profiles.insert(
+                DEFAULT_PROFILE.to_owned(),
+                parse_profile(path, "", value.clone())?,
+            );
+            (profiles, vec![(DEFAULT_PROFILE.to_owned(), value)])
+        } else {
+            let mut values: Vec<(String, serde_yaml::Value)> =
+                serde_yaml::from_value::<HashMap<String, serde_yaml::Value>>(value)
+                    .context(ParseYaml { filename: path })?
+                    .into_iter()
+                    .collect();
+            values.sort_by(|left, right| left.0.cmp(&right.0));
+            let mut profiles = HashMap::new();
+            for (profile, value) in &values {
+                profiles.insert(
+                    profile.clone(),
+                    parse_profile(path, profile, value.clone())?,
+                );
+            }
+            (profiles, values)
+        };
+
+    let mut problems = Vec::new();
+    for (profile, value) in &values {
+        problems.extend(check_keys(profile, value));
+    }
+    for (profile, conf) in &profiles {
+        problems.extend(validate(profile, conf));
+    }
+    problems.sort_by(|left, right| left.profile.cmp(&right.profile));
+
+    Ok(ValidatedConfig {
+        path: path.to_path_buf(),
+        profiles,
+        problems,
+    })
+}
+
+/// Read and validate every profile of the config, without failing on the problems found
+///
+/// # Errors
+///
+/// Fails when the config can't be read or parsed. The problems found don't fail it.
+pub async fn read_all(opt_config_path: &Option<PathBuf>) -> Result<ValidatedConfig, Error> {
+    load(&resolve_config_path(opt_config_path).await?).await
+}
+
+/// Read the config for `profile`. Fails if the profile has any [`Severity::Error`] problems,
+/// warnings are logged.
+///
+/// # Errors
+///
+/// Fails when the config can't be read or parsed, when it has no such profile, or when the profile
+/// has an error.
+pub async fn read(opt_config_path: &Option<PathBuf>, profile: &str) -> Result<Config, Error> {
+    read_profile(opt_config_path, profile, &[]).await
+}
+
+/// The keys that say how jira issues are mapped, which a new config does not have yet
+const MAPPING_KEYS: [&str; 3] = ["issue-types", "status-mapping", "resolution-mapping"];
+
+/// Read the config for `profile` to connect to jira with, before the mappings have been written.
+/// Like [`read`] but the problems with the mappings are ignored.
+///
+/// # Errors
+///
+/// Fails like [`read`].
+pub async fn read_connection(
+    opt_config_path: &Option<PathBuf>,
+    profile: &str,
+) -> Result<Config, Error> {
+    read_profile(opt_config_path, profile, &MAPPING_KEYS).await
+}
+
+async fn read_profile(
+    opt_config_path: &Option<PathBuf>,
+    profile: &str,
+    ignored_keys: &[&str],
+) -> Result<Config, Error> {
+    let path = resolve_config_path(opt_config_path).await?;
+    let mut validated = load(&path).await?;
+    let conf = validated.profiles.remove(profile).context(MissingProfile {
+        profile,
         filename: path.clone(),
     })?;
-    let config = serde_yaml::from_str(&contents).context(ParseYaml { filename: path })?;
 
-    Ok(config)
+    let (errors, warnings): (Vec<Problem>, Vec<Problem>) = validated
+        .problems
+        .into_iter()
+        .filter(|problem| problem.profile == profile)
+        .filter(|problem| !ignored_keys.contains(&problem.field.as_str()))
+        .partition(|problem| problem.severity == Severity::Error);
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    ensure!(
+        errors.is_empty(),
+        InvalidConfig {
+            filename: path,
+            problems: errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    );
+    Ok(conf)
 }
+
+#[cfg(test)]
+mod tests;
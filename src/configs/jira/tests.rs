@@ -0,0 +1,95 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Interpolating environment variables into the string values of the config.
+use super::{interpolate, Error};
+use std::path::Path;
+
+fn interpolated(yaml: &str) -> Result<serde_yaml::Value, Error> {
+    let mut value = serde_yaml::from_str(yaml).expect("valid yaml");
+    interpolate(Path::new("lectev.yml"), "", &mut value)?;
+    Ok(value)
+}
+
+fn yaml(text: &str) -> serde_yaml::Value {
+    serde_yaml::from_str(text).expect("valid yaml")
+}
+
+#[test]
+fn replaces_references_in_string_values() {
+    std::env::set_var("LECTEV_TEST_INTERPOLATE_USER", "ann@example.com");
+    assert_eq!(
+        interpolated(
+            "username: ${LECTEV_TEST_INTERPOLATE_USER}\n\
+             tags:\n  - a-${LECTEV_TEST_INTERPOLATE_USER}\n  - $${LITERAL}\n  - $5\n\
+             points: 3\n"
+        )
+        .ok(),
+        Some(yaml(
+            "username: ann@example.com\n\
+             tags: [a-ann@example.com, '${LITERAL}', $5]\n\
+             points: 3\n"
+        ))
+    );
+}
+
+#[test]
+fn values_cannot_change_the_structure() {
+    std::env::set_var(
+        "LECTEV_TEST_INTERPOLATE_INJECTED",
+        "x\njira-instance: https://evil.example.com",
+    );
+    let value = interpolated(
+        "jira-instance: https://jira.example.com\n\
+         username: ${LECTEV_TEST_INTERPOLATE_INJECTED}\n",
+    )
+    .expect("interpolated");
+    assert_eq!(
+        value["jira-instance"].as_str(),
+        Some("https://jira.example.com")
+    );
+    assert_eq!(
+        value["username"].as_str(),
+        Some("x\njira-instance: https://evil.example.com")
+    );
+}
+
+#[test]
+fn ignores_comments_and_keys() {
+    std::env::remove_var("LECTEV_TEST_INTERPOLATE_UNSET");
+    assert_eq!(
+        interpolated(
+            "# username: ${LECTEV_TEST_INTERPOLATE_UNSET}\n\
+             ${LECTEV_TEST_INTERPOLATE_UNSET}: kept\n"
+        )
+        .ok(),
+        Some(yaml("${LECTEV_TEST_INTERPOLATE_UNSET}: kept\n"))
+    );
+}
+
+#[test]
+fn names_the_key_of_a_bad_reference() {
+    std::env::remove_var("LECTEV_TEST_INTERPOLATE_MISSING");
+    match interpolated("default:\n  token-env: ${LECTEV_TEST_INTERPOLATE_MISSING}\n") {
+        Err(Error::MissingEnvironmentVariable { name, key, .. }) => {
+            assert_eq!(name, "LECTEV_TEST_INTERPOLATE_MISSING");
+            assert_eq!(key, "default.token-env");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match interpolated("labels:\n  - ok\n  - ${UNCLOSED\n") {
+        Err(Error::UnclosedInterpolation { key, .. }) => assert_eq!(key, "labels[1]"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
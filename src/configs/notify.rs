@@ -0,0 +1,227 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Provides configuration for the notification sent when a command finishes
+//!
+//! The config is a single yaml file passed with `--notify-config`. Either or both of `slack` and
+//! `smtp` are set. For example:
+//!
+//! ```yaml
+//! slack:
+//!   webhook-url: https://hooks.slack.com/services/T000/B000/XXXX
+//! smtp:
+//!   host: smtp.example.com
+//!   port: 587
+//!   tls: starttls
+//!   username: lectev
+//!   password:
+//!     token-env: SMTP_PASSWORD
+//!   from: lectev@example.com
+//!   to:
+//!     - team@example.com
+//! notify-on: failure
+//! ```
+//!
+//! The webhook url is a credential, anyone who has it can post to the channel, so it is kept out
+//! of logs and errors like the api tokens. The password is one of `token`, `token-env` or
+//! `token-command`, as for the jira config, and is only sent over an encrypted connection.
+use crate::lib::rest::{Secret, TokenSource};
+use serde::Deserialize;
+use snafu::{ensure, ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use url::Url;
+
+/// The ways reading the notification config can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The config file could not be opened
+    #[snafu(display("Could not open config from {}: {}", filename.display(), source))]
+    OpenConfig {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of opening it
+        source: std::io::Error,
+    },
+    /// The config file is not a valid notification config
+    #[snafu(display("Could not parse config from {}: {}", filename.display(), source))]
+    ParseYaml {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of parsing it
+        source: serde_yaml::Error,
+    },
+    /// The config has nowhere to send notifications
+    #[snafu(display("Config {} has neither slack nor smtp set", filename.display()))]
+    NoDestination {
+        /// The path of the config
+        filename: PathBuf,
+    },
+    /// The smtp destination has no recipients
+    #[snafu(display("Config {} has no smtp recipients in to", filename.display()))]
+    NoRecipients {
+        /// The path of the config
+        filename: PathBuf,
+    },
+    /// The slack webhook is not a url
+    #[snafu(display("Config {} has an invalid slack webhook url: {}", filename.display(), source))]
+    InvalidWebhookUrl {
+        /// The path of the config
+        filename: PathBuf,
+        /// Why it is not a url
+        source: url::ParseError,
+    },
+    /// The smtp destination has a username without a password, or a password without a username
+    #[snafu(display(
+        "Config {} must set both the smtp username and password, or neither",
+        filename.display()
+    ))]
+    IncompleteSmtpLogin {
+        /// The path of the config
+        filename: PathBuf,
+    },
+    /// The smtp destination would log in over an unencrypted connection
+    #[snafu(display(
+        "Config {} would send the smtp password unencrypted, set tls to starttls or implicit",
+        filename.display()
+    ))]
+    UnencryptedSmtpLogin {
+        /// The path of the config
+        filename: PathBuf,
+    },
+}
+
+/// Which runs are notified
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyOn {
+    /// Every run, whether it succeeded or failed
+    Always,
+    /// Only runs that failed
+    Failure,
+}
+
+impl Default for NotifyOn {
+    fn default() -> Self {
+        NotifyOn::Always
+    }
+}
+
+/// A Slack incoming webhook
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Slack {
+    /// The url of the webhook, a credential as anyone with it can post to the channel
+    pub webhook_url: Secret,
+}
+
+/// How the connection to the mail relay is secured
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tls {
+    /// Plain SMTP, for the local MTA or a relay on a trusted network
+    None,
+    /// Plain SMTP upgraded with `STARTTLS`, usually on port 587
+    Starttls,
+    /// TLS from the start, usually on port 465
+    Implicit,
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Tls::None
+    }
+}
+
+/// A mail relay
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Smtp {
+    /// The host name of the relay, which its certificate is checked against
+    pub host: String,
+    /// The port of the relay, 465 with implicit tls and 25 otherwise
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// How the connection is secured
+    #[serde(default)]
+    pub tls: Tls,
+    /// The user to log in as, the relay is used without logging in when it is not set
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The password of the user, one of `token`, `token-env` or `token-command`
+    #[serde(default)]
+    pub password: Option<TokenSource>,
+    /// The address the mail is from
+    pub from: String,
+    /// The addresses the mail is sent to
+    pub to: Vec<String>,
+}
+
+impl Smtp {
+    /// The port of the relay, the usual one for the tls when it is not set
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.tls {
+            Tls::Implicit => 465,
+            Tls::None | Tls::Starttls => 25,
+        })
+    }
+}
+
+/// Where, and for which runs, notifications are sent
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Post to a slack channel
+    #[serde(default)]
+    pub slack: Option<Slack>,
+    /// Send mail
+    #[serde(default)]
+    pub smtp: Option<Smtp>,
+    /// Which runs are notified
+    #[serde(default)]
+    pub notify_on: NotifyOn,
+}
+
+/// Read and check the notification config at `path`
+///
+/// # Errors
+///
+/// Fails when the config can't be read or parsed, has no destination, or has an smtp destination
+/// without recipients, with half of a login, or that would log in unencrypted.
+pub async fn read(path: &Path) -> Result<Config, Error> {
+    let contents = fs::read_to_string(path)
+        .await
+        .context(OpenConfig { filename: path })?;
+    let conf: Config = serde_yaml::from_str(&contents).context(ParseYaml { filename: path })?;
+    ensure!(
+        conf.slack.is_some() || conf.smtp.is_some(),
+        NoDestination { filename: path }
+    );
+    if let Some(slack) = &conf.slack {
+        Url::parse(slack.webhook_url.expose()).context(InvalidWebhookUrl { filename: path })?;
+    }
+    if let Some(smtp) = &conf.smtp {
+        ensure!(!smtp.to.is_empty(), NoRecipients { filename: path });
+        ensure!(
+            smtp.username.is_some() == smtp.password.is_some(),
+            IncompleteSmtpLogin { filename: path }
+        );
+        ensure!(
+            smtp.username.is_none() || smtp.tls != Tls::None,
+            UnencryptedSmtpLogin { filename: path }
+        );
+    }
+    Ok(conf)
+}
@@ -0,0 +1,173 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Provides the workspace config, defaults for the options of the commands run in a project
+//!
+//! The config is a `.lectev.yml` in the current directory, or the closest of its parents that has
+//! one. For example:
+//!
+//! ```yaml
+//! profile: work
+//! output-dir: reports
+//! jql-filter: project = PLAT
+//! ```
+//!
+//! Options given on the command line always win. `--profile` replaces the `profile`, an absolute
+//! `--output-path` or an object storage uri is used as it is and a relative path is taken to be
+//! under the `output-dir`. The `jql-filter` is combined with the `--jql-query` of a command, an
+//! issue has to match both. The same goes for the other outputs of a command and for the reports
+//! of a `--batch` file.
+use crate::lib::sink;
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::info;
+
+/// The name of the workspace config file
+pub const FILE_NAME: &str = ".lectev.yml";
+
+/// The ways reading the workspace config can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The current directory could not be read
+    #[snafu(display("Could not get the current directory: {}", source))]
+    CouldntGetCurrentDir {
+        /// Why it could not be read
+        source: std::io::Error,
+    },
+    /// The config file could not be opened
+    #[snafu(display("Could not open config from {}: {}", filename.display(), source))]
+    OpenConfig {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of opening it
+        source: std::io::Error,
+    },
+    /// The config file is not a valid workspace config
+    #[snafu(display("Could not parse config from {}: {}", filename.display(), source))]
+    ParseYaml {
+        /// The path of the config
+        filename: PathBuf,
+        /// The error of parsing it
+        source: serde_yaml::Error,
+    },
+}
+
+/// The defaults shared by every command run in a directory and those under it
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The profile of the jira config used when `--profile` is not given
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// The directory relative output paths are written under. A relative directory is relative to
+    /// the directory of the config file.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    /// A jql query that is combined with the query of every command
+    #[serde(default)]
+    pub jql_filter: Option<String>,
+}
+
+impl Config {
+    /// The profile to use, `profile` when it was given on the command line
+    #[must_use]
+    pub fn profile(&self, profile: Option<String>, default: &str) -> String {
+        profile
+            .or_else(|| self.profile.clone())
+            .unwrap_or_else(|| default.to_owned())
+    }
+
+    /// Where a relative `path` is written, absolute paths and object storage uris are left alone
+    #[must_use]
+    pub fn output_path(&self, path: &Path) -> PathBuf {
+        match &self.output_dir {
+            Some(output_dir) if path.is_relative() && !sink::is_uri(path) => output_dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// The query with the `jql-filter` combined into it. The `ORDER BY` of the query, if it has
+    /// one, is kept at the end.
+    #[must_use]
+    pub fn query(&self, query: &str) -> String {
+        let filter = match &self.jql_filter {
+            Some(filter) => filter,
+            None => return query.to_owned(),
+        };
+        let (condition, order_by) = split_order_by(query);
+        let combined = if condition.is_empty() {
+            format!("({})", filter)
+        } else {
+            format!("({}) AND ({})", filter, condition)
+        };
+        if order_by.is_empty() {
+            combined
+        } else {
+            format!("{} {}", combined, order_by)
+        }
+    }
+}
+
+/// Split a jql query into its condition and its `ORDER BY` clause, if it has one
+fn split_order_by(query: &str) -> (&str, &str) {
+    let (condition, order_by) = match query.to_ascii_lowercase().rfind("order by") {
+        Some(index) => query.split_at(index),
+        None => (query, ""),
+    };
+    (condition.trim(), order_by.trim())
+}
+
+/// The workspace config of the closest directory, from `start` up, that has one
+async fn find(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(FILE_NAME);
+        if fs::metadata(&candidate)
+            .await
+            .map_or(false, |metadata| metadata.is_file())
+        {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Read the workspace config of the current directory. When there is none every option keeps
+/// its usual default.
+///
+/// # Errors
+///
+/// Fails when the current directory can't be read, or the config found can't be read or parsed.
+pub async fn read() -> Result<Config, Error> {
+    let current_dir = std::env::current_dir().context(CouldntGetCurrentDir {})?;
+    let path = match find(&current_dir).await {
+        Some(path) => path,
+        None => return Ok(Config::default()),
+    };
+    info!("using the workspace config {}", path.display());
+    let contents = fs::read_to_string(&path).await.context(OpenConfig {
+        filename: path.clone(),
+    })?;
+    let mut conf: Config = serde_yaml::from_str(&contents).context(ParseYaml {
+        filename: path.clone(),
+    })?;
+    if let (Some(output_dir), Some(workspace_dir)) = (&conf.output_dir, path.parent()) {
+        conf.output_dir = Some(workspace_dir.join(output_dir));
+    }
+    Ok(conf)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,98 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Combining the workspace defaults with the options of a command.
+use super::{split_order_by, Config};
+use std::path::{Path, PathBuf};
+
+fn workspace() -> Config {
+    Config {
+        profile: Some("work".to_owned()),
+        output_dir: Some(PathBuf::from("/work/reports")),
+        jql_filter: Some("project = PLAT".to_owned()),
+    }
+}
+
+#[test]
+fn splits_the_order_by_from_the_condition() {
+    assert_eq!(
+        split_order_by("status = Done ORDER BY key"),
+        ("status = Done", "ORDER BY key")
+    );
+    assert_eq!(
+        split_order_by(" status = Done order by created DESC "),
+        ("status = Done", "order by created DESC")
+    );
+    assert_eq!(split_order_by("ORDER BY key"), ("", "ORDER BY key"));
+    assert_eq!(split_order_by("status = Done"), ("status = Done", ""));
+    assert_eq!(
+        split_order_by("summary ~ \"order by\" ORDER BY key"),
+        ("summary ~ \"order by\"", "ORDER BY key")
+    );
+}
+
+#[test]
+fn combines_the_filter_with_the_query() {
+    let workspace = workspace();
+    assert_eq!(
+        workspace.query("status = Done ORDER BY key"),
+        "(project = PLAT) AND (status = Done) ORDER BY key"
+    );
+    assert_eq!(
+        workspace.query("ORDER BY key"),
+        "(project = PLAT) ORDER BY key"
+    );
+    assert_eq!(workspace.query(""), "(project = PLAT)");
+    assert_eq!(
+        Config::default().query("status = Done ORDER BY key"),
+        "status = Done ORDER BY key"
+    );
+}
+
+#[test]
+fn writes_relative_paths_under_the_output_dir() {
+    let workspace = workspace();
+    assert_eq!(
+        workspace.output_path(Path::new("wip.csv")),
+        Path::new("/work/reports/wip.csv")
+    );
+    assert_eq!(
+        workspace.output_path(Path::new("team/wip.csv")),
+        Path::new("/work/reports/team/wip.csv")
+    );
+    assert_eq!(
+        workspace.output_path(Path::new("/tmp/wip.csv")),
+        Path::new("/tmp/wip.csv")
+    );
+    assert_eq!(
+        Config::default().output_path(Path::new("wip.csv")),
+        Path::new("wip.csv")
+    );
+}
+
+#[test]
+fn leaves_object_storage_uris_alone() {
+    let workspace = workspace();
+    for uri in &["s3://bucket/wip.csv", "gs://bucket/team/wip.csv"] {
+        assert_eq!(workspace.output_path(Path::new(uri)), Path::new(uri));
+    }
+}
+
+#[test]
+fn prefers_the_profile_of_the_command_line() {
+    let workspace = workspace();
+    assert_eq!(workspace.profile(Some("ci".to_owned()), "default"), "ci");
+    assert_eq!(workspace.profile(None, "default"), "work");
+    assert_eq!(Config::default().profile(None, "default"), "default");
+}
@@ -0,0 +1,148 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Error Output
+//!
+//! Reports the error that ended a run, either as plain text or as json for tools that wrap
+//! lectev. Every error has a stable code, that does not change when the wording of the message
+//! does, and a category. The category decides the exit code of the process.
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// How the final error of a run is written to stderr
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// A human readable message
+    Text,
+    /// A single json object describing the error and its sources
+    Json,
+}
+
+/// The values accepted by `--error-format`
+pub const FORMATS: [&str; 2] = ["text", "json"];
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown error format '{}'", value)),
+        }
+    }
+}
+
+/// The broad kind of an error. Each category has its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// The command was invoked incorrectly, or needs a feature flag that is not enabled
+    Usage,
+    /// The configuration could not be read or is not usable
+    Config,
+    /// Jira could not be reached or returned something unexpected
+    Remote,
+    /// The data could not be converted between representations
+    Data,
+    /// A local file, store or socket could not be used
+    Io,
+    /// The command ran but one or more report thresholds were breached
+    Threshold,
+    /// The command was interrupted before it finished, any output written is partial
+    Cancelled,
+    /// The command finished, but some issues could not be pulled and are missing from its output
+    Partial,
+}
+
+impl Category {
+    /// The exit code of the process when a run fails with an error in this category
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Category::Usage => 2,
+            Category::Config => 3,
+            Category::Remote => 4,
+            Category::Data => 5,
+            Category::Io => 6,
+            Category::Threshold => 7,
+            Category::Cancelled => 130,
+            Category::Partial => 8,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Category::Usage => "usage",
+            Category::Config => "config",
+            Category::Remote => "remote",
+            Category::Data => "data",
+            Category::Io => "io",
+            Category::Threshold => "threshold",
+            Category::Cancelled => "cancelled",
+            Category::Partial => "partial",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Errors that can be reported with a stable code and a category
+pub trait Coded: std::error::Error {
+    /// A short, stable, kebab-case identifier for the error
+    fn code(&self) -> &'static str;
+    /// The category the error belongs to
+    fn category(&self) -> Category;
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    code: &'static str,
+    category: Category,
+    exit_code: i32,
+    message: String,
+    sources: Vec<String>,
+}
+
+/// The messages of each source of `err`, outermost first
+fn sources(err: &dyn std::error::Error) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = err.source();
+    while let Some(source) = current {
+        messages.push(source.to_string());
+        current = source.source();
+    }
+    messages
+}
+
+/// Write `err` to stderr in the requested format
+pub fn report<E: Coded>(err: &E, format: Format) {
+    match format {
+        Format::Text => eprintln!("Error [{}]: {}", err.code(), err),
+        Format::Json => {
+            let report = Report {
+                code: err.code(),
+                category: err.category(),
+                exit_code: err.category().exit_code(),
+                message: err.to_string(),
+                sources: sources(err),
+            };
+            match serde_json::to_string(&report) {
+                Ok(json) => eprintln!("{}", json),
+                Err(_) => eprintln!("Error [{}]: {}", err.code(), err),
+            }
+        }
+    }
+}
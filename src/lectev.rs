@@ -0,0 +1,93 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Lectev
+//!
+//! The reports and the issue trackers they pull their items from, shared by the `lectev` command
+//! and by programs that embed lectev. Such a program can, for instance, run the time in status
+//! report with [`lib::jira::time_in_status`] on the items of any [`lib::tracker::Tracker`].
+#![deny(warnings)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![deny(
+    missing_docs,
+    missing_doc_code_examples,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unstable_features,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+/// The configs of the trackers, the notifications and the workspace
+pub mod configs {
+    pub mod github;
+    pub mod jira;
+    pub mod notify;
+    pub mod workspace;
+}
+pub mod config;
+pub mod utils;
+/// The trackers and the reports on their items
+pub mod lib {
+    /// Pulling issues from github
+    pub mod github {
+        pub mod api;
+        pub mod native;
+        pub mod nativetocore;
+        pub mod tracker;
+    }
+    /// Pulling issues from jira and the reports on them
+    pub mod jira {
+        pub mod adf;
+        pub mod api;
+        pub mod checkpoint;
+        pub mod core;
+        pub mod dump;
+        pub mod dump_diff;
+        pub mod epics;
+        pub mod estimate_accuracy;
+        pub mod field_history;
+        pub mod filters;
+        pub mod flow;
+        pub mod metrics;
+        pub mod native;
+        pub mod nativetocore;
+        pub mod redact;
+        pub mod responsiveness;
+        pub mod rollup;
+        pub mod sample;
+        pub mod suggest;
+        pub mod summary;
+        #[cfg(test)]
+        pub mod test_support;
+        pub mod thresholds;
+        pub mod time_in_status;
+        pub mod times_in_flight;
+        pub mod tracker;
+        pub mod transitions;
+        pub mod webhook;
+    }
+    pub mod calendar;
+    pub mod cancel;
+    pub mod notify;
+    pub mod rest;
+    pub mod sink;
+    pub mod store;
+    pub mod timezone;
+    pub mod tracker;
+}
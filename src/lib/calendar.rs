@@ -0,0 +1,164 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Working Calendar
+//!
+//! Time in a status is measured in working days rather than calendar days. By default that is
+//! the US settlement calendar, Monday to Friday without the US federal holidays. Teams with a
+//! different working week describe it with a [`WorkingWeek`], optionally along with their
+//! working hours, in which case time is measured in fractions of a working day. Either way the
+//! days start and end in the [`Timezone`] of the team rather than in UTC.
+use crate::lib::timezone::Timezone;
+use bdays::HolidayCalendar;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Time;
+use uom::si::time::day;
+
+/// The hours of the day that are worked, e.g. `start: 9` and `end: 17`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorkingHours {
+    /// The hour work starts at
+    pub start: u32,
+    /// The hour work ends at
+    pub end: u32,
+}
+
+/// The days of the week that are worked, e.g. `[Sun, Mon, Tue, Wed, Thu]`, and optionally the
+/// hours worked on each of them. Without a `reporting-timezone` the days and hours are in the
+/// timezone `utc-offset-hours` from UTC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorkingWeek {
+    /// The days that are worked
+    pub days: Vec<Weekday>,
+    /// The hours worked on each day, the whole day when it is not set
+    pub working_hours: Option<WorkingHours>,
+    /// The offset of the timezone from UTC, used when there is no `reporting-timezone`
+    #[serde(default)]
+    pub utc_offset_hours: i32,
+}
+
+/// How working time is measured
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Calendar {
+    /// Working days, or fractions of them, in a working week. Without one time is measured in
+    /// whole working days on the US settlement calendar.
+    working_week: Option<WorkingWeek>,
+    /// The timezone the days start and end in
+    timezone: Timezone,
+}
+
+/// The days from `start` up to, but not including, `end`
+fn days(start: NaiveDate, end: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    std::iter::successors(Some(start), NaiveDate::succ_opt).take_while(move |date| *date < end)
+}
+
+impl WorkingWeek {
+    fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.days.contains(&date.weekday())
+    }
+
+    /// The whole working days from the day `start` is on up to the day `end` is on
+    fn working_days(&self, timezone: Timezone, start: &DateTime<Utc>, end: &DateTime<Utc>) -> Time {
+        let count = days(timezone.date(start), timezone.date(end))
+            .filter(|date| self.is_working_day(*date))
+            .count();
+        #[allow(clippy::cast_precision_loss)]
+        Time::new::<day>(count as f64)
+    }
+
+    /// The working time between `start` and `end` as a fraction of the working day
+    fn working_time(
+        &self,
+        timezone: Timezone,
+        hours: WorkingHours,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Time {
+        let day_length = Duration::hours(i64::from(hours.end.saturating_sub(hours.start)));
+        if day_length <= Duration::zero() {
+            return Time::new::<day>(0.0);
+        }
+        let last_day = timezone.date(end);
+        let worked = days(timezone.date(start), last_day)
+            .chain(std::iter::once(last_day))
+            .filter(|date| self.is_working_day(*date))
+            .filter_map(|date| {
+                // The hours are local, so on the day the clocks change they are not a fixed time
+                // after midnight
+                let midnight = date.and_hms_opt(0, 0, 0)?;
+                let from = timezone
+                    .instant(&(midnight + Duration::hours(i64::from(hours.start))))
+                    .max(*start);
+                let to = timezone
+                    .instant(&(midnight + Duration::hours(i64::from(hours.end))))
+                    .min(*end);
+                Some(to - from).filter(|worked| *worked > Duration::zero())
+            })
+            .fold(Duration::zero(), |total, worked| total + worked);
+        #[allow(clippy::cast_precision_loss)]
+        Time::new::<day>(worked.num_seconds() as f64 / day_length.num_seconds() as f64)
+    }
+}
+
+impl Calendar {
+    /// The calendar of `working_week`, or the US settlement calendar without one, counting days
+    /// in `timezone`. Without a timezone the `utc-offset-hours` of the working week is used, or
+    /// UTC.
+    #[must_use]
+    pub fn new(working_week: Option<&WorkingWeek>, timezone: Option<&Timezone>) -> Self {
+        let timezone = match (timezone, working_week) {
+            (Some(timezone), _) => *timezone,
+            (None, Some(week)) => Timezone::fixed(week.utc_offset_hours),
+            (None, None) => Timezone::default(),
+        };
+        Calendar {
+            working_week: working_week.cloned(),
+            timezone,
+        }
+    }
+
+    /// The date `instant` falls on in the timezone of the calendar
+    #[must_use]
+    pub fn date(&self, instant: &DateTime<Utc>) -> NaiveDate {
+        self.timezone.date(instant)
+    }
+
+    /// When `date` starts in the timezone of the calendar
+    #[must_use]
+    pub fn start_of(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.timezone.instant(&date.and_hms(0, 0, 0))
+    }
+
+    /// The working time between `start` and `end`
+    #[must_use]
+    pub fn working_time(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> Time {
+        let timezone = self.timezone;
+        match &self.working_week {
+            None => {
+                let cal = bdays::calendars::us::USSettlement;
+                let days = cal.bdays(timezone.date(start), timezone.date(end));
+                Time::new::<day>(f64::from(days))
+            }
+            Some(week) => match week.working_hours {
+                Some(hours) => week.working_time(timezone, hours, start, end),
+                None => week.working_days(timezone, start, end),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,171 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Working time in working weeks with and without working hours, around the edges of the working
+//! day and week and across the change to daylight saving time in Berlin at 01:00 UTC on March
+//! 28th 2021, a Sunday.
+use super::{Calendar, WorkingHours, WorkingWeek};
+use crate::lib::timezone::Timezone;
+use chrono::{DateTime, TimeZone, Utc, Weekday};
+use uom::si::time;
+
+const MONDAY_TO_FRIDAY: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+fn utc(month: u32, day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+    Utc.ymd(2021, month, day).and_hms(hour, min, 0)
+}
+
+fn week(days: &[Weekday], hours: Option<(u32, u32)>) -> WorkingWeek {
+    WorkingWeek {
+        days: days.to_vec(),
+        working_hours: hours.map(|(start, end)| WorkingHours { start, end }),
+        utc_offset_hours: 0,
+    }
+}
+
+fn berlin() -> Timezone {
+    "Europe/Berlin".parse().expect("a timezone")
+}
+
+fn working_days(calendar: &Calendar, start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    calendar.working_time(&start, &end).get::<time::day>()
+}
+
+fn assert_days(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} working days, expected {}",
+        actual,
+        expected
+    );
+}
+
+/// Only the hours after the start on the first day and before the end on the last day count,
+/// 13:00 to 17:00 on Monday, all of Tuesday and 09:00 to 11:00 on Wednesday
+#[test]
+fn counts_part_of_the_first_and_last_day() {
+    let calendar = Calendar::new(Some(&week(&MONDAY_TO_FRIDAY, Some((9, 17)))), None);
+    assert_days(
+        working_days(&calendar, utc(6, 7, 13, 0), utc(6, 9, 11, 0)),
+        1.75,
+    );
+    assert_days(
+        working_days(&calendar, utc(6, 7, 10, 0), utc(6, 7, 12, 0)),
+        0.25,
+    );
+    assert_days(
+        working_days(&calendar, utc(6, 7, 6, 0), utc(6, 7, 20, 0)),
+        1.0,
+    );
+    assert_days(
+        working_days(&calendar, utc(6, 7, 18, 0), utc(6, 8, 8, 0)),
+        0.0,
+    );
+}
+
+/// Without working hours whole days are counted, from the day of the start up to the day of the
+/// end
+#[test]
+fn counts_whole_days_without_working_hours() {
+    let calendar = Calendar::new(Some(&week(&MONDAY_TO_FRIDAY, None)), None);
+    assert_days(
+        working_days(&calendar, utc(6, 7, 13, 0), utc(6, 9, 11, 0)),
+        2.0,
+    );
+    assert_days(
+        working_days(&calendar, utc(6, 7, 10, 0), utc(6, 7, 12, 0)),
+        0.0,
+    );
+}
+
+/// From 15:00 on Friday to 10:00 on Monday is two hours of Friday and one of Monday, the weekend
+/// is not worked
+#[test]
+fn skips_days_that_are_not_worked() {
+    let hours = Calendar::new(Some(&week(&MONDAY_TO_FRIDAY, Some((9, 17)))), None);
+    assert_days(
+        working_days(&hours, utc(6, 11, 15, 0), utc(6, 14, 10, 0)),
+        0.375,
+    );
+    let days = Calendar::new(Some(&week(&MONDAY_TO_FRIDAY, None)), None);
+    assert_days(
+        working_days(&days, utc(6, 11, 15, 0), utc(6, 14, 10, 0)),
+        1.0,
+    );
+
+    let sunday_to_thursday = [
+        Weekday::Sun,
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+    ];
+    let calendar = Calendar::new(Some(&week(&sunday_to_thursday, Some((9, 17)))), None);
+    assert_days(
+        working_days(&calendar, utc(6, 10, 9, 0), utc(6, 13, 17, 0)),
+        2.0,
+    );
+}
+
+/// The clocks go from 02:00 to 03:00 in Berlin, so midnight to 08:00 on that Sunday is only seven
+/// hours of the eight hour working day
+#[test]
+fn counts_the_hours_that_pass_on_the_day_the_clocks_change() {
+    let every_day = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+    let night = Calendar::new(Some(&week(&every_day, Some((0, 8)))), Some(&berlin()));
+    assert_days(
+        working_days(&night, utc(3, 27, 22, 0), utc(3, 28, 12, 0)),
+        0.875,
+    );
+
+    // 09:00 to 17:00 is after the change, so it is still a whole day
+    let day_shift = Calendar::new(Some(&week(&every_day, Some((9, 17)))), Some(&berlin()));
+    assert_days(
+        working_days(&day_shift, utc(3, 28, 0, 0), utc(3, 29, 0, 0)),
+        1.0,
+    );
+}
+
+/// 22:30 UTC on the Sunday is 00:30 on Monday in Berlin once the clocks have gone forward, so the
+/// whole of Sunday is counted
+#[test]
+fn counts_days_in_the_offset_after_the_clocks_change() {
+    let sunday = Calendar::new(Some(&week(&[Weekday::Sun], None)), Some(&berlin()));
+    assert_days(
+        working_days(&sunday, utc(3, 26, 23, 30), utc(3, 28, 22, 30)),
+        1.0,
+    );
+    let winter = Calendar::new(
+        Some(&week(&[Weekday::Sun], None)),
+        Some(&Timezone::fixed(1)),
+    );
+    assert_days(
+        working_days(&winter, utc(3, 26, 23, 30), utc(3, 28, 22, 30)),
+        0.0,
+    );
+}
@@ -0,0 +1,66 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Cancellation
+//!
+//! Commands that pull a lot of issues can take a long time. Rather than losing everything when
+//! they are interrupted, the first Ctrl-C cancels a [`Token`], the page being pulled is dropped
+//! and the command writes out what it already has. A second Ctrl-C exits immediately.
+use tokio::signal;
+use tokio::sync::watch;
+use tracing::warn;
+
+/// The exit code of a process killed by SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Tells whether the command has been cancelled
+#[derive(Debug, Clone)]
+pub struct Token {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Token {
+    /// Whether the token was cancelled
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Wait until the command is cancelled. If that can no longer happen this never returns.
+    pub async fn cancelled(&mut self) {
+        while !self.is_cancelled() {
+            if self.receiver.changed().await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+/// Cancel the returned token on the first Ctrl-C, and exit on the second
+#[must_use]
+pub fn on_ctrl_c() -> Token {
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        if signal::ctrl_c().await.is_ok() {
+            warn!("Interrupted, writing out what has been pulled. Interrupt again to stop now");
+            if sender.send(true).is_err() {
+                return;
+            }
+            if signal::ctrl_c().await.is_ok() {
+                std::process::exit(INTERRUPTED_EXIT_CODE);
+            }
+        }
+    });
+    Token { receiver }
+}
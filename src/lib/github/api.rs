@@ -0,0 +1,300 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # GitHub Api Integration
+//!
+//! Pulls issues and their timelines from the GitHub rest api. Like the jira api this knows
+//! nothing about the core model.
+//!
+//! The search api returns at most 1000 results for a query, so larger result sets have to be
+//! split into several queries, e.g. by creation date.
+use crate::lib::github::native;
+use crate::lib::rest;
+use futures::stream::{self, BoxStream, StreamExt};
+use futures::Future;
+use snafu::{OptionExt, ResultExt, Snafu};
+use tracing::{info, instrument, warn};
+
+/// The ways talking to the github api can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// A request could not be built for the path
+    #[snafu(display("Unable to build request for {}: {}", path, source))]
+    UnableToBuildRequest {
+        /// The path of the request
+        path: String,
+        /// Why it could not be built
+        source: rest::Error,
+    },
+    /// A page of the issues matching a search could not be pulled
+    #[snafu(display("Could not search issues with {}, page {}: {}", query, page, source))]
+    CouldNotSearchIssues {
+        /// The search query
+        query: String,
+        /// The number of the page
+        page: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// An issue could not be pulled
+    #[snafu(display("Could not get issue {}: {}", issue, source))]
+    CouldNotGetIssue {
+        /// The issue, as `owner/name#number`
+        issue: String,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// A page of the timeline of an issue could not be pulled
+    #[snafu(display("Could not get the timeline of {}, page {}: {}", issue, page, source))]
+    CouldNotGetTimeline {
+        /// The issue, as `owner/name#number`
+        issue: String,
+        /// The number of the page
+        page: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// The repository url of an issue does not end in an owner and a name
+    #[snafu(display("Could not find the repository in {}", url))]
+    InvalidRepositoryUrl {
+        /// The repository url
+        url: String,
+    },
+    /// Github kept failing until the retry budget of the client ran out
+    #[snafu(display(
+        "Gave up on github after {} attempts over {}s, the retry budget is exhausted: {}",
+        attempts,
+        elapsed_seconds,
+        source
+    ))]
+    RetryBudgetExhausted {
+        /// The number of requests made
+        attempts: u32,
+        /// How long was spent retrying
+        elapsed_seconds: u64,
+        /// The error of the last request
+        source: Box<Error>,
+    },
+    /// The circuit breaker of the client is open, so no request was made
+    #[snafu(display(
+        "Not calling github, the circuit breaker is open after {} failed requests in a row",
+        consecutive_failures
+    ))]
+    CircuitOpen {
+        /// The number of requests in a row that failed
+        consecutive_failures: u32,
+    },
+}
+
+/// The most results GitHub returns in a single page
+const PAGE_SIZE: u64 = 100;
+
+/// The most results the search api returns for a single query
+const SEARCH_LIMIT: u64 = 1000;
+
+const ACCEPT: &str = "application/vnd.github.v3+json";
+
+/// Retry `operation` within the retry budget of the client, see [`rest::retry`]
+async fn retry<T, F, Fut>(client: &rest::Client, operation: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    rest::retry(client, operation)
+        .await
+        .map_err(|failure| match failure {
+            rest::RetryFailure::BudgetExhausted {
+                attempts,
+                elapsed,
+                source,
+            } => Error::RetryBudgetExhausted {
+                attempts,
+                elapsed_seconds: elapsed.as_secs(),
+                source: Box::new(source),
+            },
+            rest::RetryFailure::CircuitOpen {
+                consecutive_failures,
+            } => Error::CircuitOpen {
+                consecutive_failures,
+            },
+            rest::RetryFailure::Rejected { source } => source,
+        })
+}
+
+/// The `owner/name` of the repository an issue belongs to
+///
+/// # Errors
+///
+/// Fails with [`Error::InvalidRepositoryUrl`] when the repository url of the issue does not end
+/// in an owner and a name.
+pub fn repository(issue: &native::Issue) -> Result<String, Error> {
+    let mut segments = issue
+        .repository_url
+        .path_segments()
+        .context(InvalidRepositoryUrl {
+            url: issue.repository_url.as_str(),
+        })?
+        .rev();
+    match (segments.next(), segments.next()) {
+        (Some(name), Some(owner)) if !name.is_empty() => Ok(format!("{}/{}", owner, name)),
+        _ => InvalidRepositoryUrl {
+            url: issue.repository_url.as_str(),
+        }
+        .fail(),
+    }
+}
+
+#[instrument(skip(client))]
+async fn search_issues_page(
+    client: &rest::Client,
+    query: &str,
+    page: u64,
+) -> Result<native::Search, Error> {
+    retry(client, || async {
+        let path = "/search/issues";
+        rest::get(client, path)
+            .context(UnableToBuildRequest { path })?
+            .header(reqwest::header::ACCEPT, ACCEPT)
+            .query(&[
+                ("q", query),
+                ("per_page", &PAGE_SIZE.to_string()),
+                ("page", &page.to_string()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotSearchIssues { query, page })?
+            .json::<native::Search>()
+            .await
+            .context(CouldNotSearchIssues { query, page })
+    })
+    .await
+}
+
+/// Page through the issues matching the search `query`, e.g. `repo:octocat/hello-world
+/// is:issue`. Paging stops at an empty or short page, or once every result has been returned.
+pub fn search_issues<'a>(
+    client: &'a rest::Client,
+    query: &'a str,
+) -> BoxStream<'a, Result<Vec<native::Issue>, Error>> {
+    stream::try_unfold((1, 0, false), move |(page, returned, done)| async move {
+        if done {
+            return Ok(None);
+        }
+        let search = search_issues_page(client, query, page).await?;
+        if page == 1 && search.total_count > SEARCH_LIMIT {
+            warn!(
+                "{} issues match {}, only the first {} are returned by github",
+                search.total_count, query, SEARCH_LIMIT
+            );
+        }
+        if search.incomplete_results {
+            warn!(
+                "github timed out searching {}, results may be missing",
+                query
+            );
+        }
+        let len = search.items.len() as u64;
+        let returned = returned + len;
+        let done = len < PAGE_SIZE || returned >= search.total_count.min(SEARCH_LIMIT);
+        Ok(Some((search.items, (page + 1, returned, done))))
+    })
+    .boxed()
+}
+
+/// Pull a single issue of `repository`, which is `owner/name`
+///
+/// # Errors
+///
+/// Fails when the issue can't be pulled within the retry budget of the client.
+#[instrument(skip(client))]
+pub async fn get_issue(
+    client: &rest::Client,
+    repository: &str,
+    number: u64,
+) -> Result<native::Issue, Error> {
+    retry(client, || async {
+        let path = format!("/repos/{}/issues/{}", repository, number);
+        let issue = format!("{}#{}", repository, number);
+        rest::get(client, &path)
+            .context(UnableToBuildRequest { path: path.clone() })?
+            .header(reqwest::header::ACCEPT, ACCEPT)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetIssue {
+                issue: issue.clone(),
+            })?
+            .json::<native::Issue>()
+            .await
+            .context(CouldNotGetIssue { issue })
+    })
+    .await
+}
+
+#[instrument(skip(client))]
+async fn get_timeline_page(
+    client: &rest::Client,
+    repository: &str,
+    number: u64,
+    page: u64,
+) -> Result<Vec<native::TimelineEvent>, Error> {
+    retry(client, || async {
+        let path = format!("/repos/{}/issues/{}/timeline", repository, number);
+        let issue = format!("{}#{}", repository, number);
+        rest::get(client, &path)
+            .context(UnableToBuildRequest { path: path.clone() })?
+            .header(reqwest::header::ACCEPT, ACCEPT)
+            .query(&[
+                ("per_page", &PAGE_SIZE.to_string()),
+                ("page", &page.to_string()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetTimeline {
+                issue: issue.clone(),
+                page,
+            })?
+            .json::<Vec<native::TimelineEvent>>()
+            .await
+            .context(CouldNotGetTimeline { issue, page })
+    })
+    .await
+}
+
+/// Pull every event in the timeline of an issue, oldest first
+///
+/// # Errors
+///
+/// Fails when the repository of the issue can't be found or a page of the timeline can't be pulled.
+#[instrument(skip(client, issue), fields(number = issue.number))]
+pub async fn get_timeline(
+    client: &rest::Client,
+    issue: &native::Issue,
+) -> Result<Vec<native::TimelineEvent>, Error> {
+    let repository = repository(issue)?;
+    info!("get timeline for {}#{}", repository, issue.number);
+
+    let mut events = Vec::new();
+    for page in 1.. {
+        let page_events = get_timeline_page(client, &repository, issue.number, page).await?;
+        let len = page_events.len() as u64;
+        events.extend(page_events);
+        if len < PAGE_SIZE {
+            break;
+        }
+    }
+    Ok(events)
+}
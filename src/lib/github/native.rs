@@ -0,0 +1,130 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # GitHub Api Description
+//!
+//! The types that represent the parts of the GitHub rest api that lectev uses, the issue search
+//! and the issue timeline. Only the fields we need are described, serde ignores the rest.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// A github user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    /// The login of the user
+    pub login: String,
+}
+
+/// A label of an issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    /// The name of the label
+    pub name: String,
+}
+
+/// Whether an issue is open
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueState {
+    /// The issue is open
+    Open,
+    /// The issue is closed
+    Closed,
+}
+
+/// An issue, as it is returned by the search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Issue {
+    /// The number of the issue in its repository
+    pub number: u64,
+    /// The title of the issue
+    pub title: String,
+    /// The url of the issue on github
+    pub html_url: Url,
+    /// The api url of the repository, e.g. `https://api.github.com/repos/octocat/hello-world`
+    pub repository_url: Url,
+    /// Whether the issue is open
+    pub state: IssueState,
+    /// Why the issue was closed, `completed` or `not_planned`
+    pub state_reason: Option<String>,
+    /// The labels of the issue
+    pub labels: Vec<Label>,
+    /// Who the issue is assigned to
+    pub assignee: Option<User>,
+    /// When the issue was created
+    pub created_at: DateTime<Utc>,
+    /// When the issue was closed, if it is
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// A page of the issues matching a search
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Search {
+    /// The number of issues across every page
+    pub total_count: u64,
+    /// Whether github gave up on the search before it found every issue
+    pub incomplete_results: bool,
+    /// The issues of the page
+    pub items: Vec<Issue>,
+}
+
+/// A single event in the timeline of an issue. Only the events that change the status or
+/// assignee of an issue are described, every other event is `Other`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum TimelineEvent {
+    /// A label was added
+    Labeled {
+        /// When the label was added
+        created_at: DateTime<Utc>,
+        /// The label
+        label: Label,
+    },
+    /// A label was removed
+    Unlabeled {
+        /// When the label was removed
+        created_at: DateTime<Utc>,
+        /// The label
+        label: Label,
+    },
+    /// The issue was closed
+    Closed {
+        /// When the issue was closed
+        created_at: DateTime<Utc>,
+    },
+    /// The issue was reopened
+    Reopened {
+        /// When the issue was reopened
+        created_at: DateTime<Utc>,
+    },
+    /// Someone was assigned to the issue
+    Assigned {
+        /// When they were assigned
+        created_at: DateTime<Utc>,
+        /// Who was assigned
+        assignee: User,
+    },
+    /// Someone was unassigned from the issue
+    Unassigned {
+        /// When they were unassigned
+        created_at: DateTime<Utc>,
+        /// Who was unassigned
+        assignee: User,
+    },
+    /// Any other event
+    #[serde(other)]
+    Other,
+}
@@ -0,0 +1,185 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # GitHub To Core Translation
+//!
+//! GitHub issues only have two states, open and closed, so the status of an issue is taken from
+//! its labels using the `label-mapping` of the config. The status history is rebuilt from the
+//! `labeled`, `unlabeled`, `closed` and `reopened` events of the timeline of the issue:
+//!
+//! * a closed issue is `Completed`
+//! * an open issue has the status of the most recently added mapped label it still has
+//! * an open issue with no mapped label is `ToDo`
+use crate::configs::github;
+use crate::lib::github::{api, native};
+use crate::lib::jira::core;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Tracks the status of an issue as the events of its timeline are applied
+#[derive(Debug)]
+struct StatusTracker<'a> {
+    conf: &'a github::Config,
+    /// The mapped labels the issue currently has, in the order they were added
+    labels: Vec<String>,
+    closed: bool,
+    open_entry: (core::ItemStatus, DateTime<Utc>),
+    timeline: Vec<core::ItemTimeLineEntry>,
+}
+
+impl<'a> StatusTracker<'a> {
+    fn new(conf: &'a github::Config, created: DateTime<Utc>) -> Self {
+        StatusTracker {
+            conf,
+            labels: Vec::new(),
+            closed: false,
+            open_entry: (core::ItemStatus::ToDo, created),
+            timeline: Vec::new(),
+        }
+    }
+
+    fn status(&self) -> core::ItemStatus {
+        if self.closed {
+            return core::ItemStatus::Completed;
+        }
+        self.labels
+            .last()
+            .and_then(|label| self.conf.label_mapping.get(label))
+            .copied()
+            .unwrap_or(core::ItemStatus::ToDo)
+    }
+
+    /// Close the open status and start a new one if applying an event changed the status
+    fn transition(&mut self, at: DateTime<Utc>) {
+        let status = self.status();
+        if status != self.open_entry.0 {
+            let (previous, start) = std::mem::replace(&mut self.open_entry, (status, at));
+            self.timeline.push(core::ItemTimeLineEntry::ClosedStatus {
+                status: previous,
+                start,
+                end: at,
+            });
+        }
+    }
+
+    fn apply(&mut self, event: &native::TimelineEvent) {
+        match event {
+            native::TimelineEvent::Labeled { created_at, label } => {
+                if self.conf.label_mapping.contains_key(&label.name) {
+                    self.labels.retain(|existing| *existing != label.name);
+                    self.labels.push(label.name.clone());
+                    self.transition(*created_at);
+                }
+            }
+            native::TimelineEvent::Unlabeled { created_at, label } => {
+                self.labels.retain(|existing| *existing != label.name);
+                self.transition(*created_at);
+            }
+            native::TimelineEvent::Closed { created_at } => {
+                self.closed = true;
+                self.transition(*created_at);
+            }
+            native::TimelineEvent::Reopened { created_at } => {
+                self.closed = false;
+                self.transition(*created_at);
+            }
+            native::TimelineEvent::Assigned {
+                created_at,
+                assignee,
+            } => self.timeline.push(core::ItemTimeLineEntry::Reassigned {
+                start: *created_at,
+                from: None,
+                to: Some(assignee.login.clone()),
+            }),
+            native::TimelineEvent::Unassigned {
+                created_at,
+                assignee,
+            } => self.timeline.push(core::ItemTimeLineEntry::Reassigned {
+                start: *created_at,
+                from: Some(assignee.login.clone()),
+                to: None,
+            }),
+            native::TimelineEvent::Other => {}
+        }
+    }
+
+    fn finish(mut self) -> Vec<core::ItemTimeLineEntry> {
+        let (status, start) = self.open_entry;
+        self.timeline
+            .push(core::ItemTimeLineEntry::OpenStatus { status, start });
+        self.timeline
+    }
+}
+
+fn resolution(issue: &native::Issue) -> core::Resolution {
+    match (issue.state, issue.state_reason.as_deref()) {
+        (native::IssueState::Open, _) => core::Resolution::UnResolved,
+        (native::IssueState::Closed, Some("not_planned")) => core::Resolution::Rejected,
+        (native::IssueState::Closed, _) => core::Resolution::Delivered,
+    }
+}
+
+/// Translate an issue, along with its timeline, into an item. The name of the item is
+/// `owner/name#number`.
+///
+/// # Errors
+///
+/// Fails with [`api::Error::InvalidRepositoryUrl`] when the repository of the issue can't be found.
+pub fn translate(
+    conf: &github::Config,
+    issue: &native::Issue,
+    timeline: &[native::TimelineEvent],
+) -> Result<core::Item, api::Error> {
+    let repository = api::repository(issue)?;
+    let mut status = StatusTracker::new(conf, issue.created_at);
+    for event in timeline {
+        status.apply(event);
+    }
+    let labels: Vec<String> = issue
+        .labels
+        .iter()
+        .map(|label| label.name.clone())
+        .collect();
+    let typ = if labels
+        .iter()
+        .any(|label| conf.operational_labels.contains(label))
+    {
+        core::ItemType::Operational
+    } else {
+        core::ItemType::Feature
+    };
+
+    Ok(core::Item {
+        id: core::ItemId(Uuid::new_v4()),
+        native_id: core::NativeId(format!("{}#{}", repository, issue.number)),
+        native_url: issue.html_url.clone(),
+        name: format!("{}#{}", repository, issue.number),
+        description: issue.title.clone(),
+        typ,
+        status: status.status(),
+        resolution: resolution(issue),
+        timeline: status.finish(),
+        assignee: issue.assignee.as_ref().map(|user| user.login.clone()),
+        team: None,
+        links: Vec::new(),
+        created: Some(issue.created_at),
+        resolved: issue.closed_at,
+        project: Some(repository),
+        labels,
+        components: Vec::new(),
+        parent: None,
+        body: None,
+        rendered_body: None,
+    })
+}
@@ -0,0 +1,126 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # GitHub Tracker
+//!
+//! The [`Tracker`] for GitHub issues. Each page of search results is followed by a request for
+//! the timeline of every issue in it, which is what the status history is rebuilt from.
+use crate::configs::github;
+use crate::lib::cancel;
+use crate::lib::github::{api, native, nativetocore};
+use crate::lib::jira::core;
+use crate::lib::rest;
+use crate::lib::tracker::{Error, Tracker};
+use futures::future::{self, BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+/// The number of issue timelines that are pulled at the same time
+const TIMELINE_CONCURRENCY: usize = 8;
+
+/// Pulls items from GitHub
+#[derive(Debug)]
+pub struct GithubTracker<'a> {
+    conf: &'a github::Config,
+    client: rest::Client,
+    cancel: Option<cancel::Token>,
+}
+
+impl<'a> GithubTracker<'a> {
+    /// A tracker of the issues of github, pulled with `client`, translated with `conf`
+    pub fn new(conf: &'a github::Config, client: rest::Client) -> Self {
+        GithubTracker {
+            conf,
+            client,
+            cancel: None,
+        }
+    }
+
+    /// Stop pulling items once `cancel` is cancelled. The page being pulled at the time is
+    /// dropped and [`Error::Cancelled`] is returned.
+    #[must_use]
+    pub fn with_cancellation(mut self, cancel: cancel::Token) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .map_or(false, cancel::Token::is_cancelled)
+    }
+
+    async fn to_item(&self, issue: native::Issue) -> Result<core::Item, Error> {
+        let timeline = api::get_timeline(&self.client, &issue)
+            .await
+            .map_err(|source| Error::Github { source })?;
+        nativetocore::translate(self.conf, &issue, &timeline)
+            .map_err(|source| Error::Github { source })
+    }
+}
+
+impl Tracker for GithubTracker<'_> {
+    fn items<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<Vec<core::Item>, Error>> {
+        api::search_issues(&self.client, query)
+            .map_err(|source| Error::Github { source })
+            .and_then(move |issues| async move {
+                if self.is_cancelled() {
+                    return Err(Error::Cancelled);
+                }
+                stream::iter(issues)
+                    .map(|issue| self.to_item(issue))
+                    .buffered(TIMELINE_CONCURRENCY)
+                    .try_collect()
+                    .await
+            })
+            .boxed()
+    }
+
+    fn status_history<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::ItemTimeLineEntry>, Error>> {
+        async move {
+            let no_such_item = || Error::NoSuchItem {
+                native_id: native_id.clone(),
+            };
+            let (repository, number) = native_id.0.split_once('#').ok_or_else(no_such_item)?;
+            let number = number.parse().map_err(|_| no_such_item())?;
+            let issue = api::get_issue(&self.client, repository, number)
+                .await
+                .map_err(|source| Error::Github { source })?;
+            Ok(self
+                .to_item(issue)
+                .await?
+                .timeline
+                .into_iter()
+                .filter(|entry| {
+                    matches!(
+                        entry,
+                        core::ItemTimeLineEntry::ClosedStatus { .. }
+                            | core::ItemTimeLineEntry::OpenStatus { .. }
+                    )
+                })
+                .collect())
+        }
+        .boxed()
+    }
+
+    /// GitHub issues have no parent, so no item has children
+    fn children<'a>(
+        &'a self,
+        _native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::Item>, Error>> {
+        future::ready(Ok(Vec::new())).boxed()
+    }
+}
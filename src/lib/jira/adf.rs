@@ -0,0 +1,253 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Atlassian Document Format
+//!
+//! Jira Cloud returns rich text fields, like the description of an issue, as a tree of nodes in
+//! the Atlassian Document Format. This renders that tree as plain text, for reports, or as
+//! markdown, which keeps the headings, lists, tables, links and emphasis. Jira Server returns the
+//! description as a string of wiki markup, which is used as it is.
+use crate::lib::jira::native::{Description, DescriptionPart};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+
+/// How a description is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// Plain text, the structure of the document is dropped
+    Plain,
+    /// Markdown, keeping the headings, lists, links and tables
+    Markdown,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Plain
+    }
+}
+
+impl Format {
+    /// The name of the format, as used in the config
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Plain => "plain",
+            Format::Markdown => "markdown",
+        }
+    }
+}
+
+/// Render a description in the given format
+#[must_use]
+pub fn render(description: &Description, format: Format) -> String {
+    match description {
+        Description::String(text) => text.clone(),
+        Description::Complex { content, .. } => blocks(format, content),
+    }
+}
+
+fn attr<'a>(attrs: &'a Option<Value>, name: &str) -> Option<&'a Value> {
+    attrs.as_ref().and_then(|attrs| attrs.get(name))
+}
+
+fn attr_str<'a>(attrs: &'a Option<Value>, name: &str) -> Option<&'a str> {
+    attr(attrs, name).and_then(Value::as_str)
+}
+
+fn children(part: &DescriptionPart) -> &[DescriptionPart] {
+    part.content.as_deref().unwrap_or(&[])
+}
+
+/// Prefixes the first line of the text with `first` and every other line with `rest`
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let prefix = if index == 0 { first } else { rest };
+            format!("{}{}", prefix, line).trim_end().to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Markdown needs a blank line between blocks, plain text is easier to read in a cell without
+fn blocks(format: Format, parts: &[DescriptionPart]) -> String {
+    match format {
+        Format::Plain => join_blocks(format, parts, "\n"),
+        Format::Markdown => join_blocks(format, parts, "\n\n"),
+    }
+}
+
+fn join_blocks(format: Format, parts: &[DescriptionPart], separator: &str) -> String {
+    parts
+        .iter()
+        .map(|part| block(format, part))
+        .filter(|rendered| !rendered.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// The blocks of a list item are kept together so the list stays tight
+fn list(format: Format, part: &DescriptionPart, ordered: bool) -> String {
+    let start = attr(&part.attrs, "order")
+        .and_then(Value::as_u64)
+        .unwrap_or(1);
+    children(part)
+        .iter()
+        .zip(start..)
+        .map(|(item, number)| {
+            let marker = if ordered {
+                format!("{}. ", number)
+            } else {
+                "- ".to_owned()
+            };
+            let indent = " ".repeat(marker.len());
+            prefix_lines(&join_blocks(format, children(item), "\n"), &marker, &indent)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn code_block(format: Format, part: &DescriptionPart) -> String {
+    let code = inline(Format::Plain, children(part));
+    match format {
+        Format::Plain => code,
+        Format::Markdown => format!(
+            "```{}\n{}\n```",
+            attr_str(&part.attrs, "language").unwrap_or(""),
+            code
+        ),
+    }
+}
+
+/// A table as a row per line, with the cells separated by `|`. Markdown tables need a header,
+/// so the first row is always used as one.
+fn table(format: Format, part: &DescriptionPart) -> String {
+    let rows: Vec<Vec<String>> = children(part)
+        .iter()
+        .map(|row| {
+            children(row)
+                .iter()
+                .map(|cell| join_blocks(format, children(cell), " ").replace('\n', " "))
+                .collect()
+        })
+        .collect();
+    let mut lines = Vec::new();
+    for (index, cells) in rows.iter().enumerate() {
+        match format {
+            Format::Plain => lines.push(cells.join(" | ")),
+            Format::Markdown => {
+                lines.push(format!("| {} |", cells.join(" | ")));
+                if index == 0 {
+                    lines.push(format!("|{}", " --- |".repeat(cells.len())));
+                }
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn block(format: Format, part: &DescriptionPart) -> String {
+    match (part.typ.as_str(), format) {
+        ("paragraph", _) | ("heading", Format::Plain) => inline(format, children(part)),
+        ("heading", Format::Markdown) => {
+            let level = attr(&part.attrs, "level")
+                .and_then(Value::as_u64)
+                .and_then(|level| usize::try_from(level).ok())
+                .unwrap_or(1);
+            format!("{} {}", "#".repeat(level), inline(format, children(part)))
+        }
+        ("bulletList", _) => list(format, part, false),
+        ("orderedList", _) => list(format, part, true),
+        ("codeBlock", _) => code_block(format, part),
+        ("blockquote", Format::Markdown) => {
+            prefix_lines(&blocks(format, children(part)), "> ", "> ")
+        }
+        ("rule", Format::Markdown) => "---".to_owned(),
+        ("rule", Format::Plain) => String::new(),
+        ("table", _) => table(format, part),
+        // Panels, expands, layouts and anything newer are rendered as the blocks they contain
+        _ if part.content.is_some() => blocks(format, children(part)),
+        _ => inline(format, std::slice::from_ref(part)),
+    }
+}
+
+/// Applies the marks of a text node, the innermost first so a link wraps any emphasis
+fn marked(part: &DescriptionPart, text: &str) -> String {
+    let has = |typ: &str| part.marks.iter().any(|mark| mark.typ == typ);
+    let mut text = text.to_owned();
+    if has("code") {
+        text = format!("`{}`", text);
+    }
+    if has("strike") {
+        text = format!("~~{}~~", text);
+    }
+    if has("em") {
+        text = format!("*{}*", text);
+    }
+    if has("strong") {
+        text = format!("**{}**", text);
+    }
+    if let Some(href) = part
+        .marks
+        .iter()
+        .find(|mark| mark.typ == "link")
+        .and_then(|mark| attr_str(&mark.attrs, "href"))
+    {
+        text = format!("[{}]({})", text, href);
+    }
+    text
+}
+
+/// Dates are stored as milliseconds since the epoch, in a string
+fn date(part: &DescriptionPart) -> String {
+    attr_str(&part.attrs, "timestamp")
+        .and_then(|timestamp| timestamp.parse::<i64>().ok())
+        .and_then(|millis| NaiveDateTime::from_timestamp_opt(millis.div_euclid(1000), 0))
+        .map_or_else(String::new, |date| date.format("%Y-%m-%d").to_string())
+}
+
+fn inline(format: Format, parts: &[DescriptionPart]) -> String {
+    parts
+        .iter()
+        .map(|part| match (part.typ.as_str(), format) {
+            ("text", Format::Plain) => part.text.clone().unwrap_or_default(),
+            ("text", Format::Markdown) => marked(part, part.text.as_deref().unwrap_or("")),
+            ("hardBreak", Format::Plain) => "\n".to_owned(),
+            // A backslash before the newline keeps the break, rather than joining the lines
+            ("hardBreak", Format::Markdown) => "\\\n".to_owned(),
+            ("mention" | "status", _) => {
+                attr_str(&part.attrs, "text").unwrap_or_default().to_owned()
+            }
+            ("emoji", _) => attr_str(&part.attrs, "text")
+                .or_else(|| attr_str(&part.attrs, "shortName"))
+                .unwrap_or_default()
+                .to_owned(),
+            ("inlineCard" | "blockCard" | "embedCard", _) => {
+                attr_str(&part.attrs, "url").unwrap_or_default().to_owned()
+            }
+            ("date", _) => date(part),
+            _ => part
+                .text
+                .clone()
+                .unwrap_or_else(|| inline(format, children(part))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
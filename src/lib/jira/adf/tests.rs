@@ -0,0 +1,219 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Rendering Atlassian Document Format descriptions as plain text and markdown.
+use super::{render, Format};
+use crate::lib::jira::native::Description;
+use serde_json::{json, Value};
+
+fn description(value: Value) -> Description {
+    serde_json::from_value(value).expect("a description")
+}
+
+fn text(text: &str) -> Value {
+    json!({ "type": "text", "text": text })
+}
+
+fn node(typ: &str, content: Value) -> Value {
+    let mut node = json!({ "type": typ });
+    node["content"] = content;
+    node
+}
+
+fn paragraph(content: Value) -> Value {
+    node("paragraph", content)
+}
+
+fn list_item(content: Value) -> Value {
+    node("listItem", content)
+}
+
+fn cell(typ: &str, content: Value) -> Value {
+    node(
+        typ,
+        Value::Array(vec![paragraph(Value::Array(vec![content]))]),
+    )
+}
+
+/// A document with one of each of the blocks and inline nodes that are rendered
+fn document() -> Description {
+    description(json!({
+        "version": 1,
+        "type": "doc",
+        "content": [
+            { "type": "heading", "attrs": { "level": 2 }, "content": [text("Context")] },
+            paragraph(json!([
+                text("See "),
+                {
+                    "type": "text",
+                    "text": "the docs",
+                    "marks": [
+                        { "type": "strong" },
+                        { "type": "link", "attrs": { "href": "https://example.com" } }
+                    ]
+                },
+                text(" and "),
+                { "type": "text", "text": "run", "marks": [{ "type": "code" }] },
+                { "type": "hardBreak" },
+                { "type": "mention", "attrs": { "id": "1", "text": "@Ann" } },
+                text(" by "),
+                { "type": "date", "attrs": { "timestamp": "1633046400000" } }
+            ])),
+            {
+                "type": "bulletList",
+                "content": [
+                    list_item(json!([
+                        paragraph(json!([text("one")])),
+                        {
+                            "type": "bulletList",
+                            "content": [list_item(json!([paragraph(json!([text("nested")]))]))]
+                        }
+                    ])),
+                    list_item(json!([paragraph(json!([
+                        { "type": "text", "text": "two", "marks": [{ "type": "em" }] }
+                    ]))]))
+                ]
+            },
+            {
+                "type": "orderedList",
+                "attrs": { "order": 3 },
+                "content": [
+                    list_item(json!([paragraph(json!([text("third")]))])),
+                    list_item(json!([paragraph(json!([text("fourth")]))]))
+                ]
+            },
+            {
+                "type": "codeBlock",
+                "attrs": { "language": "rust" },
+                "content": [text("fn main() {}")]
+            },
+            { "type": "rule" },
+            { "type": "blockquote", "content": [paragraph(json!([text("quoted")]))] },
+            {
+                "type": "table",
+                "content": [
+                    {
+                        "type": "tableRow",
+                        "content": [
+                            cell("tableHeader", text("Key")),
+                            cell("tableHeader", text("Status"))
+                        ]
+                    },
+                    {
+                        "type": "tableRow",
+                        "content": [
+                            cell("tableCell", text("LEC-1")),
+                            cell(
+                                "tableCell",
+                                json!({ "type": "status", "attrs": { "text": "DONE" } })
+                            )
+                        ]
+                    }
+                ]
+            }
+        ]
+    }))
+}
+
+#[test]
+fn renders_plain_text() {
+    assert_eq!(
+        render(&document(), Format::Plain),
+        "Context\n\
+         See the docs and run\n\
+         @Ann by 2021-10-01\n\
+         - one\n  - nested\n\
+         - two\n\
+         3. third\n\
+         4. fourth\n\
+         fn main() {}\n\
+         quoted\n\
+         Key | Status\n\
+         LEC-1 | DONE"
+    );
+}
+
+#[test]
+fn renders_markdown() {
+    assert_eq!(
+        render(&document(), Format::Markdown),
+        "## Context\n\n\
+         See [**the docs**](https://example.com) and `run`\\\n\
+         @Ann by 2021-10-01\n\n\
+         - one\n  - nested\n\
+         - *two*\n\n\
+         3. third\n\
+         4. fourth\n\n\
+         ```rust\nfn main() {}\n```\n\n\
+         ---\n\n\
+         > quoted\n\n\
+         | Key | Status |\n\
+         | --- | --- |\n\
+         | LEC-1 | DONE |"
+    );
+}
+
+#[test]
+fn keeps_wiki_markup_as_it_is() {
+    let wiki = description(json!("h2. Context\n* one\n* *two*"));
+    assert_eq!(render(&wiki, Format::Plain), "h2. Context\n* one\n* *two*");
+    assert_eq!(
+        render(&wiki, Format::Markdown),
+        "h2. Context\n* one\n* *two*"
+    );
+}
+
+#[test]
+fn tolerates_unknown_nodes_and_missing_attributes() {
+    let unusual = description(json!({
+        "version": 1,
+        "type": "doc",
+        "content": [
+            {
+                "type": "panel",
+                "attrs": { "panelType": "info" },
+                "content": [paragraph(json!([text("inside")]))]
+            },
+            { "type": "heading", "content": [text("Title")] },
+            paragraph(json!([
+                { "type": "emoji", "attrs": { "shortName": ":smile:" } },
+                text(" "),
+                { "type": "date", "attrs": { "timestamp": "soon" } },
+                { "type": "inlineCard", "attrs": { "url": "https://example.com/LEC-1" } }
+            ])),
+            { "type": "mystery", "text": "kept" },
+            { "type": "paragraph" }
+        ]
+    }));
+    assert_eq!(
+        render(&unusual, Format::Markdown),
+        "inside\n\n# Title\n\n:smile: https://example.com/LEC-1\n\nkept"
+    );
+    assert_eq!(
+        render(&unusual, Format::Plain),
+        "inside\nTitle\n:smile: https://example.com/LEC-1\nkept"
+    );
+}
+
+#[test]
+fn reads_the_format_from_the_config() {
+    for format in &[Format::Plain, Format::Markdown] {
+        assert_eq!(
+            serde_json::from_value::<Format>(json!(format.name())).ok(),
+            Some(*format)
+        );
+    }
+    assert!(serde_json::from_value::<Format>(json!("html")).is_err());
+    assert!(serde_json::from_value::<Format>(json!("Markdown")).is_err());
+}
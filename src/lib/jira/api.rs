@@ -33,33 +33,60 @@
 //! field is used that has its own resolutions. We assume that a custom resolution field is
 //! provided in the config, and use that to determine the resolution of the issue.
 
+use crate::lib::cancel;
 use crate::lib::jira::native;
 use crate::lib::rest;
-use backoff::future::retry;
-use backoff::ExponentialBackoff;
-use futures::future::{try_join_all, TryFutureExt};
+use futures::future::{join_all, try_join_all, TryFutureExt};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use futures::Future;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::convert::TryFrom;
 use tracing::{info, instrument};
 
+/// The ways talking to the jira api can fail
 #[derive(Snafu, Debug)]
 pub enum Error {
+    /// A request could not be built for the path
     #[snafu(display("Unable to build request for path {}: {}", path, source))]
-    UnableToBuildRequest { path: String, source: rest::Error },
+    UnableToBuildRequest {
+        /// The path of the request
+        path: String,
+        /// Why it could not be built
+        source: rest::Error,
+    },
+    /// An issue has a value in the epic link field that isn't the key of an issue
     #[snafu(display(
         "Field {} in issue {} did not contain an Epic Link",
         field_name,
         issue_key
     ))]
     InvalidEpicLink {
+        /// The issue with the value
         issue_key: native::IssueKey,
+        /// The id of the epic link field
         field_name: native::CustomFieldName,
     },
+    /// The instance has no custom field with the name
     #[snafu(display("No custom fields for epic name using {}", readable_name))]
-    NoEpicLinkField { readable_name: String },
+    NoEpicLinkField {
+        /// The name that was looked for
+        readable_name: String,
+    },
+    /// The custom fields of the instance could not be pulled
     #[snafu(display("Could not get custom fields when attempting to get epic name"))]
-    GetEpicLinkField { source: reqwest::Error },
+    GetEpicLinkField {
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// The statuses of the instance could not be pulled
+    #[snafu(display("Could not get the statuses of the instance: {}", source))]
+    CouldNotGetStatuses {
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// A page of the changelog of an issue could not be pulled
     #[snafu(display(
         "Could not get changelog for issue {}, starting at {}, with max results {}: {}",
         issue_key,
@@ -68,11 +95,16 @@ pub enum Error {
         source
     ))]
     CouldNotGetChangeLogForIssue {
+        /// The issue whose changelog was pulled
         issue_key: native::IssueKey,
+        /// The offset of the page
         start_at: u64,
+        /// The size of the page
         max_results: u64,
+        /// The error of the request
         source: reqwest::Error,
     },
+    /// A page of the issues matching a query could not be pulled
     #[snafu(display(
         "Could not get issues for jql ({}), starting_at: {}, with max_results{}: {}",
         jql,
@@ -81,147 +113,1252 @@ pub enum Error {
         source
     ))]
     CouldNotGetIssuesForJQLQuery {
+        /// The query
         jql: String,
+        /// The offset of the page
         start_at: u64,
+        /// The size of the page
         max_results: u64,
+        /// The error of the request
         source: reqwest::Error,
     },
+    /// A count does not fit in a u64
     #[snafu(display("Unable to size {} to u64, this should never happen: {}", size, source))]
     UnableToConvertUsizeToU64 {
+        /// The count
         size: usize,
+        /// Why it does not fit
         source: std::num::TryFromIntError,
     },
+    /// The offset of the next page overflowed
     #[snafu(display("Could not add start_at"))]
     AddStartAt {},
+    /// The size of the next page overflowed
     #[snafu(display("Max results add"))]
     AddMaxResults {},
+    /// A page of the boards could not be pulled
+    #[snafu(display("Could not get boards, starting at {}: {}", start_at, source))]
+    CouldNotGetBoards {
+        /// The offset of the page
+        start_at: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// There is no board with the name
+    #[snafu(display("No board named {}", name))]
+    NoBoardNamed {
+        /// The name that was looked for
+        name: String,
+    },
+    /// More than one board has the name
+    #[snafu(display("There are {} boards named {}", count, name))]
+    AmbiguousBoardName {
+        /// The name that was looked for
+        name: String,
+        /// The number of boards with the name
+        count: usize,
+    },
+    /// A page of the issues of a board could not be pulled
+    #[snafu(display(
+        "Could not get issues for board {}, starting at {}: {}",
+        board_id,
+        start_at,
+        source
+    ))]
+    CouldNotGetIssuesForBoard {
+        /// The board
+        board_id: native::BoardId,
+        /// The offset of the page
+        start_at: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// Jira kept failing until the retry budget of the client ran out
+    #[snafu(display(
+        "Gave up on jira after {} attempts over {}s, the retry budget is exhausted: {}",
+        attempts,
+        elapsed_seconds,
+        source
+    ))]
+    RetryBudgetExhausted {
+        /// The number of requests made
+        attempts: u32,
+        /// How long was spent retrying
+        elapsed_seconds: u64,
+        /// The error of the last request
+        source: Box<Error>,
+    },
+    /// The circuit breaker of the client is open, so no request was made
+    #[snafu(display(
+        "Not calling jira, the circuit breaker is open after {} failed requests in a row",
+        consecutive_failures
+    ))]
+    CircuitOpen {
+        /// The number of requests in a row that failed
+        consecutive_failures: u32,
+    },
+    /// A page of the worklogs of an issue could not be pulled
+    #[snafu(display(
+        "Could not get worklogs for issue {}, starting at {}: {}",
+        issue_key,
+        start_at,
+        source
+    ))]
+    CouldNotGetWorklogsForIssue {
+        /// The issue whose worklogs were pulled
+        issue_key: native::IssueKey,
+        /// The offset of the page
+        start_at: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// A page of the comments of an issue could not be pulled
+    #[snafu(display(
+        "Could not get comments for issue {}, starting at {}: {}",
+        issue_key,
+        start_at,
+        source
+    ))]
+    CouldNotGetCommentsForIssue {
+        /// The issue whose comments were pulled
+        issue_key: native::IssueKey,
+        /// The offset of the page
+        start_at: u64,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+    /// The cancellation token was cancelled while issues were being pulled
+    #[snafu(display("Interrupted before all of the issues were pulled"))]
+    Cancelled,
+    /// The query matches more issues than the limit allows
+    #[snafu(display(
+        "The query matches {} issues, more than the limit of {}. Narrow the query or raise the \
+         limit.",
+        total,
+        max_issues
+    ))]
+    TooManyIssues {
+        /// The number of issues the query matches
+        total: u64,
+        /// The limit
+        max_issues: u64,
+    },
+    /// An issue of a page does not have the expected shape
+    #[snafu(display("Could not read issue {} at {}: {}", issue_key, path, source))]
+    InvalidIssue {
+        /// The key of the issue, or its position in the results
+        issue_key: String,
+        /// Where in the issue the problem is
+        path: String,
+        /// The error of reading the issue
+        source: serde_json::Error,
+    },
 }
 
+/// An issue along with everything that is pulled for it
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssueDetail {
+    /// The issue
     pub issue: native::Issue,
+    /// The full changelog of the issue, oldest first
     pub changelog: Vec<native::ChangeGroup>,
+    /// Only pulled when asked for, see [`IssuePages::include_worklogs`]
+    #[serde(default)]
+    pub worklogs: Vec<native::Worklog>,
+    /// Only pulled when asked for, see [`IssuePages::include_comments`]
+    #[serde(default)]
+    pub comments: Vec<native::Comment>,
 }
 
+/// What is pulled for each issue along with its changelog
+#[derive(Debug, Clone, Copy, Default)]
+struct Include {
+    worklogs: bool,
+    comments: bool,
+}
+
+/// Retry `operation` within the retry budget of the client, see [`rest::retry`]
+async fn retry<T, F, Fut>(client: &rest::Client, operation: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    rest::retry(client, operation)
+        .await
+        .map_err(|failure| match failure {
+            rest::RetryFailure::BudgetExhausted {
+                attempts,
+                elapsed,
+                source,
+            } => Error::RetryBudgetExhausted {
+                attempts,
+                elapsed_seconds: elapsed.as_secs(),
+                source: Box::new(source),
+            },
+            rest::RetryFailure::CircuitOpen {
+                consecutive_failures,
+            } => Error::CircuitOpen {
+                consecutive_failures,
+            },
+            rest::RetryFailure::Rejected { source } => source,
+        })
+}
+
+/// A single page of results from one of the paged Jira endpoints. The endpoints don't agree on
+/// how they describe the end of the results, so `total` and `is_last` are both optional.
+#[derive(Debug)]
+pub struct Page<T> {
+    /// The values of the page
+    pub values: Vec<T>,
+    /// The number of values across every page, when the endpoint says
+    pub total: Option<u64>,
+    /// Whether this is the last page, when the endpoint says
+    pub is_last: Option<bool>,
+}
+
+impl From<native::ChangeLog> for Page<native::ChangeGroup> {
+    fn from(changelog: native::ChangeLog) -> Self {
+        Page {
+            values: changelog.values,
+            total: changelog.total,
+            is_last: changelog.is_last,
+        }
+    }
+}
+
+impl From<native::Worklogs> for Page<native::Worklog> {
+    fn from(worklogs: native::Worklogs) -> Self {
+        Page {
+            values: worklogs.worklogs,
+            total: Some(worklogs.total),
+            is_last: None,
+        }
+    }
+}
+
+impl From<native::Comments> for Page<native::Comment> {
+    fn from(comments: native::Comments) -> Self {
+        Page {
+            values: comments.comments,
+            total: Some(comments.total),
+            is_last: None,
+        }
+    }
+}
+
+impl<I> From<native::Search<I>> for Page<I> {
+    fn from(search: native::Search<I>) -> Self {
+        Page {
+            values: search.issues,
+            total: Some(search.total),
+            is_last: search.is_last,
+        }
+    }
+}
+
+/// Page through one of the paged Jira endpoints, starting at `start_at`. `fetch_page` is called
+/// with the offset and size of each page. The offset of the next page is advanced by the number
+/// of values actually returned, as Jira may return fewer than were asked for. Paging stops at an
+/// empty page, a page marked as the last, or once `total` values have been returned. When Jira
+/// says neither, a short page is taken as the last.
+pub fn paginate<'a, T, F, Fut>(
+    start_at: u64,
+    max_results: u64,
+    fetch_page: F,
+) -> BoxStream<'a, Result<Vec<T>, Error>>
+where
+    T: Send + 'a,
+    F: FnMut(u64, u64) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Page<T>, Error>> + Send + 'a,
+{
+    stream::try_unfold(
+        (fetch_page, start_at, false),
+        move |(mut fetch_page, start_at, done)| async move {
+            if done {
+                return Ok(None);
+            }
+            let page = fetch_page(start_at, max_results).await?;
+            let len = u64::try_from(page.values.len()).context(UnableToConvertUsizeToU64 {
+                size: page.values.len(),
+            })?;
+            let next_start_at = start_at.checked_add(len).context(AddStartAt {})?;
+            let done = len == 0
+                || page.is_last.unwrap_or_else(|| {
+                    page.total
+                        .map_or(len < max_results, |total| next_start_at >= total)
+                });
+            Ok(Some((page.values, (fetch_page, next_start_at, done))))
+        },
+    )
+    .boxed()
+}
+
+/// Like [`paginate`] but collects every value of every page
+async fn paginate_all<'a, T, F, Fut>(
+    start_at: u64,
+    max_results: u64,
+    fetch_page: F,
+) -> Result<Vec<T>, Error>
+where
+    T: Send + 'a,
+    F: FnMut(u64, u64) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Page<T>, Error>> + Send + 'a,
+{
+    let pages: Vec<Vec<T>> = paginate(start_at, max_results, fetch_page)
+        .try_collect()
+        .await?;
+    Ok(pages.into_iter().flatten().collect())
+}
+
+/// The number of changelog pages of a single issue that are pulled at the same time
+const CHANGELOG_PAGE_CONCURRENCY: usize = 8;
+
 #[instrument(skip(client))]
-async fn get_changelog_for_issue(
+async fn get_changelog_page(
     client: &rest::Client,
     key: &native::IssueKey,
-) -> Result<Vec<native::ChangeGroup>, Error> {
-    info!("get changelog for {}", key);
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::ChangeLog, Error> {
+    retry(client, || async {
+        let changelog_path = format!("/rest/api/3/issue/{}/changelog", key);
+        rest::get(client, &changelog_path)
+            .context(UnableToBuildRequest {
+                path: changelog_path,
+            })?
+            .query(&[
+                ("startAt", &start_at.to_string()),
+                ("maxResults", &max_results.to_string()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetChangeLogForIssue {
+                issue_key: key.clone(),
+                start_at,
+                max_results,
+            })?
+            .json::<native::ChangeLog>()
+            .await
+            .context(CouldNotGetChangeLogForIssue {
+                issue_key: key.clone(),
+                start_at,
+                max_results,
+            })
+    })
+    .await
+}
 
-    let mut done = false;
-    let mut changelog = Vec::new();
-    let mut start_at: u64 = 0;
-    let max_results: u64 = 100;
-    while !done {
-        let result = retry(ExponentialBackoff::default(), || async {
-            let changelog_path = format!("/rest/api/3/issue/{}/changelog", key);
-            rest::get(client, &changelog_path)
-                .context(UnableToBuildRequest {
-                    path: changelog_path,
-                })?
-                .query(&[
-                    ("startAt", &start_at.to_string()),
-                    ("maxResults", &max_results.to_string()),
-                ])
-                .send()
-                .await
-                .context(CouldNotGetChangeLogForIssue {
-                    issue_key: key.clone(),
-                    start_at,
-                    max_results,
-                })?
-                .json::<native::ChangeLog>()
-                .await
-                .context(CouldNotGetChangeLogForIssue {
-                    issue_key: key.clone(),
-                    start_at,
-                    max_results,
-                })
-                .map_err(backoff::Error::Transient)
-        })
+/// Pulls the pages from `start_at` up to `total` at fixed offsets of `page_size`, `concurrency`
+/// of them at a time, and puts them back together in order. Jira may return fewer values than
+/// were asked for, which would leave a gap before the next offset, so from the first short page
+/// on the rest are pulled one after another from where that page ended.
+async fn paginate_concurrently<'a, T, F, Fut>(
+    start_at: u64,
+    page_size: u64,
+    total: u64,
+    concurrency: usize,
+    mut fetch_page: F,
+) -> Result<Vec<T>, Error>
+where
+    T: Send + 'a,
+    F: FnMut(u64, u64) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Page<T>, Error>> + Send + 'a,
+{
+    let step = usize::try_from(page_size).unwrap_or(usize::MAX);
+    let pages: Vec<(u64, Page<T>)> = stream::iter((start_at..total).step_by(step))
+        .map(|offset| fetch_page(offset, page_size).map_ok(move |page| (offset, page)))
+        .buffered(concurrency)
+        .try_collect()
         .await?;
 
-        let len: u64 = u64::try_from(result.values.len()).context(UnableToConvertUsizeToU64 {
-            size: result.values.len(),
+    let mut values = Vec::new();
+    let mut next_start_at = start_at;
+    for (offset, page) in pages {
+        let len = u64::try_from(page.values.len()).context(UnableToConvertUsizeToU64 {
+            size: page.values.len(),
         })?;
-        start_at = len.checked_add(start_at).context(AddStartAt {})?;
-
-        match result.is_last {
-            Some(true) => done = true,
-            Some(false) | None => done = len < max_results,
+        values.extend(page.values);
+        next_start_at = offset.checked_add(len).context(AddStartAt {})?;
+        if len < page_size {
+            break;
         }
-        changelog.extend(result.values);
+    }
+    if next_start_at < total {
+        values.extend(paginate_all(next_start_at, page_size, fetch_page).await?);
+    }
+    Ok(values)
+}
+
+/// Pulls the full changelog of an issue. The first page tells us how many entries there are, so
+/// the remaining pages are pulled concurrently and put back together in order. If Jira does not
+/// return the total, or the first page is already short, the pages are pulled one after another.
+///
+/// # Errors
+///
+/// Fails when a page of the changelog can't be pulled within the retry budget of the client.
+#[instrument(skip(client))]
+pub async fn get_changelog_for_issue(
+    client: &rest::Client,
+    key: &native::IssueKey,
+) -> Result<Vec<native::ChangeGroup>, Error> {
+    info!("get changelog for {}", key);
+
+    let max_results: u64 = 100;
+    let first_page = get_changelog_page(client, key, 0, max_results).await?;
+    let len: u64 = u64::try_from(first_page.values.len()).context(UnableToConvertUsizeToU64 {
+        size: first_page.values.len(),
+    })?;
+    let done = first_page.is_last.unwrap_or(len < max_results);
+    let mut changelog = first_page.values;
+    if done || len == 0 {
+        return Ok(changelog);
+    }
+
+    let page_size = first_page.max_results.unwrap_or(len).max(1);
+    let fetch_page = |start_at, max_results| async move {
+        get_changelog_page(client, key, start_at, max_results)
+            .await
+            .map(Page::from)
+    };
+    match first_page.total {
+        Some(total) if len == page_size => changelog.extend(
+            paginate_concurrently(
+                len,
+                page_size,
+                total,
+                CHANGELOG_PAGE_CONCURRENCY,
+                fetch_page,
+            )
+            .await?,
+        ),
+        _ => changelog.extend(paginate_all(len, page_size, fetch_page).await?),
     }
 
     Ok(changelog)
 }
 
 #[instrument(skip(client))]
-async fn get_all_changelogs(
+async fn get_worklog_page(
+    client: &rest::Client,
+    key: &native::IssueKey,
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::Worklogs, Error> {
+    retry(client, || async {
+        let worklog_path = format!("/rest/api/3/issue/{}/worklog", key);
+        rest::get(client, &worklog_path)
+            .context(UnableToBuildRequest { path: worklog_path })?
+            .query(&[
+                ("startAt", &start_at.to_string()),
+                ("maxResults", &max_results.to_string()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetWorklogsForIssue {
+                issue_key: key.clone(),
+                start_at,
+            })?
+            .json()
+            .await
+            .context(CouldNotGetWorklogsForIssue {
+                issue_key: key.clone(),
+                start_at,
+            })
+    })
+    .await
+}
+
+/// Pulls every worklog of an issue
+///
+/// # Errors
+///
+/// Fails when a page of the worklogs can't be pulled within the retry budget of the client.
+#[instrument(skip(client))]
+pub async fn get_worklogs_for_issue(
+    client: &rest::Client,
+    key: &native::IssueKey,
+) -> Result<Vec<native::Worklog>, Error> {
+    paginate_all(0, 100, |start_at, max_results| async move {
+        get_worklog_page(client, key, start_at, max_results)
+            .await
+            .map(Page::from)
+    })
+    .await
+}
+
+#[instrument(skip(client))]
+async fn get_comment_page(
+    client: &rest::Client,
+    key: &native::IssueKey,
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::Comments, Error> {
+    retry(client, || async {
+        let comment_path = format!("/rest/api/3/issue/{}/comment", key);
+        rest::get(client, &comment_path)
+            .context(UnableToBuildRequest { path: comment_path })?
+            .query(&[
+                ("startAt", &start_at.to_string()),
+                ("maxResults", &max_results.to_string()),
+                ("orderBy", &"created".to_owned()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetCommentsForIssue {
+                issue_key: key.clone(),
+                start_at,
+            })?
+            .json()
+            .await
+            .context(CouldNotGetCommentsForIssue {
+                issue_key: key.clone(),
+                start_at,
+            })
+    })
+    .await
+}
+
+/// Pulls every comment of an issue, oldest first
+///
+/// # Errors
+///
+/// Fails when a page of the comments can't be pulled within the retry budget of the client.
+#[instrument(skip(client))]
+pub async fn get_comments_for_issue(
+    client: &rest::Client,
+    key: &native::IssueKey,
+) -> Result<Vec<native::Comment>, Error> {
+    paginate_all(0, 100, |start_at, max_results| async move {
+        get_comment_page(client, key, start_at, max_results)
+            .await
+            .map(Page::from)
+    })
+    .await
+}
+
+/// The changelog that came with the issue, in order, if the search returned all of it
+fn embedded_changelog(issue: &mut native::Issue) -> Option<Vec<native::ChangeGroup>> {
+    let embedded = issue.changelog.take()?;
+    match (embedded.total, embedded.max_results) {
+        (Some(total), Some(max_results)) if total <= max_results => {
+            // The changelog of a search is newest first, the changelog endpoint is oldest first
+            let mut histories = embedded.histories;
+            histories.sort_by_key(|group| group.created);
+            Some(histories)
+        }
+        _ => None,
+    }
+}
+
+#[instrument(skip(client, issue))]
+async fn get_issue_detail(
+    client: &rest::Client,
+    mut issue: native::Issue,
+    include: Include,
+) -> Result<IssueDetail, Error> {
+    let changelog = match embedded_changelog(&mut issue) {
+        Some(changelog) => changelog,
+        None => get_changelog_for_issue(client, &issue.key).await?,
+    };
+    let worklogs = if include.worklogs {
+        get_worklogs_for_issue(client, &issue.key).await?
+    } else {
+        Vec::new()
+    };
+    let comments = if include.comments {
+        get_comments_for_issue(client, &issue.key).await?
+    } else {
+        Vec::new()
+    };
+    Ok(IssueDetail {
+        issue,
+        changelog,
+        worklogs,
+        comments,
+    })
+}
+
+/// Pull the changelog, and worklogs or comments, of each issue. By default the first issue that
+/// fails fails them all. With `skip_failed_changelogs` the issues that fail are left out and
+/// recorded in `failed` instead, unless the circuit breaker opened, which means jira itself is
+/// unavailable.
+#[instrument(skip(client, issues, failed))]
+async fn get_all_details(
     client: &rest::Client,
     issues: Vec<native::Issue>,
+    include: Include,
+    skip_failed_changelogs: bool,
+    failed: &mut Vec<FailedIssue>,
 ) -> Result<Vec<IssueDetail>, Error> {
-    try_join_all(issues.iter().map(|issue| {
-        let issue_clone = issue.clone();
-        get_changelog_for_issue(client, &issue.key).and_then(|changelog| async {
-            Ok(IssueDetail {
-                issue: issue_clone,
-                changelog,
-            })
-        })
-    }))
+    if !skip_failed_changelogs {
+        return try_join_all(
+            issues
+                .into_iter()
+                .map(|issue| get_issue_detail(client, issue, include)),
+        )
+        .await;
+    }
+
+    let keys: Vec<native::IssueKey> = issues.iter().map(|issue| issue.key.clone()).collect();
+    let results = join_all(
+        issues
+            .into_iter()
+            .map(|issue| get_issue_detail(client, issue, include)),
+    )
+    .await;
+    let mut details = Vec::with_capacity(results.len());
+    for (key, result) in keys.into_iter().zip(results) {
+        match result {
+            Ok(detail) => details.push(detail),
+            Err(err @ Error::CircuitOpen { .. }) => return Err(err),
+            Err(err) => failed.push(FailedIssue {
+                issue_key: key.to_string(),
+                message: err.to_string(),
+            }),
+        }
+    }
+    Ok(details)
+}
+
+/// The readable name of the custom field that company-managed projects use to link an issue to
+/// its epic
+pub const EPIC_LINK_FIELD_NAME: &str = "Epic Link";
+
+/// Find the id of the custom field with the readable name `readable_name`
+///
+/// # Errors
+///
+/// Fails with [`Error::NoEpicLinkField`] when the instance has no field named `readable_name`,
+/// or when the fields can't be pulled.
+#[instrument(skip(client))]
+pub async fn get_custom_field_id(
+    client: &rest::Client,
+    readable_name: &str,
+) -> Result<native::CustomFieldName, Error> {
+    let field_path = "/rest/api/3/field";
+    let fields: Vec<native::CustomField> = retry(client, || async {
+        rest::get(client, field_path)
+            .context(UnableToBuildRequest { path: field_path })?
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(GetEpicLinkField {})?
+            .json()
+            .await
+            .context(GetEpicLinkField {})
+    })
+    .await?;
+
+    fields
+        .into_iter()
+        .find(|field| field.custom && field.name.0 == readable_name)
+        .map(|field| field.id)
+        .context(NoEpicLinkField { readable_name })
+}
+
+/// Every status of the instance, with its category
+///
+/// # Errors
+///
+/// Fails when the statuses can't be pulled.
+#[instrument(skip(client))]
+pub async fn get_statuses(client: &rest::Client) -> Result<Vec<native::InstanceStatus>, Error> {
+    let status_path = "/rest/api/3/status";
+    retry(client, || async {
+        rest::get(client, status_path)
+            .context(UnableToBuildRequest { path: status_path })?
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetStatuses {})?
+            .json()
+            .await
+            .context(CouldNotGetStatuses {})
+    })
     .await
 }
 
+/// What Jira is asked to include in each issue returned by a search, on top of its fields
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Expand {
+    /// The HTML rendered fields (`expand=renderedFields`), which end up in
+    /// [`native::Issue::rendered_fields`]
+    pub rendered_fields: bool,
+    /// The changelog of each issue (`expand=changelog`). It is used in place of pulling the
+    /// changelog separately unless Jira truncated it.
+    pub changelog: bool,
+}
+
+impl Expand {
+    fn names(self) -> Vec<&'static str> {
+        let mut expand = Vec::new();
+        if self.rendered_fields {
+            expand.push("renderedFields");
+        }
+        if self.changelog {
+            expand.push("changelog");
+        }
+        expand
+    }
+
+    fn param(self) -> Option<String> {
+        let expand = self.names();
+        if expand.is_empty() {
+            None
+        } else {
+            Some(expand.join(","))
+        }
+    }
+}
+
+/// Queries longer than this are sent in the body of a post rather than in the url, which Jira,
+/// and the proxies in front of it, limit the length of
+const MAX_GET_JQL_LENGTH: usize = 1500;
+
+/// The body of a search sent as a post
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchRequest<'a> {
+    jql: &'a str,
+    start_at: u64,
+    max_results: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expand: Vec<&'static str>,
+}
+
+/// A single page of the issues matching the `jql` query. Long queries are posted, see
+/// [`MAX_GET_JQL_LENGTH`].
 #[instrument(skip(client))]
-pub async fn get_issues_from_jql(
+async fn get_search_page<I: DeserializeOwned>(
     client: &rest::Client,
     jql: &str,
-) -> Result<Vec<IssueDetail>, Error> {
-    let mut done = false;
-    let mut work = Vec::new();
-    let mut start_at: u64 = 0;
-    let max_results: u64 = 100;
-    let mut keys = Vec::new();
-    while !done {
-        let search_path = "/rest/api/3/search";
-        let jql_result: native::Search = retry(ExponentialBackoff::default(), || async {
+    expand: Expand,
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::Search<I>, Error> {
+    let search_path = "/rest/api/3/search";
+    retry(client, || async {
+        let request = if jql.len() > MAX_GET_JQL_LENGTH {
+            rest::post_json(
+                client,
+                search_path,
+                &SearchRequest {
+                    jql,
+                    start_at,
+                    max_results,
+                    expand: expand.names(),
+                },
+            )
+            .context(UnableToBuildRequest { path: search_path })?
+        } else {
+            let start_at_param = start_at.to_string();
+            let max_results_param = max_results.to_string();
+            let mut query = vec![
+                ("jql", jql),
+                ("startAt", &start_at_param),
+                ("maxResults", &max_results_param),
+            ];
+            let expand_param = expand.param();
+            if let Some(expand_param) = &expand_param {
+                query.push(("expand", expand_param));
+            }
             rest::get(client, search_path)
                 .context(UnableToBuildRequest { path: search_path })?
-                .query(&[
-                    ("jql", jql),
-                    ("startAt", &start_at.to_string()),
-                    ("maxResults", &max_results.to_string()),
-                ])
-                .send()
+                .query(&query)
+        };
+        request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetIssuesForJQLQuery {
+                jql: jql.to_owned(),
+                start_at,
+                max_results,
+            })?
+            .json()
+            .await
+            .context(CouldNotGetIssuesForJQLQuery {
+                jql: jql.to_owned(),
+                start_at,
+                max_results,
+            })
+    })
+    .await
+}
+
+/// Pulls every issue matching the `jql` query without their changelogs
+///
+/// # Errors
+///
+/// Fails when a page of the results can't be pulled.
+#[instrument(skip(client))]
+pub async fn search_issues(client: &rest::Client, jql: &str) -> Result<Vec<native::Issue>, Error> {
+    paginate_all(0, 100, |start_at, max_results| async move {
+        get_search_page(client, jql, Expand::default(), start_at, max_results)
+            .await
+            .map(Page::from)
+    })
+    .await
+}
+
+/// Get the children of an epic. Team-managed projects link children to their epic with the
+/// `parent` field while company-managed projects use the [`EPIC_LINK_FIELD_NAME`] custom field,
+/// so both are searched. Instances without the custom field only have the `parent` link.
+///
+/// # Errors
+///
+/// Fails when the fields of the instance or a page of the children can't be pulled.
+#[instrument(skip(client))]
+pub async fn get_epic_children(
+    client: &rest::Client,
+    epic_key: &native::IssueKey,
+) -> Result<Vec<native::Issue>, Error> {
+    let jql = match get_custom_field_id(client, EPIC_LINK_FIELD_NAME).await {
+        Ok(field_id) => match field_id.0.strip_prefix("customfield_") {
+            Some(number) => format!("parent = {} OR cf[{}] = {}", epic_key, number, epic_key),
+            None => format!("parent = {} OR \"{}\" = {}", epic_key, field_id, epic_key),
+        },
+        Err(Error::NoEpicLinkField { .. }) => format!("parent = {}", epic_key),
+        Err(err) => return Err(err),
+    };
+    search_issues(client, &jql).await
+}
+
+/// The number of issues that match the `jql` query, without pulling any of them
+///
+/// # Errors
+///
+/// Fails when the search can't be made.
+#[instrument(skip(client))]
+pub async fn count_issues_from_jql(client: &rest::Client, jql: &str) -> Result<u64, Error> {
+    Ok(
+        get_search_page::<serde_json::Value>(client, jql, Expand::default(), 0, 0)
+            .await?
+            .total,
+    )
+}
+
+impl From<native::Boards> for Page<native::Board> {
+    fn from(boards: native::Boards) -> Self {
+        Page {
+            values: boards.values,
+            total: Some(boards.total),
+            is_last: boards.is_last,
+        }
+    }
+}
+
+impl<I> From<native::BoardIssues<I>> for Page<I> {
+    fn from(board_issues: native::BoardIssues<I>) -> Self {
+        Page {
+            values: board_issues.issues,
+            total: Some(board_issues.total),
+            is_last: board_issues.is_last,
+        }
+    }
+}
+
+#[instrument(skip(client))]
+async fn get_board_page(
+    client: &rest::Client,
+    name: Option<&str>,
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::Boards, Error> {
+    let board_path = "/rest/agile/1.0/board";
+    retry(client, || async {
+        let start_at_param = start_at.to_string();
+        let max_results_param = max_results.to_string();
+        let mut query = vec![
+            ("startAt", start_at_param.as_str()),
+            ("maxResults", &max_results_param),
+        ];
+        if let Some(name) = name {
+            query.push(("name", name));
+        }
+        rest::get(client, board_path)
+            .context(UnableToBuildRequest { path: board_path })?
+            .query(&query)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetBoards { start_at })?
+            .json()
+            .await
+            .context(CouldNotGetBoards { start_at })
+    })
+    .await
+}
+
+/// Pulls every board the user can see. When `name` is given only the boards whose name contains
+/// it are returned.
+///
+/// # Errors
+///
+/// Fails when a page of the boards can't be pulled.
+#[instrument(skip(client))]
+pub async fn get_boards(
+    client: &rest::Client,
+    name: Option<&str>,
+) -> Result<Vec<native::Board>, Error> {
+    paginate_all(0, 50, |start_at, max_results| async move {
+        get_board_page(client, name, start_at, max_results)
+            .await
+            .map(Page::from)
+    })
+    .await
+}
+
+/// Find the board named exactly `name`
+///
+/// # Errors
+///
+/// Fails with [`Error::NoBoardNamed`] or [`Error::AmbiguousBoardName`] unless exactly one board has
+/// the name, or when the boards can't be pulled.
+#[instrument(skip(client))]
+pub async fn get_board_by_name(client: &rest::Client, name: &str) -> Result<native::Board, Error> {
+    let mut boards: Vec<native::Board> = get_boards(client, Some(name))
+        .await?
+        .into_iter()
+        .filter(|board| board.name == name)
+        .collect();
+    ensure!(
+        boards.len() < 2,
+        AmbiguousBoardName {
+            name,
+            count: boards.len()
+        }
+    );
+    boards.pop().context(NoBoardNamed { name })
+}
+
+#[instrument(skip(client))]
+async fn get_board_issue_page<I: DeserializeOwned>(
+    client: &rest::Client,
+    board_id: native::BoardId,
+    jql: Option<&str>,
+    expand: Expand,
+    start_at: u64,
+    max_results: u64,
+) -> Result<native::BoardIssues<I>, Error> {
+    let board_issue_path = format!("/rest/agile/1.0/board/{}/issue", board_id);
+    retry(client, || async {
+        let start_at_param = start_at.to_string();
+        let max_results_param = max_results.to_string();
+        let mut query = vec![
+            ("startAt", start_at_param.as_str()),
+            ("maxResults", &max_results_param),
+        ];
+        if let Some(jql) = jql {
+            query.push(("jql", jql));
+        }
+        let expand_param = expand.param();
+        if let Some(expand_param) = &expand_param {
+            query.push(("expand", expand_param));
+        }
+        rest::get(client, &board_issue_path)
+            .context(UnableToBuildRequest {
+                path: &board_issue_path,
+            })?
+            .query(&query)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(CouldNotGetIssuesForBoard { board_id, start_at })?
+            .json()
+            .await
+            .context(CouldNotGetIssuesForBoard { board_id, start_at })
+    })
+    .await
+}
+
+/// The number of issues on the board with the id `board_id` that match `jql`, without pulling
+/// any of them
+///
+/// # Errors
+///
+/// Fails when the search can't be made.
+#[instrument(skip(client))]
+pub async fn count_issues_for_board(
+    client: &rest::Client,
+    board_id: native::BoardId,
+    jql: Option<&str>,
+) -> Result<u64, Error> {
+    Ok(
+        get_board_issue_page::<serde_json::Value>(client, board_id, jql, Expand::default(), 0, 0)
+            .await?
+            .total,
+    )
+}
+
+/// An issue that was left out of a page because it did not have the expected shape
+#[derive(Debug, Clone)]
+pub struct SkippedIssue {
+    /// The key of the issue, or its position in the results when even that could not be read
+    pub issue_key: String,
+    /// Where in the issue the problem is, e.g. `fields.status.statusCategory`
+    pub path: String,
+    /// Why it could not be read
+    pub message: String,
+}
+
+/// An issue that was left out because its changelog, worklogs or comments could not be pulled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedIssue {
+    /// The key of the issue
+    pub issue_key: String,
+    /// Why its changelog, worklogs or comments could not be pulled
+    pub message: String,
+}
+
+/// Pages through every issue matching a jql query, pulling the full changelog of the issues in
+/// each page. Only a single page is held at a time, so the size of the result set does not
+/// affect memory use.
+///
+/// Each issue of a page is read on its own. By default an issue that can't be read fails the
+/// page, with [`IssuePages::skip_invalid_issues`] it is left out and recorded instead. The same
+/// goes for an issue whose changelog can't be pulled, with
+/// [`IssuePages::skip_failed_changelogs`].
+pub struct IssuePages<'a> {
+    client: &'a rest::Client,
+    source: String,
+    include: Include,
+    cancel: Option<cancel::Token>,
+    skip_invalid_issues: bool,
+    skipped: Vec<SkippedIssue>,
+    skip_failed_changelogs: bool,
+    failed: Vec<FailedIssue>,
+    read: usize,
+    pages: BoxStream<'a, Result<Vec<serde_json::Value>, Error>>,
+}
+
+/// The page stream can't be shown, so only where the issues come from is
+impl std::fmt::Debug for IssuePages<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IssuePages")
+            .field("source", &self.source)
+            .field("include", &self.include)
+            .field("cancel", &self.cancel)
+            .field("skip_invalid_issues", &self.skip_invalid_issues)
+            .field("skipped", &self.skipped)
+            .field("skip_failed_changelogs", &self.skip_failed_changelogs)
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read each of the raw issues of a page. `read` is the number of issues read before this page,
+/// it is used to name the issues without a key.
+fn read_issues(
+    values: Vec<serde_json::Value>,
+    skip_invalid_issues: bool,
+    read: &mut usize,
+    skipped: &mut Vec<SkippedIssue>,
+) -> Result<Vec<native::Issue>, Error> {
+    let mut issues = Vec::with_capacity(values.len());
+    for value in values {
+        let issue_key = value
+            .get("key")
+            .and_then(serde_json::Value::as_str)
+            .map_or_else(|| format!("#{}", *read), str::to_owned);
+        *read += 1;
+        match serde_path_to_error::deserialize(value) {
+            Ok(issue) => issues.push(issue),
+            Err(err) if skip_invalid_issues => skipped.push(SkippedIssue {
+                issue_key,
+                path: err.path().to_string(),
+                message: err.into_inner().to_string(),
+            }),
+            Err(err) => {
+                return Err(Error::InvalidIssue {
+                    issue_key,
+                    path: err.path().to_string(),
+                    source: err.into_inner(),
+                })
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// Refuse to page through more than `max_issues` issues. The total is checked as soon as the
+/// first page is in, before any changelogs are pulled.
+fn check_total<T>(page: Page<T>, start_at: u64, max_issues: Option<u64>) -> Result<Page<T>, Error> {
+    if let (0, Some(max_issues), Some(total)) = (start_at, max_issues, page.total) {
+        ensure!(total <= max_issues, TooManyIssues { total, max_issues });
+    }
+    Ok(page)
+}
+
+/// Start paging through the issues matching the `jql` query, with what is in `expand` included
+/// in each issue. When the query matches more than `max_issues` the first page fails with
+/// [`Error::TooManyIssues`].
+pub fn get_issues_from_jql<'a>(
+    client: &'a rest::Client,
+    jql: &'a str,
+    expand: Expand,
+    max_issues: Option<u64>,
+) -> IssuePages<'a> {
+    get_issues_from_jql_starting_at(client, jql, expand, max_issues, 0)
+}
+
+/// Like [`get_issues_from_jql`] but skipping the first `start_at` issues, to continue an earlier
+/// pull. The total is only checked against `max_issues` when starting from the first issue.
+pub fn get_issues_from_jql_starting_at<'a>(
+    client: &'a rest::Client,
+    jql: &'a str,
+    expand: Expand,
+    max_issues: Option<u64>,
+    start_at: u64,
+) -> IssuePages<'a> {
+    IssuePages {
+        client,
+        source: format!("jql {}", jql),
+        include: Include::default(),
+        cancel: None,
+        skip_invalid_issues: false,
+        skipped: Vec::new(),
+        skip_failed_changelogs: false,
+        failed: Vec::new(),
+        read: 0,
+        pages: paginate(start_at, 100, move |start_at, max_results| async move {
+            get_search_page(client, jql, expand, start_at, max_results)
                 .await
-                .context(CouldNotGetIssuesForJQLQuery {
-                    jql: jql.to_owned(),
-                    start_at,
-                    max_results,
-                })?
-                .json()
+                .map(Page::from)
+                .and_then(|page| check_total(page, start_at, max_issues))
+        }),
+    }
+}
+
+/// Start paging through the issues on the board with the id `board_id`, limited to those
+/// matching `jql` when it is given, with what is in `expand` included in each issue. The issues
+/// are limited to `max_issues` like [`get_issues_from_jql`], and the first `start_at` are
+/// skipped like [`get_issues_from_jql_starting_at`].
+pub fn get_issues_for_board<'a>(
+    client: &'a rest::Client,
+    board_id: native::BoardId,
+    jql: Option<&'a str>,
+    expand: Expand,
+    max_issues: Option<u64>,
+    start_at: u64,
+) -> IssuePages<'a> {
+    IssuePages {
+        client,
+        source: format!("board {}", board_id),
+        include: Include::default(),
+        cancel: None,
+        skip_invalid_issues: false,
+        skipped: Vec::new(),
+        skip_failed_changelogs: false,
+        failed: Vec::new(),
+        read: 0,
+        pages: paginate(start_at, 100, move |start_at, max_results| async move {
+            get_board_issue_page(client, board_id, jql, expand, start_at, max_results)
                 .await
-                .context(CouldNotGetIssuesForJQLQuery {
-                    jql: jql.to_owned(),
-                    start_at,
-                    max_results,
-                })
-                .map_err(backoff::Error::Transient)
-        })
-        .await?;
+                .map(Page::from)
+                .and_then(|page| check_total(page, start_at, max_issues))
+        }),
+    }
+}
 
-        keys.extend(jql_result.issues.iter().map(|issue| issue.key.clone()));
-        work.extend(get_all_changelogs(client, jql_result.issues).await?);
-        start_at = jql_result
-            .max_results
-            .checked_add(start_at)
-            .context(AddStartAt {})?;
+impl IssuePages<'_> {
+    /// Also pull the worklogs of each issue
+    #[must_use]
+    pub fn include_worklogs(mut self) -> Self {
+        self.include.worklogs = true;
+        self
+    }
+
+    /// Also pull the comments of each issue
+    #[must_use]
+    pub fn include_comments(mut self) -> Self {
+        self.include.comments = true;
+        self
+    }
+
+    /// Stop pulling pages once `cancel` is cancelled. The page being pulled at the time is
+    /// dropped and [`Error::Cancelled`] is returned.
+    #[must_use]
+    pub fn with_cancellation(mut self, cancel: cancel::Token) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Leave out the issues that can't be read rather than failing, see [`IssuePages::skipped`]
+    #[must_use]
+    pub fn skip_invalid_issues(mut self) -> Self {
+        self.skip_invalid_issues = true;
+        self
+    }
+
+    /// The issues that were left out so far because they could not be read
+    #[must_use]
+    pub fn skipped(&self) -> &[SkippedIssue] {
+        &self.skipped
+    }
+
+    /// Leave out the issues whose changelog can't be pulled rather than failing, see
+    /// [`IssuePages::failed`]
+    #[must_use]
+    pub fn skip_failed_changelogs(mut self) -> Self {
+        self.skip_failed_changelogs = true;
+        self
+    }
+
+    /// The issues that were left out so far because their changelog could not be pulled
+    #[must_use]
+    pub fn failed(&self) -> &[FailedIssue] {
+        &self.failed
+    }
 
-        done = start_at >= jql_result.total;
+    /// The issues read so far, including those that were skipped
+    #[must_use]
+    pub fn read(&self) -> usize {
+        self.read
     }
 
-    Ok(work)
+    /// Get the next page of issues along with their changelogs, or `None` once every issue has
+    /// been returned
+    ///
+    /// # Errors
+    ///
+    /// Fails when a page can't be pulled, when the token is cancelled, when the query matches more
+    /// issues than the limit, or on an issue that can't be read or whose changelog can't be pulled unless
+    /// those are skipped.
+    #[instrument(skip(self))]
+    pub async fn next(&mut self) -> Result<Option<Vec<IssueDetail>>, Error> {
+        let client = self.client;
+        let include = self.include;
+        let skip_invalid_issues = self.skip_invalid_issues;
+        let skip_failed_changelogs = self.skip_failed_changelogs;
+        let skipped = &mut self.skipped;
+        let failed = &mut self.failed;
+        let read = &mut self.read;
+        let pages = &mut self.pages;
+        let page = async move {
+            match pages.try_next().await? {
+                Some(values) => {
+                    let issues = read_issues(values, skip_invalid_issues, read, skipped)?;
+                    Ok(Some(
+                        get_all_details(client, issues, include, skip_failed_changelogs, failed)
+                            .await?,
+                    ))
+                }
+                None => Ok(None),
+            }
+        };
+        match &mut self.cancel {
+            Some(cancel) => tokio::select! {
+                page = page => page,
+                () = cancel.cancelled() => Cancelled.fail(),
+            },
+            None => page.await,
+        }
+    }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,75 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Paging over an in memory endpoint that, like Jira, may return fewer values than were asked
+//! for.
+use super::{paginate_concurrently, Error, Page};
+use futures::future::{self, Ready};
+use std::sync::{Arc, Mutex};
+
+/// An endpoint over `0..total` that never returns more than `cap` values a page, recording the
+/// offsets it was asked for
+fn endpoint(
+    total: u64,
+    cap: u64,
+    requests: Arc<Mutex<Vec<u64>>>,
+) -> impl FnMut(u64, u64) -> Ready<Result<Page<u64>, Error>> {
+    move |start_at, max_results| {
+        requests.lock().expect("requests lock").push(start_at);
+        let end = total.min(start_at + max_results.min(cap));
+        future::ready(Ok(Page {
+            values: (start_at..end).collect(),
+            total: Some(total),
+            is_last: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn pulls_full_pages_at_fixed_offsets() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let values = paginate_concurrently(10, 10, 45, 4, endpoint(45, 10, requests.clone()))
+        .await
+        .expect("pages");
+
+    assert_eq!(values, (10..45).collect::<Vec<_>>());
+    assert_eq!(
+        *requests.lock().expect("requests lock"),
+        vec![10, 20, 30, 40]
+    );
+}
+
+#[tokio::test]
+async fn falls_back_to_sequential_paging_after_a_short_page() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let values = paginate_concurrently(10, 10, 45, 4, endpoint(45, 7, requests.clone()))
+        .await
+        .expect("pages");
+
+    assert_eq!(values, (10..45).collect::<Vec<_>>());
+    let requests = requests.lock().expect("requests lock");
+    assert_eq!(requests[..4], [10, 20, 30, 40]);
+    assert_eq!(requests[4..], [17, 24, 31, 38]);
+}
+
+#[tokio::test]
+async fn stops_at_the_total() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let values = paginate_concurrently(10, 10, 10, 4, endpoint(10, 10, requests.clone()))
+        .await
+        .expect("pages");
+
+    assert!(values.is_empty());
+    assert!(requests.lock().expect("requests lock").is_empty());
+}
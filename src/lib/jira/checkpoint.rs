@@ -0,0 +1,347 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Pull Checkpoints
+//!
+//! Pulling a large query takes long enough that a dropped connection or a restart is likely to
+//! interrupt it. Each page of issues, with its changelogs, is spooled to a directory as it is
+//! pulled, along with a manifest of where to continue from. A pull that fails part way can then
+//! be resumed from the spool rather than started over. The spool is removed once the pull is
+//! finished.
+//!
+//! Each jira instance and query is spooled to its own directory, see [`spool_dir`]. A resume is
+//! refused unless the jira instance and the query it was started with are the same. The pull
+//! continues from an offset into the results, so an issue that joined or left them since would
+//! shift those after it and some would be skipped or pulled twice. The number of issues the query
+//! matched is recorded when the pull starts, and a resume is also refused when it has changed.
+use crate::lib::jira::api::IssueDetail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::warn;
+use url::Url;
+
+/// The ways spooling a pull can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The spool directory could not be created
+    #[snafu(display("Could not create spool directory {}: {}", path.display(), source))]
+    CouldNotCreateSpool {
+        /// The path of the directory
+        path: PathBuf,
+        /// The error of creating it
+        source: std::io::Error,
+    },
+    /// A page or the manifest could not be written to the spool
+    #[snafu(display("Could not write to spool {}: {}", path.display(), source))]
+    CouldNotWriteSpool {
+        /// The path of the file
+        path: PathBuf,
+        /// The error of writing it
+        source: std::io::Error,
+    },
+    /// A page or the manifest could not be read from the spool
+    #[snafu(display("Could not read from spool {}: {}", path.display(), source))]
+    CouldNotReadSpool {
+        /// The path of the file
+        path: PathBuf,
+        /// The error of reading it
+        source: std::io::Error,
+    },
+    /// A page could not be serialized
+    #[snafu(display("Could not serialize spooled page: {}", source))]
+    UnableToSerializePage {
+        /// The error of serializing it
+        source: serde_json::Error,
+    },
+    /// A page or the manifest of the spool is not valid json
+    #[snafu(display("Invalid spool file {}: {}", path.display(), source))]
+    InvalidSpoolFile {
+        /// The path of the file
+        path: PathBuf,
+        /// The error of parsing it
+        source: serde_json::Error,
+    },
+    /// There is no spooled pull to resume
+    #[snafu(display("There is no pull to resume in {}", path.display()))]
+    NoCheckpoint {
+        /// The spool directory
+        path: PathBuf,
+    },
+    /// The spooled pull is of another query or instance
+    #[snafu(display(
+        "The pull in the spool is of {} on {}, not {} on {}",
+        spooled_source,
+        spooled_instance,
+        source_description,
+        instance
+    ))]
+    CheckpointMismatch {
+        /// The query or board of the spooled pull
+        spooled_source: String,
+        /// The instance of the spooled pull
+        spooled_instance: String,
+        /// The query or board of the pull being resumed
+        source_description: String,
+        /// The instance of the pull being resumed
+        instance: String,
+    },
+    /// The query matches a different number of issues than when the spooled pull was started
+    #[snafu(display(
+        "The query matched {} issues when the pull was started and matches {} now, resuming \
+         could skip issues. Run it again without --resume to start over",
+        spooled_total,
+        total
+    ))]
+    ResultsChanged {
+        /// The number of issues it matched when the pull was started
+        spooled_total: u64,
+        /// The number of issues it matches now
+        total: u64,
+    },
+}
+
+/// What was pulled, and how far the pull got
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    instance: Url,
+    /// The query or board the issues are pulled from
+    source: String,
+    /// When the pull was started, used as when the issues were fetched
+    started_at: DateTime<Utc>,
+    /// The pages spooled so far
+    pages: usize,
+    /// The offset of the first issue that has not been pulled yet
+    next_start_at: u64,
+    /// The number of issues the query matched when the pull was started, not recorded by older
+    /// versions
+    #[serde(default)]
+    total: Option<u64>,
+}
+
+/// The spooled pages of a single pull
+#[derive(Debug)]
+pub struct Spool {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+const MANIFEST: &str = "manifest.json";
+
+/// The directory under `base` that a pull of `source` from `instance` is spooled to, named by a
+/// hash of the two so that pulls of different queries don't replace each other's spool
+#[must_use]
+pub fn spool_dir(base: &Path, instance: &Url, source: &str) -> PathBuf {
+    let digest = openssl::sha::sha256(format!("{}\n{}", instance, source).as_bytes());
+    let key: String = digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    base.join(key)
+}
+
+fn page_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("page-{:05}.json", index))
+}
+
+async fn read_manifest(dir: &Path) -> Result<Option<Manifest>, Error> {
+    let path = dir.join(MANIFEST);
+    match fs::read(&path).await {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map(Some)
+            .context(InvalidSpoolFile { path }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).context(CouldNotReadSpool { path }),
+    }
+}
+
+async fn remove_if_present(path: PathBuf) -> Result<(), Error> {
+    match fs::remove_file(&path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).context(CouldNotWriteSpool { path })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Remove the files of the pull spooled to `dir`. Only the files the spool wrote are removed,
+/// the directory itself is only removed when that leaves it empty.
+async fn clear(dir: &Path, manifest: &Manifest) -> Result<(), Error> {
+    for index in 0..manifest.pages {
+        remove_if_present(page_path(dir, index)).await?;
+    }
+    remove_if_present(dir.join(MANIFEST)).await?;
+    let _ = fs::remove_dir(dir).await;
+    Ok(())
+}
+
+impl Spool {
+    /// Start spooling a new pull of `source` from `instance` to `dir`, replacing any pull that was
+    /// spooled there before. `total` is the number of issues `source` matches.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the previous pull can't be cleared or the spool can't be written.
+    pub async fn create(
+        dir: &Path,
+        instance: &Url,
+        source: &str,
+        total: u64,
+    ) -> Result<Spool, Error> {
+        if let Some(previous) = read_manifest(dir).await? {
+            clear(dir, &previous).await?;
+        }
+        fs::create_dir_all(dir)
+            .await
+            .context(CouldNotCreateSpool { path: dir })?;
+        let spool = Spool {
+            dir: dir.to_path_buf(),
+            manifest: Manifest {
+                instance: instance.clone(),
+                source: source.to_owned(),
+                started_at: Utc::now(),
+                pages: 0,
+                next_start_at: 0,
+                total: Some(total),
+            },
+        };
+        spool.write_manifest().await?;
+        Ok(spool)
+    }
+
+    /// Continue the pull spooled to `dir`. It must be a pull of `source` from `instance`, which
+    /// still matches the `total` issues it matched when the pull was started.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::NoCheckpoint`] when nothing is spooled in `dir`,
+    /// [`Error::CheckpointMismatch`] when the spooled pull is of another source or instance, and
+    /// [`Error::ResultsChanged`] when the number of issues changed, or when the manifest can't be read.
+    pub async fn resume(
+        dir: &Path,
+        instance: &Url,
+        source: &str,
+        total: u64,
+    ) -> Result<Spool, Error> {
+        let manifest = match read_manifest(dir).await? {
+            Some(manifest) => manifest,
+            None => return NoCheckpoint { path: dir }.fail(),
+        };
+        ensure!(
+            manifest.instance == *instance && manifest.source == source,
+            CheckpointMismatch {
+                spooled_source: manifest.source.clone(),
+                spooled_instance: manifest.instance.as_str(),
+                source_description: source,
+                instance: instance.as_str(),
+            }
+        );
+        if let Some(spooled_total) = manifest.total {
+            ensure!(
+                spooled_total == total,
+                ResultsChanged {
+                    spooled_total,
+                    total
+                }
+            );
+        } else {
+            warn!(
+                "The spooled pull did not record how many issues it matched, issues that changed \
+                 since it was started may be missing"
+            );
+        }
+        Ok(Spool {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// When the pull was first started
+    #[must_use]
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.manifest.started_at
+    }
+
+    /// The number of pages spooled so far
+    #[must_use]
+    pub fn page_count(&self) -> usize {
+        self.manifest.pages
+    }
+
+    /// The offset to continue pulling from
+    #[must_use]
+    pub fn next_start_at(&self) -> u64 {
+        self.manifest.next_start_at
+    }
+
+    /// Read one of the spooled pages
+    ///
+    /// # Errors
+    ///
+    /// Fails when the page can't be read or parsed.
+    pub async fn read_page(&self, index: usize) -> Result<Vec<IssueDetail>, Error> {
+        let path = page_path(&self.dir, index);
+        let contents = fs::read(&path)
+            .await
+            .context(CouldNotReadSpool { path: &path })?;
+        serde_json::from_slice(&contents).context(InvalidSpoolFile { path })
+    }
+
+    /// Spool a page of issues. `next_start_at` is the offset of the first issue after the page.
+    /// The manifest is only updated once the page is written, so an interrupted write leaves the
+    /// spool as it was.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the page or the manifest can't be written.
+    pub async fn push(&mut self, issues: &[IssueDetail], next_start_at: u64) -> Result<(), Error> {
+        let path = page_path(&self.dir, self.manifest.pages);
+        let contents = serde_json::to_vec(issues).context(UnableToSerializePage {})?;
+        fs::write(&path, contents)
+            .await
+            .context(CouldNotWriteSpool { path })?;
+        self.manifest.pages += 1;
+        self.manifest.next_start_at = next_start_at;
+        self.write_manifest().await
+    }
+
+    /// Remove the spool of a finished pull
+    ///
+    /// # Errors
+    ///
+    /// Fails when a file of the spool can't be removed.
+    pub async fn remove(self) -> Result<(), Error> {
+        clear(&self.dir, &self.manifest).await
+    }
+
+    /// Written to a temporary file first and renamed, so the manifest is never half written
+    async fn write_manifest(&self) -> Result<(), Error> {
+        let path = self.dir.join(MANIFEST);
+        let temporary_path = self.dir.join(format!("{}.tmp", MANIFEST));
+        let contents = serde_json::to_vec(&self.manifest).context(UnableToSerializePage {})?;
+        fs::write(&temporary_path, contents)
+            .await
+            .context(CouldNotWriteSpool {
+                path: &temporary_path,
+            })?;
+        fs::rename(&temporary_path, &path)
+            .await
+            .context(CouldNotWriteSpool { path })
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,52 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Resuming a spooled pull only when it is of the same issues.
+use super::{spool_dir, Error, Spool};
+use url::Url;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn resumes_only_while_the_query_matches_the_same_issues() {
+    let instance = Url::parse("https://jira.example.com").expect("a url");
+    let base = std::env::temp_dir().join(format!("lectev-spool-test-{}", Uuid::new_v4()));
+    let dir = spool_dir(&base, &instance, "jql project = LEC");
+
+    let mut spool = Spool::create(&dir, &instance, "jql project = LEC", 250)
+        .await
+        .expect("a new spool");
+    spool.push(&[], 100).await.expect("a spooled page");
+
+    let resumed = Spool::resume(&dir, &instance, "jql project = LEC", 250)
+        .await
+        .expect("a resumed spool");
+    assert_eq!(resumed.page_count(), 1);
+    assert_eq!(resumed.next_start_at(), 100);
+
+    assert!(matches!(
+        Spool::resume(&dir, &instance, "jql project = LEC", 251).await,
+        Err(Error::ResultsChanged {
+            spooled_total: 250,
+            total: 251
+        })
+    ));
+    assert!(matches!(
+        Spool::resume(&dir, &instance, "jql project = OTHER", 250).await,
+        Err(Error::CheckpointMismatch { .. })
+    ));
+
+    resumed.remove().await.expect("the spool removed");
+    assert!(!dir.exists());
+    let _ = std::fs::remove_dir(&base);
+}
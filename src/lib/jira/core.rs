@@ -12,42 +12,91 @@
 //
 //  You should have received a copy of the GNU General Public License
 //  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Core Model
+//!
+//! The items the reports are calculated from, and what happened to them, independent of the
+//! tracker they were pulled from.
 use chrono::prelude::{DateTime, Utc};
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use uom::si::f64::Time;
 use url::Url;
 use uuid::Uuid;
 
 /// Id of the item
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ItemId(pub Uuid);
 
+/// The id of an item in the tracker it came from, e.g. the key of a jira issue
 #[derive(Display, Hash, Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct NativeId(pub String);
 
-#[derive(Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+/// Id of an entry of the timeline of an item
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ItemTimeLineEntryId(pub Uuid);
 
 /// Provides the potential resolutions for an issue
-#[derive(Display, Debug, Clone, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Resolution {
+    /// The item is not resolved yet
     UnResolved,
+    /// The item was resolved without being done, e.g. as a duplicate or `Won't Do`
     Rejected,
+    /// The item was done
     Delivered,
 }
 
+/// The values accepted for [`Resolution`] on the command line
+pub const RESOLUTIONS: [&str; 3] = ["UnResolved", "Rejected", "Delivered"];
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "UnResolved" => Ok(Resolution::UnResolved),
+            "Rejected" => Ok(Resolution::Rejected),
+            "Delivered" => Ok(Resolution::Delivered),
+            _ => Err(format!("Unknown resolution '{}'", value)),
+        }
+    }
+}
+
 /// Provides the internal representation of status' for an item
-#[derive(Display, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ItemStatus {
+    /// The item has not been looked at yet
     ToDo,
+    /// The item is ready to be worked on
     Ready,
+    /// The item is being worked on
     InDev,
+    /// The item is being tested or reviewed
     InTest,
+    /// The item is held up, e.g. waiting on another team
     Waiting,
+    /// The item is finished
     Completed,
 }
 
+impl ItemStatus {
+    /// Where the status falls in the flow of work, so moving to a status with a lower rank is
+    /// moving backwards. An item can be waiting at any point in the flow, so `Waiting` has no
+    /// rank.
+    #[must_use]
+    pub fn rank(&self) -> Option<u8> {
+        match self {
+            ItemStatus::ToDo => Some(0),
+            ItemStatus::Ready => Some(1),
+            ItemStatus::InDev => Some(2),
+            ItemStatus::InTest => Some(3),
+            ItemStatus::Waiting => None,
+            ItemStatus::Completed => Some(4),
+        }
+    }
+}
+
 /// Timeline entry
 ///
 /// This currently only contains status' in the future it may contain other things.
@@ -56,37 +105,164 @@ pub enum ItemTimeLineEntry {
     /// ClosedStatus is for a status that is complete. Ie, the item has transitioned to a new status
     /// and this status will no longer be updated
     ClosedStatus {
+        /// The status the item was in
         status: ItemStatus,
+        /// When the item moved into the status
         start: DateTime<Utc>,
+        /// When the item moved out of the status
         end: DateTime<Utc>,
     },
     /// An open status is a status that is not complete. Essentially, the item is still in this
     /// status at the time the report was run
     OpenStatus {
+        /// The status the item is in
         status: ItemStatus,
+        /// When the item moved into the status
         start: DateTime<Utc>,
     },
+    /// The item was estimated, or its estimate changed
     Estimate {
+        /// When the estimate was made
         start: DateTime<Utc>,
+        /// The estimate
         days: Time,
     },
+    /// The item was flagged as impeded. If `end` is `None` the item was still flagged at the
+    /// time the report was run
+    Blocked {
+        /// When the item was flagged
+        start: DateTime<Utc>,
+        /// When the flag was cleared
+        end: Option<DateTime<Utc>>,
+    },
+    /// Time logged against the item
+    Worklog {
+        /// When the work started
+        start: DateTime<Utc>,
+        /// The time logged
+        spent: Time,
+    },
+    /// The assignee of the item changed. `None` is unassigned.
+    Reassigned {
+        /// When the assignee changed
+        start: DateTime<Utc>,
+        /// Who the item was assigned to before
+        from: Option<String>,
+        /// Who the item is assigned to after
+        to: Option<String>,
+    },
+    /// The status of the item changed. `from` and `to` are the names of the statuses in the
+    /// tracker, `from_status` and `to_status` what they map to. `author` is who changed it.
+    Transitioned {
+        /// When the status changed
+        start: DateTime<Utc>,
+        /// The status before
+        from: Option<String>,
+        /// The status after
+        to: Option<String>,
+        /// What the status before maps to
+        from_status: ItemStatus,
+        /// What the status after maps to
+        to_status: ItemStatus,
+        /// Who changed the status
+        author: Option<String>,
+    },
+}
+/// The kind of relationship one item has to another
+#[derive(Display, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkType {
+    /// This item must be done before the target can be
+    Blocks,
+    /// The target must be done before this item can be
+    IsBlockedBy,
+    /// The items are related without either one holding up the other
+    Relates,
 }
-#[derive(Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+
+impl LinkType {
+    /// The same relationship seen from the other end of the link
+    #[must_use]
+    pub fn inverse(self) -> LinkType {
+        match self {
+            LinkType::Blocks => LinkType::IsBlockedBy,
+            LinkType::IsBlockedBy => LinkType::Blocks,
+            LinkType::Relates => LinkType::Relates,
+        }
+    }
+}
+
+/// A typed relationship from an item to another item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemLink {
+    /// The kind of relationship
+    pub typ: LinkType,
+    /// The item at the other end of the link
+    pub target: NativeId,
+}
+
+/// What the work on an item is for
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ItemType {
+    /// Keeping things running, the issue types listed under `operational`, e.g. incidents
     Operational,
+    /// Paying down technical debt and improving the way things are built
     Reinvestment,
+    /// Work that adds to the product, the issue types listed under `features`
     Feature,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A unit of work translated from a tracker, with everything the reports need from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
+    /// The id of the item
     pub id: ItemId,
+    /// The id of the item in the tracker it came from
     pub native_id: NativeId,
+    /// The url of the item in the tracker
     pub native_url: Url,
+    /// The name of the item, e.g. the key of a jira issue
     pub name: String,
+    /// The summary of the item
     pub description: String,
+    /// What the work is for
     pub typ: ItemType,
+    /// The current status of the item
     pub status: ItemStatus,
+    /// How the item was resolved, if it was
     pub resolution: Resolution,
+    /// What happened to the item, in the order it happened
     pub timeline: Vec<ItemTimeLineEntry>,
+    /// The display name of the person the item is assigned to
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// The team that owns the item, when a team field is configured
+    #[serde(default)]
+    pub team: Option<String>,
+    /// The relationships this item has to other items
+    #[serde(default)]
+    pub links: Vec<ItemLink>,
+    /// When the item was created
+    #[serde(default)]
+    pub created: Option<DateTime<Utc>>,
+    /// When the item was resolved, if it has been
+    #[serde(default)]
+    pub resolved: Option<DateTime<Utc>>,
+    /// The key of the project the item belongs to
+    #[serde(default)]
+    pub project: Option<String>,
+    /// The labels of the item
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// The names of the components of the item
+    #[serde(default)]
+    pub components: Vec<String>,
+    /// The item this one is a child of, its epic or, for a sub-task, its story
+    #[serde(default)]
+    pub parent: Option<NativeId>,
+    /// The full description of the item as text, `description` holds its summary
+    #[serde(default)]
+    pub body: Option<String>,
+    /// The description as HTML, when the tracker was asked to render it
+    #[serde(default)]
+    pub rendered_body: Option<String>,
 }
@@ -0,0 +1,294 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Jira Dumps
+//!
+//! The format of a `--debug-jira-file`, the issues as they were pulled from jira along with when
+//! they were pulled and, when `infer-status-categories` is set, every status of the instance.
+//! Reports can be run on a dump in place of jira. A dump is written a page at a
+//! time with a [`Writer`], so the whole of it never has to be in memory at once.
+use crate::lib::jira::{api, native, redact};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// The ways reading and writing a dump can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The dump could not be read
+    #[snafu(display("Could not read the jira dump {}: {}", path.display(), source))]
+    CouldNotRead {
+        /// The path of the dump
+        path: PathBuf,
+        /// The error of reading it
+        source: std::io::Error,
+    },
+    /// The dump is not valid json, or not a dump
+    #[snafu(display("The jira dump {} is not valid: {}", path.display(), source))]
+    InvalidDump {
+        /// The path of the dump
+        path: PathBuf,
+        /// The error of parsing it
+        source: serde_json::Error,
+    },
+    /// The dump could not be created
+    #[snafu(display("Could not create the jira dump {}: {}", path.display(), source))]
+    CouldNotCreate {
+        /// The path of the dump
+        path: PathBuf,
+        /// The error of creating it
+        source: std::io::Error,
+    },
+    /// The issues could not be serialized
+    #[snafu(display("Could not serialize the issues of the jira dump: {}", source))]
+    UnableToSerialize {
+        /// The error of serializing them
+        source: serde_json::Error,
+    },
+    /// The dump could not be written
+    #[snafu(display("Could not write to the jira dump {}: {}", path.display(), source))]
+    CouldNotWrite {
+        /// The path of the dump
+        path: PathBuf,
+        /// The error of writing it
+        source: std::io::Error,
+    },
+}
+
+/// The contents of a 'debug-jira-file'
+#[derive(Debug, Deserialize)]
+pub struct JiraDump {
+    /// When the issues were pulled from jira. Dumps written before this was recorded don't have
+    /// it.
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Set when pulling the issues was interrupted, or some of them failed, so only some of them
+    /// are in the dump
+    #[serde(default)]
+    pub partial: bool,
+    /// The issues that were left out because their changelog could not be pulled
+    #[serde(default)]
+    pub errors: Vec<api::FailedIssue>,
+    /// The issues, in the order they were pulled
+    pub issues: Vec<api::IssueDetail>,
+    /// Every status of the instance, for the categories of the statuses the changelogs go through.
+    /// Only written when `infer-status-categories` needed them, and dumps written before they were
+    /// recorded don't have them.
+    #[serde(default)]
+    pub statuses: Vec<native::InstanceStatus>,
+}
+
+/// Older dumps are a bare array of issues
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StoredJiraDump {
+    WithMetadata(JiraDump),
+    Legacy(Vec<api::IssueDetail>),
+}
+
+/// Parse the `contents` of the dump at `path`, warning when it is missing issues
+///
+/// # Errors
+///
+/// Fails when `contents` is not a dump.
+pub fn parse(contents: &str, path: &Path) -> Result<JiraDump, serde_json::Error> {
+    match serde_json::from_str(contents)? {
+        StoredJiraDump::WithMetadata(dump) => {
+            if !dump.errors.is_empty() {
+                warn!(
+                    "The changelogs of {} issues could not be pulled into {}, they are missing",
+                    dump.errors.len(),
+                    path.display()
+                );
+            } else if dump.partial {
+                warn!(
+                    "{} was interrupted while it was pulled, it is missing issues",
+                    path.display()
+                );
+            }
+            Ok(dump)
+        }
+        StoredJiraDump::Legacy(issues) => Ok(JiraDump {
+            fetched_at: None,
+            partial: false,
+            errors: Vec::new(),
+            issues,
+            statuses: Vec::new(),
+        }),
+    }
+}
+
+/// Read the dump at `path`, see [`parse`]
+///
+/// # Errors
+///
+/// Fails when the dump can't be read or is not valid.
+pub async fn read(path: &Path) -> Result<JiraDump, Error> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .context(CouldNotRead { path })?;
+    parse(&contents, path).context(InvalidDump { path })
+}
+
+/// The time from which updates need to be pulled to bring the dump up to date. That is when the
+/// dump was fetched or, for older dumps, the most recent update of any issue in it. A partial
+/// dump is missing issues regardless of when they were updated, so it has to be pulled again.
+#[must_use]
+pub fn updated_since(dump: &JiraDump) -> Option<DateTime<Utc>> {
+    if dump.partial {
+        return None;
+    }
+    dump.fetched_at.or_else(|| {
+        dump.issues
+            .iter()
+            .filter_map(|detail| {
+                DateTime::<FixedOffset>::from_str(&detail.issue.fields.updated).ok()
+            })
+            .map(|updated| updated.with_timezone(&Utc))
+            .max()
+    })
+}
+
+/// Replace the issues in `existing` with their updated versions, keeping the existing order, and
+/// add any issues that are new at the end
+#[must_use]
+pub fn merge(
+    existing: Vec<api::IssueDetail>,
+    updated: Vec<api::IssueDetail>,
+) -> Vec<api::IssueDetail> {
+    let mut updated_by_key: HashMap<native::IssueKey, api::IssueDetail> = HashMap::new();
+    let mut new_keys = Vec::new();
+    for detail in updated {
+        if !updated_by_key.contains_key(&detail.issue.key) {
+            new_keys.push(detail.issue.key.clone());
+        }
+        updated_by_key.insert(detail.issue.key.clone(), detail);
+    }
+
+    let mut merged: Vec<api::IssueDetail> = existing
+        .into_iter()
+        .map(|detail| updated_by_key.remove(&detail.issue.key).unwrap_or(detail))
+        .collect();
+    merged.extend(new_keys.iter().filter_map(|key| updated_by_key.remove(key)));
+    merged
+}
+
+/// Writes the raw jira data, along with when it was fetched, one segment at a time, so the whole
+/// data set never has to be in memory at once.
+#[derive(Debug)]
+pub struct Writer {
+    path: PathBuf,
+    file: File,
+    is_first: bool,
+    redact_pii: bool,
+}
+
+impl Writer {
+    /// Start the dump at `path` of the issues fetched at `fetched_at`. With `redact_pii` the
+    /// people in the issues are redacted, see [`redact::redact_pii`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the dump can't be created or written.
+    pub async fn create(
+        path: &Path,
+        fetched_at: &DateTime<Utc>,
+        redact_pii: bool,
+    ) -> Result<Writer, Error> {
+        let mut file = File::create(path).await.context(CouldNotCreate { path })?;
+        let header = format!(
+            "{{\"fetched_at\":{},\"issues\":[",
+            serde_json::to_string(fetched_at).context(UnableToSerialize {})?
+        );
+        file.write_all(header.as_bytes())
+            .await
+            .context(CouldNotWrite { path })?;
+        Ok(Writer {
+            path: path.to_path_buf(),
+            file,
+            is_first: true,
+            redact_pii,
+        })
+    }
+
+    /// Add a segment of issues to the dump
+    ///
+    /// # Errors
+    ///
+    /// Fails when an issue can't be serialized or the dump can't be written.
+    pub async fn write(&mut self, data: &[api::IssueDetail]) -> Result<(), Error> {
+        for detail in data {
+            let mut contents = if self.is_first {
+                Vec::new()
+            } else {
+                b",".to_vec()
+            };
+            if self.redact_pii {
+                let mut redacted = detail.clone();
+                redact::redact_pii(&mut redacted);
+                serde_json::to_writer(&mut contents, &redacted)
+            } else {
+                serde_json::to_writer(&mut contents, detail)
+            }
+            .context(UnableToSerialize {})?;
+            self.file
+                .write_all(&contents)
+                .await
+                .context(CouldNotWrite { path: &self.path })?;
+            self.is_first = false;
+        }
+        Ok(())
+    }
+
+    /// Close the dump, marking it as partial when pulling the issues was interrupted or some of
+    /// them failed. The failed issues are listed in its `errors`, and the `statuses` of the
+    /// instance are kept with the issues.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the dump can't be written.
+    pub async fn finish(
+        mut self,
+        partial: bool,
+        errors: &[api::FailedIssue],
+        statuses: &[native::InstanceStatus],
+    ) -> Result<(), Error> {
+        let mut footer = "]".to_owned();
+        if partial || !errors.is_empty() {
+            footer.push_str(",\"partial\":true");
+        }
+        if !errors.is_empty() {
+            footer.push_str(",\"errors\":");
+            footer.push_str(&serde_json::to_string(errors).context(UnableToSerialize {})?);
+        }
+        if !statuses.is_empty() {
+            footer.push_str(",\"statuses\":");
+            footer.push_str(&serde_json::to_string(statuses).context(UnableToSerialize {})?);
+        }
+        footer.push('}');
+        self.file
+            .write_all(footer.as_bytes())
+            .await
+            .context(CouldNotWrite { path: &self.path })?;
+        self.file
+            .flush()
+            .await
+            .context(CouldNotWrite { path: &self.path })
+    }
+}
@@ -0,0 +1,166 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Dump Diffs
+//!
+//! Compares two snapshots of the same query, as written to a `debug-jira-file`, and lists what
+//! changed between them. Issues that are new or gone, issues that were resolved or reopened, and
+//! issues whose status or remaining estimate changed each get a row. The jira statuses are
+//! compared, rather than the statuses they map to, so no config is needed.
+use crate::lib::jira::api::IssueDetail;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::instrument;
+use uom::si::f64::Time;
+use uom::si::time::{day, second};
+
+/// What happened to an issue between the snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// Only in the newer snapshot
+    New,
+    /// Only in the older snapshot, it no longer matches the query or was deleted
+    Removed,
+    /// It was unresolved in the older snapshot and is resolved in the newer one
+    Resolved,
+    /// It was resolved in the older snapshot and is unresolved in the newer one
+    Reopened,
+    /// Its status changed
+    StatusChanged,
+    /// Its estimate changed
+    EstimateChanged,
+}
+
+/// A row of the diff of two dumps, a change to a single issue
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The key of the issue
+    pub issue: &'a str,
+    /// What changed
+    pub change: Change,
+    /// The summary of the issue, in the newer snapshot when it is in both
+    pub summary: &'a str,
+    /// The resolution, status or estimate, in days, in the older snapshot
+    pub from: Option<String>,
+    /// The resolution, status or estimate, in days, in the newer snapshot
+    pub to: Option<String>,
+}
+
+/// The remaining estimate, in days, as it was last changed in the changelog
+fn estimate(detail: &IssueDetail) -> Option<String> {
+    detail
+        .changelog
+        .iter()
+        .flat_map(|group| group.items.iter())
+        .rev()
+        .find(|entry| entry.field == "timeestimate")
+        .and_then(|entry| entry.to.as_deref())
+        .and_then(|seconds| f64::from_str(seconds).ok())
+        .map(|seconds| Time::new::<second>(seconds).get::<day>().to_string())
+}
+
+fn resolution(detail: &IssueDetail) -> Option<String> {
+    detail
+        .issue
+        .fields
+        .resolution
+        .as_ref()
+        .map(|resolution| resolution.name.clone())
+}
+
+fn entry(
+    detail: &IssueDetail,
+    change: Change,
+    from: Option<String>,
+    to: Option<String>,
+) -> Entry<'_> {
+    Entry {
+        issue: &detail.issue.key.0,
+        change,
+        summary: &detail.issue.fields.summary,
+        from,
+        to,
+    }
+}
+
+/// The changes to a single issue that is in both snapshots
+fn changes<'a>(old: &IssueDetail, new: &'a IssueDetail) -> Vec<Entry<'a>> {
+    let mut entries = Vec::new();
+    match (resolution(old), resolution(new)) {
+        (None, Some(to)) => entries.push(entry(new, Change::Resolved, None, Some(to))),
+        (Some(from), None) => entries.push(entry(new, Change::Reopened, Some(from), None)),
+        _ => {}
+    }
+    let (old_status, new_status) = (&old.issue.fields.status.name, &new.issue.fields.status.name);
+    if old_status != new_status {
+        entries.push(entry(
+            new,
+            Change::StatusChanged,
+            Some(old_status.clone()),
+            Some(new_status.clone()),
+        ));
+    }
+    let (old_estimate, new_estimate) = (estimate(old), estimate(new));
+    if old_estimate != new_estimate {
+        entries.push(entry(
+            new,
+            Change::EstimateChanged,
+            old_estimate,
+            new_estimate,
+        ));
+    }
+    entries
+}
+
+/// Every change from the `old` snapshot to the `new` one, in the order of the issues in the new
+/// snapshot, followed by the issues that were removed
+#[instrument(skip(old, new))]
+pub fn diff<'a>(old: &'a [IssueDetail], new: &'a [IssueDetail]) -> Vec<Entry<'a>> {
+    let old_by_key: HashMap<&str, &IssueDetail> = old
+        .iter()
+        .map(|detail| (detail.issue.key.0.as_str(), detail))
+        .collect();
+    let new_by_key: HashMap<&str, &IssueDetail> = new
+        .iter()
+        .map(|detail| (detail.issue.key.0.as_str(), detail))
+        .collect();
+
+    let mut entries = Vec::new();
+    for detail in new {
+        match old_by_key.get(detail.issue.key.0.as_str()) {
+            Some(old_detail) => entries.extend(changes(old_detail, detail)),
+            None => entries.push(entry(
+                detail,
+                Change::New,
+                None,
+                Some(detail.issue.fields.status.name.clone()),
+            )),
+        }
+    }
+    entries.extend(
+        old.iter()
+            .filter(|detail| !new_by_key.contains_key(detail.issue.key.0.as_str()))
+            .map(|detail| {
+                entry(
+                    detail,
+                    Change::Removed,
+                    Some(detail.issue.fields.status.name.clone()),
+                    None,
+                )
+            }),
+    );
+    entries
+}
@@ -0,0 +1,87 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Epic Rollups
+//!
+//! Summarizes the current status of the children of an epic. Only children whose issue type is
+//! mapped in the config are counted, the same as every other report.
+use crate::lib::jira::core;
+use serde::Serialize;
+use tracing::instrument;
+use url::Url;
+
+/// A row of the epic report, the children of an epic counted by their current status
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The url of the epic in jira
+    pub url: String,
+    /// The key of the epic
+    pub name: &'a str,
+    /// The summary of the epic
+    pub description: &'a str,
+    /// The number of children
+    pub children: usize,
+    /// The children in `ToDo`
+    pub todo: usize,
+    /// The children in `Ready`
+    pub ready: usize,
+    /// The children in `InDev`
+    pub in_dev: usize,
+    /// The children in `InTest`
+    pub in_test: usize,
+    /// The children in `Waiting`
+    pub waiting: usize,
+    /// The children in `Completed`
+    pub completed: usize,
+    /// The percentage of the children that are completed
+    pub percent_completed: f64,
+    /// The description of the epic as HTML, when jira was asked to render it
+    pub rendered_description: Option<&'a str>,
+}
+
+/// Count the children of `epic` by their current status
+#[instrument(skip(epic, children), fields(epic = %epic.name))]
+#[allow(clippy::cast_precision_loss)]
+pub fn rollup<'a>(instance_url: &Url, epic: &'a core::Item, children: &[core::Item]) -> Entry<'a> {
+    let mut entry = Entry {
+        url: format!("{}browse/{}", instance_url.as_str(), epic.name),
+        name: &epic.name,
+        description: &epic.description,
+        children: children.len(),
+        todo: 0,
+        ready: 0,
+        in_dev: 0,
+        in_test: 0,
+        waiting: 0,
+        completed: 0,
+        percent_completed: 0.0,
+        rendered_description: epic.rendered_body.as_deref(),
+    };
+
+    for child in children {
+        match child.status {
+            core::ItemStatus::ToDo => entry.todo += 1,
+            core::ItemStatus::Ready => entry.ready += 1,
+            core::ItemStatus::InDev => entry.in_dev += 1,
+            core::ItemStatus::InTest => entry.in_test += 1,
+            core::ItemStatus::Waiting => entry.waiting += 1,
+            core::ItemStatus::Completed => entry.completed += 1,
+        }
+    }
+    if entry.children > 0 {
+        entry.percent_completed = entry.completed as f64 / entry.children as f64 * 100.0;
+    }
+
+    entry
+}
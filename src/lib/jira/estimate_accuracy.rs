@@ -0,0 +1,153 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Estimate Accuracy
+//!
+//! Compares the time logged against each resolved item with the first estimate it was given. The
+//! accuracy ratio is the logged time divided by the estimate, so a ratio above one means the
+//! item took longer than estimated.
+use crate::lib::jira::core;
+use serde::Serialize;
+use tracing::instrument;
+use uom::si::f64::Time;
+use uom::si::time::hour;
+use url::Url;
+
+/// A row of the estimate accuracy report, the first estimate of an item against the time logged
+/// against it
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The url of the item in the tracker
+    pub url: String,
+    /// The name of the item
+    pub name: &'a str,
+    /// The summary of the item
+    pub description: &'a str,
+    /// The first estimate, in hours
+    pub first_estimate_hours: Option<f64>,
+    /// The time logged, in hours
+    pub logged_hours: f64,
+    /// The time logged over the first estimate
+    pub accuracy_ratio: Option<f64>,
+}
+
+/// The accuracy over every entry of the report
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Summary {
+    /// The items reported on
+    pub items: usize,
+    /// The items that had both an estimate and logged time
+    pub compared_items: usize,
+    /// The total estimate of the compared items, in hours
+    pub estimated_hours: f64,
+    /// The total time logged against every item, in hours
+    pub logged_hours: f64,
+    /// The total logged time of the compared items over their total estimate
+    pub aggregate_ratio: Option<f64>,
+    /// The average of the ratios of the compared items
+    pub mean_ratio: Option<f64>,
+}
+
+fn first_estimate(item: &core::Item) -> Option<Time> {
+    item.timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            core::ItemTimeLineEntry::Estimate { start, days } => Some((start, *days)),
+            _ => None,
+        })
+        .min_by_key(|(start, _)| *start)
+        .map(|(_, days)| days)
+}
+
+fn logged(item: &core::Item) -> Time {
+    item.timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            core::ItemTimeLineEntry::Worklog { spent, .. } => Some(*spent),
+            _ => None,
+        })
+        .fold(Time::new::<hour>(0.0), |total, spent| total + spent)
+}
+
+/// Compare the logged time with the first estimate for every resolved item. Items that are not
+/// resolved are left out.
+#[instrument(skip(items))]
+pub fn calculate<'a>(instance_url: &Url, items: &'a [core::Item]) -> Vec<Entry<'a>> {
+    items
+        .iter()
+        .filter(|item| !matches!(item.resolution, core::Resolution::UnResolved))
+        .map(|item| {
+            let first_estimate_hours = first_estimate(item).map(|estimate| estimate.get::<hour>());
+            let logged_hours = logged(item).get::<hour>();
+            Entry {
+                url: format!("{}browse/{}", instance_url.as_str(), &item.name),
+                name: &item.name,
+                description: &item.description,
+                first_estimate_hours,
+                logged_hours,
+                accuracy_ratio: first_estimate_hours
+                    .filter(|estimate| *estimate > 0.0 && logged_hours > 0.0)
+                    .map(|estimate| logged_hours / estimate),
+            }
+        })
+        .collect()
+}
+
+/// Running totals of the entries written so far, so that the summary can be built without
+/// holding every entry in memory
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Totals {
+    items: usize,
+    compared_items: usize,
+    estimated_hours: f64,
+    logged_hours: f64,
+    compared_logged_hours: f64,
+    ratio_total: f64,
+}
+
+impl Totals {
+    /// Add an entry to the totals
+    pub fn add(&mut self, entry: &Entry) {
+        self.items += 1;
+        self.logged_hours += entry.logged_hours;
+        if let (Some(estimate), Some(ratio)) = (entry.first_estimate_hours, entry.accuracy_ratio) {
+            self.compared_items += 1;
+            self.estimated_hours += estimate;
+            self.compared_logged_hours += entry.logged_hours;
+            self.ratio_total += ratio;
+        }
+    }
+
+    /// The summary of the entries added so far
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        Summary {
+            items: self.items,
+            compared_items: self.compared_items,
+            estimated_hours: self.estimated_hours,
+            logged_hours: self.logged_hours,
+            aggregate_ratio: if self.estimated_hours > 0.0 {
+                Some(self.compared_logged_hours / self.estimated_hours)
+            } else {
+                None
+            },
+            mean_ratio: if self.compared_items == 0 {
+                None
+            } else {
+                Some(self.ratio_total / self.compared_items as f64)
+            },
+        }
+    }
+}
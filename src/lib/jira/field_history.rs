@@ -0,0 +1,83 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Field History
+//!
+//! Every change jira recorded to a chosen set of fields, one row per change. This works on the
+//! native changelog rather than on [`core::Item`]s, since the items only keep the fields that the
+//! reports use.
+//!
+//! [`core::Item`]: crate::lib::jira::core::Item
+use crate::lib::jira::api;
+use crate::lib::jira::native;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+
+/// A row of the field history export, a single change of a field of an issue
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The key of the issue
+    pub issue: &'a str,
+    /// The name of the field
+    pub field: &'a str,
+    /// The value before, as it is shown
+    pub from: Option<&'a str>,
+    /// The value after, as it is shown
+    pub to: Option<&'a str>,
+    /// When the field changed
+    pub changed_at: DateTime<Utc>,
+    /// Who changed it
+    pub author: &'a str,
+}
+
+/// Jira names fields in the changelog by their display name, e.g. `Story Points`, and gives
+/// custom fields an id, e.g. `customfield_10002`, so either is accepted, ignoring case
+fn is_wanted(fields: &[String], change: &native::ChangeLogEntry) -> bool {
+    fields.iter().any(|field| {
+        change.field.eq_ignore_ascii_case(field)
+            || change
+                .field_id
+                .as_ref()
+                .map_or(false, |field_id| field_id.eq_ignore_ascii_case(field))
+    })
+}
+
+/// Every change to one of `fields` in the changelogs of `details`, oldest first for each issue.
+/// The readable value of a change is used when jira provides one, otherwise the raw value is.
+#[instrument(skip(details))]
+pub fn entries<'a>(fields: &[String], details: &'a [api::IssueDetail]) -> Vec<Entry<'a>> {
+    let mut entries = Vec::new();
+    for detail in details {
+        let mut changelog: Vec<&native::ChangeGroup> = detail.changelog.iter().collect();
+        changelog.sort_by_key(|group| group.created);
+        for group in changelog {
+            for change in group
+                .items
+                .iter()
+                .filter(|change| is_wanted(fields, change))
+            {
+                entries.push(Entry {
+                    issue: &detail.issue.key.0,
+                    field: &change.field,
+                    from: change.from_string.as_deref().or(change.from.as_deref()),
+                    to: change.to_string.as_deref().or(change.to.as_deref()),
+                    changed_at: group.created,
+                    author: &group.author.display_name,
+                });
+            }
+        }
+    }
+    entries
+}
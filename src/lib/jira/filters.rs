@@ -0,0 +1,178 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Report Filters
+//!
+//! Some questions can't be asked in JQL, such as which issues spent more than ten business days
+//! in test. Filters are applied to each entry of a report after the time in each status has been
+//! calculated, and only the entries that pass every filter are reported.
+//!
+//! A filter is written as `<column><comparison><value>`, for example `in_test>10`.
+//!
+//! * The column is either one of the day columns accepted by [`thresholds`], such as `in_test`
+//!   or `cycle_time`, or one of the text columns `status`, `resolution`, `assignee` or `team`.
+//! * The comparison is one of `>`, `>=`, `<`, `<=`, `==` or `!=`. Text columns only support `==`
+//!   and `!=`.
+//! * The value is a number of days, optionally followed by a `d`, for day columns and the exact
+//!   text for text columns. An entry without an assignee or team has an empty value, so
+//!   `assignee==` keeps the unassigned entries.
+//!
+//! An entry without a value for a day column, such as an item that was never estimated, does not
+//! pass a filter on that column.
+use crate::lib::jira::thresholds::{self, Column, Comparison};
+use crate::lib::jira::times_in_flight;
+use derive_more::Display;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::str::FromStr;
+
+/// The ways parsing a filter can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The expression has no comparison operator
+    #[snafu(display("No comparison operator found in filter '{}'", expression))]
+    MissingComparison {
+        /// The expression
+        expression: String,
+    },
+    /// The column is not one that can be filtered on
+    #[snafu(display("Unknown column '{}' in filter '{}'", column, expression))]
+    UnknownColumn {
+        /// The column
+        column: String,
+        /// The expression
+        expression: String,
+    },
+    /// The value of a column of days is not a number
+    #[snafu(display("Invalid value '{}' in filter '{}': {}", value, expression, source))]
+    InvalidValue {
+        /// The value
+        value: String,
+        /// The expression
+        expression: String,
+        /// The error of parsing it
+        source: std::num::ParseFloatError,
+    },
+    /// A text column is compared with something other than `==` or `!=`
+    #[snafu(display("Text column in filter '{}' only supports == and !=", expression))]
+    UnsupportedTextComparison {
+        /// The expression
+        expression: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextColumn {
+    Status,
+    Resolution,
+    Assignee,
+    Team,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Days(Column, f64),
+    Text(TextColumn, String),
+}
+
+/// A single parsed filter expression
+#[derive(Display, Debug, Clone)]
+#[display(fmt = "{}", expression)]
+pub struct Filter {
+    expression: String,
+    comparison: Comparison,
+    condition: Condition,
+}
+
+fn parse_text_column(name: &str) -> Option<TextColumn> {
+    match name {
+        "status" => Some(TextColumn::Status),
+        "resolution" => Some(TextColumn::Resolution),
+        "assignee" => Some(TextColumn::Assignee),
+        "team" => Some(TextColumn::Team),
+        _ => None,
+    }
+}
+
+/// Parse a filter expression like `in_test>10d`
+///
+/// # Errors
+///
+/// Fails when the expression has no comparison or names an unknown column, when the value of a
+/// column of days is not a number, or when a text column is compared with an operator other than
+/// `==` or `!=`.
+pub fn parse(expression: &str) -> Result<Filter, Error> {
+    let (column, comparison, raw_value) =
+        thresholds::split_comparison(expression).context(MissingComparison { expression })?;
+
+    let condition = if let Some(text_column) = parse_text_column(column) {
+        ensure!(
+            matches!(comparison, Comparison::Equal | Comparison::NotEqual),
+            UnsupportedTextComparison { expression }
+        );
+        Condition::Text(text_column, raw_value.to_owned())
+    } else {
+        let day_column =
+            thresholds::parse_column(column).context(UnknownColumn { column, expression })?;
+        let value_str = raw_value.strip_suffix('d').unwrap_or(raw_value).trim();
+        let value = f64::from_str(value_str).context(InvalidValue {
+            value: raw_value,
+            expression,
+        })?;
+        Condition::Days(day_column, value)
+    };
+
+    Ok(Filter {
+        expression: expression.to_owned(),
+        comparison,
+        condition,
+    })
+}
+
+fn text_value(column: TextColumn, entry: &times_in_flight::Entry) -> String {
+    match column {
+        TextColumn::Status => entry.status.to_string(),
+        TextColumn::Resolution => entry.resolution.to_string(),
+        TextColumn::Assignee => entry.assignee.unwrap_or_default().to_owned(),
+        TextColumn::Team => entry.team.unwrap_or_default().to_owned(),
+    }
+}
+
+impl Filter {
+    /// True when the entry passes the filter
+    #[must_use]
+    pub fn matches(&self, entry: &times_in_flight::Entry) -> bool {
+        match &self.condition {
+            Condition::Days(column, value) => thresholds::column_value(*column, entry)
+                .map_or(false, |actual| {
+                    thresholds::compare(self.comparison, actual, *value)
+                }),
+            Condition::Text(column, value) => {
+                let is_equal = text_value(*column, entry) == *value;
+                match self.comparison {
+                    Comparison::NotEqual => !is_equal,
+                    _ => is_equal,
+                }
+            }
+        }
+    }
+}
+
+/// True when the entry passes every filter
+#[must_use]
+pub fn matches_all(filters: &[Filter], entry: &times_in_flight::Entry) -> bool {
+    filters.iter().all(|filter| filter.matches(entry))
+}
+
+#[cfg(test)]
+mod tests;
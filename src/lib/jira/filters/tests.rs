@@ -0,0 +1,145 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Parsing filter expressions and matching them against report entries.
+use super::{matches_all, parse, Condition, Error, Filter, TextColumn};
+use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::jira::test_support;
+use crate::lib::jira::thresholds::{Column, Comparison};
+use crate::lib::jira::times_in_flight::Entry;
+
+fn filter(expression: &str) -> Filter {
+    parse(expression).expect("a valid filter")
+}
+
+/// An entry of an item that spent `in_test` days in test, with the given assignee and an
+/// estimate of three days
+fn entry(in_test: f64, assignee: Option<&'static str>) -> Entry<'static> {
+    Entry {
+        in_dev: 2.0,
+        in_test,
+        first_estimate: Some(3.0),
+        last_estimate: Some(3.0),
+        estimate_drift: Some(0.0),
+        assignee,
+        ..test_support::entry(&ItemStatus::InTest, &Resolution::UnResolved)
+    }
+}
+
+#[test]
+fn parses_day_and_text_columns() {
+    let parsed = filter("in_test>10d");
+    assert_eq!(parsed.comparison, Comparison::Greater);
+    assert_eq!(parsed.condition, Condition::Days(Column::InTest, 10.0));
+    assert_eq!(parsed.to_string(), "in_test>10d");
+
+    let parsed = filter(" cycle_time <= 2.5 ");
+    assert_eq!(parsed.comparison, Comparison::LessOrEqual);
+    assert_eq!(parsed.condition, Condition::Days(Column::CycleTime, 2.5));
+
+    let parsed = filter("status!=Waiting");
+    assert_eq!(parsed.comparison, Comparison::NotEqual);
+    assert_eq!(
+        parsed.condition,
+        Condition::Text(TextColumn::Status, "Waiting".to_owned())
+    );
+
+    let parsed = filter("team==Platform Core");
+    assert_eq!(parsed.comparison, Comparison::Equal);
+    assert_eq!(
+        parsed.condition,
+        Condition::Text(TextColumn::Team, "Platform Core".to_owned())
+    );
+
+    let parsed = filter("assignee==");
+    assert_eq!(
+        parsed.condition,
+        Condition::Text(TextColumn::Assignee, String::new())
+    );
+}
+
+#[test]
+fn rejects_malformed_filters() {
+    for expression in &["in_test", "status Waiting", ""] {
+        assert!(
+            matches!(parse(expression), Err(Error::MissingComparison { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &["lead_time>1", "p85_cycle_time>1", "==Done", "Status==Done"] {
+        assert!(
+            matches!(parse(expression), Err(Error::UnknownColumn { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &["in_test>ten", "in_test>", "in_test>1w"] {
+        assert!(
+            matches!(parse(expression), Err(Error::InvalidValue { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &["status>Waiting", "assignee<=bob", "team>=Core"] {
+        assert!(
+            matches!(
+                parse(expression),
+                Err(Error::UnsupportedTextComparison { .. })
+            ),
+            "{} was accepted",
+            expression
+        );
+    }
+}
+
+#[test]
+fn matches_day_columns() {
+    let slow = entry(12.0, None);
+    let quick = entry(1.0, None);
+
+    assert!(filter("in_test>10").matches(&slow));
+    assert!(!filter("in_test>10").matches(&quick));
+    assert!(filter("cycle_time==14d").matches(&slow));
+    assert!(filter("last_estimate<5").matches(&quick));
+
+    let mut unestimated = entry(1.0, None);
+    unestimated.last_estimate = None;
+    assert!(!filter("last_estimate<5").matches(&unestimated));
+    assert!(!filter("last_estimate>=5").matches(&unestimated));
+}
+
+#[test]
+fn matches_text_columns() {
+    let assigned = entry(1.0, Some("bob"));
+    let unassigned = entry(1.0, None);
+
+    assert!(filter("status==InTest").matches(&assigned));
+    assert!(!filter("status==Waiting").matches(&assigned));
+    assert!(filter("resolution!=Delivered").matches(&assigned));
+    assert!(filter("assignee==bob").matches(&assigned));
+    assert!(!filter("assignee==bob").matches(&unassigned));
+    assert!(filter("assignee==").matches(&unassigned));
+    assert!(filter("assignee!=").matches(&assigned));
+}
+
+#[test]
+fn matches_every_filter() {
+    let filters = [filter("in_test>10"), filter("assignee==bob")];
+
+    assert!(matches_all(&filters, &entry(12.0, Some("bob"))));
+    assert!(!matches_all(&filters, &entry(12.0, Some("alice"))));
+    assert!(!matches_all(&filters, &entry(1.0, Some("bob"))));
+    assert!(matches_all(&[], &entry(1.0, None)));
+}
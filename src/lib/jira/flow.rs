@@ -0,0 +1,181 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Flow Metrics
+//!
+//! The work in progress, throughput and cycle time of a set of items for each week, and how well
+//! they agree with Little's Law, that the average work in progress is the throughput times the
+//! average cycle time. A week where they disagree by a lot had a flow that was not stable, e.g.
+//! work was started much faster than it was finished, so averages over it are not to be trusted.
+//!
+//! An item is in progress from when it first moved to `InDev` or `InTest` until it was
+//! completed. Unlike the time in status report the days are calendar days, as Little's Law
+//! counts every day the work is in progress, but they start and end in the timezone of the
+//! calendar.
+use crate::lib::calendar::Calendar;
+use crate::lib::jira::core;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+/// When an item was in progress. `end` is `None` for items that are still in progress.
+#[derive(Debug)]
+struct Span {
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+}
+
+/// The spans of every item observed
+#[derive(Debug, Default)]
+pub struct Flow {
+    spans: Vec<Span>,
+}
+
+/// The flow of a single week, starting on the Monday `week`
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Week {
+    /// The Monday the week starts on
+    pub week: NaiveDate,
+    /// The items in progress at the end of each day, averaged over the week
+    pub average_wip: f64,
+    /// The items completed during the week
+    pub throughput: usize,
+    /// The average calendar days from start to completion of the items completed in the week
+    pub average_cycle_time_days: Option<f64>,
+    /// The work in progress Little's Law expects from the throughput and cycle time
+    pub predicted_wip: Option<f64>,
+    /// How far the average work in progress is from the prediction, as a share of the average
+    pub deviation: Option<f64>,
+    /// Set when the deviation is more than the allowed maximum
+    pub inconsistent: bool,
+}
+
+/// When the item was in progress, or `None` if it never started
+fn span(item: &core::Item) -> Option<Span> {
+    let start = item
+        .timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            core::ItemTimeLineEntry::ClosedStatus { status, start, .. }
+            | core::ItemTimeLineEntry::OpenStatus { status, start }
+                if *status == core::ItemStatus::InDev || *status == core::ItemStatus::InTest =>
+            {
+                Some(*start)
+            }
+            _ => None,
+        })
+        .min()?;
+    let end = item.timeline.iter().find_map(|entry| match entry {
+        core::ItemTimeLineEntry::OpenStatus {
+            status: core::ItemStatus::Completed,
+            start,
+        } => Some(*start),
+        _ => None,
+    });
+    Some(Span { start, end })
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn days_between(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    (end - start).num_seconds() as f64 / 86_400.0
+}
+
+impl Flow {
+    /// Record the span of an item, items that never started are left out
+    pub fn observe(&mut self, item: &core::Item) {
+        self.spans.extend(span(item));
+    }
+
+    /// The weeks from the first start up to the week of `now`, with the days of `calendar`. A
+    /// week where the average work in progress differs from the prediction by more than
+    /// `max_deviation` is inconsistent.
+    #[allow(clippy::cast_precision_loss)]
+    #[instrument(skip(self))]
+    pub fn weeks(&self, calendar: &Calendar, now: DateTime<Utc>, max_deviation: f64) -> Vec<Week> {
+        let today = calendar.date(&now);
+        let first = match self.spans.iter().map(|span| span.start).min() {
+            Some(first) => calendar.date(&first),
+            None => return Vec::new(),
+        };
+
+        // The items in progress at the end of each day, an item completed during a day no longer
+        // counts for it
+        let mut wip: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for span in &self.spans {
+            let last = span
+                .end
+                .map_or(today, |end| calendar.date(&end) - Duration::days(1))
+                .min(today);
+            let mut day = calendar.date(&span.start);
+            while day <= last {
+                *wip.entry(day).or_default() += 1;
+                day += Duration::days(1);
+            }
+        }
+
+        let mut weeks = Vec::new();
+        let mut monday = monday_of(first);
+        while monday <= today {
+            let days: Vec<NaiveDate> = (0..7)
+                .map(|offset| monday + Duration::days(offset))
+                .filter(|day| *day <= today)
+                .collect();
+            let average_wip = days
+                .iter()
+                .map(|day| wip.get(day).copied().unwrap_or_default())
+                .sum::<usize>() as f64
+                / days.len() as f64;
+            let completed: Vec<f64> = self
+                .spans
+                .iter()
+                .filter_map(|span| span.end.map(|end| (span.start, end)))
+                .filter(|(_, end)| monday_of(calendar.date(end)) == monday)
+                .map(|(start, end)| days_between(start, end))
+                .collect();
+            let throughput = completed.len();
+            let average_cycle_time_days = if completed.is_empty() {
+                None
+            } else {
+                Some(completed.iter().sum::<f64>() / throughput as f64)
+            };
+            let predicted_wip = average_cycle_time_days
+                .map(|cycle_time| throughput as f64 / days.len() as f64 * cycle_time);
+            let deviation = match predicted_wip {
+                Some(predicted) if average_wip > 0.0 => {
+                    Some((average_wip - predicted).abs() / average_wip)
+                }
+                _ => None,
+            };
+            weeks.push(Week {
+                week: monday,
+                average_wip,
+                throughput,
+                average_cycle_time_days,
+                predicted_wip,
+                deviation,
+                inconsistent: deviation.map_or(false, |deviation| deviation > max_deviation),
+            });
+            monday += Duration::days(7);
+        }
+        weeks
+    }
+}
+
+#[cfg(test)]
+mod tests;
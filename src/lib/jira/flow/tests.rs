@@ -0,0 +1,108 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Little's Law over synthetic spans, the week of 2021-06-07 is a Monday to Sunday week
+use super::{Flow, Span, Week};
+use crate::lib::calendar::Calendar;
+use crate::lib::timezone::Timezone;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+fn at(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.ymd(2021, 6, day).and_hms(hour, 0, 0)
+}
+
+fn flow(spans: &[(DateTime<Utc>, Option<DateTime<Utc>>)]) -> Flow {
+    Flow {
+        spans: spans
+            .iter()
+            .map(|(start, end)| Span {
+                start: *start,
+                end: *end,
+            })
+            .collect(),
+    }
+}
+
+fn close(actual: Option<f64>, expected: f64) -> bool {
+    actual.map_or(false, |actual| (actual - expected).abs() < 1e-9)
+}
+
+fn the_week_of(weeks: &[Week], day: u32) -> &Week {
+    let monday = NaiveDate::from_ymd(2021, 6, day);
+    weeks
+        .iter()
+        .find(|week| week.week == monday)
+        .expect("the week is reported")
+}
+
+/// An item in progress from Monday to Wednesday is in progress at the end of two of the seven
+/// days, which is what its throughput of one and cycle time of two days predict
+#[test]
+fn agrees_with_littles_law_for_a_stable_flow() {
+    let weeks = flow(&[(at(7, 0), Some(at(9, 0)))]).weeks(&Calendar::default(), at(13, 12), 0.1);
+    assert_eq!(weeks.len(), 1);
+    let week = &weeks[0];
+    assert!(close(Some(week.average_wip), 2.0 / 7.0));
+    assert_eq!(week.throughput, 1);
+    assert!(close(week.average_cycle_time_days, 2.0));
+    assert!(close(week.predicted_wip, 2.0 / 7.0));
+    assert!(close(week.deviation, 0.0));
+    assert!(!week.inconsistent);
+}
+
+/// Without completed items there is no cycle time to predict from
+#[test]
+fn predicts_nothing_without_throughput() {
+    let weeks = flow(&[(at(7, 0), None)]).weeks(&Calendar::default(), at(13, 12), 0.1);
+    let week = the_week_of(&weeks, 7);
+    assert!(close(Some(week.average_wip), 1.0));
+    assert_eq!(week.throughput, 0);
+    assert_eq!(week.average_cycle_time_days, None);
+    assert_eq!(week.predicted_wip, None);
+    assert!(!week.inconsistent);
+}
+
+/// An item that started on the Tuesday before and finished on Thursday took nine days, far more
+/// than the three days it was in progress this week
+#[test]
+fn is_inconsistent_when_the_flow_is_not_stable() {
+    let weeks = flow(&[(at(1, 0), Some(at(10, 0)))]).weeks(&Calendar::default(), at(13, 12), 0.5);
+    assert_eq!(weeks.len(), 2);
+    let earlier = &weeks[0];
+    assert_eq!(earlier.week, NaiveDate::from_ymd(2021, 5, 31));
+    assert!(close(Some(earlier.average_wip), 6.0 / 7.0));
+    assert_eq!(earlier.throughput, 0);
+    let week = the_week_of(&weeks, 7);
+    assert!(close(Some(week.average_wip), 3.0 / 7.0));
+    assert_eq!(week.throughput, 1);
+    assert!(close(week.average_cycle_time_days, 9.0));
+    assert!(close(week.predicted_wip, 9.0 / 7.0));
+    assert!(close(week.deviation, 2.0));
+    assert!(week.inconsistent);
+}
+
+/// Late on Sunday in UTC is already Monday two hours east of it
+#[test]
+fn counts_days_in_the_timezone_of_the_calendar() {
+    let spans = [(at(6, 23), Some(at(8, 23)))];
+    let utc = flow(&spans).weeks(&Calendar::default(), at(13, 12), 0.1);
+    assert_eq!(utc.len(), 2);
+
+    let east = Calendar::new(None, Some(&Timezone::fixed(2)));
+    let weeks = flow(&spans).weeks(&east, at(13, 12), 0.1);
+    assert_eq!(weeks.len(), 1);
+    let week = the_week_of(&weeks, 7);
+    assert!(close(Some(week.average_wip), 2.0 / 7.0));
+    assert_eq!(week.throughput, 1);
+}
@@ -0,0 +1,140 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Report Metrics
+//!
+//! Aggregates of a time in status report in the Prometheus text exposition format, so a
+//! scheduled run can be scraped, e.g. through the node exporter's textfile collector, and the
+//! trend followed over time. The metrics are
+//!
+//! * `lectev_issues`, the number of issues in the report
+//! * `lectev_status_days_average`, the average days the issues spent in each status
+//! * `lectev_wip_issues`, the issues that are currently in each status other than `completed`
+//! * `lectev_resolved_issues`, the issues in the report that are resolved, its throughput
+//! * `lectev_blocked_days_average`, the average days the issues were blocked
+use crate::lib::jira::core::ItemStatus;
+use crate::lib::jira::times_in_flight;
+use std::fmt;
+
+/// The statuses in the order, and with the label values, they are written in
+const STATUSES: [(ItemStatus, &str); 6] = [
+    (ItemStatus::ToDo, "todo"),
+    (ItemStatus::Ready, "ready"),
+    (ItemStatus::InDev, "in_dev"),
+    (ItemStatus::InTest, "in_test"),
+    (ItemStatus::Waiting, "waiting"),
+    (ItemStatus::Completed, "completed"),
+];
+
+/// The running totals of every entry of a report
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics {
+    issues: usize,
+    resolved: usize,
+    days: [f64; 6],
+    wip: [usize; 6],
+    blocked_days: f64,
+}
+
+fn status_index(status: ItemStatus) -> usize {
+    STATUSES
+        .iter()
+        .position(|(candidate, _)| *candidate == status)
+        .unwrap_or_default()
+}
+
+impl Metrics {
+    /// Record a single report entry
+    pub fn observe(&mut self, entry: &times_in_flight::Entry) {
+        self.issues += 1;
+        if entry.resolved.is_some() {
+            self.resolved += 1;
+        }
+        for (total, days) in self.days.iter_mut().zip(&[
+            entry.todo,
+            entry.ready,
+            entry.in_dev,
+            entry.in_test,
+            entry.waiting,
+            entry.completed,
+        ]) {
+            *total += days;
+        }
+        if *entry.status != ItemStatus::Completed {
+            self.wip[status_index(*entry.status)] += 1;
+        }
+        self.blocked_days += entry.blocked_days;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn average(&self, total: f64) -> f64 {
+        if self.issues == 0 {
+            0.0
+        } else {
+            total / self.issues as f64
+        }
+    }
+}
+
+fn header(f: &mut fmt::Formatter<'_>, name: &str, help: &str) -> fmt::Result {
+    writeln!(f, "# HELP {} {}", name, help)?;
+    writeln!(f, "# TYPE {} gauge", name)
+}
+
+/// The metrics in the Prometheus text exposition format
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        header(f, "lectev_issues", "The number of issues in the report")?;
+        writeln!(f, "lectev_issues {}", self.issues)?;
+        header(
+            f,
+            "lectev_status_days_average",
+            "The average days the issues of the report spent in each status",
+        )?;
+        for ((_, label), total) in STATUSES.iter().zip(&self.days) {
+            writeln!(
+                f,
+                "lectev_status_days_average{{status=\"{}\"}} {}",
+                label,
+                self.average(*total)
+            )?;
+        }
+        header(
+            f,
+            "lectev_wip_issues",
+            "The issues of the report that are currently in each status",
+        )?;
+        for ((status, label), wip) in STATUSES.iter().zip(&self.wip) {
+            if *status != ItemStatus::Completed {
+                writeln!(f, "lectev_wip_issues{{status=\"{}\"}} {}", label, wip)?;
+            }
+        }
+        header(
+            f,
+            "lectev_resolved_issues",
+            "The issues of the report that are resolved",
+        )?;
+        writeln!(f, "lectev_resolved_issues {}", self.resolved)?;
+        header(
+            f,
+            "lectev_blocked_days_average",
+            "The average days the issues of the report were blocked",
+        )?;
+        writeln!(
+            f,
+            "lectev_blocked_days_average {}",
+            self.average(self.blocked_days)
+        )
+    }
+}
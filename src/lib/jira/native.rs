@@ -32,6 +32,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 use url::Url;
 
+/// The name of a team, as held in the `team-field` of the config
 #[derive(Display, Hash, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct TeamName(pub String);
 
@@ -39,406 +40,794 @@ pub struct TeamName(pub String);
 #[derive(Clone, Display, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct CustomFieldName(pub String);
 
+/// The type of the values of a custom field
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomFieldSchema {
+    /// The type of the value, e.g. `string`, `number` or `array`
     #[serde(rename = "type")]
     pub typ: String,
+    /// The system field the custom field is based on, if any
     pub system: Option<String>,
 }
 
+/// A project a custom field is limited to
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomFieldProject {
+    /// The id of the project
     pub id: String,
 }
 
+/// Where a custom field can be used
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomFieldScope {
+    /// The kind of scope, e.g. `PROJECT`
     #[serde(rename = "type")]
     pub typ: String,
+    /// The project of the scope
     pub project: CustomFieldProject,
 }
 
+/// A field of the instance, as listed by `/rest/api/3/field`, whether it is custom or not
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[allow(clippy::struct_excessive_bools)]
 pub struct CustomField {
+    /// The id the field is referred to by in issues, e.g. `customfield_10014`
     pub id: CustomFieldName,
+    /// The key of the field, when it differs from the id
     pub key: Option<CustomFieldName>,
+    /// The name shown for the field
     pub name: CustomFieldName,
+    /// True for custom fields, false for the system fields
     pub custom: bool,
+    /// Whether issues can be ordered by the field
     pub orderable: bool,
+    /// Whether the field can be shown in the issue navigator
     pub navigable: bool,
+    /// Whether issues can be searched by the field
     pub searchable: bool,
+    /// The names the field can be referred to by in jql
     pub clause_names: Vec<String>,
+    /// The type of the values of the field
     pub schema: Option<CustomFieldSchema>,
+    /// The name of the field before it was translated into the language of the user
     pub untranslated_name: Option<CustomFieldName>,
+    /// Where the field can be used, when it is limited to a project
     pub scope: Option<CustomFieldScope>,
 }
 
+/// Every field of the instance
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CustomFields(pub Vec<CustomField>);
 
+/// The key of an issue, e.g. `LEC-1`
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Display)]
 pub struct IssueKey(pub String);
 
+/// The name of a board
 #[derive(Display, Clone, Debug, Serialize, Deserialize)]
 pub struct BoardName(pub String);
 
+/// The name of a project
 #[derive(Display, Clone, Debug, Serialize, Deserialize)]
 pub struct ProjectName(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize, Display)]
+/// The id of a board
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Display)]
 pub struct BoardId(pub i64);
 
+/// The project a board belongs to
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Location {
+    /// The id of the project
     pub project_id: i64,
+    /// The name of the project followed by its key
     pub display_name: String,
+    /// The name of the project
     pub project_name: String,
+    /// The key of the project, e.g. `LEC`
     pub project_key: String,
+    /// The type of the project, e.g. `software`
     pub project_type_key: String,
+    /// The url of the avatar of the project
     #[serde(rename = "avatarURI")]
     pub avatar_uri: String,
+    /// The name shown for the location
     pub name: String,
 }
 
+/// A scrum or kanban board
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Board {
+    /// The id of the board
     pub id: BoardId,
+    /// The url of the board in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The name of the board
     pub name: String,
+    /// The type of the board, `scrum` or `kanban`
     #[serde(rename = "type")]
     pub typ: String,
+    /// The project the board belongs to, absent for boards of several projects
     pub location: Option<Location>,
 }
 
+/// A page of the boards of the instance
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Boards {
+    /// The most boards the page could hold
     pub max_results: u64,
+    /// The offset of the first board of the page
     pub start_at: u64,
+    /// The number of boards across every page
     pub total: u64,
+    /// Whether this is the last page, when jira says
     pub is_last: Option<bool>,
+    /// The boards of the page
     pub values: Vec<Board>,
 }
 
+/// A page of the issues of a board. The issues are read as [`Issue`]s unless asked for as
+/// something else, such as raw json to be read one at a time.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BoardIssues {
+pub struct BoardIssues<I = Issue> {
+    /// The most issues the page could hold
     pub max_results: u64,
+    /// The offset of the first issue of the page
     pub start_at: u64,
+    /// The number of issues across every page
     pub total: u64,
+    /// Whether this is the last page, when jira says
     pub is_last: Option<bool>,
-    pub issues: Vec<Issue>,
+    /// The issues of the page
+    pub issues: Vec<I>,
 }
 
+/// A change to a single field of an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeLogEntry {
+    /// The name of the field that changed, e.g. `status`
     pub field: String,
+    /// Whether the field is a `jira` or a `custom` field
     pub fieldtype: String,
+    /// The id of the field, not given for some of the older changes
     pub field_id: Option<String>,
+    /// The id of the value before the change, e.g. the id of a status
     pub from: Option<String>,
+    /// The value before the change as it is shown
     pub from_string: Option<String>,
+    /// The id of the value after the change
     pub to: Option<String>,
+    /// The value after the change as it is shown
     pub to_string: Option<String>,
 }
 
+/// The changes made to an issue at once, by one person
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeGroup {
+    /// The id of the change
     pub id: String,
+    /// Who made the change
     pub author: Assignee,
+    /// When the change was made
     pub created: DateTime<Utc>,
+    /// The fields that changed
     pub items: Vec<ChangeLogEntry>,
 }
 
+/// A page of the changelog of an issue, from `/rest/api/3/issue/{key}/changelog`
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeLog {
+    /// The url of the changelog in the api
     #[serde(rename = "self")]
     pub sel: Option<String>,
+    /// The most changes the page could hold
     pub max_results: Option<u64>,
+    /// The offset of the first change of the page
     pub start_at: Option<u64>,
+    /// The number of changes across every page
     pub total: Option<u64>,
+    /// Whether this is the last page, when jira says
     pub is_last: Option<bool>,
+    /// The changes of the page, oldest first
     pub values: Vec<ChangeGroup>,
 }
 
+/// The changelog embedded in an issue by `expand=changelog`. Jira truncates it when there are
+/// more than `max_results` entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueChangeLog {
+    /// The offset of the first change embedded
+    pub start_at: Option<u64>,
+    /// The most changes that are embedded
+    pub max_results: Option<u64>,
+    /// The number of changes the issue has, more than are embedded when it was truncated
+    pub total: Option<u64>,
+    /// The changes embedded, oldest first
+    pub histories: Vec<ChangeGroup>,
+}
+
+/// The priority of an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Priority {
+    /// The url of the priority in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The url of the icon of the priority
     pub icon_url: Url,
+    /// The name of the priority, e.g. `High`
     pub name: String,
+    /// The id of the priority
     pub id: String,
 }
 
+/// The category of a status, which is one of `new`, `indeterminate` or `done`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusCategory {
+    /// The url of the category in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the category
     pub id: i64,
+    /// The key of the category, e.g. `indeterminate`
     pub key: String,
+    /// The color the category is shown in
     pub color_name: String,
+    /// The name of the category, e.g. `In Progress`
     pub name: String,
 }
 
+/// The status of an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Status {
+    /// The url of the status in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// What the status means
     pub description: String,
+    /// The url of the icon of the status
     pub icon_url: String,
+    /// The name of the status, e.g. `In Review`
     pub name: String,
+    /// The id of the status
     pub id: String,
+    /// The category of the status
     pub status_category: StatusCategory,
 }
 
+/// One of the statuses of the instance, as listed by `/rest/api/3/status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceStatus {
+    /// The id of the status
+    pub id: String,
+    /// The name of the status
+    pub name: String,
+    /// The category of the status
+    pub status_category: StatusCategory,
+}
+
+/// The type of an issue, e.g. `Story` or `Bug`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IssueType {
+    /// The url of the issue type in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the issue type
     pub id: String,
+    /// What the issue type is for
     pub description: String,
+    /// The url of the icon of the issue type
     pub icon_url: String,
+    /// The name of the issue type
     pub name: String,
+    /// Whether issues of the type are subtasks of another issue
     pub subtask: bool,
+    /// The id of the avatar of the issue type
     pub avatar_id: Option<i64>,
 }
 
+/// The kind of a link between two issues, e.g. `Blocks`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IssueLinksType {
+    /// The id of the link type
     pub id: String,
+    /// The name of the link type
     pub name: String,
+    /// How the link reads from the inward issue, e.g. `is blocked by`
     pub inward: String,
+    /// How the link reads from the outward issue, e.g. `blocks`
     pub outward: String,
+    /// The url of the link type in the api
     #[serde(rename = "self")]
     pub sel: Url,
 }
 
+/// The fields of a linked issue that come with the link
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OutwardIssueField {
+    /// The summary of the linked issue
     pub summary: String,
+    /// The status of the linked issue
     pub status: Status,
+    /// The priority of the linked issue
     pub priority: Priority,
+    /// The type of the linked issue
     pub issuetype: IssueType,
 }
 
+/// The other issue of a link
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OutwardIssue {
+    /// The id of the linked issue
     pub id: String,
+    /// The key of the linked issue
     pub key: String,
+    /// The url of the linked issue in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The fields of the linked issue that come with the link
     pub fields: OutwardIssueField,
 }
 
+/// A link from an issue to another issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IssueLink {
+    /// The id of the link
     pub id: String,
+    /// The url of the link in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The kind of link
     #[serde(rename = "type")]
     pub typ: IssueLinksType,
+    /// The issue linked to, when the link is outward
     pub outward_issue: Option<OutwardIssue>,
+    /// The issue linked from, when the link is inward
+    pub inward_issue: Option<OutwardIssue>,
 }
 
+/// How an issue was resolved, e.g. `Done` or `Won't Do`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Resolution {
+    /// The url of the resolution in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the resolution
     pub id: String,
+    /// What the resolution means
     pub description: String,
+    /// The name of the resolution
     pub name: String,
 }
 
+/// The category of a project
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectCategory {
+    /// The url of the category in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the category
     pub id: String,
+    /// What the category is for
     pub description: String,
+    /// The name of the category
     pub name: String,
 }
 
+/// The urls of the avatar of a user or project in each of its sizes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AvatarUrl {
+    /// The 48 by 48 pixel avatar
     #[serde(rename = "48x48")]
     pub f48x48: Url,
+    /// The 24 by 24 pixel avatar
     #[serde(rename = "24x24")]
     pub f24x24: Url,
+    /// The 16 by 16 pixel avatar
     #[serde(rename = "16x16")]
     pub f16x16: Url,
+    /// The 32 by 32 pixel avatar
     #[serde(rename = "32x32")]
     pub f32x32: Url,
 }
 
+/// A user, as they are referred to by issues, e.g. as the assignee or the author of a change
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Assignee {
+    /// The url of the user in the api
     #[serde(rename = "self")]
     pub sel: Option<Url>,
+    /// The user name, only on jira server
     pub name: Option<String>,
+    /// The key of the user, only on jira server
     pub key: Option<String>,
+    /// The email address, when the privacy settings of the user show it
     pub email_address: Option<String>,
+    /// The avatar of the user
     pub avatar_urls: AvatarUrl,
+    /// The name shown for the user
     pub display_name: String,
+    /// Whether the account is active
     pub active: bool,
+    /// The timezone of the user, e.g. `Europe/London`
     pub time_zone: String,
 }
 
+/// The fields of a subtask that come with its parent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Field {
+    /// The summary of the subtask
     pub summary: String,
+    /// The status of the subtask
     pub status: Status,
+    /// The type of the subtask
     pub issue_type: Option<IssueType>,
 }
 
+/// A subtask of an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Subtask {
+    /// The id of the subtask
     pub id: String,
+    /// The key of the subtask
     pub key: String,
+    /// The url of the subtask in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The fields of the subtask that come with its parent
     pub fields: Field,
 }
 
+/// The votes for an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Vote {
+    /// The url of the votes in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The number of votes
     pub votes: i64,
+    /// Whether the user the report is run as voted
     pub has_voted: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The time logged against an issue and the time that was estimated for it, in seconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Progress {
+    /// The time logged
     pub progress: i64,
+    /// The time that was estimated, or logged when that is more
     pub total: i64,
 }
 
+/// A component of a project that an issue belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    /// The id of the component
+    pub id: Option<String>,
+    /// The name of the component
+    pub name: String,
+}
+
+/// The project an issue belongs to
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
+    /// The url of the project in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the project
     pub id: String,
+    /// The key of the project, e.g. `LEC`
     pub key: String,
+    /// The name of the project
     pub name: String,
+    /// The type of the project, e.g. `software`
     pub project_type_key: String,
+    /// The avatar of the project
     pub avatar_urls: AvatarUrl,
+    /// The category of the project, when it has one
     pub project_category: Option<ProjectCategory>,
 }
 
+/// Who is watching an issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Watch {
+    /// The url of the watchers in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The number of watchers
     pub watch_count: i64,
+    /// Whether the user the report is run as is watching
     pub is_watching: bool,
 }
 
+/// A version an issue is fixed in
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FixVersion {
+    /// The url of the version in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The id of the version
     pub id: String,
+    /// The name of the version
     pub name: String,
+    /// Whether the version is archived
     pub archived: bool,
+    /// Whether the version is released
     pub released: bool,
 }
 
+/// A node of a rich text field in the Atlassian Document Format. Block nodes have `content`,
+/// text nodes have `text` and the `marks` applied to it. The `attrs` depend on the type of the
+/// node, e.g. the `level` of a heading or the `url` of a card.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DescriptionPart {
+    /// The type of the node, e.g. `paragraph` or `text`
     #[serde(rename = "type")]
     pub typ: String,
+    /// The nodes of a block node
     pub content: Option<Vec<DescriptionPart>>,
+    /// The text of a text node
+    pub text: Option<String>,
+    /// The formatting of a text node
+    #[serde(default)]
+    pub marks: Vec<DescriptionMark>,
+    /// The attributes of the node
+    pub attrs: Option<Value>,
 }
 
+/// Formatting applied to a text node, e.g. `strong`, `em`, `code` or a `link` with its `href`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DescriptionMark {
+    /// The type of the mark, e.g. `strong`
+    #[serde(rename = "type")]
+    pub typ: String,
+    /// The attributes of the mark, e.g. the `href` of a link
+    pub attrs: Option<Value>,
+}
+
+/// The description of an issue, wiki markup on jira server and the Atlassian Document Format on jira cloud
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Description {
+    /// The description in wiki markup
     String(String),
+    /// The description as a document in the Atlassian Document Format
     Complex {
+        /// The version of the format
         version: u64,
+        /// The type of the document, `doc`
         #[serde(rename = "type")]
         typ: String,
+        /// The blocks of the document
         content: Vec<DescriptionPart>,
     },
 }
 
+/// The issue an issue is a child of. Sub-tasks always have one, as do the children of an epic in
+/// a team-managed project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueParent {
+    /// The id of the parent
+    pub id: String,
+    /// The key of the parent
+    pub key: IssueKey,
+}
+
+/// The fields of an issue that are read, every other field is kept in `custom_fields`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IssuesField {
+    /// The type of the issue
     pub issuetype: IssueType,
+    /// How the issue was resolved, when it is
     pub resolution: Option<Resolution>,
+    /// The links to other issues
     pub issuelinks: Vec<IssueLink>,
+    /// Who the issue is assigned to
     pub assignee: Option<Assignee>,
+    /// The subtasks of the issue
     pub subtasks: Vec<Subtask>,
+    /// The votes for the issue
     pub votes: Option<Vote>,
+    /// The current status of the issue
     pub status: Status,
+    /// Who created the issue
     pub creator: Option<Assignee>,
+    /// The time logged as a percentage of the time estimated
     pub workratio: i64,
+    /// The labels of the issue
     pub labels: Vec<String>,
+    /// Who reported the issue
     pub reporter: Option<Assignee>,
+    /// The time logged against the issue
     pub progress: Progress,
+    /// The project the issue belongs to
     pub project: Project,
+    /// When the issue was resolved, as jira formats it
     pub resolutiondate: Option<String>,
+    /// Who is watching the issue
     pub watches: Watch,
+    /// When the issue was last updated, as jira formats it
     pub updated: String,
+    /// The description of the issue
     pub description: Option<Description>,
+    /// The summary of the issue
     pub summary: String,
+    /// The priority of the issue
     pub priority: Option<Priority>,
+    /// The time logged against the issue and its subtasks
     pub aggregateprogress: Progress,
+    /// When the issue was created
     pub created: DateTime<Utc>,
+    /// The versions the issue is fixed in
     pub fix_versions: Vec<FixVersion>,
+    /// The components of the issue
+    #[serde(default)]
+    pub components: Vec<Component>,
+    /// The parent of the issue
+    #[serde(default)]
+    pub parent: Option<IssueParent>,
+    /// The custom fields of the issue by their id, e.g. the team and the story points
     #[serde(flatten)]
     pub custom_fields: HashMap<CustomFieldName, Value>,
 }
 
+/// A comment as rendered to HTML by Jira
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedComment {
+    /// The id of the comment
+    pub id: String,
+    /// The comment as HTML
+    pub body: String,
+}
+
+/// The comments of an issue as rendered to HTML by Jira
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedComments {
+    /// The comments, oldest first
+    pub comments: Vec<RenderedComment>,
+}
+
+/// The HTML rendered versions of the issue fields. These are only returned by Jira when
+/// `expand=renderedFields` is part of the request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedFields {
+    /// The description as HTML
+    pub description: Option<String>,
+    /// The comments as HTML
+    pub comment: Option<RenderedComments>,
+}
+
+/// An issue as it is returned by the api
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Issue {
+    /// The parts of the issue that were expanded
     pub expand: Option<String>,
+    /// The id of the issue
     pub id: String,
+    /// The url of the issue in the api
     #[serde(rename = "self")]
     pub sel: Url,
+    /// The key of the issue
     pub key: IssueKey,
+    /// The fields of the issue
     pub fields: IssuesField,
+    /// The fields rendered to HTML, when they were expanded
+    pub rendered_fields: Option<RenderedFields>,
+    /// Only present when the changelog was expanded in the search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<IssueChangeLog>,
+}
+
+/// Time logged against an issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Worklog {
+    /// The id of the worklog
+    pub id: String,
+    /// Who logged the time
+    pub author: Option<Assignee>,
+    /// When the work started
+    pub started: DateTime<Utc>,
+    /// The time logged, in seconds
+    pub time_spent_seconds: u64,
+}
+
+/// A page of the worklogs of an issue
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Worklogs {
+    /// The most worklogs the page could hold
+    pub max_results: u64,
+    /// The offset of the first worklog of the page
+    pub start_at: u64,
+    /// The number of worklogs across every page
+    pub total: u64,
+    /// The worklogs of the page
+    pub worklogs: Vec<Worklog>,
+}
+
+/// A comment on an issue. Only who made it and when are kept, not what it says.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    /// The id of the comment
+    pub id: String,
+    /// Who made the comment
+    pub author: Option<Assignee>,
+    /// When the comment was made
+    pub created: DateTime<Utc>,
+    /// Whether a service desk comment can be seen by the customer, `false` for internal notes.
+    /// Not set outside of service desk projects.
+    pub jsd_public: Option<bool>,
+}
+
+/// A page of the comments of an issue
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comments {
+    /// The most comments the page could hold
+    pub max_results: u64,
+    /// The offset of the first comment of the page
+    pub start_at: u64,
+    /// The number of comments across every page
+    pub total: u64,
+    /// The comments of the page
+    pub comments: Vec<Comment>,
 }
 
+/// A page of the issues matching a search, read like [`BoardIssues`]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Search {
+pub struct Search<I = Issue> {
+    /// The url of the search in the api
     #[serde(rename = "self")]
     pub sel: Option<String>,
+    /// The most issues the page could hold
     pub max_results: u64,
+    /// The offset of the first issue of the page
     pub start_at: u64,
+    /// The number of issues across every page
     pub total: u64,
+    /// Whether this is the last page, when jira says
     pub is_last: Option<bool>,
-    pub issues: Vec<Issue>,
+    /// The issues of the page
+    pub issues: Vec<I>,
 }
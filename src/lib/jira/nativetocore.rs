@@ -20,56 +20,249 @@
 //! This is simply a A -> B translation.
 use crate::configs::jira;
 use crate::lib::jira::native;
-use crate::lib::jira::{api, core};
-use chrono::{DateTime, Utc};
+use crate::lib::jira::{adf, api, core, dump};
+use chrono::{DateTime, FixedOffset, Utc};
 use snafu::{Backtrace, ResultExt, Snafu};
+use std::collections::HashMap;
 use std::str::FromStr;
+use tracing::warn;
 use uom::si::f64::Time;
-use uom::si::time::second;
+use uom::si::time::{day, second};
 use url::ParseError;
 use uuid::Uuid;
 
+/// The ways translating jira issues into items can fail
 #[derive(Debug, Snafu)]
 pub enum Error {
+    /// A resolution has no entry in the `resolution-mapping`
     #[snafu(display("No mapping for resolution {}", unmapped_resolution_name))]
     MissingResolutionMapping {
+        /// The name of the resolution
         unmapped_resolution_name: String,
+        /// Where the translation failed
         backtrace: Backtrace,
     },
+    /// A status has no entry in the `status-mapping` or `status-patterns`, and there is no
+    /// `default-status`
     #[snafu(display("No mapping for status {}", unmapped_status_name))]
     MissingStatusMapping {
+        /// The name of the status
         unmapped_status_name: String,
+        /// Where the translation failed
         backtrace: Backtrace,
     },
+    /// The value of the `resolution-field` of an issue is not one that can hold a resolution
     #[snafu(display(
-        "Invalid resolution field could not extract value from {} in issue {}",
+        "Invalid resolution field {} in issue {}, expected an option, a string or a list of \
+         them but found {}",
         resolution_field,
-        issue_key
+        issue_key,
+        found
     ))]
     InvalidResolutionField {
+        /// The id of the field
         resolution_field: String,
+        /// The issue with the value
+        issue_key: String,
+        /// The value that was found
+        found: String,
+        /// Where the translation failed
+        backtrace: Backtrace,
+    },
+    /// The value of the `team-field` of an issue is not one that can hold a team
+    #[snafu(display(
+        "Invalid team field could not extract value from {} in issue {}",
+        team_field,
+        issue_key
+    ))]
+    InvalidTeamField {
+        /// The id of the field
+        team_field: String,
+        /// The issue with the value
         issue_key: String,
+        /// Where the translation failed
         backtrace: Backtrace,
     },
+    /// The resolution date of an issue is not a date
+    #[snafu(display("Invalid resolution date {} in issue {}: {}", value, issue_key, source))]
+    InvalidResolutionDate {
+        /// The value that was found
+        value: String,
+        /// The issue with the value
+        issue_key: String,
+        /// The error of parsing it
+        source: chrono::ParseError,
+    },
+    /// The url of an issue could not be made from the url of the instance
     #[snafu(display("Could not create new url for {}: {}", target, source))]
-    CouldNotCreateUrl { target: String, source: ParseError },
+    CouldNotCreateUrl {
+        /// What the url was for
+        target: String,
+        /// Why it could not be made
+        source: ParseError,
+    },
+    /// A status of the timeline that was already closed was closed again
     #[snafu(display("Can not close closed status"))]
     CanNotCloseClosedStatus {},
+    /// An estimate of the timeline was closed like a status
     #[snafu(display("Can not close estimate"))]
     CanNotCloseEstimate {},
+    /// A flag of the timeline was closed like a status
+    #[snafu(display("Can not close blocked"))]
+    CanNotCloseBlocked {},
+    /// A worklog of the timeline was closed like a status
+    #[snafu(display("Can not close worklog"))]
+    CanNotCloseWorklog {},
+    /// A reassignment of the timeline was closed like a status
+    #[snafu(display("Can not close a reassignment"))]
+    CanNotCloseReassigned {},
+    /// A transition of the timeline was closed like a status
+    #[snafu(display("Can not close a transition"))]
+    CanNotCloseTransition {},
+    /// An estimate is not a number of points
+    #[snafu(display("Unable to parse estimate ({}) into points: {}", value, source))]
+    UnableToParsePoints {
+        /// The value that was found
+        value: String,
+        /// The error of parsing it
+        source: std::num::ParseFloatError,
+    },
+    /// The estimate field of an issue is not a number of days
     #[snafu(display("Unable to parse field ({}) into days: {}", value, source))]
     UnableToParseDays {
+        /// The value that was found
         value: String,
+        /// The error of parsing it
         source: std::num::ParseFloatError,
     },
 }
 
+/// Whether `name` matches the glob `pattern`, where `*` matches any run of characters and `?`
+/// matches a single character
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let mut pattern_at = 0;
+    let mut name_at = 0;
+    // The position of the last `*` seen and the position in the name it is matched up to
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while name_at < name.len() {
+        match pattern.get(pattern_at) {
+            Some('*') => {
+                last_star = Some((pattern_at, name_at));
+                pattern_at += 1;
+            }
+            Some(pattern_char) if *pattern_char == '?' || *pattern_char == name[name_at] => {
+                pattern_at += 1;
+                name_at += 1;
+            }
+            _ => match last_star {
+                Some((star_at, matched_to)) => {
+                    last_star = Some((star_at, matched_to + 1));
+                    pattern_at = star_at + 1;
+                    name_at = matched_to + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_at..]
+        .iter()
+        .all(|pattern_char| *pattern_char == '*')
+}
+
+/// The status inferred from each of Jira's status categories, by the key of the category
+pub const STATUS_CATEGORIES: [(&str, core::ItemStatus); 3] = [
+    ("new", core::ItemStatus::ToDo),
+    ("indeterminate", core::ItemStatus::InDev),
+    ("done", core::ItemStatus::Completed),
+];
+
+/// The key of the category of each status, by status name. The changelog only names the
+/// statuses, so the categories come from the list of every status of the instance, see
+/// [`api::get_statuses`]. Empty unless `infer-status-categories` is set.
+#[derive(Debug, Clone, Default)]
+pub struct StatusCategories(HashMap<String, String>);
+
+impl StatusCategories {
+    /// The categories of `statuses`
+    #[must_use]
+    pub fn new(conf: &jira::Config, statuses: &[native::InstanceStatus]) -> Self {
+        if !conf.infer_status_categories {
+            return StatusCategories::default();
+        }
+        StatusCategories(
+            statuses
+                .iter()
+                .map(|status| (status.name.clone(), status.status_category.key.clone()))
+                .collect(),
+        )
+    }
+
+    /// The categories of the current statuses of `issues`, for issues read from a dump that
+    /// doesn't have the statuses of the instance. Only the statuses that one of the issues is in
+    /// now are known, so `issues` should be all of them rather than a single page.
+    #[must_use]
+    pub fn of_issues(conf: &jira::Config, issues: &[api::IssueDetail]) -> Self {
+        if !conf.infer_status_categories {
+            return StatusCategories::default();
+        }
+        StatusCategories(
+            issues
+                .iter()
+                .map(|issue_detail| &issue_detail.issue.fields.status)
+                .map(|status| (status.name.clone(), status.status_category.key.clone()))
+                .collect(),
+        )
+    }
+
+    /// The categories of the statuses of the instance kept in `dump`, or of the current statuses
+    /// of its issues when it is older than that, see [`StatusCategories::of_issues`]
+    #[must_use]
+    pub fn of_dump(conf: &jira::Config, dump: &dump::JiraDump) -> Self {
+        if dump.statuses.is_empty() {
+            StatusCategories::of_issues(conf, &dump.issues)
+        } else {
+            StatusCategories::new(conf, &dump.statuses)
+        }
+    }
+
+    fn category(&self, status_name: &str) -> Option<&str> {
+        self.0.get(status_name).map(String::as_str)
+    }
+}
+
+/// Looks the status up by name, then by pattern, then by the category of the status and finally
+/// falls back to the default status
 fn get_status_mapping(
     conf: &jira::Config,
+    categories: &StatusCategories,
     jira_status_name: &str,
 ) -> Result<core::ItemStatus, Error> {
-    match conf.status_mapping.get(jira_status_name) {
-        Some(item_status) => Ok(item_status.clone()),
+    let pattern_status = || {
+        conf.status_patterns
+            .iter()
+            .find(|status_pattern| glob_matches(&status_pattern.pattern, jira_status_name))
+            .map(|status_pattern| &status_pattern.status)
+    };
+    let category_status = || {
+        categories.category(jira_status_name).and_then(|category| {
+            STATUS_CATEGORIES
+                .iter()
+                .find(|(key, _)| *key == category)
+                .map(|(_, status)| status)
+        })
+    };
+    match conf
+        .status_mapping
+        .get(jira_status_name)
+        .or_else(pattern_status)
+        .or_else(category_status)
+        .or(conf.default_status.as_ref())
+    {
+        Some(item_status) => Ok(*item_status),
         None => MissingStatusMapping {
             unmapped_status_name: jira_status_name.to_owned(),
         }
@@ -92,24 +285,114 @@ fn close_entry(
             start: start_date,
             status,
         } => Ok(core::ItemTimeLineEntry::ClosedStatus {
-            status: status.clone(),
+            status: *status,
             start: *start_date,
             end: *end_date,
         }),
         core::ItemTimeLineEntry::ClosedStatus { .. } => CanNotCloseClosedStatus.fail(),
         core::ItemTimeLineEntry::Estimate { .. } => CanNotCloseEstimate.fail(),
+        core::ItemTimeLineEntry::Blocked { .. } => CanNotCloseBlocked.fail(),
+        core::ItemTimeLineEntry::Worklog { .. } => CanNotCloseWorklog.fail(),
+        core::ItemTimeLineEntry::Reassigned { .. } => CanNotCloseReassigned.fail(),
+        core::ItemTimeLineEntry::Transitioned { .. } => CanNotCloseTransition.fail(),
+    }
+}
+
+/// The changelog field Jira uses to record that an issue has been flagged as impeded
+const FLAGGED_FIELD: &str = "Flagged";
+
+/// Tracks the flagged/unflagged transitions of an issue. Returns the start of the current
+/// blocked period, if any, along with the completed blocked entry when the issue was unflagged.
+fn handle_flagged_entry(
+    blocked_since: Option<DateTime<Utc>>,
+    changed_at: &DateTime<Utc>,
+    entry: &native::ChangeLogEntry,
+) -> (Option<DateTime<Utc>>, Option<core::ItemTimeLineEntry>) {
+    let is_flagged = matches!(&entry.to_string, Some(value) if !value.is_empty());
+    match (blocked_since, is_flagged) {
+        (None, true) => (Some(*changed_at), None),
+        (Some(start), false) => (
+            None,
+            Some(core::ItemTimeLineEntry::Blocked {
+                start,
+                end: Some(*changed_at),
+            }),
+        ),
+        (blocked_since, _) => (blocked_since, None),
     }
 }
 
+/// The status of an entry that is still open
+fn open_status(entry: &core::ItemTimeLineEntry) -> Option<&core::ItemStatus> {
+    match entry {
+        core::ItemTimeLineEntry::OpenStatus { status, .. } => Some(status),
+        _ => None,
+    }
+}
+
+/// The change of status recorded by `entry`, from the status of `last_status` to that of
+/// `new_status`, made by the author of `group`
+fn transition(
+    group: &native::ChangeGroup,
+    entry: &native::ChangeLogEntry,
+    last_status: &core::ItemTimeLineEntry,
+    new_status: &core::ItemTimeLineEntry,
+) -> Option<core::ItemTimeLineEntry> {
+    if entry.field != "status" {
+        return None;
+    }
+    Some(core::ItemTimeLineEntry::Transitioned {
+        start: group.created,
+        from: entry.from_string.clone(),
+        to: entry.to_string.clone(),
+        from_status: *open_status(last_status)?,
+        to_status: *open_status(new_status)?,
+        author: Some(group.author.display_name.clone()),
+    })
+}
+
+/// The estimate set by a change to the field of the `estimate-source`, with its points converted
+/// to days. Clearing the field sets no estimate.
+fn handle_points_entry(
+    estimate_source: &jira::EstimateSource,
+    open_entry: &core::ItemTimeLineEntry,
+    new_start_date: &DateTime<Utc>,
+    entry: &native::ChangeLogEntry,
+) -> Result<Option<EntryMarker>, Error> {
+    let points = match entry
+        .to_string
+        .as_ref()
+        .or(entry.to.as_ref())
+        .map(|points| points.trim())
+        .filter(|points| !points.is_empty())
+    {
+        Some(points) => f64::from_str(points).context(UnableToParsePoints { value: points })?,
+        None => return Ok(None),
+    };
+    Ok(Some(EntryMarker {
+        completed_entry: core::ItemTimeLineEntry::Estimate {
+            start: *new_start_date,
+            days: Time::new::<day>(estimate_source.conversion.days(points)),
+        },
+        new_entry: open_entry.clone(),
+    }))
+}
+
 fn handle_changelog_entry<'a>(
     conf: &jira::Config,
+    categories: &StatusCategories,
     open_entry: &'a core::ItemTimeLineEntry,
     new_start_date: &'a DateTime<Utc>,
     entry: &native::ChangeLogEntry,
 ) -> Result<Option<EntryMarker>, Error> {
+    if let Some(estimate_source) = &conf.estimate_source {
+        if estimate_source.is_field(&entry.field, entry.field_id.as_deref()) {
+            return handle_points_entry(estimate_source, open_entry, new_start_date, entry);
+        }
+    }
     match (&entry.to_string, entry.field.as_str()) {
         (Some(name), "status") => {
-            let new_status = get_status_mapping(conf, name)?;
+            let new_status = get_status_mapping(conf, categories, name)?;
             let started_entry = core::ItemTimeLineEntry::OpenStatus {
                 start: *new_start_date,
                 status: new_status,
@@ -120,7 +403,7 @@ fn handle_changelog_entry<'a>(
                 new_entry: started_entry,
             }))
         }
-        (_, "timeestimate") => {
+        (_, "timeestimate") if conf.estimate_source.is_none() => {
             if let Some(estimate_string) = &entry.to {
                 let entry = core::ItemTimeLineEntry::Estimate {
                     start: *new_start_date,
@@ -138,12 +421,21 @@ fn handle_changelog_entry<'a>(
                 Ok(None)
             }
         }
+        (_, "assignee") => Ok(Some(EntryMarker {
+            completed_entry: core::ItemTimeLineEntry::Reassigned {
+                start: *new_start_date,
+                from: entry.from_string.clone(),
+                to: entry.to_string.clone(),
+            },
+            new_entry: (*open_entry).clone(),
+        })),
         _ => Ok(None),
     }
 }
 
 fn convert_changelog(
     conf: &jira::Config,
+    categories: &StatusCategories,
     issue: &native::Issue,
     changelog: &[native::ChangeGroup],
 ) -> Result<Vec<core::ItemTimeLineEntry>, Error> {
@@ -153,13 +445,21 @@ fn convert_changelog(
     };
 
     let mut item_change_log = Vec::new();
+    let mut blocked_since = None;
     for group in changelog {
         for entry in &group.items {
-            if let Some(EntryMarker {
+            if entry.field == FLAGGED_FIELD {
+                let (new_blocked_since, completed_entry) =
+                    handle_flagged_entry(blocked_since, &group.created, entry);
+                blocked_since = new_blocked_since;
+                item_change_log.extend(completed_entry);
+            } else if let Some(EntryMarker {
                 completed_entry,
                 new_entry,
-            }) = handle_changelog_entry(conf, &last_status, &group.created, entry)?
+            }) =
+                handle_changelog_entry(conf, categories, &last_status, &group.created, entry)?
             {
+                item_change_log.extend(transition(group, entry, &last_status, &new_entry));
                 item_change_log.push(completed_entry);
                 last_status = new_entry;
             }
@@ -167,16 +467,30 @@ fn convert_changelog(
     }
 
     item_change_log.push(last_status);
+    if let Some(start) = blocked_since {
+        item_change_log.push(core::ItemTimeLineEntry::Blocked { start, end: None });
+    }
 
     Ok(item_change_log)
 }
 
+#[allow(clippy::cast_precision_loss)]
+fn convert_worklogs(worklogs: &[native::Worklog]) -> Vec<core::ItemTimeLineEntry> {
+    worklogs
+        .iter()
+        .map(|worklog| core::ItemTimeLineEntry::Worklog {
+            start: worklog.started,
+            spent: Time::new::<second>(worklog.time_spent_seconds as f64),
+        })
+        .collect()
+}
+
 fn get_resolution_value_mapping(
     conf: &jira::Config,
     jira_resolution_name: &str,
 ) -> Result<core::Resolution, Error> {
     match conf.resolution_mapping.get(jira_resolution_name) {
-        Some(resolution) => Ok(resolution.clone()),
+        Some(resolution) => Ok(*resolution),
         None => MissingResolutionMapping {
             unmapped_resolution_name: jira_resolution_name.to_owned(),
         }
@@ -184,43 +498,87 @@ fn get_resolution_value_mapping(
     }
 }
 
-fn extract_value_from_field(
-    conf: &jira::Config,
-    issue_key: &native::IssueKey,
-    value: &serde_json::Map<String, serde_json::Value>,
-) -> Result<core::Resolution, Error> {
-    match value.get("value") {
-        Some(serde_json::Value::String(name)) => get_resolution_value_mapping(conf, name),
-        Some(_) | None => InvalidResolutionField {
-            resolution_field: conf
-                .resolution_field
-                .as_ref()
-                .map_or_else(|| "".to_owned(), |field| field.0.clone()),
-            issue_key: issue_key.0.clone(),
-        }
-        .fail(),
+/// What kind of json `value` is, for error messages
+fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "a list",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// The name of a single value of a resolution field, either a select option, which is an object
+/// with the name as its `value`, or a plain string. Otherwise what was found instead.
+fn option_name(value: &serde_json::Value) -> Result<&str, String> {
+    match value {
+        serde_json::Value::String(name) => Ok(name),
+        serde_json::Value::Object(option) => match option.get("value") {
+            Some(serde_json::Value::String(name)) => Ok(name),
+            Some(other) => Err(format!("an option with {} as its value", json_type(other))),
+            None => Err("an object without a value".to_owned()),
+        },
+        other => Err(json_type(other).to_owned()),
+    }
+}
+
+/// Which resolution wins when the values of a multi-value resolution field map to more than one.
+/// A rejection anywhere rejects the item, and any resolution beats none.
+fn precedence(resolution: core::Resolution) -> u8 {
+    match resolution {
+        core::Resolution::Rejected => 2,
+        core::Resolution::Delivered => 1,
+        core::Resolution::UnResolved => 0,
     }
 }
 
+/// Resolution fields may be a single or multi select, a checkbox or a text field, so the value is
+/// an option, a string, or a list of either. When a list maps to more than one resolution the
+/// one with the highest [`precedence`] is used.
 fn get_custom_resolution_with_mapping(
     conf: &jira::Config,
     resolution_field: &native::CustomFieldName,
     issue: &native::Issue,
 ) -> Result<core::Resolution, Error> {
-    match issue.fields.custom_fields.get(resolution_field) {
-        Some(serde_json::Value::Object(value_map)) => {
-            extract_value_from_field(conf, &issue.key, value_map)
-        }
-        Some(serde_json::Value::Null) | None => Ok(core::Resolution::UnResolved),
-        Some(_) => InvalidResolutionField {
-            resolution_field: conf
-                .resolution_field
-                .as_ref()
-                .map_or_else(|| "".to_owned(), |field| field.0.clone()),
-            issue_key: issue.key.0.clone(),
+    let names = match issue.fields.custom_fields.get(resolution_field) {
+        Some(serde_json::Value::Null) | None => Ok(Vec::new()),
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .map(|value| option_name(value).map_err(|found| format!("{} in the list", found)))
+            .collect(),
+        Some(value) => option_name(value).map(|name| vec![name]),
+    };
+    let names = match names {
+        Ok(names) => names,
+        Err(found) => {
+            return InvalidResolutionField {
+                resolution_field: resolution_field.0.clone(),
+                issue_key: issue.key.0.clone(),
+                found,
+            }
+            .fail()
         }
-        .fail(),
+    };
+    let resolutions = names
+        .iter()
+        .map(|name| get_resolution_value_mapping(conf, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let resolution = resolutions
+        .iter()
+        .max_by_key(|resolution| precedence(**resolution))
+        .copied()
+        .unwrap_or(core::Resolution::UnResolved);
+    if resolutions.iter().any(|other| *other != resolution) {
+        warn!(
+            "{} has the resolutions {} which map to different resolutions, using {}",
+            issue.key,
+            names.join(", "),
+            resolution
+        );
     }
+    Ok(resolution)
 }
 
 fn get_resolution_with_mapping(
@@ -240,20 +598,114 @@ fn get_resolution(conf: &jira::Config, issue: &native::Issue) -> Result<core::Re
     }
 }
 
+fn get_resolved(issue: &native::Issue) -> Result<Option<DateTime<Utc>>, Error> {
+    match &issue.fields.resolutiondate {
+        Some(value) => DateTime::<FixedOffset>::from_str(value)
+            .map(|resolved| Some(resolved.with_timezone(&Utc)))
+            .context(InvalidResolutionDate {
+                value,
+                issue_key: issue.key.0.clone(),
+            }),
+        None => Ok(None),
+    }
+}
+
+/// Team fields come in a few shapes depending on how they were created. A plain text field is a
+/// string, a select list is an object with a `value` and the Atlassian team field is an object
+/// with a `name` or `title`.
+fn get_team(conf: &jira::Config, issue: &native::Issue) -> Result<Option<String>, Error> {
+    let team_field = match &conf.team_field {
+        Some(team_field) => team_field,
+        None => return Ok(None),
+    };
+    match issue.fields.custom_fields.get(team_field) {
+        Some(serde_json::Value::Null) | None => Ok(None),
+        Some(serde_json::Value::String(name)) => Ok(Some(name.clone())),
+        Some(serde_json::Value::Object(value_map)) => {
+            let name = ["value", "name", "title"]
+                .iter()
+                .find_map(|key| value_map.get(*key).and_then(serde_json::Value::as_str));
+            match name {
+                Some(name) => Ok(Some(name.to_owned())),
+                None => InvalidTeamField {
+                    team_field: team_field.0.clone(),
+                    issue_key: issue.key.0.clone(),
+                }
+                .fail(),
+            }
+        }
+        Some(_) => InvalidTeamField {
+            team_field: team_field.0.clone(),
+            issue_key: issue.key.0.clone(),
+        }
+        .fail(),
+    }
+}
+
+/// The key of the parent of an issue. Company-managed projects link an issue to its epic with the
+/// custom field configured as the `epic-link-field`, and that link is taken when it is set. Without
+/// it the `parent` field is used, which names the parent of sub-tasks and, in team-managed projects,
+/// the epic of an issue.
+fn get_parent(conf: &jira::Config, issue: &native::Issue) -> Option<core::NativeId> {
+    let parent = issue
+        .fields
+        .parent
+        .as_ref()
+        .map(|parent| parent.key.0.as_str());
+    let epic_link = conf
+        .epic_link_field
+        .as_ref()
+        .and_then(|epic_link_field| issue.fields.custom_fields.get(epic_link_field))
+        .and_then(serde_json::Value::as_str);
+    epic_link
+        .or(parent)
+        .map(|key| core::NativeId(key.to_owned()))
+}
+
+/// Jira records a link once, on both issues. The issue on the far end of the link is either the
+/// outward issue, in which case the link reads in the direction of its type, or the inward issue,
+/// in which case it reads in the opposite direction.
+fn convert_links(conf: &jira::Config, issue: &native::Issue) -> Vec<core::ItemLink> {
+    issue
+        .fields
+        .issuelinks
+        .iter()
+        .filter_map(|link| {
+            let typ = conf.link_mapping.get(&link.typ.name)?;
+            match (&link.outward_issue, &link.inward_issue) {
+                (Some(outward), _) => Some(core::ItemLink {
+                    typ: *typ,
+                    target: core::NativeId(outward.key.clone()),
+                }),
+                (None, Some(inward)) => Some(core::ItemLink {
+                    typ: typ.inverse(),
+                    target: core::NativeId(inward.key.clone()),
+                }),
+                (None, None) => None,
+            }
+        })
+        .collect()
+}
+
+/// Classifies an issue type using the `project-issue-types` of the issue's project, when it has
+/// any, and the global `issue-types` otherwise.
 fn convert_issue_type(
     conf: &jira::Config,
+    project: &native::Project,
     issue_type: &native::IssueType,
 ) -> Option<core::ItemType> {
     let issue_type_name = issue_type.name.as_str();
-    if conf
-        .issue_types
+    let issue_types = conf
+        .project_issue_types
+        .get(&project.key)
+        .unwrap_or(&conf.issue_types);
+    if issue_types
         .features
         .iter()
         .any(|member| member == issue_type_name)
     {
         Some(core::ItemType::Feature)
-    } else if conf
-        .issue_types
+    } else if issue_types
         .operational
         .iter()
         .any(|member| member == issue_type_name)
@@ -266,9 +718,10 @@ fn convert_issue_type(
 
 fn convert_issue(
     conf: &jira::Config,
+    categories: &StatusCategories,
+    id: core::ItemId,
     issue_detail: &api::IssueDetail,
 ) -> Result<Option<core::Item>, Error> {
-    let id = core::ItemId(Uuid::new_v4());
     let description = issue_detail.issue.fields.summary.clone();
     let native_url = issue_detail
         .issue
@@ -276,10 +729,30 @@ fn convert_issue(
         .join(&format!("/browse/{}", issue_detail.issue.key))
         .context(CouldNotCreateUrl { target: "issue" })?;
     let native_id = core::NativeId(issue_detail.issue.key.0.clone());
-    let timeline = convert_changelog(conf, &issue_detail.issue, &issue_detail.changelog)?;
-    let current_status = get_status_mapping(conf, &issue_detail.issue.fields.status.name)?;
+    let mut timeline = convert_changelog(
+        conf,
+        categories,
+        &issue_detail.issue,
+        &issue_detail.changelog,
+    )?;
+    timeline.extend(convert_worklogs(&issue_detail.worklogs));
+    let current_status =
+        get_status_mapping(conf, categories, &issue_detail.issue.fields.status.name)?;
     let resolution = get_resolution(conf, &issue_detail.issue)?;
-    match convert_issue_type(conf, &issue_detail.issue.fields.issuetype) {
+    let assignee = issue_detail
+        .issue
+        .fields
+        .assignee
+        .as_ref()
+        .map(|assignee| assignee.display_name.clone());
+    let team = get_team(conf, &issue_detail.issue)?;
+    let links = convert_links(conf, &issue_detail.issue);
+    let resolved = get_resolved(&issue_detail.issue)?;
+    match convert_issue_type(
+        conf,
+        &issue_detail.issue.fields.project,
+        &issue_detail.issue.fields.issuetype,
+    ) {
         Some(issue_type) => Ok(Some(core::Item {
             id,
             name: issue_detail.issue.key.0.clone(),
@@ -290,22 +763,93 @@ fn convert_issue(
             timeline,
             status: current_status,
             resolution,
+            assignee,
+            team,
+            links,
+            created: Some(issue_detail.issue.fields.created),
+            resolved,
+            project: Some(issue_detail.issue.fields.project.key.clone()),
+            labels: issue_detail.issue.fields.labels.clone(),
+            components: issue_detail
+                .issue
+                .fields
+                .components
+                .iter()
+                .map(|component| component.name.clone())
+                .collect(),
+            parent: get_parent(conf, &issue_detail.issue),
+            body: issue_detail
+                .issue
+                .fields
+                .description
+                .as_ref()
+                .map(|description| adf::render(description, conf.body_format))
+                .filter(|body| !body.is_empty()),
+            rendered_body: issue_detail
+                .issue
+                .rendered_fields
+                .as_ref()
+                .and_then(|rendered| rendered.description.clone())
+                .filter(|body| !body.is_empty()),
         })),
         None => Ok(None),
     }
 }
 
+/// What to do with an issue that has a status that can not be mapped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnmappedStatus {
+    /// Fail the whole translation
+    Fail,
+    /// Warn and leave the issue out of the result
+    Skip,
+}
+
+/// Translate the issues into items, mapping their statuses and resolutions through the config and
+/// the statuses they are in at the time through the `categories`
+///
+/// # Errors
+///
+/// Fails when a status or resolution isn't mapped, unless `unmapped_status` skips the issues with
+/// unmapped statuses, or when a field of an issue can't be read.
 pub fn translate(
     conf: &jira::Config,
+    categories: &StatusCategories,
     issues: &[api::IssueDetail],
+    unmapped_status: UnmappedStatus,
+) -> Result<Vec<core::Item>, Error> {
+    translate_items(conf, categories, issues, unmapped_status, || {
+        core::ItemId(Uuid::new_v4())
+    })
+}
+
+/// Translation doesn't depend on the time it is run at, the only thing that differs between
+/// two translations of the same issues are the random ids of the items. Those come from
+/// `new_id`, so that the tests can fix them.
+pub(crate) fn translate_items(
+    conf: &jira::Config,
+    categories: &StatusCategories,
+    issues: &[api::IssueDetail],
+    unmapped_status: UnmappedStatus,
+    mut new_id: impl FnMut() -> core::ItemId,
 ) -> Result<Vec<core::Item>, Error> {
     let mut items: Vec<core::Item> = Vec::with_capacity(issues.len());
 
     for issue in issues {
-        if let Some(item) = convert_issue(conf, issue)? {
-            items.push(item);
+        match convert_issue(conf, categories, new_id(), issue) {
+            Ok(Some(item)) => items.push(item),
+            Ok(None) => (),
+            Err(err @ Error::MissingStatusMapping { .. })
+                if unmapped_status == UnmappedStatus::Skip =>
+            {
+                warn!("skipping {}: {}", issue.issue.key, err);
+            }
+            Err(err) => return Err(err),
         }
     }
 
     Ok(items)
 }
+
+#[cfg(test)]
+mod tests;
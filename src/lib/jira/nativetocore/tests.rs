@@ -0,0 +1,189 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Golden file tests of the translation. Each directory of `tests/fixtures/translate` holds a
+//! `config.yml`, the `issues.json` to translate and the `expected.json` items they translate to,
+//! and may hold the `statuses.json` of the instance.
+//! Run the tests with `LECTEV_UPDATE_GOLDEN=1` to rewrite the expected items after a change to
+//! the translation, and review the difference before committing it.
+use super::{translate_items, Error, StatusCategories, UnmappedStatus};
+use crate::lib::jira::test_support::{fixture, fixture_config};
+use crate::lib::jira::{api, core, dump, native};
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Translate the issues of a fixture, numbering the items from 1 so that the ids are the same on
+/// every run. The status categories are those of the fixture's `statuses.json`, the statuses of
+/// the instance, when it has one.
+fn translate_fixture(
+    name: &str,
+    unmapped_status: UnmappedStatus,
+) -> Result<Vec<core::Item>, Error> {
+    let dir = fixture(name);
+    let statuses = match fs::read_to_string(dir.join("statuses.json")) {
+        Ok(statuses) => {
+            let statuses: Vec<native::InstanceStatus> =
+                serde_json::from_str(&statuses).expect("valid fixture statuses");
+            Some(statuses)
+        }
+        Err(_) => None,
+    };
+    translate_issues_of(name, statuses.as_deref(), unmapped_status)
+}
+
+/// Translate the issues of a fixture with the categories of `statuses`, or of the issues
+/// themselves when there are none
+fn translate_issues_of(
+    name: &str,
+    statuses: Option<&[native::InstanceStatus]>,
+    unmapped_status: UnmappedStatus,
+) -> Result<Vec<core::Item>, Error> {
+    let dir = fixture(name);
+    let conf = fixture_config(name);
+    let issues = fs::read_to_string(dir.join("issues.json")).expect("fixture issues");
+    let issues: Vec<api::IssueDetail> =
+        serde_json::from_str(&issues).expect("valid fixture issues");
+    let categories = match statuses {
+        Some(statuses) => StatusCategories::new(&conf, statuses),
+        None => StatusCategories::of_issues(&conf, &issues),
+    };
+    let mut next_id = 0;
+    translate_items(&conf, &categories, &issues, unmapped_status, || {
+        next_id += 1;
+        core::ItemId(Uuid::from_u128(next_id))
+    })
+}
+
+/// Translate the issues of a fixture as they would be read back from a dump, with the categories
+/// of the dump. The fixture's `statuses.json` is written into the dump when `with_statuses` is set,
+/// as it is when it was pulled with `infer-status-categories`.
+fn translate_dump_of(
+    name: &str,
+    with_statuses: bool,
+    unmapped_status: UnmappedStatus,
+) -> Result<Vec<core::Item>, Error> {
+    let dir = fixture(name);
+    let conf = fixture_config(name);
+    let issues = fs::read_to_string(dir.join("issues.json")).expect("fixture issues");
+    let issues: serde_json::Value = serde_json::from_str(&issues).expect("valid fixture issues");
+    let statuses = if with_statuses {
+        let statuses = fs::read_to_string(dir.join("statuses.json")).expect("fixture statuses");
+        serde_json::from_str(&statuses).expect("valid fixture statuses")
+    } else {
+        serde_json::json!([])
+    };
+    let contents = serde_json::json!({
+        "fetched_at": "2021-06-01T00:00:00Z",
+        "issues": issues,
+        "statuses": statuses,
+    });
+    let dump = dump::parse(&contents.to_string(), Path::new("dump.json")).expect("valid dump");
+    let categories = StatusCategories::of_dump(&conf, &dump);
+    let mut next_id = 0;
+    translate_items(&conf, &categories, &dump.issues, unmapped_status, || {
+        next_id += 1;
+        core::ItemId(Uuid::from_u128(next_id))
+    })
+}
+
+fn check(name: &str, unmapped_status: UnmappedStatus) {
+    let items = translate_fixture(name, unmapped_status).expect("fixture translates");
+    check_items(name, &items);
+}
+
+/// Compare `items` with the `expected.json` of the fixture, rewriting it when
+/// `LECTEV_UPDATE_GOLDEN` is set
+fn check_items(name: &str, items: &[core::Item]) {
+    let actual = serde_json::to_value(items).expect("items serialize");
+    let expected_path = fixture(name).join("expected.json");
+    if std::env::var_os("LECTEV_UPDATE_GOLDEN").is_some() {
+        let pretty = serde_json::to_string_pretty(&actual).expect("items serialize");
+        fs::write(&expected_path, pretty + "\n").expect("expected items written");
+    }
+    let expected = fs::read_to_string(&expected_path).expect("fixture expected items");
+    let expected: serde_json::Value =
+        serde_json::from_str(&expected).expect("valid fixture expected items");
+    assert_eq!(
+        actual,
+        expected,
+        "the translation of {} no longer matches {}",
+        name,
+        expected_path.display()
+    );
+}
+
+#[test]
+fn maps_statuses_by_name_pattern_and_default() {
+    check("statuses", UnmappedStatus::Fail);
+}
+
+#[test]
+fn infers_statuses_from_their_categories() {
+    check("categories", UnmappedStatus::Fail);
+}
+
+/// The changelog goes through statuses no issue is in now, only the statuses of the instance
+/// know their categories
+#[test]
+fn infers_categories_of_statuses_no_issue_is_in() {
+    check("instance_statuses", UnmappedStatus::Fail);
+}
+
+/// A dump keeps the statuses of the instance, so its issues translate as they did when pulled
+#[test]
+fn infers_categories_of_past_statuses_from_a_dump() {
+    let items = translate_dump_of("instance_statuses", true, UnmappedStatus::Fail)
+        .expect("dump translates");
+    check_items("instance_statuses", &items);
+}
+
+/// Dumps written before the statuses were kept only know the categories of the current statuses
+/// of their issues
+#[test]
+fn fails_on_past_statuses_of_a_dump_without_statuses() {
+    assert!(matches!(
+        translate_dump_of("instance_statuses", false, UnmappedStatus::Fail),
+        Err(Error::MissingStatusMapping { .. })
+    ));
+}
+
+#[test]
+fn reads_single_and_multi_value_resolution_fields() {
+    check("resolutions", UnmappedStatus::Fail);
+}
+
+#[test]
+fn converts_story_points_to_days() {
+    check("estimates", UnmappedStatus::Fail);
+}
+
+#[test]
+fn skips_issues_with_unmapped_statuses() {
+    check("unmapped", UnmappedStatus::Skip);
+}
+
+#[test]
+fn fails_on_unmapped_statuses() {
+    assert!(matches!(
+        translate_fixture("unmapped", UnmappedStatus::Fail),
+        Err(Error::MissingStatusMapping { .. })
+    ));
+}
+
+/// The epic link of company-managed projects is taken over the parent field when both are set
+#[test]
+fn takes_the_epic_link_over_the_parent() {
+    check("parents", UnmappedStatus::Fail);
+}
@@ -0,0 +1,82 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Personal Information Redaction
+//!
+//! Removes the names, email addresses, avatars and time zones of people from raw jira data so that
+//! debug dumps can be shared. Only the fields that we know hold people are redacted: the assignee, creator and
+//! reporter of an issue, the author of each change, worklog and comment and the values of assignee
+//! and reporter changes. Custom fields are left as they are.
+use crate::lib::jira::{api, native};
+use url::Url;
+
+/// What names are replaced with
+const REDACTED: &str = "redacted";
+
+/// What avatar urls are replaced with, the urls hold the id of the person
+const REDACTED_AVATAR: &str = "https://redacted.invalid/avatar";
+
+/// The changelog fields whose values are people
+const PERSON_FIELDS: [&str; 2] = ["assignee", "reporter"];
+
+fn redact_person(person: &mut native::Assignee) {
+    person.sel = None;
+    person.name = None;
+    person.key = None;
+    person.email_address = None;
+    person.display_name = REDACTED.to_owned();
+    let avatar = Url::parse(REDACTED_AVATAR).expect("the redacted avatar url is valid");
+    person.avatar_urls = native::AvatarUrl {
+        f48x48: avatar.clone(),
+        f24x24: avatar.clone(),
+        f16x16: avatar.clone(),
+        f32x32: avatar,
+    };
+    person.time_zone = String::new();
+}
+
+fn redact_change(entry: &mut native::ChangeLogEntry) {
+    if PERSON_FIELDS.contains(&entry.field.as_str()) {
+        entry.from = None;
+        entry.from_string = None;
+        entry.to = None;
+        entry.to_string = None;
+    }
+}
+
+/// Redact the people in an issue and its changelog
+pub fn redact_pii(detail: &mut api::IssueDetail) {
+    let fields = &mut detail.issue.fields;
+    for person in fields
+        .assignee
+        .iter_mut()
+        .chain(fields.creator.iter_mut())
+        .chain(fields.reporter.iter_mut())
+    {
+        redact_person(person);
+    }
+
+    for group in &mut detail.changelog {
+        redact_person(&mut group.author);
+        group.items.iter_mut().for_each(redact_change);
+    }
+
+    for worklog in &mut detail.worklogs {
+        worklog.author.iter_mut().for_each(redact_person);
+    }
+
+    for comment in &mut detail.comments {
+        comment.author.iter_mut().for_each(redact_person);
+    }
+}
@@ -0,0 +1,190 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Responsiveness
+//!
+//! How quickly issues get a response, as a proxy for how responsive a support queue is. A
+//! response is the first comment made by someone other than the reporter of the issue. Service
+//! desk comments that are internal notes can't be seen by the customer, so they are counted as
+//! comments but not as responses.
+use crate::lib::calendar::Calendar;
+use crate::lib::jira::{api, native};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+use uom::si::time::day;
+use url::Url;
+
+/// A row of the responsiveness report, how long an issue waited for its first response
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The url of the issue in jira
+    pub url: String,
+    /// The key of the issue
+    pub name: &'a str,
+    /// The summary of the issue
+    pub description: &'a str,
+    /// The name of the status the issue is in
+    pub status: &'a str,
+    /// When the issue was created
+    pub created: DateTime<Utc>,
+    /// The comments on the issue
+    pub comments: usize,
+    /// The comments that count as a response
+    pub responses: usize,
+    /// When the first response was made
+    pub first_response: Option<DateTime<Utc>>,
+    /// Who made the first response
+    pub first_responder: Option<&'a str>,
+    /// The elapsed hours from the creation of the issue to its first response
+    pub hours_to_first_response: Option<f64>,
+    /// The working days from the creation of the issue to its first response, see [`Calendar`]
+    pub working_days_to_first_response: Option<f64>,
+    /// The description of the issue as HTML, when jira was asked to render it
+    pub rendered_description: Option<&'a str>,
+}
+
+/// The responsiveness over every entry of the report
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Summary {
+    /// The issues reported on
+    pub issues: usize,
+    /// The issues that have had a response
+    pub responded_issues: usize,
+    /// The comments on the issues
+    pub comments: usize,
+    /// The mean of the elapsed hours to the first response, over the issues that had one
+    pub mean_hours_to_first_response: Option<f64>,
+    /// The median of the elapsed hours to the first response, over the issues that had one
+    pub median_hours_to_first_response: Option<f64>,
+    /// The mean of the working days to the first response, over every issue
+    pub mean_working_days_to_first_response: Option<f64>,
+}
+
+fn is_response(reporter: Option<&str>, comment: &native::Comment) -> bool {
+    let author = comment
+        .author
+        .as_ref()
+        .map(|author| author.display_name.as_str());
+    comment.jsd_public != Some(false) && (reporter.is_none() || author != reporter)
+}
+
+/// The responsiveness of each issue
+#[instrument(skip(calendar, details))]
+pub fn calculate<'a>(
+    instance_url: &Url,
+    calendar: &Calendar,
+    details: &'a [api::IssueDetail],
+) -> Vec<Entry<'a>> {
+    details
+        .iter()
+        .map(|detail| {
+            let fields = &detail.issue.fields;
+            let reporter = fields
+                .reporter
+                .as_ref()
+                .map(|reporter| reporter.display_name.as_str());
+            let mut responses: Vec<&native::Comment> = detail
+                .comments
+                .iter()
+                .filter(|comment| is_response(reporter, comment))
+                .collect();
+            responses.sort_by_key(|comment| comment.created);
+            let first = responses.first();
+            let first_response = first.map(|comment| comment.created);
+            Entry {
+                url: format!("{}browse/{}", instance_url.as_str(), detail.issue.key),
+                name: &detail.issue.key.0,
+                description: &fields.summary,
+                status: &fields.status.name,
+                created: fields.created,
+                comments: detail.comments.len(),
+                responses: responses.len(),
+                first_response,
+                first_responder: first.and_then(|comment| {
+                    comment
+                        .author
+                        .as_ref()
+                        .map(|author| author.display_name.as_str())
+                }),
+                #[allow(clippy::cast_precision_loss)]
+                hours_to_first_response: first_response
+                    .map(|responded| (responded - fields.created).num_seconds() as f64 / 3600.0),
+                working_days_to_first_response: first_response.map(|responded| {
+                    calendar
+                        .working_time(&fields.created, &responded)
+                        .get::<day>()
+                }),
+                rendered_description: detail
+                    .issue
+                    .rendered_fields
+                    .as_ref()
+                    .and_then(|rendered| rendered.description.as_deref()),
+            }
+        })
+        .collect()
+}
+
+/// Running totals of the entries written so far. Only the hours to the first response of each
+/// issue are kept, for the median.
+#[derive(Debug, Default)]
+pub struct Totals {
+    issues: usize,
+    comments: usize,
+    hours_to_first_response: Vec<f64>,
+    working_days_total: f64,
+}
+
+impl Totals {
+    /// Add an entry to the totals
+    pub fn add(&mut self, entry: &Entry) {
+        self.issues += 1;
+        self.comments += entry.comments;
+        if let Some(hours) = entry.hours_to_first_response {
+            self.hours_to_first_response.push(hours);
+        }
+        self.working_days_total += entry.working_days_to_first_response.unwrap_or(0.0);
+    }
+
+    /// The summary of the entries added so far
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let mut hours = self.hours_to_first_response.clone();
+        hours.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let responded = hours.len();
+        let median = match responded {
+            0 => None,
+            _ if responded % 2 == 0 => {
+                Some((hours[responded / 2 - 1] + hours[responded / 2]) / 2.0)
+            }
+            _ => Some(hours[responded / 2]),
+        };
+        let mean = |total: f64| {
+            if responded == 0 {
+                None
+            } else {
+                Some(total / responded as f64)
+            }
+        };
+        Summary {
+            issues: self.issues,
+            responded_issues: responded,
+            comments: self.comments,
+            mean_hours_to_first_response: mean(hours.iter().sum()),
+            median_hours_to_first_response: median,
+            mean_working_days_to_first_response: mean(self.working_days_total),
+        }
+    }
+}
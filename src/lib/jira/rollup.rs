@@ -0,0 +1,122 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Hierarchy Rollups
+//!
+//! Sums the time in status of the items of a report by the epic they belong to. An item belongs
+//! to the epic that is its parent. A sub-task belongs to the epic of its story, when the story
+//! is part of the report too, otherwise to the story itself. Items without a parent are summed
+//! under `(none)`.
+use crate::lib::calendar::Calendar;
+use crate::lib::jira::core;
+use crate::lib::jira::times_in_flight;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::instrument;
+use url::Url;
+
+/// The epic used for the items that have no parent
+const NO_EPIC: &str = "(none)";
+
+/// The summed days in each status of the children of a single epic
+#[derive(Debug, Default, Serialize)]
+pub struct Entry {
+    /// The key of the epic, `(none)` for the items without one
+    pub epic: String,
+    /// Empty for the items without an epic
+    pub url: Option<String>,
+    /// The summary of the epic, when the epic itself is part of the report
+    pub description: Option<String>,
+    /// The number of items
+    pub items: usize,
+    /// The number of items that are completed
+    pub completed_items: usize,
+    /// The percentage of the children that are completed
+    pub percent_completed: f64,
+    /// Business days spent in `ToDo`
+    pub todo: f64,
+    /// Business days spent in `Ready`
+    pub ready: f64,
+    /// Business days spent in `InDev`
+    pub in_dev: f64,
+    /// Business days spent in `InTest`
+    pub in_test: f64,
+    /// Business days spent in `Waiting`
+    pub waiting: f64,
+    /// Business days spent in `Completed`
+    pub completed: f64,
+    /// Business days the items were flagged as impeded
+    pub blocked_days: f64,
+}
+
+/// The epic of every item that has one, by item name
+fn epics(items: &[core::Item]) -> HashMap<&str, &str> {
+    let parents: HashMap<&str, &str> = items
+        .iter()
+        .filter_map(|item| {
+            item.parent
+                .as_ref()
+                .map(|parent| (item.name.as_str(), parent.0.as_str()))
+        })
+        .collect();
+    parents
+        .iter()
+        .map(|(name, parent)| (*name, parents.get(parent).copied().unwrap_or(parent)))
+        .collect()
+}
+
+/// Sum the days in each status of `items` by their epic, ordered by epic
+#[instrument(skip(calendar, items))]
+#[allow(clippy::cast_precision_loss)]
+pub fn rollup(instance_url: &Url, calendar: &Calendar, items: &[core::Item]) -> Vec<Entry> {
+    let epics = epics(items);
+    let epic_names: HashSet<&str> = epics.values().copied().collect();
+    let mut rollups: BTreeMap<&str, Entry> = BTreeMap::new();
+    for entry in times_in_flight::calculate(instance_url, calendar, items) {
+        if epic_names.contains(entry.name) {
+            continue;
+        }
+        let epic = epics.get(entry.name).copied().unwrap_or(NO_EPIC);
+        let rollup = rollups.entry(epic).or_insert_with(|| Entry {
+            epic: epic.to_owned(),
+            ..Entry::default()
+        });
+        rollup.items += 1;
+        if *entry.status == core::ItemStatus::Completed {
+            rollup.completed_items += 1;
+        }
+        rollup.todo += entry.todo;
+        rollup.ready += entry.ready;
+        rollup.in_dev += entry.in_dev;
+        rollup.in_test += entry.in_test;
+        rollup.waiting += entry.waiting;
+        rollup.completed += entry.completed;
+        rollup.blocked_days += entry.blocked_days;
+    }
+
+    rollups
+        .into_iter()
+        .map(|(epic, mut rollup)| {
+            if epic != NO_EPIC {
+                rollup.url = Some(format!("{}browse/{}", instance_url.as_str(), epic));
+                rollup.description = items
+                    .iter()
+                    .find(|item| item.name == epic)
+                    .map(|item| item.description.clone());
+            }
+            rollup.percent_completed = rollup.completed_items as f64 / rollup.items as f64 * 100.0;
+            rollup
+        })
+        .collect()
+}
@@ -0,0 +1,448 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Sample Issues
+//!
+//! Synthesizes jira issues, along with their changelogs, that move through a small workflow. Each
+//! issue is estimated, assigned, sometimes flagged, sometimes sent back from review, and resolved
+//! if it reached the end of the workflow in time. Together with the matching [`config`] they let
+//! the reports be tried without a jira instance.
+//!
+//! The issues are generated from a seed, so the same seed always gives the same issues relative
+//! to when they were generated.
+use crate::configs::jira;
+use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::jira::{adf, api, native};
+use crate::lib::rest::{RetryPolicy, TokenSource};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use url::Url;
+
+/// The jira instance the sample issues claim to come from
+pub const INSTANCE: &str = "https://jira.example.com/";
+
+/// The key of the project of the sample issues
+const PROJECT_KEY: &str = "SAMPLE";
+
+const TO_DO: &str = "To Do";
+const READY: &str = "Ready";
+const IN_PROGRESS: &str = "In Progress";
+const IN_REVIEW: &str = "In Review";
+const WAITING: &str = "Waiting";
+const DONE: &str = "Done";
+
+/// The statuses of the sample workflow and what each is mapped to
+const STATUSES: [(&str, ItemStatus); 6] = [
+    (TO_DO, ItemStatus::ToDo),
+    (READY, ItemStatus::Ready),
+    (IN_PROGRESS, ItemStatus::InDev),
+    (IN_REVIEW, ItemStatus::InTest),
+    (WAITING, ItemStatus::Waiting),
+    (DONE, ItemStatus::Completed),
+];
+
+const FEATURE_TYPES: [&str; 2] = ["Story", "Bug"];
+const OPERATIONAL_TYPES: [&str; 1] = ["Task"];
+const COMPONENTS: [&str; 3] = ["API", "Web", "Mobile"];
+const LABELS: [&str; 3] = ["customer", "tech-debt", "security"];
+
+/// How many sample issues to generate and what they look like
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// The number of issues
+    pub issues: usize,
+    /// The number of people the issues are assigned to
+    pub people: usize,
+    /// The issues are created over this many days, up to when they are generated
+    pub days: u32,
+    /// The seed of the random numbers, the same seed generates the same issues
+    pub seed: u64,
+}
+
+/// A splitmix64 generator. The samples only need to look random, and have to be the same for the
+/// same seed so they can be used as fixtures.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, values: &'a [T]) -> &'a T {
+        let index = self.below(u64::try_from(values.len()).unwrap_or(u64::MAX));
+        &values[usize::try_from(index).unwrap_or(0)]
+    }
+
+    /// A duration of between `min` and `max` hours, to the minute
+    fn hours(&mut self, min: u64, max: u64) -> Duration {
+        let minutes = min * 60 + self.below((max - min) * 60 + 1);
+        Duration::minutes(i64::try_from(minutes).unwrap_or(i64::MAX))
+    }
+}
+
+/// The config that maps the statuses, issue types and resolutions of the sample issues. The
+/// token is never used, the samples are read from a file.
+#[must_use]
+pub fn config(instance: &Url) -> jira::Config {
+    jira::Config {
+        jira_instance: instance.clone(),
+        username: "sample@example.com".to_owned(),
+        token: TokenSource::TokenEnv("LECTEV_SAMPLE_TOKEN".to_owned()),
+        resolution_field: None,
+        team_field: None,
+        epic_link_field: None,
+        issue_types: jira::IssueTypes {
+            features: FEATURE_TYPES.iter().map(|&name| name.to_owned()).collect(),
+            operational: OPERATIONAL_TYPES
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect(),
+        },
+        project_issue_types: HashMap::new(),
+        status_mapping: STATUSES
+            .iter()
+            .map(|(name, status)| ((*name).to_owned(), *status))
+            .collect(),
+        status_patterns: Vec::new(),
+        infer_status_categories: false,
+        default_status: None,
+        resolution_mapping: vec![
+            (DONE.to_owned(), Resolution::Delivered),
+            ("Won't Do".to_owned(), Resolution::Rejected),
+        ]
+        .into_iter()
+        .collect(),
+        exclude_resolutions: Vec::new(),
+        link_mapping: HashMap::new(),
+        retry: RetryPolicy::default(),
+        history_fields: Vec::new(),
+        working_week: None,
+        reporting_timezone: None,
+        estimate_source: None,
+        body_format: adf::Format::default(),
+        max_issues: None,
+        webhook_secret: None,
+    }
+}
+
+fn person(instance: &Url, name: &str) -> native::Assignee {
+    native::Assignee {
+        sel: None,
+        name: None,
+        key: None,
+        email_address: Some(format!(
+            "{}@example.com",
+            name.to_lowercase().replace(' ', ".")
+        )),
+        avatar_urls: native::AvatarUrl {
+            f48x48: instance.clone(),
+            f24x24: instance.clone(),
+            f16x16: instance.clone(),
+            f32x32: instance.clone(),
+        },
+        display_name: name.to_owned(),
+        active: true,
+        time_zone: "UTC".to_owned(),
+    }
+}
+
+fn status(instance: &Url, name: &str) -> native::Status {
+    let (id, key, category) = match name {
+        TO_DO | READY => (2, "new", "To Do"),
+        DONE => (3, "done", "Done"),
+        _ => (4, "indeterminate", "In Progress"),
+    };
+    native::Status {
+        sel: instance.clone(),
+        description: String::new(),
+        icon_url: String::new(),
+        name: name.to_owned(),
+        id: name.to_lowercase().replace(' ', "-"),
+        status_category: native::StatusCategory {
+            sel: instance.clone(),
+            id,
+            key: key.to_owned(),
+            color_name: String::new(),
+            name: category.to_owned(),
+        },
+    }
+}
+
+fn project(instance: &Url) -> native::Project {
+    native::Project {
+        sel: instance.clone(),
+        id: "10000".to_owned(),
+        key: PROJECT_KEY.to_owned(),
+        name: "Sample".to_owned(),
+        project_type_key: "software".to_owned(),
+        avatar_urls: person(instance, "").avatar_urls,
+        project_category: None,
+    }
+}
+
+fn change(field: &str, from: Option<&str>, to: Option<&str>) -> native::ChangeLogEntry {
+    native::ChangeLogEntry {
+        field: field.to_owned(),
+        fieldtype: "jira".to_owned(),
+        field_id: Some(field.to_owned()),
+        from: from.map(str::to_owned),
+        from_string: from.map(str::to_owned),
+        to: to.map(str::to_owned),
+        to_string: to.map(str::to_owned),
+    }
+}
+
+/// Builds the changelog of a single issue, a change group at a time
+#[derive(Debug)]
+struct Changelog<'a> {
+    instance: &'a Url,
+    author: native::Assignee,
+    groups: Vec<native::ChangeGroup>,
+}
+
+impl Changelog<'_> {
+    fn push(&mut self, created: DateTime<Utc>, items: Vec<native::ChangeLogEntry>) {
+        self.groups.push(native::ChangeGroup {
+            id: (self.groups.len() + 1).to_string(),
+            author: self.author.clone(),
+            created,
+            items,
+        });
+    }
+
+    fn reassign(&mut self, at: DateTime<Utc>, from: Option<&str>, to: &str) {
+        self.author = person(self.instance, to);
+        self.push(at, vec![change("assignee", from, Some(to))]);
+    }
+}
+
+/// The status an issue in `current` moves to next, or `None` at the end of the workflow
+fn next_status(rng: &mut Rng, current: &str) -> Option<&'static str> {
+    match current {
+        TO_DO => Some(READY),
+        READY | WAITING => Some(IN_PROGRESS),
+        IN_PROGRESS if rng.chance(10) => Some(WAITING),
+        IN_PROGRESS => Some(IN_REVIEW),
+        IN_REVIEW if rng.chance(20) => Some(IN_PROGRESS),
+        IN_REVIEW => Some(DONE),
+        _ => None,
+    }
+}
+
+/// Where an issue ended up after moving through the workflow
+#[derive(Debug)]
+struct History {
+    changelog: Vec<native::ChangeGroup>,
+    status: &'static str,
+    assignee: Option<String>,
+    last_change: DateTime<Utc>,
+}
+
+/// Move an issue created at `created` through the workflow until it is done or the next change
+/// would be after `now`
+fn history(
+    rng: &mut Rng,
+    instance: &Url,
+    people: &[String],
+    reporter: &native::Assignee,
+    created: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> History {
+    let mut changelog = Changelog {
+        instance,
+        author: reporter.clone(),
+        groups: Vec::new(),
+    };
+    let estimate_seconds = (1 + rng.below(5)) * 8 * 60 * 60;
+    changelog.push(
+        created,
+        vec![change(
+            "timeestimate",
+            None,
+            Some(&estimate_seconds.to_string()),
+        )],
+    );
+
+    let mut current = TO_DO;
+    let mut assignee: Option<String> = None;
+    let mut at = created;
+    while let Some(next) = next_status(rng, current) {
+        let next_at = at + rng.hours(4, 120);
+        if next_at > now {
+            break;
+        }
+        if current == IN_PROGRESS && rng.chance(15) {
+            changelog.push(
+                at + (next_at - at) / 4,
+                vec![change("Flagged", None, Some("Impediment"))],
+            );
+            changelog.push(
+                at + (next_at - at) / 2,
+                vec![change("Flagged", Some("Impediment"), Some(""))],
+            );
+        }
+        at = next_at;
+        if next == IN_PROGRESS && (assignee.is_none() || rng.chance(10)) {
+            let to = rng.pick(people).clone();
+            changelog.reassign(at, assignee.as_deref(), &to);
+            assignee = Some(to);
+        }
+        changelog.push(at, vec![change("status", Some(current), Some(next))]);
+        current = next;
+    }
+
+    History {
+        changelog: changelog.groups,
+        status: current,
+        assignee,
+        last_change: at,
+    }
+}
+
+fn issue(
+    rng: &mut Rng,
+    options: Options,
+    instance: &Url,
+    now: DateTime<Utc>,
+    number: usize,
+) -> Result<api::IssueDetail, url::ParseError> {
+    let people: Vec<String> = (1..=options.people.max(1))
+        .map(|person| format!("Person {}", person))
+        .collect();
+    let window_minutes = u64::from(options.days) * 24 * 60;
+    let created = now - Duration::days(i64::from(options.days))
+        + Duration::minutes(i64::try_from(rng.below(window_minutes)).unwrap_or(0));
+    let reporter = person(instance, rng.pick(&people).as_str());
+    let History {
+        changelog,
+        status: current,
+        assignee,
+        last_change: at,
+    } = history(rng, instance, &people, &reporter, created, now);
+
+    let resolution = if current == DONE {
+        Some(native::Resolution {
+            sel: instance.clone(),
+            id: "1".to_owned(),
+            description: String::new(),
+            name: DONE.to_owned(),
+        })
+    } else {
+        None
+    };
+    let is_feature = rng.chance(80);
+    let issue_type = if is_feature {
+        rng.pick(&FEATURE_TYPES)
+    } else {
+        rng.pick(&OPERATIONAL_TYPES)
+    };
+    let labels = if rng.chance(30) {
+        vec![(*rng.pick(&LABELS)).to_owned()]
+    } else {
+        Vec::new()
+    };
+    let id = (10_000 + number).to_string();
+    let key = native::IssueKey(format!("{}-{}", PROJECT_KEY, number));
+    let progress = native::Progress {
+        progress: 0,
+        total: 0,
+    };
+    let issue = native::Issue {
+        expand: None,
+        sel: instance.join(&format!("rest/api/3/issue/{}", id))?,
+        id,
+        fields: native::IssuesField {
+            issuetype: native::IssueType {
+                sel: instance.clone(),
+                id: issue_type.to_lowercase(),
+                description: String::new(),
+                icon_url: String::new(),
+                name: (*issue_type).to_owned(),
+                subtask: false,
+                avatar_id: None,
+            },
+            resolutiondate: resolution.as_ref().map(|_| at.to_rfc3339()),
+            resolution,
+            issuelinks: Vec::new(),
+            assignee: assignee.as_deref().map(|name| person(instance, name)),
+            subtasks: Vec::new(),
+            votes: None,
+            status: status(instance, current),
+            creator: Some(reporter.clone()),
+            workratio: -1,
+            labels,
+            reporter: Some(reporter),
+            progress,
+            project: project(instance),
+            watches: native::Watch {
+                sel: instance.clone(),
+                watch_count: 1,
+                is_watching: false,
+            },
+            updated: at.to_rfc3339(),
+            description: None,
+            summary: format!("Sample {} {}", issue_type.to_lowercase(), number),
+            priority: None,
+            aggregateprogress: progress,
+            created,
+            fix_versions: Vec::new(),
+            components: vec![native::Component {
+                id: None,
+                name: (*rng.pick(&COMPONENTS)).to_owned(),
+            }],
+            parent: None,
+            custom_fields: HashMap::new(),
+        },
+        key,
+        rendered_fields: None,
+        changelog: None,
+    };
+
+    Ok(api::IssueDetail {
+        issue,
+        changelog,
+        worklogs: Vec::new(),
+        comments: Vec::new(),
+    })
+}
+
+/// Generate the sample issues, as if they were pulled from `instance` at `now`
+///
+/// # Errors
+///
+/// Fails when the url of an issue can't be made from `instance`.
+pub fn issues(
+    options: Options,
+    instance: &Url,
+    now: DateTime<Utc>,
+) -> Result<Vec<api::IssueDetail>, url::ParseError> {
+    let mut rng = Rng(options.seed);
+    (1..=options.issues)
+        .map(|number| issue(&mut rng, options, instance, now, number))
+        .collect()
+}
@@ -0,0 +1,177 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Config Suggestions
+//!
+//! Collects the statuses, resolutions and issue types that a set of issues actually use and
+//! writes a starting point for the `status-mapping`, `resolution-mapping` and `issue-types` of
+//! the config. Each name is mapped by the words it contains, e.g. a status with `review` in its
+//! name maps to `InTest`, and otherwise by its jira status category. Names without an obvious
+//! mapping are written commented out, to be mapped by hand.
+use crate::lib::jira::api::IssueDetail;
+use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::jira::nativetocore;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Words that give away the status a jira status maps to, checked in order
+const STATUS_WORDS: [(&str, ItemStatus); 16] = [
+    ("block", ItemStatus::Waiting),
+    ("wait", ItemStatus::Waiting),
+    ("hold", ItemStatus::Waiting),
+    ("test", ItemStatus::InTest),
+    ("qa", ItemStatus::InTest),
+    ("review", ItemStatus::InTest),
+    ("verif", ItemStatus::InTest),
+    ("ready", ItemStatus::Ready),
+    ("selected", ItemStatus::Ready),
+    ("backlog", ItemStatus::ToDo),
+    ("to do", ItemStatus::ToDo),
+    ("open", ItemStatus::ToDo),
+    ("progress", ItemStatus::InDev),
+    ("done", ItemStatus::Completed),
+    ("closed", ItemStatus::Completed),
+    ("resolved", ItemStatus::Completed),
+];
+
+/// Words that give away the resolution a jira resolution maps to
+const RESOLUTION_WORDS: [(&str, Resolution); 10] = [
+    ("won't", Resolution::Rejected),
+    ("duplicate", Resolution::Rejected),
+    ("reproduce", Resolution::Rejected),
+    ("declined", Resolution::Rejected),
+    ("rejected", Resolution::Rejected),
+    ("incomplete", Resolution::Rejected),
+    ("obsolete", Resolution::Rejected),
+    ("done", Resolution::Delivered),
+    ("fixed", Resolution::Delivered),
+    ("complete", Resolution::Delivered),
+];
+
+/// Issue types that are usually unplanned work
+const OPERATIONAL_TYPES: [&str; 5] = ["bug", "incident", "problem", "support", "defect"];
+
+/// Epics hold the issues that are reported on, so they are left out of the issue types
+const EPIC: &str = "epic";
+
+/// The names seen in the issues, along with the status category of the statuses that issues are
+/// currently in
+#[derive(Debug, Default)]
+pub struct Suggestions {
+    issues: usize,
+    statuses: BTreeMap<String, Option<String>>,
+    resolutions: BTreeSet<String>,
+    issue_types: BTreeSet<String>,
+}
+
+impl Suggestions {
+    /// Record the names used by a single issue, including the statuses in its changelog
+    pub fn observe(&mut self, detail: &IssueDetail) {
+        let fields = &detail.issue.fields;
+        self.issues += 1;
+        self.statuses.insert(
+            fields.status.name.clone(),
+            Some(fields.status.status_category.key.clone()),
+        );
+        for entry in detail
+            .changelog
+            .iter()
+            .flat_map(|group| group.items.iter())
+            .filter(|entry| entry.field == "status")
+        {
+            for name in entry.from_string.iter().chain(entry.to_string.iter()) {
+                self.statuses.entry(name.clone()).or_insert(None);
+            }
+        }
+        if let Some(resolution) = &fields.resolution {
+            self.resolutions.insert(resolution.name.clone());
+        }
+        self.issue_types.insert(fields.issuetype.name.clone());
+    }
+}
+
+fn guess_status(name: &str, category: Option<&str>) -> Option<ItemStatus> {
+    let name = name.to_lowercase();
+    STATUS_WORDS
+        .iter()
+        .find(|(word, _)| name.contains(word))
+        .map(|(_, status)| *status)
+        .or_else(|| {
+            nativetocore::STATUS_CATEGORIES
+                .iter()
+                .find(|(key, _)| Some(*key) == category)
+                .map(|(_, status)| *status)
+        })
+}
+
+fn guess_resolution(name: &str) -> Option<Resolution> {
+    let name = name.to_lowercase();
+    RESOLUTION_WORDS
+        .iter()
+        .find(|(word, _)| name.contains(word))
+        .map(|(_, resolution)| *resolution)
+}
+
+/// Names are written as json strings, which yaml reads as they are, so any name can be written
+fn quoted(name: &str) -> String {
+    serde_json::Value::String(name.to_owned()).to_string()
+}
+
+fn write_names(f: &mut fmt::Formatter<'_>, key: &str, names: &[&String]) -> fmt::Result {
+    if names.is_empty() {
+        return writeln!(f, "  {}: []", key);
+    }
+    writeln!(f, "  {}:", key)?;
+    for name in names {
+        writeln!(f, "    - {}", quoted(name))?;
+    }
+    Ok(())
+}
+
+/// Writes the suggestions as yaml to paste into the config
+impl fmt::Display for Suggestions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# Suggested from {} issues, check each mapping before using it",
+            self.issues
+        )?;
+        writeln!(f, "status-mapping:")?;
+        for (name, category) in &self.statuses {
+            match guess_status(name, category.as_deref()) {
+                Some(status) => writeln!(f, "  {}: {}", quoted(name), status)?,
+                None => writeln!(f, "  # {}: no obvious status, map it by hand", quoted(name))?,
+            }
+        }
+        writeln!(f, "resolution-mapping:")?;
+        for name in &self.resolutions {
+            match guess_resolution(name) {
+                Some(resolution) => writeln!(f, "  {}: {}", quoted(name), resolution)?,
+                None => writeln!(
+                    f,
+                    "  # {}: no obvious resolution, map it by hand",
+                    quoted(name)
+                )?,
+            }
+        }
+        let (operational, features): (Vec<&String>, Vec<&String>) = self
+            .issue_types
+            .iter()
+            .filter(|name| name.to_lowercase() != EPIC)
+            .partition(|name| OPERATIONAL_TYPES.contains(&name.to_lowercase().as_str()));
+        writeln!(f, "issue-types:")?;
+        write_names(f, "features", &features)?;
+        write_names(f, "operational", &operational)
+    }
+}
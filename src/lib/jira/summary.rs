@@ -0,0 +1,144 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Terminal Summary
+//!
+//! A short overview of a time in status report, printed once it is written so the report can be
+//! looked over without opening it. It lists the issues with the longest cycle time, the days
+//! spent in development, test and waiting, and the average days the issues spent in each status.
+use crate::lib::jira::core::ItemStatus;
+use crate::lib::jira::thresholds::{self, Column};
+use crate::lib::jira::times_in_flight;
+use colored::Colorize;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The statuses in the order they are listed in
+const STATUSES: [ItemStatus; 6] = [
+    ItemStatus::ToDo,
+    ItemStatus::Ready,
+    ItemStatus::InDev,
+    ItemStatus::InTest,
+    ItemStatus::Waiting,
+    ItemStatus::Completed,
+];
+
+#[derive(Debug)]
+struct Slow {
+    name: String,
+    status: ItemStatus,
+    cycle_time: f64,
+}
+
+/// The slowest issues and status totals of every entry of a report
+#[derive(Debug)]
+pub struct Summary {
+    title: String,
+    top: usize,
+    issues: usize,
+    days: [f64; 6],
+    /// The slowest issues so far, slowest first
+    slowest: Vec<Slow>,
+}
+
+/// Start a summary titled `title` that lists the `top` slowest issues
+#[must_use]
+pub fn new(title: String, top: usize) -> Summary {
+    Summary {
+        title,
+        top,
+        issues: 0,
+        days: [0.0; 6],
+        slowest: Vec::new(),
+    }
+}
+
+impl Summary {
+    /// Record a single report entry
+    pub fn observe(&mut self, entry: &times_in_flight::Entry) {
+        self.issues += 1;
+        for (total, days) in self.days.iter_mut().zip(&[
+            entry.todo,
+            entry.ready,
+            entry.in_dev,
+            entry.in_test,
+            entry.waiting,
+            entry.completed,
+        ]) {
+            *total += days;
+        }
+        let cycle_time = thresholds::column_value(Column::CycleTime, entry).unwrap_or_default();
+        let position = self.slowest.partition_point(|slow| {
+            slow.cycle_time.partial_cmp(&cycle_time) != Some(Ordering::Less)
+        });
+        if position < self.top {
+            self.slowest.insert(
+                position,
+                Slow {
+                    name: entry.name.to_owned(),
+                    status: *entry.status,
+                    cycle_time,
+                },
+            );
+            self.slowest.truncate(self.top);
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn average(&self, total: f64) -> f64 {
+        if self.issues == 0 {
+            0.0
+        } else {
+            total / self.issues as f64
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            format!("{}, {} issues", self.title, self.issues).bold()
+        )?;
+        if !self.slowest.is_empty() {
+            writeln!(f, "\n{}", "Slowest issues by cycle time".underline())?;
+            let width = self
+                .slowest
+                .iter()
+                .map(|slow| slow.name.len())
+                .max()
+                .unwrap_or_default();
+            for slow in &self.slowest {
+                writeln!(
+                    f,
+                    "  {}  {:<9}  {}",
+                    format!("{:<width$}", slow.name, width = width).yellow(),
+                    slow.status.to_string(),
+                    format!("{:>7.1} days", slow.cycle_time).red()
+                )?;
+            }
+        }
+        writeln!(f, "\n{}", "Average days in each status".underline())?;
+        for (status, total) in STATUSES.iter().zip(&self.days) {
+            writeln!(
+                f,
+                "  {:<9}  {}",
+                status.to_string(),
+                format!("{:>7.1}", self.average(*total)).cyan()
+            )?;
+        }
+        Ok(())
+    }
+}
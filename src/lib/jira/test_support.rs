@@ -0,0 +1,86 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Test Support
+//!
+//! The translation fixtures of `tests/fixtures/translate` shared by the tests of the reports. Each
+//! directory holds a `config.yml` and the `issues.json` translated with it. Along with them is an
+//! entry of the time in status report for tests that only set the columns they look at.
+use crate::configs::jira;
+use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::jira::times_in_flight::Entry;
+use std::fs;
+use std::path::PathBuf;
+
+/// The directory of the translation fixture `name`
+#[must_use]
+pub fn fixture(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "fixtures",
+        "translate",
+        name,
+    ]
+    .iter()
+    .collect()
+}
+
+/// The `config.yml` of the translation fixture `name`
+///
+/// # Panics
+///
+/// When the fixture has no `config.yml`, or it is not a valid configuration
+#[must_use]
+pub fn fixture_config(name: &str) -> jira::Config {
+    let config = fs::read_to_string(fixture(name).join("config.yml")).expect("fixture config");
+    serde_yaml::from_str(&config).expect("valid fixture config")
+}
+
+/// An entry of `LEC-1` in `status` with `resolution` and nothing else known about it, every time
+/// zero and every other column empty
+#[must_use]
+pub fn entry(status: &'static ItemStatus, resolution: &'static Resolution) -> Entry<'static> {
+    Entry {
+        url: String::new(),
+        name: "LEC-1",
+        description: "",
+        todo: 0.0,
+        ready: 0.0,
+        in_dev: 0.0,
+        in_test: 0.0,
+        waiting: 0.0,
+        completed: 0.0,
+        blocked_days: 0.0,
+        first_estimate: None,
+        last_estimate: None,
+        estimate_changes: 0,
+        estimate_drift: None,
+        status,
+        resolution,
+        assignee: None,
+        team: None,
+        created: None,
+        resolved: None,
+        calendar_lead_time_days: None,
+        project: None,
+        labels: String::new(),
+        components: String::new(),
+        assignee_changes: 0,
+        distinct_assignees: 0,
+        backward_transitions: 0,
+        reopened_count: 0,
+        body: None,
+    }
+}
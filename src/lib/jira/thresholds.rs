@@ -0,0 +1,629 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Report Thresholds
+//!
+//! Provides simple assertions over the aggregate results of a report. These let a pipeline fail
+//! when a metric crosses a line without having to parse the report itself.
+//!
+//! A threshold is written as `<metric><comparison><value>`, for example `p85_cycle_time>20d`.
+//!
+//! * The metric is either `count`, the number of entries in the report, or
+//!   `<aggregate>_<column>`. The aggregate is one of `count`, `avg`, `min`, `max`, `sum` or
+//!   `pNN`, a percentile such as `p85` using the nearest rank method. The column is one of the
+//!   numeric columns of the report, see [`Observed::COLUMNS`]. For the time in status report
+//!   those are the day columns, `todo`, `ready`, `in_dev`, `in_test`, `waiting`, `completed`,
+//!   `blocked`, `first_estimate`, `last_estimate`, `estimate_drift`, or `cycle_time`, which is
+//!   the sum of `in_dev`, `in_test` and `waiting`.
+//! * The comparison is one of `>`, `>=`, `<`, `<=`, `==` or `!=`.
+//! * The value is a number, of days for the day columns, optionally followed by a `d`.
+//!
+//! The threshold is *breached* when the comparison is true.
+//!
+//! The report is written as it is produced, so thresholds are checked against [`Observations`],
+//! which keep only the values of the columns that the thresholds refer to and the cycle time,
+//! the headline number of the time in status report.
+use crate::lib::jira::{epics, estimate_accuracy, flow, responsiveness, rollup, times_in_flight};
+use derive_more::Display;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The ways parsing a threshold can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The expression has no comparison operator
+    #[snafu(display("No comparison operator found in threshold '{}'", expression))]
+    MissingComparison {
+        /// The expression
+        expression: String,
+    },
+    /// The metric is not an aggregate of a known column
+    #[snafu(display("Unknown metric '{}' in threshold '{}'", metric, expression))]
+    UnknownMetric {
+        /// The metric
+        metric: String,
+        /// The expression
+        expression: String,
+    },
+    /// The percentile is not a whole number from 1 to 100
+    #[snafu(display("Invalid percentile '{}' in threshold '{}'", percentile, expression))]
+    InvalidPercentile {
+        /// The percentile
+        percentile: String,
+        /// The expression
+        expression: String,
+    },
+    /// The value is not a number of days
+    #[snafu(display("Invalid value '{}' in threshold '{}': {}", value, expression, source))]
+    InvalidValue {
+        /// The value
+        value: String,
+        /// The expression
+        expression: String,
+        /// The error of parsing it
+        source: std::num::ParseFloatError,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Count,
+    Average,
+    Minimum,
+    Maximum,
+    Sum,
+    Percentile(u8),
+}
+
+/// A numeric column of a report, named in thresholds by its name in the csv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// `todo`, the days spent to do, or the number of children to do
+    ToDo,
+    /// `ready`, the days spent ready, or the number of children ready
+    Ready,
+    /// `in_dev`, the days spent in development, or the number of children in development
+    InDev,
+    /// `in_test`, the days spent in test, or the number of children in test
+    InTest,
+    /// `waiting`, the days spent waiting, or the number of children waiting
+    Waiting,
+    /// `completed`, the days spent completed, or the number of children completed
+    Completed,
+    /// `blocked`, or `blocked_days`, the days spent flagged
+    Blocked,
+    /// `first_estimate`, in days
+    FirstEstimate,
+    /// `last_estimate`, in days
+    LastEstimate,
+    /// `estimate_drift`, in days
+    EstimateDrift,
+    /// `cycle_time`, the sum of the days in development, in test and waiting
+    CycleTime,
+    /// `items`, the number of items of an epic
+    Items,
+    /// `completed_items`, the number of completed items of an epic
+    CompletedItems,
+    /// `children`, the number of children of an epic
+    Children,
+    /// `percent_completed`, the percentage of the items of an epic that are completed
+    PercentCompleted,
+    /// `first_estimate_hours`, the first estimate of an issue in hours
+    FirstEstimateHours,
+    /// `logged_hours`, the time logged against an issue in hours
+    LoggedHours,
+    /// `accuracy_ratio`, the time logged over the first estimate
+    AccuracyRatio,
+    /// `comments`, the number of comments of an issue
+    Comments,
+    /// `responses`, the number of comments of an issue that count as a response
+    Responses,
+    /// `hours_to_first_response`
+    HoursToFirstResponse,
+    /// `working_days_to_first_response`
+    WorkingDaysToFirstResponse,
+    /// `average_wip`, the items in progress at the end of each day of a week, averaged
+    AverageWip,
+    /// `throughput`, the number of items completed in a week
+    Throughput,
+    /// `average_cycle_time_days`, the average calendar days to complete the items of a week
+    AverageCycleTime,
+    /// `deviation`, of the work in progress of a week from what Little's Law predicts
+    Deviation,
+}
+
+/// The name of each column in the thresholds and filters
+const COLUMN_NAMES: [(&str, Column); 27] = [
+    ("todo", Column::ToDo),
+    ("ready", Column::Ready),
+    ("in_dev", Column::InDev),
+    ("in_test", Column::InTest),
+    ("waiting", Column::Waiting),
+    ("completed", Column::Completed),
+    ("blocked", Column::Blocked),
+    ("blocked_days", Column::Blocked),
+    ("first_estimate", Column::FirstEstimate),
+    ("last_estimate", Column::LastEstimate),
+    ("estimate_drift", Column::EstimateDrift),
+    ("cycle_time", Column::CycleTime),
+    ("items", Column::Items),
+    ("completed_items", Column::CompletedItems),
+    ("children", Column::Children),
+    ("percent_completed", Column::PercentCompleted),
+    ("first_estimate_hours", Column::FirstEstimateHours),
+    ("logged_hours", Column::LoggedHours),
+    ("accuracy_ratio", Column::AccuracyRatio),
+    ("comments", Column::Comments),
+    ("responses", Column::Responses),
+    ("hours_to_first_response", Column::HoursToFirstResponse),
+    (
+        "working_days_to_first_response",
+        Column::WorkingDaysToFirstResponse,
+    ),
+    ("average_wip", Column::AverageWip),
+    ("throughput", Column::Throughput),
+    ("average_cycle_time_days", Column::AverageCycleTime),
+    ("deviation", Column::Deviation),
+];
+
+/// An entry of a report that thresholds can be checked against
+pub trait Observed {
+    /// The columns of the report, thresholds over any other column are refused
+    const COLUMNS: &'static [Column];
+
+    /// The value of `column` in the entry, when it has one
+    fn value(&self, column: Column) -> Option<f64>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// A single parsed threshold expression
+#[derive(Display, Debug, Clone)]
+#[display(fmt = "{}", expression)]
+pub struct Threshold {
+    expression: String,
+    aggregate: Aggregate,
+    column: Option<Column>,
+    comparison: Comparison,
+    value: f64,
+}
+
+/// A threshold that was breached along with the actual value of the metric
+#[derive(Display, Debug, Clone)]
+#[display(fmt = "{} (actual: {:.2})", threshold, actual)]
+pub struct Breach {
+    /// The threshold that was breached
+    pub threshold: Threshold,
+    /// The value of its metric
+    pub actual: f64,
+}
+
+/// The values, from every entry of a report, needed to check a set of thresholds
+#[derive(Debug)]
+pub struct Observations {
+    entries: usize,
+    values: HashMap<Column, Vec<f64>>,
+}
+
+/// Start observing the values needed by `thresholds`, along with the cycle time
+#[must_use]
+pub fn observations(thresholds: &[Threshold]) -> Observations {
+    Observations {
+        entries: 0,
+        values: thresholds
+            .iter()
+            .filter_map(|threshold| threshold.column)
+            .chain(std::iter::once(Column::CycleTime))
+            .map(|column| (column, Vec::new()))
+            .collect(),
+    }
+}
+
+impl Observations {
+    /// Record the values of a single report entry
+    pub fn observe(&mut self, entry: &impl Observed) {
+        self.entries += 1;
+        for (column, values) in &mut self.values {
+            values.extend(entry.value(*column));
+        }
+    }
+
+    /// Add the values observed in another report of the same thresholds
+    pub fn merge(&mut self, other: Self) {
+        self.entries += other.entries;
+        for (column, values) in other.values {
+            self.values.entry(column).or_default().extend(values);
+        }
+    }
+
+    /// The number of entries observed
+    #[must_use]
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// The given percentile of the cycle time, in days, when any entry was observed
+    #[must_use]
+    pub fn cycle_time_percentile(&self, value: u8) -> Option<f64> {
+        percentile(&self.sorted_values(Column::CycleTime)?, value)
+    }
+
+    fn sorted_values(&self, column: Column) -> Option<Vec<f64>> {
+        let mut values = self.values.get(&column)?.clone();
+        values.sort_by(|left, right| left.partial_cmp(right).unwrap_or(std::cmp::Ordering::Equal));
+        Some(values)
+    }
+}
+
+const COMPARISONS: [(&str, Comparison); 6] = [
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+];
+
+fn parse_aggregate(name: &str, expression: &str) -> Result<Aggregate, Error> {
+    match name {
+        "count" => Ok(Aggregate::Count),
+        "avg" => Ok(Aggregate::Average),
+        "min" => Ok(Aggregate::Minimum),
+        "max" => Ok(Aggregate::Maximum),
+        "sum" => Ok(Aggregate::Sum),
+        percentile if percentile.starts_with('p') => {
+            let parsed = u8::from_str(&percentile[1..]).ok();
+            match parsed {
+                Some(value) if value > 0 && value <= 100 => Ok(Aggregate::Percentile(value)),
+                _ => InvalidPercentile {
+                    percentile,
+                    expression,
+                }
+                .fail(),
+            }
+        }
+        _ => UnknownMetric {
+            metric: name,
+            expression,
+        }
+        .fail(),
+    }
+}
+
+/// The column named `name` among `columns`
+fn find_column(columns: &[Column], name: &str) -> Option<Column> {
+    COLUMN_NAMES
+        .iter()
+        .find(|(column_name, column)| *column_name == name && columns.contains(column))
+        .map(|(_, column)| *column)
+}
+
+/// The day column of the time in status report named `name`
+pub(crate) fn parse_column(name: &str) -> Option<Column> {
+    find_column(times_in_flight::Entry::COLUMNS, name)
+}
+
+fn parse_metric(
+    metric: &str,
+    expression: &str,
+    columns: &[Column],
+) -> Result<(Aggregate, Option<Column>), Error> {
+    if metric == "count" {
+        return Ok((Aggregate::Count, None));
+    }
+
+    let (aggregate, column) = metric.split_at(
+        metric
+            .find('_')
+            .context(UnknownMetric { metric, expression })?,
+    );
+    let column = &column[1..];
+    Ok((
+        parse_aggregate(aggregate, expression)?,
+        Some(find_column(columns, column).context(UnknownMetric {
+            metric: column,
+            expression,
+        })?),
+    ))
+}
+
+/// Split an expression at its first comparison operator into the trimmed left hand side, the
+/// comparison and the trimmed right hand side
+pub(crate) fn split_comparison(expression: &str) -> Option<(&str, Comparison, &str)> {
+    let (position, operator, comparison) = COMPARISONS
+        .iter()
+        .filter_map(|(operator, comparison)| {
+            expression
+                .find(operator)
+                .map(|position| (position, *operator, *comparison))
+        })
+        .min_by_key(|(position, _, _)| *position)?;
+
+    Some((
+        expression[..position].trim(),
+        comparison,
+        expression[position + operator.len()..].trim(),
+    ))
+}
+
+/// Parse a threshold expression like `p85_cycle_time>20d` over the time in status report
+///
+/// # Errors
+///
+/// Fails when the expression has no comparison, names an unknown metric or an invalid percentile,
+/// or its value is not a number.
+pub fn parse(expression: &str) -> Result<Threshold, Error> {
+    parse_for::<times_in_flight::Entry>(expression)
+}
+
+/// Parse a threshold expression over the report with entries `T`, e.g. `max_throughput<2` over
+/// the weeks of the flow metrics
+///
+/// # Errors
+///
+/// Fails when the expression has no comparison, names a metric that is unknown or not a column
+/// of the report or an invalid percentile, or its value is not a number.
+pub fn parse_for<T: Observed>(expression: &str) -> Result<Threshold, Error> {
+    let (metric, comparison, raw_value) =
+        split_comparison(expression).context(MissingComparison { expression })?;
+    let value_str = raw_value.strip_suffix('d').unwrap_or(raw_value).trim();
+    ensure!(!metric.is_empty(), UnknownMetric { metric, expression });
+
+    let (aggregate, column) = parse_metric(metric, expression, T::COLUMNS)?;
+    let value = f64::from_str(value_str).context(InvalidValue {
+        value: raw_value,
+        expression,
+    })?;
+
+    Ok(Threshold {
+        expression: expression.to_owned(),
+        aggregate,
+        column,
+        comparison,
+        value,
+    })
+}
+
+pub(crate) fn column_value(column: Column, entry: &times_in_flight::Entry) -> Option<f64> {
+    match column {
+        Column::ToDo => Some(entry.todo),
+        Column::Ready => Some(entry.ready),
+        Column::InDev => Some(entry.in_dev),
+        Column::InTest => Some(entry.in_test),
+        Column::Waiting => Some(entry.waiting),
+        Column::Completed => Some(entry.completed),
+        Column::Blocked => Some(entry.blocked_days),
+        Column::FirstEstimate => entry.first_estimate,
+        Column::LastEstimate => entry.last_estimate,
+        Column::EstimateDrift => entry.estimate_drift,
+        Column::CycleTime => Some(entry.in_dev + entry.in_test + entry.waiting),
+        _ => None,
+    }
+}
+
+impl Observed for times_in_flight::Entry<'_> {
+    const COLUMNS: &'static [Column] = &[
+        Column::ToDo,
+        Column::Ready,
+        Column::InDev,
+        Column::InTest,
+        Column::Waiting,
+        Column::Completed,
+        Column::Blocked,
+        Column::FirstEstimate,
+        Column::LastEstimate,
+        Column::EstimateDrift,
+        Column::CycleTime,
+    ];
+
+    fn value(&self, column: Column) -> Option<f64> {
+        column_value(column, self)
+    }
+}
+
+impl Observed for rollup::Entry {
+    const COLUMNS: &'static [Column] = &[
+        Column::Items,
+        Column::CompletedItems,
+        Column::PercentCompleted,
+        Column::ToDo,
+        Column::Ready,
+        Column::InDev,
+        Column::InTest,
+        Column::Waiting,
+        Column::Completed,
+        Column::Blocked,
+        Column::CycleTime,
+    ];
+
+    #[allow(clippy::cast_precision_loss)]
+    fn value(&self, column: Column) -> Option<f64> {
+        match column {
+            Column::Items => Some(self.items as f64),
+            Column::CompletedItems => Some(self.completed_items as f64),
+            Column::PercentCompleted => Some(self.percent_completed),
+            Column::ToDo => Some(self.todo),
+            Column::Ready => Some(self.ready),
+            Column::InDev => Some(self.in_dev),
+            Column::InTest => Some(self.in_test),
+            Column::Waiting => Some(self.waiting),
+            Column::Completed => Some(self.completed),
+            Column::Blocked => Some(self.blocked_days),
+            Column::CycleTime => Some(self.in_dev + self.in_test + self.waiting),
+            _ => None,
+        }
+    }
+}
+
+impl Observed for epics::Entry<'_> {
+    const COLUMNS: &'static [Column] = &[
+        Column::Children,
+        Column::ToDo,
+        Column::Ready,
+        Column::InDev,
+        Column::InTest,
+        Column::Waiting,
+        Column::Completed,
+        Column::PercentCompleted,
+    ];
+
+    #[allow(clippy::cast_precision_loss)]
+    fn value(&self, column: Column) -> Option<f64> {
+        let count = match column {
+            Column::Children => self.children,
+            Column::ToDo => self.todo,
+            Column::Ready => self.ready,
+            Column::InDev => self.in_dev,
+            Column::InTest => self.in_test,
+            Column::Waiting => self.waiting,
+            Column::Completed => self.completed,
+            Column::PercentCompleted => return Some(self.percent_completed),
+            _ => return None,
+        };
+        Some(count as f64)
+    }
+}
+
+impl Observed for estimate_accuracy::Entry<'_> {
+    const COLUMNS: &'static [Column] = &[
+        Column::FirstEstimateHours,
+        Column::LoggedHours,
+        Column::AccuracyRatio,
+    ];
+
+    fn value(&self, column: Column) -> Option<f64> {
+        match column {
+            Column::FirstEstimateHours => self.first_estimate_hours,
+            Column::LoggedHours => Some(self.logged_hours),
+            Column::AccuracyRatio => self.accuracy_ratio,
+            _ => None,
+        }
+    }
+}
+
+impl Observed for responsiveness::Entry<'_> {
+    const COLUMNS: &'static [Column] = &[
+        Column::Comments,
+        Column::Responses,
+        Column::HoursToFirstResponse,
+        Column::WorkingDaysToFirstResponse,
+    ];
+
+    #[allow(clippy::cast_precision_loss)]
+    fn value(&self, column: Column) -> Option<f64> {
+        match column {
+            Column::Comments => Some(self.comments as f64),
+            Column::Responses => Some(self.responses as f64),
+            Column::HoursToFirstResponse => self.hours_to_first_response,
+            Column::WorkingDaysToFirstResponse => self.working_days_to_first_response,
+            _ => None,
+        }
+    }
+}
+
+impl Observed for flow::Week {
+    const COLUMNS: &'static [Column] = &[
+        Column::AverageWip,
+        Column::Throughput,
+        Column::AverageCycleTime,
+        Column::Deviation,
+    ];
+
+    #[allow(clippy::cast_precision_loss)]
+    fn value(&self, column: Column) -> Option<f64> {
+        match column {
+            Column::AverageWip => Some(self.average_wip),
+            Column::Throughput => Some(self.throughput as f64),
+            Column::AverageCycleTime => self.average_cycle_time_days,
+            Column::Deviation => self.deviation,
+            _ => None,
+        }
+    }
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn percentile(sorted_values: &[f64], percentile: u8) -> Option<f64> {
+    if sorted_values.is_empty() {
+        return None;
+    }
+    let rank = (f64::from(percentile) / 100.0 * sorted_values.len() as f64).ceil() as usize;
+    sorted_values.get(rank.max(1) - 1).copied()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn aggregate(threshold: &Threshold, observations: &Observations) -> Option<f64> {
+    let column = match threshold.column {
+        Some(column) => column,
+        None => return Some(observations.entries as f64),
+    };
+
+    let values = observations.sorted_values(column)?;
+    if values.is_empty() {
+        return None;
+    }
+
+    match threshold.aggregate {
+        Aggregate::Count => Some(values.len() as f64),
+        Aggregate::Average => Some(values.iter().sum::<f64>() / values.len() as f64),
+        Aggregate::Minimum => values.first().copied(),
+        Aggregate::Maximum => values.last().copied(),
+        Aggregate::Sum => Some(values.iter().sum()),
+        Aggregate::Percentile(value) => percentile(&values, value),
+    }
+}
+
+#[allow(clippy::float_cmp)]
+pub(crate) fn compare(comparison: Comparison, actual: f64, value: f64) -> bool {
+    match comparison {
+        Comparison::Greater => actual > value,
+        Comparison::GreaterOrEqual => actual >= value,
+        Comparison::Less => actual < value,
+        Comparison::LessOrEqual => actual <= value,
+        Comparison::Equal => actual == value,
+        Comparison::NotEqual => actual != value,
+    }
+}
+
+/// Evaluate each threshold against the observed report, returning every threshold that was
+/// breached. A threshold over a column that has no values at all is never breached.
+#[must_use]
+pub fn check(thresholds: &[Threshold], observations: &Observations) -> Vec<Breach> {
+    thresholds
+        .iter()
+        .filter_map(|threshold| {
+            aggregate(threshold, observations)
+                .filter(|actual| compare(threshold.comparison, *actual, threshold.value))
+                .map(|actual| Breach {
+                    threshold: threshold.clone(),
+                    actual,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
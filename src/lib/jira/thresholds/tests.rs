@@ -0,0 +1,224 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Parsing threshold expressions and checking them against the observed entries of a report.
+use super::{
+    check, observations, parse, parse_for, Aggregate, Column, Comparison, Error, Threshold,
+};
+use crate::lib::jira::core::{ItemStatus, Resolution};
+use crate::lib::jira::times_in_flight::Entry;
+use crate::lib::jira::{flow, rollup, test_support};
+
+fn threshold(expression: &str) -> Threshold {
+    parse(expression).expect("a valid threshold")
+}
+
+fn entry(in_dev: f64, in_test: f64, waiting: f64) -> Entry<'static> {
+    Entry {
+        in_dev,
+        in_test,
+        waiting,
+        ..test_support::entry(&ItemStatus::Completed, &Resolution::Delivered)
+    }
+}
+
+#[test]
+fn parses_metrics_comparisons_and_values() {
+    let parsed = threshold("p85_cycle_time>20d");
+    assert_eq!(parsed.aggregate, Aggregate::Percentile(85));
+    assert_eq!(parsed.column, Some(Column::CycleTime));
+    assert_eq!(parsed.comparison, Comparison::Greater);
+    assert!((parsed.value - 20.0).abs() < f64::EPSILON);
+    assert_eq!(parsed.to_string(), "p85_cycle_time>20d");
+
+    let parsed = threshold("count<=3");
+    assert_eq!(parsed.aggregate, Aggregate::Count);
+    assert_eq!(parsed.column, None);
+    assert_eq!(parsed.comparison, Comparison::LessOrEqual);
+
+    let parsed = threshold(" avg_in_dev >= 1.5 d");
+    assert_eq!(parsed.aggregate, Aggregate::Average);
+    assert_eq!(parsed.column, Some(Column::InDev));
+    assert_eq!(parsed.comparison, Comparison::GreaterOrEqual);
+    assert!((parsed.value - 1.5).abs() < f64::EPSILON);
+
+    for (expression, aggregate, column, comparison) in &[
+        (
+            "min_todo<1",
+            Aggregate::Minimum,
+            Column::ToDo,
+            Comparison::Less,
+        ),
+        (
+            "max_blocked_days!=0",
+            Aggregate::Maximum,
+            Column::Blocked,
+            Comparison::NotEqual,
+        ),
+        (
+            "sum_waiting==2d",
+            Aggregate::Sum,
+            Column::Waiting,
+            Comparison::Equal,
+        ),
+        (
+            "count_last_estimate>0",
+            Aggregate::Count,
+            Column::LastEstimate,
+            Comparison::Greater,
+        ),
+        (
+            "p100_estimate_drift>=3",
+            Aggregate::Percentile(100),
+            Column::EstimateDrift,
+            Comparison::GreaterOrEqual,
+        ),
+    ] {
+        let parsed = threshold(expression);
+        assert_eq!(parsed.aggregate, *aggregate, "{}", expression);
+        assert_eq!(parsed.column, Some(*column), "{}", expression);
+        assert_eq!(parsed.comparison, *comparison, "{}", expression);
+    }
+}
+
+#[test]
+fn rejects_malformed_thresholds() {
+    for expression in &["p85_cycle_time", "count 20", ""] {
+        assert!(
+            matches!(parse(expression), Err(Error::MissingComparison { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &[
+        "median_cycle_time>1",
+        "p85_lead_time>1",
+        "avg>1",
+        ">5",
+        "total>1",
+    ] {
+        assert!(
+            matches!(parse(expression), Err(Error::UnknownMetric { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &["p0_cycle_time>1", "p101_cycle_time>1", "pxx_cycle_time>1"] {
+        assert!(
+            matches!(parse(expression), Err(Error::InvalidPercentile { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+    for expression in &["count>five", "count>", "count>1w"] {
+        assert!(
+            matches!(parse(expression), Err(Error::InvalidValue { .. })),
+            "{} was accepted",
+            expression
+        );
+    }
+}
+
+#[test]
+fn reports_the_breached_thresholds() {
+    let thresholds = [
+        threshold("count>2"),
+        threshold("p85_cycle_time>8"),
+        threshold("max_in_dev<5"),
+        threshold("avg_cycle_time>10"),
+        threshold("max_first_estimate>0"),
+    ];
+    let mut observed = observations(&thresholds);
+    for days in 1..=10 {
+        observed.observe(&entry(f64::from(days), 0.0, 0.0));
+    }
+
+    let breached: Vec<_> = check(&thresholds, &observed)
+        .iter()
+        .map(|breach| (breach.threshold.to_string(), breach.actual))
+        .collect();
+    // The 85th percentile of ten values is the ninth by nearest rank, no entry has an estimate
+    assert_eq!(
+        breached,
+        vec![
+            ("count>2".to_owned(), 10.0),
+            ("p85_cycle_time>8".to_owned(), 9.0)
+        ]
+    );
+}
+
+#[test]
+fn observes_the_cycle_time_across_reports() {
+    let mut observed = observations(&[]);
+    assert_eq!(observed.cycle_time_percentile(85), None);
+    observed.observe(&entry(1.0, 1.0, 1.0));
+
+    let mut other = observations(&[]);
+    other.observe(&entry(4.0, 0.0, 0.0));
+    other.observe(&entry(2.0, 0.0, 0.0));
+    observed.merge(other);
+
+    assert_eq!(observed.entries(), 3);
+    assert_eq!(observed.cycle_time_percentile(50), Some(3.0));
+    assert_eq!(observed.cycle_time_percentile(85), Some(4.0));
+}
+
+#[test]
+fn only_accepts_the_columns_of_the_report() {
+    let parsed = parse_for::<flow::Week>("max_throughput<2").expect("a valid threshold");
+    assert_eq!(parsed.column, Some(Column::Throughput));
+    let parsed = parse_for::<rollup::Entry>("p85_blocked_days>10d").expect("a valid threshold");
+    assert_eq!(parsed.column, Some(Column::Blocked));
+
+    assert!(matches!(
+        parse("max_throughput<2"),
+        Err(Error::UnknownMetric { .. })
+    ));
+    assert!(matches!(
+        parse_for::<flow::Week>("p85_cycle_time>20d"),
+        Err(Error::UnknownMetric { .. })
+    ));
+}
+
+#[test]
+fn checks_the_entries_of_other_reports() {
+    let epic = |blocked_days: f64| rollup::Entry {
+        epic: "LEC-1".to_owned(),
+        url: None,
+        description: None,
+        items: 2,
+        completed_items: 1,
+        percent_completed: 50.0,
+        todo: 0.0,
+        ready: 0.0,
+        in_dev: 1.0,
+        in_test: 1.0,
+        waiting: 0.0,
+        completed: 0.0,
+        blocked_days,
+    };
+    let thresholds = [
+        parse_for::<rollup::Entry>("max_blocked_days>5").expect("a valid threshold"),
+        parse_for::<rollup::Entry>("min_percent_completed<50").expect("a valid threshold"),
+    ];
+    let mut observed = observations(&thresholds);
+    observed.observe(&epic(1.0));
+    observed.observe(&epic(6.0));
+
+    let breached: Vec<_> = check(&thresholds, &observed)
+        .iter()
+        .map(|breach| (breach.threshold.to_string(), breach.actual))
+        .collect();
+    assert_eq!(breached, vec![("max_blocked_days>5".to_owned(), 6.0)]);
+}
@@ -0,0 +1,222 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Time In Status
+//!
+//! The time in status report, as run by the `time-in-status-wip` command and by programs that
+//! embed lectev. The items are pulled from a [`Tracker`] a page at a time, and the entries of each
+//! page are returned rather than written out. Only the items and entries picked by the
+//! [`Options`] are reported on.
+use crate::configs::jira;
+use crate::lib::calendar::Calendar;
+use crate::lib::jira::{core, filters, times_in_flight};
+use crate::lib::tracker::{Error, Tracker};
+use chrono::NaiveDate;
+use futures::stream::{BoxStream, TryStreamExt};
+use std::fmt;
+use url::Url;
+
+/// Selects the items to report on by their labels, components and resolutions. Each list that is
+/// empty selects every item.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Only the items with one of these labels
+    pub include_labels: Vec<String>,
+    /// Leave out the items with any of these labels
+    pub exclude_labels: Vec<String>,
+    /// Only the items in one of these components
+    pub components: Vec<String>,
+    /// Only the items with one of these resolutions
+    pub include_resolutions: Vec<core::Resolution>,
+    /// Leave out the items with any of these resolutions
+    pub exclude_resolutions: Vec<core::Resolution>,
+}
+
+impl Selection {
+    /// Whether the item is reported on
+    #[must_use]
+    pub fn selects(&self, item: &core::Item) -> bool {
+        let has_any = |wanted: &[String], values: &[String]| {
+            wanted.iter().any(|value| values.contains(value))
+        };
+        (self.include_labels.is_empty() || has_any(&self.include_labels, &item.labels))
+            && !has_any(&self.exclude_labels, &item.labels)
+            && (self.components.is_empty() || has_any(&self.components, &item.components))
+            && (self.include_resolutions.is_empty()
+                || self.include_resolutions.contains(&item.resolution))
+            && !self.exclude_resolutions.contains(&item.resolution)
+    }
+}
+
+/// What is reported on and the period the time in each status is counted over. The default
+/// reports on every item over all of its time.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Which items are reported on
+    pub selection: Selection,
+    /// Only the entries matching every one of them are reported
+    pub filters: Vec<filters::Filter>,
+    /// With either date only the entries resolved on or after `resolved_after`, and before
+    /// `resolved_before`, are reported
+    pub resolved_after: Option<NaiveDate>,
+    /// See `resolved_after`
+    pub resolved_before: Option<NaiveDate>,
+    /// The time in each status is only counted from the start of `since` up to the start of
+    /// `until`, see [`times_in_flight::Window`]
+    pub since: Option<NaiveDate>,
+    /// See `since`
+    pub until: Option<NaiveDate>,
+    /// Keep the body of each item in its entry
+    pub include_body: bool,
+}
+
+/// How the entries of the report are calculated from its items
+#[derive(Debug)]
+pub struct Report {
+    instance_url: Url,
+    calendar: Calendar,
+    window: times_in_flight::Window,
+    options: Options,
+}
+
+impl Report {
+    /// The days are counted with the working week and timezone of the `conf`
+    #[must_use]
+    pub fn new(conf: &jira::Config, options: Options) -> Self {
+        let calendar = Calendar::new(conf.working_week.as_ref(), conf.reporting_timezone.as_ref());
+        Report {
+            instance_url: conf.jira_instance.clone(),
+            window: times_in_flight::Window::new(&calendar, options.since, options.until),
+            calendar,
+            options,
+        }
+    }
+
+    /// The calendar the days are counted with
+    #[must_use]
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    /// Without a window every entry is reported. With one, only entries resolved inside of it are.
+    fn is_in_resolution_window(&self, entry: &times_in_flight::Entry) -> bool {
+        let (after, before) = (self.options.resolved_after, self.options.resolved_before);
+        if after.is_none() && before.is_none() {
+            return true;
+        }
+        match entry.resolved.map(|resolved| self.calendar.date(&resolved)) {
+            Some(resolved) => {
+                after.map_or(true, |after| resolved >= after)
+                    && before.map_or(true, |before| resolved < before)
+            }
+            None => false,
+        }
+    }
+
+    /// The entries of the selected `items` that are reported, each along with its item
+    #[must_use]
+    pub fn entries<'a>(
+        &self,
+        items: &'a [core::Item],
+    ) -> Vec<(times_in_flight::Entry<'a>, &'a core::Item)> {
+        times_in_flight::calculate_in_window(
+            &self.instance_url,
+            &self.calendar,
+            &self.window,
+            items,
+        )
+        .into_iter()
+        .zip(items)
+        .filter(|(_, item)| self.options.selection.selects(item))
+        .map(|(mut entry, item)| {
+            if !self.options.include_body {
+                entry.body = None;
+            }
+            (entry, item)
+        })
+        .filter(|(entry, _)| {
+            self.is_in_resolution_window(entry)
+                && filters::matches_all(&self.options.filters, entry)
+        })
+        .collect()
+    }
+}
+
+/// The time in status report on the items matching a query, pulled a page at a time
+pub struct TimeInStatus<'a> {
+    report: Report,
+    pages: BoxStream<'a, Result<Vec<core::Item>, Error>>,
+}
+
+impl fmt::Debug for TimeInStatus<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeInStatus")
+            .field("report", &self.report)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A page of the items of the report
+#[derive(Debug)]
+pub struct Page<'a> {
+    report: &'a Report,
+    items: Vec<core::Item>,
+}
+
+impl Page<'_> {
+    /// The items of the page, including those that are not reported
+    #[must_use]
+    pub fn items(&self) -> &[core::Item] {
+        &self.items
+    }
+
+    /// The entries of the page that are reported, see [`Report::entries`]
+    #[must_use]
+    pub fn entries(&self) -> Vec<(times_in_flight::Entry<'_>, &core::Item)> {
+        self.report.entries(&self.items)
+    }
+}
+
+impl TimeInStatus<'_> {
+    /// The next page of the report, `None` once every item has been pulled. The errors are those
+    /// of the tracker, e.g. [`Error::Incomplete`] once the items that could be pulled have been.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the errors of the tracker.
+    pub async fn next_page(&mut self) -> Result<Option<Page<'_>>, Error> {
+        let items = self.pages.try_next().await?;
+        Ok(items.map(move |items| Page {
+            report: &self.report,
+            items,
+        }))
+    }
+}
+
+/// Run the time in status report on the items `tracker` has for `query`, with the working week
+/// and timezone of the `conf`, as the command does
+pub fn time_in_status<'a>(
+    conf: &jira::Config,
+    tracker: &'a dyn Tracker,
+    query: &'a str,
+    options: Options,
+) -> TimeInStatus<'a> {
+    TimeInStatus {
+        report: Report::new(conf, options),
+        pages: tracker.items(query),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,138 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Runs the report the way an embedding program would, on the issues of the `statuses`
+//! translation fixture.
+use super::{time_in_status, Options, Selection};
+use crate::configs::jira;
+use crate::lib::jira::nativetocore::UnmappedStatus;
+use crate::lib::jira::test_support::{fixture, fixture_config};
+use crate::lib::jira::tracker::DumpTracker;
+use crate::lib::jira::{core, filters};
+use crate::lib::tracker::InMemory;
+use chrono::NaiveDate;
+use std::path::PathBuf;
+
+fn config() -> jira::Config {
+    fixture_config("statuses")
+}
+
+/// The issues of the fixture
+fn issues() -> PathBuf {
+    fixture("statuses").join("issues.json")
+}
+
+/// The names of the entries reported on the issues of the fixture
+async fn reported(options: Options) -> Vec<String> {
+    let conf = config();
+    let dump = DumpTracker::new(&conf, issues(), UnmappedStatus::Fail);
+    let mut report = time_in_status(&conf, &dump, "", options);
+    let mut names = Vec::new();
+    while let Some(page) = report.next_page().await.expect("page pulled") {
+        names.extend(
+            page.entries()
+                .iter()
+                .map(|(entry, _)| entry.name.to_owned()),
+        );
+    }
+    names
+}
+
+#[tokio::test]
+async fn reports_on_a_dump() {
+    let conf = config();
+    let dump = DumpTracker::new(&conf, issues(), UnmappedStatus::Fail);
+    let mut report = time_in_status(&conf, &dump, "", Options::default());
+    let page = report
+        .next_page()
+        .await
+        .expect("page pulled")
+        .expect("a page");
+
+    let entries = page.entries();
+    let names: Vec<&str> = entries.iter().map(|(entry, _)| entry.name).collect();
+    assert_eq!(names, ["FIX-1", "FIX-2"]);
+    assert_eq!(entries[0].0.status, &core::ItemStatus::Completed);
+    assert_eq!(entries[0].0.url, "https://jira.example.com/browse/FIX-1");
+    assert_eq!(entries[0].0.body, None);
+}
+
+#[tokio::test]
+async fn reports_on_items_in_memory() {
+    let conf = config();
+    let dump = DumpTracker::new(&conf, issues(), UnmappedStatus::Fail);
+    let mut pulled = time_in_status(&conf, &dump, "", Options::default());
+    let items = pulled
+        .next_page()
+        .await
+        .expect("page pulled")
+        .expect("a page")
+        .items()
+        .to_vec();
+
+    let in_memory = InMemory::new(items);
+    let mut report = time_in_status(&conf, &in_memory, "", Options::default());
+    let page = report
+        .next_page()
+        .await
+        .expect("page pulled")
+        .expect("a page");
+    assert_eq!(page.entries().len(), 2);
+    assert!(report.next_page().await.expect("no error").is_none());
+}
+
+#[tokio::test]
+async fn only_reports_on_the_selected_items() {
+    let options = Options {
+        selection: Selection {
+            exclude_resolutions: vec![core::Resolution::Delivered],
+            ..Selection::default()
+        },
+        ..Options::default()
+    };
+    assert_eq!(reported(options).await, ["FIX-2"]);
+
+    let options = Options {
+        selection: Selection {
+            include_labels: vec!["web".to_owned()],
+            ..Selection::default()
+        },
+        ..Options::default()
+    };
+    assert_eq!(reported(options).await, ["FIX-1"]);
+}
+
+#[tokio::test]
+async fn only_reports_on_items_resolved_in_the_window() {
+    let options = Options {
+        resolved_after: Some(NaiveDate::from_ymd(2024, 3, 1)),
+        ..Options::default()
+    };
+    assert_eq!(reported(options).await, ["FIX-1"]);
+
+    let options = Options {
+        resolved_before: Some(NaiveDate::from_ymd(2024, 3, 12)),
+        ..Options::default()
+    };
+    assert!(reported(options).await.is_empty());
+}
+
+#[tokio::test]
+async fn only_reports_on_entries_matching_the_filters() {
+    let options = Options {
+        filters: vec![filters::parse("status==Waiting").expect("valid filter")],
+        ..Options::default()
+    };
+    assert_eq!(reported(options).await, ["FIX-2"]);
+}
@@ -12,13 +12,19 @@
 //
 //  You should have received a copy of the GNU General Public License
 //  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Times In Flight
+//!
+//! The business days each item spent in each status, the rows of the time in status report, along
+//! with the timeline export and the averages per assignee or team.
+use crate::lib::calendar::Calendar;
 use crate::lib::jira::core;
-use bdays::HolidayCalendar;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
 use tracing::instrument;
 use uom::si::f64::Time;
-use uom::si::time::day;
+use uom::si::time::{day, second};
 use url::Url;
 
 #[derive(Debug, Serialize)]
@@ -30,33 +36,348 @@ struct WorkingEntry<'a> {
     in_test: Time,
     waiting: Time,
     completed: Time,
-    oldest_estimate: Option<Time>,
+    blocked: Time,
+    estimates: Estimates,
+    assignee_changes: usize,
+    assignees: BTreeSet<&'a str>,
+    backward_transitions: usize,
+    reopened_count: usize,
 }
 
+/// A row of the time in status report, the business days an item spent in each status along with
+/// what else is known about it
 #[derive(Debug, Serialize)]
 pub struct Entry<'a> {
+    /// The url of the item in the tracker
     pub url: String,
+    /// The name of the item, e.g. the key of a jira issue
     pub name: &'a str,
+    /// The summary of the item
     pub description: &'a str,
+    /// Business days spent in `ToDo`
     pub todo: f64,
+    /// Business days spent in `Ready`
     pub ready: f64,
+    /// Business days spent in `InDev`
     pub in_dev: f64,
+    /// Business days spent in `InTest`
     pub in_test: f64,
+    /// Business days spent in `Waiting`
     pub waiting: f64,
+    /// Business days spent in `Completed`
     pub completed: f64,
+    /// Business days the item was flagged as impeded
+    pub blocked_days: f64,
+    /// The first estimate given, in days
     pub first_estimate: Option<f64>,
+    /// The estimate as it was last changed to
+    pub last_estimate: Option<f64>,
+    /// The times the estimate was changed after it was first given
+    pub estimate_changes: usize,
+    /// How far the estimate moved from the first to the last, `last_estimate - first_estimate`
+    pub estimate_drift: Option<f64>,
+    /// The current status of the item
     pub status: &'a core::ItemStatus,
+    /// How the item was resolved, if it was
     pub resolution: &'a core::Resolution,
+    /// Who the item is assigned to
+    pub assignee: Option<&'a str>,
+    /// The team that owns the item
+    pub team: Option<&'a str>,
+    /// When the item was created
+    pub created: Option<DateTime<Utc>>,
+    /// When the item was resolved
+    pub resolved: Option<DateTime<Utc>>,
+    /// The calendar days, not business days, from creation to resolution
+    pub calendar_lead_time_days: Option<f64>,
+    /// The key of the project the item belongs to
+    pub project: Option<&'a str>,
+    /// The labels, separated by `;`
+    pub labels: String,
+    /// The components, separated by `;`
+    pub components: String,
+    /// Every change of assignee, including the first assignment and unassigning the item
+    pub assignee_changes: usize,
+    /// The people the item has been assigned to at any time
+    pub distinct_assignees: usize,
+    /// The moves to an earlier status in the flow, e.g. from `InTest` back to `InDev`. Time
+    /// spent waiting in between is ignored, so `InTest`, `Waiting`, `InDev` is one move back.
+    pub backward_transitions: usize,
+    /// The moves out of `Completed` to any other status
+    pub reopened_count: usize,
+    /// The full description of the item, when the report includes it
+    pub body: Option<&'a str>,
 }
 
-#[instrument]
-fn get_business_days(start: &DateTime<Utc>, end: &DateTime<Utc>) -> Time {
-    let cal = bdays::calendars::us::USSettlement;
-    Time::new::<day>(f64::from(cal.bdays(*start, *end)))
+/// A single row of the long layout of the report, the days an item spent in one status
+#[derive(Debug, Serialize)]
+pub struct LongEntry<'a> {
+    /// The name of the item
+    pub issue: &'a str,
+    /// The status
+    pub status: core::ItemStatus,
+    /// Business days spent in the status
+    pub business_days: f64,
+}
+
+impl<'a> Entry<'a> {
+    /// The entry as one row per status, for tools that would rather pivot the data themselves
+    #[must_use]
+    pub fn long_entries(&self) -> [LongEntry<'a>; 6] {
+        let row = |status, business_days| LongEntry {
+            issue: self.name,
+            status,
+            business_days,
+        };
+        [
+            row(core::ItemStatus::ToDo, self.todo),
+            row(core::ItemStatus::Ready, self.ready),
+            row(core::ItemStatus::InDev, self.in_dev),
+            row(core::ItemStatus::InTest, self.in_test),
+            row(core::ItemStatus::Waiting, self.waiting),
+            row(core::ItemStatus::Completed, self.completed),
+        ]
+    }
+}
+
+/// Whether an item is still in a status
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineState {
+    /// The item is still in the status
+    Open,
+    /// The item has left the status
+    Closed,
+}
+
+/// A single row of the timeline export, when an item entered and left a status
+#[derive(Debug, Serialize)]
+pub struct TimelineEntry<'a> {
+    /// The name of the item
+    pub issue: &'a str,
+    /// The status
+    pub status: &'a core::ItemStatus,
+    /// When the item entered the status
+    pub start: DateTime<Utc>,
+    /// Empty while the item is still in the status
+    pub end: Option<DateTime<Utc>>,
+    /// Whether the item is still in the status
+    pub state: TimelineState,
+}
+
+/// The statuses of the timeline of an item, in the order they were entered
+pub fn timeline_entries(item: &core::Item) -> impl Iterator<Item = TimelineEntry<'_>> {
+    item.timeline
+        .iter()
+        .filter_map(move |timeline_entry| match timeline_entry {
+            core::ItemTimeLineEntry::ClosedStatus { status, start, end } => Some(TimelineEntry {
+                issue: &item.name,
+                status,
+                start: *start,
+                end: Some(*end),
+                state: TimelineState::Closed,
+            }),
+            core::ItemTimeLineEntry::OpenStatus { status, start } => Some(TimelineEntry {
+                issue: &item.name,
+                status,
+                start: *start,
+                end: None,
+                state: TimelineState::Open,
+            }),
+            _ => None,
+        })
+}
+
+/// The people dimension the report can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// Group by who the items are assigned to
+    Assignee,
+    /// Group by the team that owns the items
+    Team,
+}
+
+/// The values accepted for [`GroupBy`] on the command line
+pub const GROUP_BY: [&str; 2] = ["assignee", "team"];
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "assignee" => Ok(GroupBy::Assignee),
+            "team" => Ok(GroupBy::Team),
+            _ => Err(format!("Unknown group '{}'", value)),
+        }
+    }
+}
+
+impl GroupBy {
+    /// The name of the group, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupBy::Assignee => "assignee",
+            GroupBy::Team => "team",
+        }
+    }
+}
+
+/// The group used for entries that have no assignee or team
+const UNGROUPED: &str = "(none)";
+
+/// The average days spent in each status by the items of a single group
+#[derive(Debug, Serialize)]
+pub struct GroupEntry {
+    /// The assignee or team, `(none)` for the items that have neither
+    pub group: String,
+    /// The number of items in the group
+    pub items: usize,
+    /// Average business days spent in `ToDo`
+    pub todo: f64,
+    /// Average business days spent in `Ready`
+    pub ready: f64,
+    /// Average business days spent in `InDev`
+    pub in_dev: f64,
+    /// Average business days spent in `InTest`
+    pub in_test: f64,
+    /// Average business days spent in `Waiting`
+    pub waiting: f64,
+    /// Average business days spent in `Completed`
+    pub completed: f64,
+    /// Average business days the items were flagged as impeded
+    pub blocked_days: f64,
+}
+
+#[derive(Debug, Default)]
+struct GroupTotals {
+    items: usize,
+    todo: f64,
+    ready: f64,
+    in_dev: f64,
+    in_test: f64,
+    waiting: f64,
+    completed: f64,
+    blocked_days: f64,
+}
+
+/// Accumulates the entries of a report into per group totals
+#[derive(Debug)]
+pub struct Groups {
+    group_by: GroupBy,
+    totals: BTreeMap<String, GroupTotals>,
+}
+
+/// Start grouping entries by `group_by`
+#[must_use]
+pub fn groups(group_by: GroupBy) -> Groups {
+    Groups {
+        group_by,
+        totals: BTreeMap::new(),
+    }
+}
+
+impl Groups {
+    /// The dimension the entries are grouped by
+    #[must_use]
+    pub fn group_by(&self) -> GroupBy {
+        self.group_by
+    }
+
+    /// Add a single report entry to its group
+    pub fn add(&mut self, entry: &Entry) {
+        let group = match self.group_by {
+            GroupBy::Assignee => entry.assignee,
+            GroupBy::Team => entry.team,
+        };
+        let totals = self
+            .totals
+            .entry(group.unwrap_or(UNGROUPED).to_owned())
+            .or_default();
+        totals.items += 1;
+        totals.todo += entry.todo;
+        totals.ready += entry.ready;
+        totals.in_dev += entry.in_dev;
+        totals.in_test += entry.in_test;
+        totals.waiting += entry.waiting;
+        totals.completed += entry.completed;
+        totals.blocked_days += entry.blocked_days;
+    }
+
+    /// The averages of every group, ordered by group name
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn averages(&self) -> Vec<GroupEntry> {
+        self.totals
+            .iter()
+            .map(|(group, totals)| {
+                let items = totals.items as f64;
+                GroupEntry {
+                    group: group.clone(),
+                    items: totals.items,
+                    todo: totals.todo / items,
+                    ready: totals.ready / items,
+                    in_dev: totals.in_dev / items,
+                    in_test: totals.in_test / items,
+                    waiting: totals.waiting / items,
+                    completed: totals.completed / items,
+                    blocked_days: totals.blocked_days / items,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The period the time in each status is counted over, e.g. a quarter. Time outside of it is
+/// left out, so an interval that is partly inside of it only counts the part that is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Window {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl Window {
+    /// The window from the start of `since` up to the start of `until`, the days starting in the
+    /// timezone of the `calendar`. Either end may be open.
+    #[must_use]
+    pub fn new(calendar: &Calendar, since: Option<NaiveDate>, until: Option<NaiveDate>) -> Self {
+        Window {
+            since: since.map(|since| calendar.start_of(since)),
+            until: until.map(|until| calendar.start_of(until)),
+        }
+    }
+
+    /// The part of the interval from `start` to `end` inside of the window, `None` when it is
+    /// entirely outside of it
+    fn clip(
+        &self,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.since.is_none() && self.until.is_none() {
+            return Some((*start, *end));
+        }
+        let start = self.since.map_or(*start, |since| since.max(*start));
+        let end = self.until.map_or(*end, |until| until.min(*end));
+        Some((start, end)).filter(|(start, end)| start < end)
+    }
+
+    /// The working time between `start` and `end` that is inside of the window
+    fn working_time(
+        &self,
+        calendar: &Calendar,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Time {
+        self.clip(start, end)
+            .map_or(Time::new::<day>(0.0), |(start, end)| {
+                calendar.working_time(&start, &end)
+            })
+    }
 }
 
 #[instrument]
-fn set_days(entry: &mut WorkingEntry, status: &core::ItemStatus, days: Time) {
+fn set_days(entry: &mut WorkingEntry, status: core::ItemStatus, days: Time) {
     match status {
         core::ItemStatus::ToDo => entry.todo += days,
         core::ItemStatus::Ready => entry.ready += days,
@@ -67,35 +388,67 @@ fn set_days(entry: &mut WorkingEntry, status: &core::ItemStatus, days: Time) {
     }
 }
 
-#[instrument]
-fn get_latest_estimate(
-    old: Option<core::ItemTimeLineEntry>,
-    new: &core::ItemTimeLineEntry,
-) -> Option<core::ItemTimeLineEntry> {
-    match (&old, new) {
-        (
-            Some(core::ItemTimeLineEntry::Estimate {
-                start: old_start, ..
-            }),
-            core::ItemTimeLineEntry::Estimate {
-                start: new_start, ..
-            },
-        ) if old_start > new_start => Some(new.clone()),
-        (
-            Some(core::ItemTimeLineEntry::Estimate {
-                start: old_start, ..
-            }),
-            core::ItemTimeLineEntry::Estimate {
-                start: new_start, ..
-            },
-        ) if old_start < new_start => old,
-        (None, _) => Some(new.clone()),
-        _ => old,
+/// Count the move from the status ranked `last_rank` to `status` if it goes backwards. Returns
+/// the rank to compare the next status with.
+fn count_transition(
+    entry: &mut WorkingEntry,
+    last_rank: Option<u8>,
+    status: core::ItemStatus,
+) -> Option<u8> {
+    let rank = match status.rank() {
+        Some(rank) => rank,
+        None => return last_rank,
+    };
+    if let Some(last) = last_rank.filter(|last| rank < *last) {
+        entry.backward_transitions += 1;
+        if core::ItemStatus::Completed.rank() == Some(last) {
+            entry.reopened_count += 1;
+        }
+    }
+    Some(rank)
+}
+
+/// The estimates given to an item, from the first to the last
+#[derive(Debug, Default, Serialize)]
+struct Estimates {
+    first: Option<(DateTime<Utc>, Time)>,
+    last: Option<(DateTime<Utc>, Time)>,
+    count: usize,
+}
+
+impl Estimates {
+    /// Record an estimate. The timeline is not necessarily in order, so the first and last
+    /// estimates are those that were given earliest and latest.
+    fn add(&mut self, start: DateTime<Utc>, days: Time) {
+        self.count += 1;
+        if self.first.map_or(true, |(first, _)| start < first) {
+            self.first = Some((start, days));
+        }
+        if self.last.map_or(true, |(last, _)| start >= last) {
+            self.last = Some((start, days));
+        }
+    }
+
+    fn first(&self) -> Option<Time> {
+        self.first.map(|(_, days)| days)
+    }
+
+    fn last(&self) -> Option<Time> {
+        self.last.map(|(_, days)| days)
+    }
+
+    /// The times the estimate was changed after the first
+    fn changes(&self) -> usize {
+        self.count.saturating_sub(1)
     }
 }
 
 #[instrument]
-fn calculate_time_in_flight<'a>(item: &'a core::Item) -> WorkingEntry<'a> {
+fn calculate_time_in_flight<'a>(
+    calendar: &Calendar,
+    window: &Window,
+    item: &'a core::Item,
+) -> WorkingEntry<'a> {
     let mut entry = WorkingEntry {
         item,
         todo: Time::new::<day>(0.0),
@@ -104,38 +457,64 @@ fn calculate_time_in_flight<'a>(item: &'a core::Item) -> WorkingEntry<'a> {
         in_test: Time::new::<day>(0.0),
         waiting: Time::new::<day>(0.0),
         completed: Time::new::<day>(0.0),
-        oldest_estimate: None,
+        blocked: Time::new::<day>(0.0),
+        estimates: Estimates::default(),
+        assignee_changes: 0,
+        assignees: item.assignee.as_deref().into_iter().collect(),
+        backward_transitions: 0,
+        reopened_count: 0,
     };
 
     let now = Utc::now();
-    let mut oldest_estimate = None;
+    let mut last_rank = None;
 
     for timeline_entry in &item.timeline {
         match timeline_entry {
             core::ItemTimeLineEntry::OpenStatus { status, start } => {
-                set_days(&mut entry, status, get_business_days(start, &now));
+                last_rank = count_transition(&mut entry, last_rank, *status);
+                set_days(
+                    &mut entry,
+                    *status,
+                    window.working_time(calendar, start, &now),
+                );
             }
 
             core::ItemTimeLineEntry::ClosedStatus { status, start, end } => {
-                set_days(&mut entry, status, get_business_days(start, end));
+                last_rank = count_transition(&mut entry, last_rank, *status);
+                set_days(
+                    &mut entry,
+                    *status,
+                    window.working_time(calendar, start, end),
+                );
+            }
+
+            core::ItemTimeLineEntry::Estimate { start, days } => {
+                entry.estimates.add(*start, *days);
             }
 
-            new_estimate @ core::ItemTimeLineEntry::Estimate { .. } => {
-                oldest_estimate = get_latest_estimate(oldest_estimate, new_estimate);
+            core::ItemTimeLineEntry::Blocked { start, end } => {
+                entry.blocked += window.working_time(calendar, start, end.as_ref().unwrap_or(&now));
+            }
+
+            core::ItemTimeLineEntry::Worklog { .. }
+            | core::ItemTimeLineEntry::Transitioned { .. } => {}
+
+            core::ItemTimeLineEntry::Reassigned { from, to, .. } => {
+                entry.assignee_changes += 1;
+                entry
+                    .assignees
+                    .extend(from.as_deref().into_iter().chain(to.as_deref()));
             }
         }
     }
-    entry.oldest_estimate = oldest_estimate.and_then(|estimate| {
-        if let core::ItemTimeLineEntry::Estimate { days, .. } = estimate {
-            Some(days)
-        } else {
-            None
-        }
-    });
-
     entry
 }
 
+#[allow(clippy::cast_precision_loss)]
+fn calendar_days(start: &DateTime<Utc>, end: &DateTime<Utc>) -> f64 {
+    Time::new::<second>((*end - *start).num_seconds() as f64).get::<day>()
+}
+
 #[instrument]
 fn prepare_for_display<'a>(base_url: &Url, entry: WorkingEntry<'a>) -> Entry<'a> {
     let url = format!("{}browse/{}", base_url.as_str(), &entry.item.name);
@@ -150,17 +529,65 @@ fn prepare_for_display<'a>(base_url: &Url, entry: WorkingEntry<'a>) -> Entry<'a>
         in_test: entry.in_test.get::<day>(),
         waiting: entry.waiting.get::<day>(),
         completed: entry.completed.get::<day>(),
-        first_estimate: entry.oldest_estimate.map(|estimate| estimate.get::<day>()),
+        blocked_days: entry.blocked.get::<day>(),
+        first_estimate: entry
+            .estimates
+            .first()
+            .map(|estimate| estimate.get::<day>()),
+        last_estimate: entry.estimates.last().map(|estimate| estimate.get::<day>()),
+        estimate_changes: entry.estimates.changes(),
+        estimate_drift: entry
+            .estimates
+            .first()
+            .zip(entry.estimates.last())
+            .map(|(first, last)| (last - first).get::<day>()),
         status: &entry.item.status,
         resolution: &entry.item.resolution,
+        assignee: entry.item.assignee.as_deref(),
+        team: entry.item.team.as_deref(),
+        created: entry.item.created,
+        resolved: entry.item.resolved,
+        calendar_lead_time_days: entry
+            .item
+            .created
+            .as_ref()
+            .zip(entry.item.resolved.as_ref())
+            .map(|(created, resolved)| calendar_days(created, resolved)),
+        project: entry.item.project.as_deref(),
+        labels: entry.item.labels.join(";"),
+        components: entry.item.components.join(";"),
+        assignee_changes: entry.assignee_changes,
+        distinct_assignees: entry.assignees.len(),
+        backward_transitions: entry.backward_transitions,
+        reopened_count: entry.reopened_count,
+        body: entry.item.body.as_deref(),
     }
 }
 
+/// Calculate an entry for each item, counting the business days of the `calendar`
 #[instrument]
-pub fn calculate<'a>(instance_url: &Url, items: &'a [core::Item]) -> Vec<Entry<'a>> {
+pub fn calculate<'a>(
+    instance_url: &Url,
+    calendar: &Calendar,
+    items: &'a [core::Item],
+) -> Vec<Entry<'a>> {
+    calculate_in_window(instance_url, calendar, &Window::default(), items)
+}
+
+/// Calculate the entries counting only the time inside of the `window`
+#[instrument]
+pub fn calculate_in_window<'a>(
+    instance_url: &Url,
+    calendar: &Calendar,
+    window: &Window,
+    items: &'a [core::Item],
+) -> Vec<Entry<'a>> {
     items
         .iter()
-        .map(calculate_time_in_flight)
+        .map(|item| calculate_time_in_flight(calendar, window, item))
         .map(|working_entry| prepare_for_display(instance_url, working_entry))
         .collect()
 }
+
+#[cfg(test)]
+mod tests;
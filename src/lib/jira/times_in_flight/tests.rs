@@ -0,0 +1,162 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! The estimates of items translated from synthetic changelogs. The issue is that of the
+//! `estimates` translation fixture, with its changelog replaced.
+use super::calculate;
+use crate::configs::jira;
+use crate::lib::calendar::Calendar;
+use crate::lib::jira::api;
+use crate::lib::jira::core::{self, ItemStatus};
+use crate::lib::jira::nativetocore::{translate_items, StatusCategories, UnmappedStatus};
+use crate::lib::jira::test_support::{fixture, fixture_config};
+use serde_json::{json, Value};
+use std::fs;
+use uuid::Uuid;
+
+fn config() -> jira::Config {
+    let mut conf = fixture_config("estimates");
+    conf.status_mapping
+        .insert("Done".to_owned(), ItemStatus::Completed);
+    conf
+}
+
+/// The `To Do` issue of the fixture, without its changelog
+fn issue() -> Value {
+    let issues =
+        fs::read_to_string(fixture("estimates").join("issues.json")).expect("fixture issues");
+    let issues: Vec<Value> = serde_json::from_str(&issues).expect("valid fixture issues");
+    let mut issue = issues
+        .into_iter()
+        .find(|issue| issue["issue"]["key"] == "FIX-2")
+        .expect("fixture has FIX-2");
+    issue["changelog"] = json!([]);
+    issue
+}
+
+/// A change of `field` made on the `day` of March 2024
+fn change(day: u32, field: &str, from: Option<&str>, to: Option<&str>) -> Value {
+    json!({
+        "id": day.to_string(),
+        "author": {
+            "displayName": "Rae Porter",
+            "active": true,
+            "timeZone": "UTC",
+            "avatarUrls": {
+                "48x48": "https://jira.example.com/",
+                "24x24": "https://jira.example.com/",
+                "16x16": "https://jira.example.com/",
+                "32x32": "https://jira.example.com/"
+            }
+        },
+        "created": format!("2024-03-{:02}T09:00:00Z", day),
+        "items": [{
+            "field": field,
+            "fieldtype": "custom",
+            "fieldId": if field == "status" { "status" } else { "customfield_10016" },
+            "from": from,
+            "fromString": from,
+            "to": to,
+            "toString": to
+        }]
+    })
+}
+
+fn points(day: u32, from: Option<&str>, to: Option<&str>) -> Value {
+    change(day, "Story Points", from, to)
+}
+
+/// The estimate columns of the issue: the first and last estimates, the changes and the drift
+fn estimates(issue: Value) -> (Option<f64>, Option<f64>, usize, Option<f64>) {
+    let issue: api::IssueDetail = serde_json::from_value(issue).expect("valid issue");
+    let mut next_id = 0;
+    let items = translate_items(
+        &config(),
+        &StatusCategories::default(),
+        &[issue],
+        UnmappedStatus::Fail,
+        || {
+            next_id += 1;
+            core::ItemId(Uuid::from_u128(next_id))
+        },
+    )
+    .expect("issue translates");
+    let instance = "https://jira.example.com/".parse().expect("instance url");
+    let entries = calculate(&instance, &Calendar::default(), &items);
+    let entry = &entries[0];
+    (
+        entry.first_estimate,
+        entry.last_estimate,
+        entry.estimate_changes,
+        entry.estimate_drift,
+    )
+}
+
+#[test]
+fn has_no_estimates_without_changes() {
+    assert_eq!(estimates(issue()), (None, None, 0, None));
+}
+
+#[test]
+fn tracks_every_change_of_the_estimate() {
+    let mut issue = issue();
+    issue["changelog"] = json!([
+        points(4, None, Some("3")),
+        points(5, Some("3"), Some("5")),
+        points(6, Some("5"), Some("1")),
+    ]);
+
+    assert_eq!(estimates(issue), (Some(2.0), Some(0.5), 2, Some(-1.5)));
+}
+
+#[test]
+fn orders_the_estimates_by_when_they_were_given() {
+    let mut issue = issue();
+    issue["changelog"] = json!([points(6, Some("3"), Some("5")), points(4, None, Some("3"))]);
+
+    assert_eq!(estimates(issue), (Some(2.0), Some(4.0), 1, Some(2.0)));
+}
+
+/// Clearing the field gives no estimate, the last is the one given before it was cleared
+#[test]
+fn ignores_a_removed_estimate() {
+    let mut issue = issue();
+    issue["changelog"] = json!([
+        points(4, None, Some("3")),
+        points(5, Some("3"), Some("5")),
+        points(6, Some("5"), None),
+    ]);
+
+    assert_eq!(estimates(issue), (Some(2.0), Some(4.0), 1, Some(2.0)));
+}
+
+#[test]
+fn counts_changes_after_resolution() {
+    let mut issue = issue();
+    issue["issue"]["fields"]["status"]["name"] = json!("Done");
+    issue["issue"]["fields"]["resolution"] = json!({
+        "self": "https://jira.example.com/",
+        "id": "1",
+        "description": "",
+        "name": "Done"
+    });
+    issue["issue"]["fields"]["resolutiondate"] = json!("2024-03-06T09:00:00+00:00");
+    issue["changelog"] = json!([
+        points(4, None, Some("3")),
+        change(6, "status", Some("To Do"), Some("Done")),
+        points(8, Some("3"), Some("5")),
+    ]);
+
+    assert_eq!(estimates(issue), (Some(2.0), Some(4.0), 1, Some(2.0)));
+}
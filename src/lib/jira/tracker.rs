@@ -0,0 +1,672 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Jira Tracker
+//!
+//! The [`Tracker`] for jira. Issues are pulled with [`api`] and translated with
+//! [`nativetocore`], so the items are the same as those the jira commands report on. The issues
+//! of a pull can be kept as they are pulled, see [`Recording`]. A [`DumpTracker`] has the items of
+//! the issues in a jira dump instead, optionally refreshing the dump from jira first.
+use crate::configs::jira;
+use crate::lib::cancel;
+use crate::lib::jira::{api, checkpoint, core, dump, native, nativetocore};
+use crate::lib::rest;
+use crate::lib::tracker::{self, Error, Tracker};
+use chrono::{Duration, Utc};
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Keeps the issues of a pull as they are pulled. With a spool the pages spooled by an earlier
+/// attempt at the same pull are returned first, and the pull continues from where that attempt
+/// stopped. With a dump the issues are written to it.
+#[derive(Debug, Default)]
+pub struct Recording {
+    spool: Option<checkpoint::Spool>,
+    dump: Option<dump::Writer>,
+}
+
+impl Recording {
+    /// Record to the `spool` and to a dump at `dump_path`, either of which may be left out. The
+    /// dump is of the issues fetched when the spooled pull was started, or now without a spool.
+    /// With `redact_pii` the people in the dumped issues are redacted.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the dump can't be created.
+    pub async fn new(
+        spool: Option<checkpoint::Spool>,
+        dump_path: Option<&Path>,
+        redact_pii: bool,
+    ) -> Result<Self, Error> {
+        let fetched_at = spool
+            .as_ref()
+            .map_or_else(Utc::now, checkpoint::Spool::started_at);
+        let dump = match dump_path {
+            Some(path) => Some(
+                dump::Writer::create(path, &fetched_at, redact_pii)
+                    .await
+                    .map_err(|source| Error::JiraDump { source })?,
+            ),
+            None => None,
+        };
+        Ok(Recording { spool, dump })
+    }
+}
+
+/// Pulls items from a jira instance
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct JiraTracker<'a> {
+    conf: &'a jira::Config,
+    client: &'a rest::Client,
+    unmapped_status: nativetocore::UnmappedStatus,
+    cancel: Option<cancel::Token>,
+    /// The board the issues are pulled from, the query only narrows them down
+    board: Option<native::BoardId>,
+    expand: api::Expand,
+    include_worklogs: bool,
+    include_comments: bool,
+    max_issues: Option<u64>,
+    skip_invalid_issues: bool,
+    skip_failed_changelogs: bool,
+    /// Taken by the first pull, so only it is recorded
+    recording: Mutex<Option<Recording>>,
+}
+
+/// How far a pull has got
+struct Pull<'a> {
+    pages: api::IssuePages<'a>,
+    /// The categories of the statuses, pulled along with the first page
+    categories: Option<nativetocore::StatusCategories>,
+    /// The statuses the categories are of, kept in the dump
+    statuses: Vec<native::InstanceStatus>,
+    recording: Recording,
+    /// The number of spooled pages returned so far
+    replayed: usize,
+    /// The offset `pages` started from, after the issues that were spooled
+    start_at: u64,
+    /// Resuming a pull continues from an offset into the results of the query, so issues created
+    /// in the meantime can shift an issue that was already returned onto the pages still to come
+    seen: HashSet<native::IssueKey>,
+}
+
+impl<'a> JiraTracker<'a> {
+    /// A tracker of the issues of the instance of `conf`, pulled with `client`. Issues with unmapped
+    /// statuses fail or are skipped as `unmapped_status` says.
+    pub fn new(
+        conf: &'a jira::Config,
+        client: &'a rest::Client,
+        unmapped_status: nativetocore::UnmappedStatus,
+    ) -> Self {
+        JiraTracker {
+            conf,
+            client,
+            unmapped_status,
+            cancel: None,
+            board: None,
+            expand: api::Expand::default(),
+            include_worklogs: false,
+            include_comments: false,
+            max_issues: None,
+            skip_invalid_issues: false,
+            skip_failed_changelogs: false,
+            recording: Mutex::new(None),
+        }
+    }
+
+    /// Stop pulling items once `cancel` is cancelled, see [`api::IssuePages::with_cancellation`]
+    #[must_use]
+    pub fn with_cancellation(mut self, cancel: cancel::Token) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Pull the issues of the board with the id `board`. The query then only narrows them down
+    /// and may be empty.
+    #[must_use]
+    pub fn on_board(mut self, board: native::BoardId) -> Self {
+        self.board = Some(board);
+        self
+    }
+
+    /// Include what is in `expand` in each issue pulled
+    #[must_use]
+    pub fn with_expand(mut self, expand: api::Expand) -> Self {
+        self.expand = expand;
+        self
+    }
+
+    /// Also pull the worklogs of each issue, see [`api::IssuePages::include_worklogs`]
+    #[must_use]
+    pub fn include_worklogs(mut self) -> Self {
+        self.include_worklogs = true;
+        self
+    }
+
+    /// Also pull the comments of each issue, see [`api::IssuePages::include_comments`]
+    #[must_use]
+    pub fn include_comments(mut self) -> Self {
+        self.include_comments = true;
+        self
+    }
+
+    /// Refuse to pull more than `max_issues` rather than the `max-issues` of the config
+    #[must_use]
+    pub fn with_max_issues(mut self, max_issues: u64) -> Self {
+        self.max_issues = Some(max_issues);
+        self
+    }
+
+    /// Leave out the issues that can't be read, see [`api::IssuePages::skip_invalid_issues`]
+    #[must_use]
+    pub fn skip_invalid_issues(mut self) -> Self {
+        self.skip_invalid_issues = true;
+        self
+    }
+
+    /// Leave out the issues whose changelog can't be pulled, see
+    /// [`api::IssuePages::skip_failed_changelogs`]. Once every other item is returned the pull
+    /// fails with [`Error::Incomplete`].
+    #[must_use]
+    pub fn skip_failed_changelogs(mut self) -> Self {
+        self.skip_failed_changelogs = true;
+        self
+    }
+
+    /// Keep the issues of the next pull as they are pulled
+    #[must_use]
+    pub fn recording(self, recording: Recording) -> Self {
+        JiraTracker {
+            recording: Mutex::new(Some(recording)),
+            ..self
+        }
+    }
+
+    /// The board named `name`
+    ///
+    /// # Errors
+    ///
+    /// Fails unless exactly one board has the name.
+    pub async fn board(&self, name: &str) -> Result<native::Board, Error> {
+        api::get_board_by_name(self.client, name)
+            .await
+            .map_err(from_api)
+    }
+
+    /// The number of issues matching `query`, on the board when there is one
+    ///
+    /// # Errors
+    ///
+    /// Fails when the search can't be made.
+    pub async fn count(&self, query: &str) -> Result<u64, Error> {
+        match self.board {
+            Some(board) => api::count_issues_for_board(self.client, board, narrowing(query)).await,
+            None => api::count_issues_from_jql(self.client, query).await,
+        }
+        .map_err(from_api)
+    }
+
+    /// Page through the issues matching `query` as jira has them, for the reports that need more
+    /// of an issue than its item has, such as the changes to any of its fields. Only a single page
+    /// is held at a time, and the pull is not recorded.
+    pub fn issues<'b>(
+        &'b self,
+        query: &'b str,
+    ) -> BoxStream<'b, Result<Vec<api::IssueDetail>, Error>> {
+        stream::try_unfold(self.pages(query, 0), |mut pages| async move {
+            if let Some(issues) = pages.next().await.map_err(from_api)? {
+                return Ok(Some((issues, pages)));
+            }
+            warn_skipped(pages.skipped());
+            check_failed(pages.failed()).map(|()| None)
+        })
+        .boxed()
+    }
+
+    /// Every status of the instance, when `infer-status-categories` needs their categories
+    async fn statuses(&self) -> Result<Vec<native::InstanceStatus>, Error> {
+        if !self.conf.infer_status_categories {
+            return Ok(Vec::new());
+        }
+        api::get_statuses(self.client).await.map_err(from_api)
+    }
+
+    /// The categories of every status of the instance, when `infer-status-categories` needs them
+    async fn status_categories(&self) -> Result<nativetocore::StatusCategories, Error> {
+        let statuses = self.statuses().await?;
+        Ok(nativetocore::StatusCategories::new(self.conf, &statuses))
+    }
+
+    fn translate(
+        &self,
+        categories: &nativetocore::StatusCategories,
+        issues: &[api::IssueDetail],
+    ) -> Result<Vec<core::Item>, Error> {
+        nativetocore::translate(self.conf, categories, issues, self.unmapped_status)
+            .map_err(|source| Error::JiraTranslate { source })
+    }
+
+    /// Page through the issues matching `query`, skipping the first `start_at`
+    fn pages<'b>(&'b self, query: &'b str, start_at: u64) -> api::IssuePages<'b> {
+        let max_issues = Some(self.max_issues.unwrap_or_else(|| self.conf.issue_limit()));
+        let mut pages = match self.board {
+            Some(board) => api::get_issues_for_board(
+                self.client,
+                board,
+                narrowing(query),
+                self.expand,
+                max_issues,
+                start_at,
+            ),
+            None => api::get_issues_from_jql_starting_at(
+                self.client,
+                query,
+                self.expand,
+                max_issues,
+                start_at,
+            ),
+        };
+        if self.include_worklogs {
+            pages = pages.include_worklogs();
+        }
+        if self.include_comments {
+            pages = pages.include_comments();
+        }
+        if self.skip_invalid_issues {
+            pages = pages.skip_invalid_issues();
+        }
+        if self.skip_failed_changelogs {
+            pages = pages.skip_failed_changelogs();
+        }
+        match &self.cancel {
+            Some(cancel) => pages.with_cancellation(cancel.clone()),
+            None => pages,
+        }
+    }
+
+    /// The items of the next page of the pull, the spooled pages first
+    async fn next_page(
+        &self,
+        mut pull: Pull<'a>,
+    ) -> Result<Option<(Vec<core::Item>, Pull<'a>)>, Error> {
+        if pull.categories.is_none() {
+            pull.statuses = self.statuses().await?;
+        }
+        let categories = pull
+            .categories
+            .take()
+            .unwrap_or_else(|| nativetocore::StatusCategories::new(self.conf, &pull.statuses));
+        let spooled = pull
+            .recording
+            .spool
+            .as_ref()
+            .map_or(0, checkpoint::Spool::page_count);
+        let issues = if pull.replayed < spooled {
+            if pull.replayed == 0 {
+                info!("resuming after the {} issues already pulled", pull.start_at);
+            }
+            let spool = pull.recording.spool.as_ref().expect("spooled pages");
+            let issues = spool
+                .read_page(pull.replayed)
+                .await
+                .map_err(|source| Error::JiraCheckpoint { source })?;
+            pull.replayed += 1;
+            issues
+        } else {
+            match pull.pages.next().await {
+                Ok(Some(issues)) => {
+                    let next_start_at = pull.start_at + pull.pages.read() as u64;
+                    if let Some(spool) = &mut pull.recording.spool {
+                        spool
+                            .push(&issues, next_start_at)
+                            .await
+                            .map_err(|source| Error::JiraCheckpoint { source })?;
+                    }
+                    issues
+                }
+                Ok(None) => return pull.finish(false).await.map(|()| None),
+                Err(api::Error::Cancelled) => return pull.finish(true).await.map(|()| None),
+                Err(source) => {
+                    if pull.recording.spool.is_some() {
+                        warn!("Run the report again with --resume to continue the pull");
+                    }
+                    return Err(Error::Jira { source });
+                }
+            }
+        };
+        let seen = &mut pull.seen;
+        let issues: Vec<_> = issues
+            .into_iter()
+            .filter(|detail| seen.insert(detail.issue.key.clone()))
+            .collect();
+        if let Some(dump) = &mut pull.recording.dump {
+            dump.write(&issues)
+                .await
+                .map_err(|source| Error::JiraDump { source })?;
+        }
+        let items = self.translate(&categories, &issues)?;
+        pull.categories = Some(categories);
+        Ok(Some((items, pull)))
+    }
+
+    /// Pull the issues matching `query` that were updated since `dump` was fetched, merge them
+    /// into it and rewrite it to `path`, along with the statuses of the instance. The rewritten
+    /// dump is returned with the issues whose changelog could not be pulled. The date used is a day before the fetch, to be certain
+    /// that no updates are missed because jira interprets jql dates in the timezone of the user.
+    /// If pulling the updates is interrupted the dump is left as it was.
+    async fn refresh(
+        &self,
+        path: &Path,
+        dump: dump::JiraDump,
+        query: &str,
+        redact_pii: bool,
+    ) -> Result<(dump::JiraDump, Vec<api::FailedIssue>), Error> {
+        let query = match dump::updated_since(&dump) {
+            Some(since) => {
+                let updated = format!(
+                    "updated >= \"{}\"",
+                    (since - Duration::days(1)).format("%Y-%m-%d")
+                );
+                match narrowing(query) {
+                    Some(query) => format!("({}) AND {}", query, updated),
+                    None => updated,
+                }
+            }
+            None => query.to_owned(),
+        };
+
+        let fetched_at = Utc::now();
+        let mut updated = Vec::new();
+        let mut pages = self.pages(&query, 0);
+        while let Some(issues) = pages.next().await.map_err(from_api)? {
+            updated.extend(issues);
+        }
+        warn_skipped(pages.skipped());
+        let failed = pages.failed().to_vec();
+        if !failed.is_empty() {
+            warn!("The issues whose changelog failed keep what they had in the dump");
+        }
+        info!(
+            "merging {} updated issues into {}",
+            updated.len(),
+            path.display()
+        );
+        let merged = dump::merge(dump.issues, updated);
+        let statuses = self.statuses().await?;
+
+        let write = async {
+            let mut writer = dump::Writer::create(path, &fetched_at, redact_pii).await?;
+            writer.write(&merged).await?;
+            writer.finish(false, &failed, &statuses).await
+        };
+        write.await.map_err(|source| Error::JiraDump { source })?;
+        let refreshed = dump::JiraDump {
+            fetched_at: Some(fetched_at),
+            partial: !failed.is_empty(),
+            errors: failed.clone(),
+            issues: merged,
+            statuses,
+        };
+        Ok((refreshed, failed))
+    }
+}
+
+impl Pull<'_> {
+    /// Close the recording once every page is pulled, or the pull was `cancelled`. A pull that
+    /// was cancelled fails with [`Error::Cancelled`] and keeps its spool. One that left out issues
+    /// whose changelog could not be pulled fails with [`Error::Incomplete`].
+    async fn finish(self, cancelled: bool) -> Result<(), Error> {
+        let Pull {
+            pages,
+            recording,
+            statuses,
+            ..
+        } = self;
+        if let Some(dump) = recording.dump {
+            dump.finish(cancelled, pages.failed(), &statuses)
+                .await
+                .map_err(|source| Error::JiraDump { source })?;
+        }
+        warn_skipped(pages.skipped());
+        if cancelled {
+            if recording.spool.is_some() {
+                info!("Run the report again with --resume to continue the pull");
+            }
+            return Err(Error::Cancelled);
+        }
+        if let Some(spool) = recording.spool {
+            spool
+                .remove()
+                .await
+                .map_err(|source| Error::JiraCheckpoint { source })?;
+        }
+        check_failed(pages.failed())
+    }
+}
+
+/// The query narrowing down the issues of a board, if there is one
+fn narrowing(query: &str) -> Option<&str> {
+    Some(query).filter(|query| !query.trim().is_empty())
+}
+
+fn from_api(source: api::Error) -> Error {
+    match source {
+        api::Error::Cancelled => Error::Cancelled,
+        source => Error::Jira { source },
+    }
+}
+
+/// Summarize the issues that were left out because they could not be read
+fn warn_skipped(skipped: &[api::SkippedIssue]) {
+    if skipped.is_empty() {
+        return;
+    }
+    warn!(
+        "{} issues could not be read and are missing from the report",
+        skipped.len()
+    );
+    for skipped_issue in skipped {
+        warn!(
+            "  {} at {}: {}",
+            skipped_issue.issue_key, skipped_issue.path, skipped_issue.message
+        );
+    }
+}
+
+/// Fail with [`Error::Incomplete`] when the changelogs of some issues could not be pulled,
+/// listing them as a warning first
+fn check_failed(failed: &[api::FailedIssue]) -> Result<(), Error> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    warn!(
+        "The changelogs of {} issues could not be pulled and they are missing from the report",
+        failed.len()
+    );
+    for failed_issue in failed {
+        warn!("  {}: {}", failed_issue.issue_key, failed_issue.message);
+    }
+    Err(Error::Incomplete {
+        count: failed.len(),
+        issue_keys: failed
+            .iter()
+            .map(|failed_issue| failed_issue.issue_key.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    })
+}
+
+impl Tracker for JiraTracker<'_> {
+    fn items<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<Vec<core::Item>, Error>> {
+        let recording = self
+            .recording
+            .lock()
+            .ok()
+            .and_then(|mut recording| recording.take())
+            .unwrap_or_default();
+        let start_at = recording
+            .spool
+            .as_ref()
+            .map_or(0, checkpoint::Spool::next_start_at);
+        let pull = Pull {
+            pages: self.pages(query, start_at),
+            categories: None,
+            statuses: Vec::new(),
+            recording,
+            replayed: 0,
+            start_at,
+            seen: HashSet::new(),
+        };
+        stream::try_unfold(pull, move |pull| self.next_page(pull)).boxed()
+    }
+
+    fn status_history<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::ItemTimeLineEntry>, Error>> {
+        async move {
+            let jql = format!("key = \"{}\"", native_id);
+            let categories = self.status_categories().await?;
+            let mut items = Vec::new();
+            let mut pages =
+                api::get_issues_from_jql(self.client, &jql, api::Expand::default(), None);
+            while let Some(issues) = pages.next().await.map_err(from_api)? {
+                items.extend(self.translate(&categories, &issues)?);
+            }
+            tracker::status_history(items, native_id)
+        }
+        .boxed()
+    }
+
+    fn children<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::Item>, Error>> {
+        async move {
+            let categories = self.status_categories().await?;
+            let epic_key = native::IssueKey(native_id.0.clone());
+            // Only the current status of each child is needed, so the changelogs are not pulled
+            let children: Vec<api::IssueDetail> = api::get_epic_children(self.client, &epic_key)
+                .await
+                .map_err(from_api)?
+                .into_iter()
+                .map(|issue| api::IssueDetail {
+                    issue,
+                    changelog: Vec::new(),
+                    worklogs: Vec::new(),
+                    comments: Vec::new(),
+                })
+                .collect();
+            self.translate(&categories, &children)
+        }
+        .boxed()
+    }
+}
+
+/// The items of the issues in a jira dump. The dump was pulled with a query of its own, so the
+/// query the items are asked for with is only used to refresh the dump, see
+/// [`DumpTracker::refreshed_from`].
+#[derive(Debug)]
+pub struct DumpTracker<'a> {
+    conf: &'a jira::Config,
+    path: PathBuf,
+    unmapped_status: nativetocore::UnmappedStatus,
+    /// Where the dump is refreshed from, and whether the people in it are redacted
+    refresh: Option<(&'a JiraTracker<'a>, bool)>,
+}
+
+impl<'a> DumpTracker<'a> {
+    /// A tracker of the issues in the dump at `path`, translated with `conf`. Issues with unmapped
+    /// statuses fail or are skipped as `unmapped_status` says.
+    pub fn new(
+        conf: &'a jira::Config,
+        path: impl Into<PathBuf>,
+        unmapped_status: nativetocore::UnmappedStatus,
+    ) -> Self {
+        DumpTracker {
+            conf,
+            path: path.into(),
+            unmapped_status,
+            refresh: None,
+        }
+    }
+
+    /// Before returning the items, pull the issues updated since the dump was fetched from
+    /// `jira` and merge them into the dump, see [`dump::merge`]. With `redact_pii` the people in
+    /// the rewritten dump are redacted. When the changelogs of some of the updated issues could
+    /// not be pulled, the items are followed by [`Error::Incomplete`].
+    #[must_use]
+    pub fn refreshed_from(mut self, jira: &'a JiraTracker<'a>, redact_pii: bool) -> Self {
+        self.refresh = Some((jira, redact_pii));
+        self
+    }
+
+    /// The items of the dump, and whether every issue of the refresh was pulled
+    async fn read(&self, query: &str) -> Result<(Vec<core::Item>, Result<(), Error>), Error> {
+        let dump = dump::read(&self.path)
+            .await
+            .map_err(|source| Error::JiraDump { source })?;
+        let (dump, failed) = match self.refresh {
+            Some((jira, redact_pii)) => jira.refresh(&self.path, dump, query, redact_pii).await?,
+            None => (dump, Vec::new()),
+        };
+        let categories = nativetocore::StatusCategories::of_dump(self.conf, &dump);
+        let items =
+            nativetocore::translate(self.conf, &categories, &dump.issues, self.unmapped_status)
+                .map_err(|source| Error::JiraTranslate { source })?;
+        Ok((items, check_failed(&failed)))
+    }
+
+    /// Every item of the dump, as it is on disk
+    async fn dumped_items(&self) -> Result<Vec<core::Item>, Error> {
+        let dump = dump::read(&self.path)
+            .await
+            .map_err(|source| Error::JiraDump { source })?;
+        let categories = nativetocore::StatusCategories::of_dump(self.conf, &dump);
+        nativetocore::translate(self.conf, &categories, &dump.issues, self.unmapped_status)
+            .map_err(|source| Error::JiraTranslate { source })
+    }
+}
+
+impl Tracker for DumpTracker<'_> {
+    fn items<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<Vec<core::Item>, Error>> {
+        stream::once(self.read(query))
+            .flat_map(|read| {
+                let (items, incomplete) = match read {
+                    Ok((items, checked)) => (Ok(items), checked.err()),
+                    Err(err) => (Err(err), None),
+                };
+                stream::iter(std::iter::once(items).chain(incomplete.map(Err)))
+            })
+            .boxed()
+    }
+
+    fn status_history<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::ItemTimeLineEntry>, Error>> {
+        async move { tracker::status_history(self.dumped_items().await?, native_id) }.boxed()
+    }
+
+    fn children<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::Item>, Error>> {
+        async move { Ok(tracker::children(self.dumped_items().await?, native_id)) }.boxed()
+    }
+}
@@ -0,0 +1,73 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Status Transitions
+//!
+//! Every change of status of a set of items, with who made it, one row per change. The statuses
+//! the jira statuses map to are given too, so the moves through the flow can be grouped by the
+//! people, and so the roles, that make them.
+use crate::lib::jira::core;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+
+/// A row of the transitions export, a single change of the status of an item
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    /// The name of the item
+    pub issue: &'a str,
+    /// When the status changed
+    pub changed_at: DateTime<Utc>,
+    /// Who changed it
+    pub author: Option<&'a str>,
+    /// The name of the status before, in the tracker
+    pub from: Option<&'a str>,
+    /// The name of the status after, in the tracker
+    pub to: Option<&'a str>,
+    /// What the status before maps to
+    pub from_status: &'a core::ItemStatus,
+    /// What the status after maps to
+    pub to_status: &'a core::ItemStatus,
+}
+
+/// Every transition of `items`, in the order they were made for each item
+#[instrument(skip(items))]
+pub fn entries(items: &[core::Item]) -> Vec<Entry<'_>> {
+    items
+        .iter()
+        .flat_map(|item| {
+            item.timeline
+                .iter()
+                .filter_map(move |timeline_entry| match timeline_entry {
+                    core::ItemTimeLineEntry::Transitioned {
+                        start,
+                        from,
+                        to,
+                        from_status,
+                        to_status,
+                        author,
+                    } => Some(Entry {
+                        issue: &item.name,
+                        changed_at: *start,
+                        author: author.as_deref(),
+                        from: from.as_deref(),
+                        to: to.as_deref(),
+                        from_status,
+                        to_status,
+                    }),
+                    _ => None,
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Jira Webhooks
+//!
+//! Jira can notify us whenever an issue is created or updated. The webhook payload contains the
+//! issue, but only the single change that triggered the event, so the full changelog is pulled
+//! from the api before the issue is translated into a [`core::Item`].
+//!
+//! When the webhook is registered with a secret jira signs each body with it, an HMAC-SHA256 in
+//! the [`SIGNATURE_HEADER`], see [`verify_signature`].
+use crate::configs::jira;
+use crate::lib::jira::{api, core, native, nativetocore};
+use crate::lib::rest;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use tracing::{info, instrument};
+
+/// The ways handling a webhook can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The body is not a jira issue event
+    #[snafu(display("Webhook payload is not a valid jira issue event: {}", source))]
+    InvalidPayload {
+        /// The error of parsing it
+        source: serde_json::Error,
+    },
+    /// The changelog of the issue of the event could not be pulled
+    #[snafu(display("Could not get changelog for {}: {}", issue_key, source))]
+    CouldNotGetChangelog {
+        /// The issue of the event
+        issue_key: native::IssueKey,
+        /// The error of pulling it
+        source: api::Error,
+    },
+    /// The issue of the event could not be translated into an item
+    #[snafu(display("Could not translate {}: {}", issue_key, source))]
+    CouldNotTranslateIssue {
+        /// The issue of the event
+        issue_key: native::IssueKey,
+        /// The error of translating it
+        source: nativetocore::Error,
+    },
+}
+
+/// The events we care about. Every other event is acknowledged and ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    /// An issue was created
+    #[serde(rename = "jira:issue_created")]
+    IssueCreated,
+    /// An issue was updated
+    #[serde(rename = "jira:issue_updated")]
+    IssueUpdated,
+    /// Any other event
+    #[serde(other)]
+    Other,
+}
+
+/// The body of a webhook sent by jira for issue events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEvent {
+    /// What happened
+    #[serde(rename = "webhookEvent")]
+    pub event_type: WebhookEventType,
+    /// When it happened, in milliseconds since the epoch
+    pub timestamp: Option<i64>,
+    /// The issue it happened to, for issue events
+    pub issue: Option<native::Issue>,
+}
+
+/// The header jira sends the signature of the body in, `sha256=` and the hex of the HMAC
+pub const SIGNATURE_HEADER: &str = "x-hub-signature";
+
+/// True when `signature`, the value of the [`SIGNATURE_HEADER`], is the HMAC-SHA256 of `body`
+/// keyed with `secret`. The signatures are compared in constant time.
+#[must_use]
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let expected = PKey::hmac(secret).and_then(|key| {
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(body)?;
+        signer.sign_to_vec()
+    });
+    let expected: String = match expected {
+        Ok(mac) => mac.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        Err(_) => return false,
+    };
+    signature.strip_prefix("sha256=").map_or(false, |actual| {
+        let actual = actual.to_ascii_lowercase();
+        actual.len() == expected.len()
+            && openssl::memcmp::eq(actual.as_bytes(), expected.as_bytes())
+    })
+}
+
+/// Parse the body of a webhook
+///
+/// # Errors
+///
+/// Fails with [`Error::InvalidPayload`] when the body is not a jira issue event.
+pub fn parse(body: &[u8]) -> Result<WebhookEvent, Error> {
+    serde_json::from_slice(body).context(InvalidPayload {})
+}
+
+/// Turn a webhook event into an updated item. Returns `None` for events that don't describe an
+/// issue we track, either because the event is not an issue create/update or the issue type is
+/// not mapped in the config.
+///
+/// # Errors
+///
+/// Fails when the changelog of the issue can't be pulled or the issue can't be translated.
+#[instrument(skip(conf, categories, client, event))]
+pub async fn to_item(
+    conf: &jira::Config,
+    categories: &nativetocore::StatusCategories,
+    client: &rest::Client,
+    event: WebhookEvent,
+) -> Result<Option<core::Item>, Error> {
+    let issue = match (event.event_type, event.issue) {
+        (WebhookEventType::IssueCreated | WebhookEventType::IssueUpdated, Some(issue)) => issue,
+        (event_type, _) => {
+            info!("ignoring webhook event {:?}", event_type);
+            return Ok(None);
+        }
+    };
+
+    let changelog = api::get_changelog_for_issue(client, &issue.key)
+        .await
+        .context(CouldNotGetChangelog {
+            issue_key: issue.key.clone(),
+        })?;
+    let issue_key = issue.key.clone();
+    let items = nativetocore::translate(
+        conf,
+        categories,
+        &[api::IssueDetail {
+            issue,
+            changelog,
+            worklogs: Vec::new(),
+            comments: Vec::new(),
+        }],
+        nativetocore::UnmappedStatus::Fail,
+    )
+    .context(CouldNotTranslateIssue { issue_key })?;
+
+    Ok(items.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,43 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Checking the signatures jira sends with webhooks.
+use super::verify_signature;
+
+const SECRET: &[u8] = b"It's a Secret to Everybody";
+const BODY: &[u8] = br#"{"webhookEvent":"jira:issue_updated"}"#;
+const SIGNATURE: &str = "sha256=a6cf6f381610d569fcfc6fc838f18f288fce38a2c2d29bd4c459de8b0a733944";
+
+#[test]
+fn accepts_the_signature_of_the_body() {
+    assert!(verify_signature(SECRET, BODY, SIGNATURE));
+    assert!(verify_signature(
+        SECRET,
+        BODY,
+        &SIGNATURE.to_ascii_uppercase().replace("SHA256=", "sha256=")
+    ));
+}
+
+#[test]
+fn rejects_other_signatures() {
+    assert!(!verify_signature(b"another secret", BODY, SIGNATURE));
+    assert!(!verify_signature(
+        SECRET,
+        br#"{"webhookEvent":"jira:issue_created"}"#,
+        SIGNATURE
+    ));
+    assert!(!verify_signature(SECRET, BODY, &SIGNATURE[7..]));
+    assert!(!verify_signature(SECRET, BODY, &SIGNATURE[..40]));
+    assert!(!verify_signature(SECRET, BODY, ""));
+}
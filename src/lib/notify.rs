@@ -0,0 +1,349 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Notifications
+//!
+//! Tells a Slack channel or a mailbox how a run went, so a scheduled run that fails doesn't go
+//! unnoticed. The summary has the subcommand that was run, whether it succeeded, the error when it
+//! did not, how long it took and, for the reports, their headline numbers. The arguments of the
+//! command are left out, as they may hold credentials or queries naming people.
+//!
+//! The webhook url is a credential, so it is kept out of the spans and of the errors, reqwest
+//! includes the url of the request in its errors so only their cause is reported. Mail is handed
+//! to the relay in SMTP, upgraded with `STARTTLS` or over TLS from the start when the config asks
+//! for it, and logs in with `AUTH PLAIN` when the config has a username. Each destination is given
+//! a minute, so a relay or webhook that stops answering doesn't hold up a scheduled run.
+use crate::configs::notify::{Config, NotifyOn, Slack, Smtp, Tls};
+use crate::lib::rest;
+use chrono::Utc;
+use openssl::ssl::{SslConnector, SslMethod};
+use serde_json::json;
+use snafu::{ensure, ResultExt, Snafu};
+use std::error::Error as _;
+use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time;
+use tokio_openssl::SslStream;
+use tracing::{info, instrument};
+
+/// How long a destination has to take the notification
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The ways sending a notification can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The webhook could not be posted to
+    #[snafu(display("Could not post to the slack webhook: {}", reason))]
+    CouldNotPostToSlack {
+        /// Why, without the url of the webhook
+        reason: String,
+    },
+    /// The mail could not be sent to the relay
+    #[snafu(display("Could not send mail through {}: {}", host, source))]
+    CouldNotSendMail {
+        /// The host of the relay
+        host: String,
+        /// The error of the connection
+        source: std::io::Error,
+    },
+    /// The relay answered with an error
+    #[snafu(display("{} did not accept the mail: {}", host, reply))]
+    MailRejected {
+        /// The host of the relay
+        host: String,
+        /// What it answered
+        reply: String,
+    },
+    /// Tls could not be set up
+    #[snafu(display("Could not set up tls to {}: {}", host, source))]
+    CouldNotSetUpTls {
+        /// The host of the relay
+        host: String,
+        /// The error of setting it up
+        source: openssl::error::ErrorStack,
+    },
+    /// Tls could not be negotiated with the relay, e.g. as its certificate isn't trusted
+    #[snafu(display("Could not negotiate tls with {}: {}", host, source))]
+    TlsHandshakeFailed {
+        /// The host of the relay
+        host: String,
+        /// The error of the handshake
+        source: openssl::ssl::Error,
+    },
+    /// The smtp password could not be resolved
+    #[snafu(display("Could not resolve the smtp password: {}", source))]
+    CouldNotResolvePassword {
+        /// Why it could not be resolved
+        source: rest::Error,
+    },
+    /// The relay did not take the mail in time
+    #[snafu(display("{} did not take the mail within {}s", host, TIMEOUT.as_secs()))]
+    TimedOut {
+        /// The host of the relay
+        host: String,
+        /// The error of the timeout
+        source: time::error::Elapsed,
+    },
+}
+
+/// The connection to the relay, plain or encrypted
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+type Stream = BufReader<Box<dyn Connection>>;
+
+/// The headline numbers of a report
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Headline {
+    /// The number of issues processed
+    pub issues: usize,
+    /// The 85th percentile of the time in flight of the issues, in days, when there were any
+    pub p85_cycle_time: Option<f64>,
+}
+
+/// How a run went
+#[derive(Debug)]
+pub struct Summary {
+    /// The subcommand that was run, such as `jira time-in-status-wip`, without its arguments
+    pub command: String,
+    /// The error that ended the run, with its code, if it failed
+    pub error: Option<String>,
+    /// The headline numbers, for the commands that report them
+    pub headline: Option<Headline>,
+    /// How long the run took
+    pub elapsed_seconds: u64,
+}
+
+impl Summary {
+    fn subject(&self) -> String {
+        let outcome = if self.error.is_some() {
+            "failed"
+        } else {
+            "succeeded"
+        };
+        format!("lectev {} {}", self.command, outcome)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} after {}s", self.subject(), self.elapsed_seconds)?;
+        if let Some(headline) = &self.headline {
+            writeln!(f, "Issues processed: {}", headline.issues)?;
+            if let Some(days) = headline.p85_cycle_time {
+                writeln!(f, "85th percentile time in flight: {:.1} days", days)?;
+            }
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a request to the webhook failed, without the url that the error of reqwest includes
+fn without_url(err: &reqwest::Error) -> Error {
+    let reason = match err.status() {
+        Some(status) => format!("the webhook answered {}", status),
+        None if err.is_timeout() => format!("no answer within {}s", TIMEOUT.as_secs()),
+        None => err
+            .source()
+            .map_or_else(|| "the request failed".to_owned(), ToString::to_string),
+    };
+    Error::CouldNotPostToSlack { reason }
+}
+
+#[instrument(skip(slack, summary))]
+async fn post_to_slack(slack: &Slack, summary: &Summary) -> Result<(), Error> {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|err| without_url(&err))?
+        .post(slack.webhook_url.expose())
+        .json(&json!({ "text": summary.to_string() }))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| without_url(&err))?;
+    Ok(())
+}
+
+/// Read a reply of the relay, the last line when it spans several
+async fn reply(stream: &mut Stream, host: &str) -> Result<String, Error> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = stream
+            .read_line(&mut line)
+            .await
+            .context(CouldNotSendMail { host })?;
+        ensure!(
+            read > 0,
+            MailRejected {
+                host,
+                reply: "the connection was closed"
+            }
+        );
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(line.trim_end().to_owned());
+        }
+    }
+}
+
+/// Send `command` and fail unless the reply code starts with `expected`
+async fn exchange(
+    stream: &mut Stream,
+    host: &str,
+    command: &str,
+    expected: char,
+) -> Result<(), Error> {
+    stream
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .context(CouldNotSendMail { host })?;
+    let reply = reply(stream, host).await?;
+    ensure!(reply.starts_with(expected), MailRejected { host, reply });
+    Ok(())
+}
+
+/// The mail, headers and body, with the lines that start with a `.` escaped
+fn message(smtp: &Smtp, summary: &Summary) -> String {
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; \
+         charset=utf-8\r\n\r\n",
+        smtp.from,
+        smtp.to.join(", "),
+        summary.subject(),
+        Utc::now().to_rfc2822()
+    );
+    for line in summary.to_string().lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push('.');
+    message
+}
+
+/// Negotiate tls over the connection, checking the certificate of the relay against `host`
+async fn encrypt(
+    connection: Box<dyn Connection>,
+    host: &str,
+) -> Result<Box<dyn Connection>, Error> {
+    let ssl = SslConnector::builder(SslMethod::tls())
+        .context(CouldNotSetUpTls { host })?
+        .build()
+        .configure()
+        .context(CouldNotSetUpTls { host })?
+        .into_ssl(host)
+        .context(CouldNotSetUpTls { host })?;
+    let mut stream = SslStream::new(ssl, connection).context(CouldNotSetUpTls { host })?;
+    Pin::new(&mut stream)
+        .connect()
+        .await
+        .context(TlsHandshakeFailed { host })?;
+    Ok(Box::new(stream))
+}
+
+async fn deliver(smtp: &Smtp, summary: &Summary) -> Result<(), Error> {
+    let host = smtp.host.as_str();
+    let connection: Box<dyn Connection> = Box::new(
+        TcpStream::connect((host, smtp.port()))
+            .await
+            .context(CouldNotSendMail { host })?,
+    );
+    let mut stream = match smtp.tls {
+        Tls::Implicit => BufReader::new(encrypt(connection, host).await?),
+        Tls::None | Tls::Starttls => BufReader::new(connection),
+    };
+    let greeting = reply(&mut stream, host).await?;
+    ensure!(
+        greeting.starts_with('2'),
+        MailRejected {
+            host,
+            reply: greeting
+        }
+    );
+    exchange(&mut stream, host, "EHLO lectev", '2').await?;
+    if smtp.tls == Tls::Starttls {
+        exchange(&mut stream, host, "STARTTLS", '2').await?;
+        stream = BufReader::new(encrypt(stream.into_inner(), host).await?);
+        // The relay forgets what it was told before the upgrade
+        exchange(&mut stream, host, "EHLO lectev", '2').await?;
+    }
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        let password = rest::resolve_token(password)
+            .await
+            .context(CouldNotResolvePassword {})?;
+        let credentials = base64::encode(format!("\0{}\0{}", username, password.expose()));
+        exchange(
+            &mut stream,
+            host,
+            &format!("AUTH PLAIN {}", credentials),
+            '2',
+        )
+        .await?;
+    }
+    exchange(
+        &mut stream,
+        host,
+        &format!("MAIL FROM:<{}>", smtp.from),
+        '2',
+    )
+    .await?;
+    for recipient in &smtp.to {
+        exchange(&mut stream, host, &format!("RCPT TO:<{}>", recipient), '2').await?;
+    }
+    exchange(&mut stream, host, "DATA", '3').await?;
+    exchange(&mut stream, host, &message(smtp, summary), '2').await?;
+    exchange(&mut stream, host, "QUIT", '2').await
+}
+
+#[instrument(skip(smtp, summary), fields(host = %smtp.host))]
+async fn send_mail(smtp: &Smtp, summary: &Summary) -> Result<(), Error> {
+    time::timeout(TIMEOUT, deliver(smtp, summary))
+        .await
+        .context(TimedOut {
+            host: smtp.host.as_str(),
+        })?
+}
+
+/// Send the summary to every destination of the config, unless it only wants failures and the
+/// run succeeded. Every destination is tried, the first error is returned.
+///
+/// # Errors
+///
+/// Fails with the first error of the destinations, after every destination was tried.
+pub async fn send(conf: &Config, summary: &Summary) -> Result<(), Error> {
+    if conf.notify_on == NotifyOn::Failure && summary.error.is_none() {
+        return Ok(());
+    }
+    info!("sending notification: {}", summary.subject());
+    let slack = match &conf.slack {
+        Some(slack) => post_to_slack(slack, summary).await,
+        None => Ok(()),
+    };
+    let mail = match &conf.smtp {
+        Some(smtp) => send_mail(smtp, summary).await,
+        None => Ok(()),
+    };
+    slack.and(mail)
+}
@@ -18,68 +18,303 @@
 //! So we provide this mostly to make it easy to supply default credentials and reuse them in every
 //! call rather than spreading them around to every call site.
 //!
+use backoff::ExponentialBackoff;
 use base64::write::EncoderWriter as Base64Encoder;
-use snafu::{ResultExt, Snafu};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+use std::fmt;
+use std::future::Future;
 use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::warn;
 use url::Url;
 
+/// The ways building a client, resolving a token or making a request can fail
 #[derive(Debug, Snafu)]
 pub enum Error {
+    /// The username could not be encoded into the authorization header
     #[snafu(display("Invalid username {}: {}", username, source))]
     InvalidUsername {
+        /// The username
         username: String,
+        /// The error of encoding it
         source: std::io::Error,
     },
+    /// The password could not be encoded into the authorization header
     #[snafu(display("Could not parse password from: {}", source))]
-    InvalidPassword { source: std::io::Error },
+    InvalidPassword {
+        /// The error of encoding it
+        source: std::io::Error,
+    },
+    /// The authorization header is not a valid header value
     #[snafu(display("Could not convert to value: {}", source))]
     InvalidHeaderValue {
+        /// Why it is not valid
         source: reqwest::header::InvalidHeaderValue,
     },
+    /// The http client could not be built
     #[snafu(display("Unable to build reqwest::Client: {}", source))]
-    UnableToBuildClient { source: reqwest::Error },
+    UnableToBuildClient {
+        /// Why it could not be built
+        source: reqwest::Error,
+    },
+    /// The path does not join onto the base url of the client
     #[snafu(display("Unable to build url {}: {}", path, source))]
     UnableToBuildUrl {
+        /// The path
         path: String,
+        /// Why it does not join
         source: url::ParseError,
     },
+    /// A request failed
     #[snafu(display("Unable to get request for url {}: {}", path, source))]
     UnableToGetRequestForUrl {
+        /// The path of the request
         path: String,
+        /// The error of the request
         source: reqwest::Error,
     },
+    /// The body of a response is not the json that was expected
     #[snafu(display("Unable to parse json for url {}: {}", path, source))]
     UnableToParseJsonForUrl {
+        /// The path of the request
         path: String,
+        /// The error of reading the body
         source: reqwest::Error,
     },
+    /// The environment variable that should hold the token can't be read
+    #[snafu(display("Could not read token from environment variable {}: {}", name, source))]
+    MissingTokenEnvironmentVariable {
+        /// The name of the variable
+        name: String,
+        /// Why it can't be read
+        source: std::env::VarError,
+    },
+    /// The token command could not be run
+    #[snafu(display("Could not run token command `{}`: {}", command, source))]
+    FailedToRunTokenCommand {
+        /// The command
+        command: String,
+        /// Why it could not be run
+        source: std::io::Error,
+    },
+    /// The token command exited unsuccessfully
+    #[snafu(display("Token command `{}` failed with {}", command, status))]
+    TokenCommandFailed {
+        /// The command
+        command: String,
+        /// How it exited
+        status: std::process::ExitStatus,
+    },
+    /// The token command wrote something other than utf8
+    #[snafu(display("Token command `{}` did not produce valid utf8: {}", command, source))]
+    InvalidTokenCommandOutput {
+        /// The command
+        command: String,
+        /// Why it is not utf8
+        source: std::string::FromUtf8Error,
+    },
 }
+
+/// A value, such as an api token, that must never end up in logs or traces. Both `Debug` and
+/// `Display` are masked, the value is only available through [`Secret::expose`].
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// The secret value itself
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(********)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "********")
+    }
+}
+
+/// Where the password or api token for the client comes from. Keeping the token as plain text
+/// in a config file is convenient but not very secure, so the token may also come from an
+/// environment variable or from the stdout of a command. The command is the way to reach the os
+/// keychain, e.g. `security find-generic-password -s jira -w` on macOS or
+/// `secret-tool lookup service jira` on linux.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenSource {
+    /// The token itself
+    Token(Secret),
+    /// The name of an environment variable that contains the token
+    TokenEnv(String),
+    /// A shell command whose stdout is the token
+    TokenCommand(String),
+}
+
+/// Limits how long a single request is retried and when the client stops calling the server
+/// altogether. A request is retried with an exponential backoff until it succeeds, it has been
+/// attempted `max-attempts` times, or `max-elapsed-seconds` have passed since the first attempt.
+/// Requests the server rejects with a 4xx other than 429, e.g. for bad JQL or credentials, fail
+/// straight away.
+///
+/// The circuit breaker counts failed attempts across every request of the client. Once
+/// `circuit-breaker-failures` attempts in a row have failed, every request fails immediately for
+/// `circuit-breaker-cooldown-seconds`, after which requests are attempted again.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct RetryPolicy {
+    /// The most times a request is attempted
+    pub max_attempts: u32,
+    /// The most seconds spent retrying a request, counted from its first attempt
+    pub max_elapsed_seconds: u64,
+    /// The failed attempts in a row, across every request, that open the circuit breaker
+    pub circuit_breaker_failures: u32,
+    /// How long the circuit breaker stays open
+    pub circuit_breaker_cooldown_seconds: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 8,
+            max_elapsed_seconds: 120,
+            circuit_breaker_failures: 20,
+            circuit_breaker_cooldown_seconds: 30,
+        }
+    }
+}
+
+/// Why [`retry`] gave up on a request
 #[derive(Debug)]
+pub enum RetryFailure<E> {
+    /// Every attempt the retry budget allowed failed, `source` is the error of the last one
+    BudgetExhausted {
+        /// The number of attempts made
+        attempts: u32,
+        /// How long was spent on them
+        elapsed: Duration,
+        /// The error of the last attempt
+        source: E,
+    },
+    /// The circuit breaker was open so the request was not attempted
+    CircuitOpen {
+        /// The number of attempts in a row that failed
+        consecutive_failures: u32,
+    },
+    /// The server rejected the request in a way that retrying will not change, e.g. bad JQL or
+    /// bad credentials
+    Rejected {
+        /// The error the server answered with
+        source: E,
+    },
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// A client of a rest api that authenticates every request and retries them within a budget
 pub struct Client {
     base_url: Url,
     client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    breaker: Mutex<CircuitBreaker>,
+}
+
+/// Only the base url is shown, the underlying client carries the authorization header
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url.as_str())
+            .field("retry_policy", &self.retry_policy)
+            .finish_non_exhaustive()
+    }
 }
 
-fn basic_auth(username: &str, password: &str) -> Result<reqwest::header::HeaderValue, Error> {
+fn basic_auth(username: &str, password: &Secret) -> Result<reqwest::header::HeaderValue, Error> {
     let mut header_value = b"Basic ".to_vec();
     {
         let mut encoder = Base64Encoder::new(&mut header_value, base64::STANDARD);
         // The unwraps here are fine because Vec::write* is infallible.
         write!(encoder, "{}:", username).context(InvalidUsername { username })?;
-        write!(encoder, "{}", password).context(InvalidPassword {})?;
+        write!(encoder, "{}", password.expose()).context(InvalidPassword {})?;
     }
 
-    let encoded_header =
+    let mut encoded_header =
         reqwest::header::HeaderValue::from_bytes(&header_value).context(InvalidHeaderValue {})?;
+    // Keeps the header out of the debug output of requests
+    encoded_header.set_sensitive(true);
 
     Ok(encoded_header)
 }
-pub fn new(base_url: &Url, username: &str, password: &str) -> Result<Client, Error> {
-    let header_value = basic_auth(username, password)?;
+/// The token itself, read from the environment or the stdout of the command when it is not
+/// in the config
+///
+/// # Errors
+///
+/// Fails when the environment variable can't be read, or when the command can't be run, fails
+/// or doesn't write utf8.
+pub async fn resolve_token(token: &TokenSource) -> Result<Secret, Error> {
+    match token {
+        TokenSource::Token(token) => Ok(token.clone()),
+        TokenSource::TokenEnv(name) => std::env::var(name)
+            .map(Secret)
+            .context(MissingTokenEnvironmentVariable { name }),
+        TokenSource::TokenCommand(command) => {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .output()
+                .await
+                .context(FailedToRunTokenCommand { command })?;
+            ensure!(
+                output.status.success(),
+                TokenCommandFailed {
+                    command,
+                    status: output.status
+                }
+            );
+            let stdout =
+                String::from_utf8(output.stdout).context(InvalidTokenCommandOutput { command })?;
+            Ok(Secret(
+                stdout.trim_end_matches(&['\r', '\n'][..]).to_owned(),
+            ))
+        }
+    }
+}
+
+/// A client of the api at `base_url` that authenticates as `username` with the resolved `token`
+///
+/// # Errors
+///
+/// Fails when the token can't be resolved or the client can't be built.
+pub async fn new(
+    base_url: &Url,
+    username: &str,
+    token: &TokenSource,
+    retry_policy: &RetryPolicy,
+) -> Result<Client, Error> {
+    let password = resolve_token(token).await?;
+    let header_value = basic_auth(username, &password)?;
 
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(reqwest::header::AUTHORIZATION, header_value);
     let client = reqwest::Client::builder()
+        // GitHub rejects requests without a user agent
+        .user_agent(concat!("lectev/", env!("CARGO_PKG_VERSION")))
         .default_headers(headers)
         .build()
         .context(UnableToBuildClient {})?;
@@ -87,11 +322,142 @@ pub fn new(base_url: &Url, username: &str, password: &str) -> Result<Client, Err
     Ok(Client {
         base_url: base_url.clone(),
         client,
+        retry_policy: *retry_policy,
+        breaker: Mutex::new(CircuitBreaker::default()),
     })
 }
+/// A get of `path`, relative to the base url of the client
+///
+/// # Errors
+///
+/// Fails when `path` does not join onto the base url.
 pub fn get(client: &Client, path: &str) -> Result<reqwest::RequestBuilder, Error> {
     let new_url = client.base_url.join(path).context(UnableToBuildUrl {
         path: path.to_owned(),
     })?;
     Ok(client.client.get(new_url))
 }
+
+/// A post of `body`, as json, to `path`
+///
+/// # Errors
+///
+/// Fails when `path` does not join onto the base url.
+pub fn post_json<T: Serialize + ?Sized>(
+    client: &Client,
+    path: &str,
+    body: &T,
+) -> Result<reqwest::RequestBuilder, Error> {
+    let new_url = client.base_url.join(path).context(UnableToBuildUrl {
+        path: path.to_owned(),
+    })?;
+    Ok(client.client.post(new_url).json(body))
+}
+
+impl Client {
+    /// The number of failures in a row when the circuit is open, or `None` when requests may be
+    /// attempted
+    fn open_circuit(&self) -> Option<u32> {
+        let breaker = self.breaker.lock().unwrap_or_else(PoisonError::into_inner);
+        match breaker.open_until {
+            Some(open_until) if Instant::now() < open_until => Some(breaker.consecutive_failures),
+            _ => None,
+        }
+    }
+
+    fn record_attempt(&self, answered: bool) {
+        let mut breaker = self.breaker.lock().unwrap_or_else(PoisonError::into_inner);
+        if answered {
+            *breaker = CircuitBreaker::default();
+            return;
+        }
+        breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+        if breaker.consecutive_failures >= self.retry_policy.circuit_breaker_failures {
+            warn!(
+                "{} requests in a row failed, pausing requests to {} for {}s",
+                breaker.consecutive_failures,
+                self.base_url,
+                self.retry_policy.circuit_breaker_cooldown_seconds
+            );
+            breaker.open_until = Some(
+                Instant::now()
+                    + Duration::from_secs(self.retry_policy.circuit_breaker_cooldown_seconds),
+            );
+        }
+    }
+}
+
+/// Whether `error`, or one of its sources, is a response the server will give again however
+/// often it is asked, any 4xx other than 429 (too many requests)
+fn is_rejection(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(status) = error
+            .downcast_ref::<reqwest::Error>()
+            .and_then(reqwest::Error::status)
+        {
+            return status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS;
+        }
+        current = error.source();
+    }
+    false
+}
+
+/// Run `operation` until it succeeds, retrying with an exponential backoff within the retry
+/// budget of the client. Every attempt is checked against, and recorded in, the circuit breaker
+/// of the client. Requests the server rejects (see `is_rejection`) are not retried, and as the
+/// server did answer they don't count towards opening the circuit.
+///
+/// # Errors
+///
+/// Fails with [`RetryFailure::Rejected`] as soon as the server rejects the request,
+/// [`RetryFailure::CircuitOpen`] when the circuit breaker is open, and
+/// [`RetryFailure::BudgetExhausted`] once the retry budget is spent.
+pub async fn retry<T, E, F, Fut>(client: &Client, mut operation: F) -> Result<T, RetryFailure<E>>
+where
+    E: std::error::Error + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let attempts = AtomicU32::new(0);
+    let backoff = ExponentialBackoff {
+        max_elapsed_time: Some(Duration::from_secs(client.retry_policy.max_elapsed_seconds)),
+        ..ExponentialBackoff::default()
+    };
+
+    backoff::future::retry(backoff, || {
+        let attempt = operation();
+        let attempts = &attempts;
+        async move {
+            if let Some(consecutive_failures) = client.open_circuit() {
+                return Err(backoff::Error::Permanent(RetryFailure::CircuitOpen {
+                    consecutive_failures,
+                }));
+            }
+            let attempt_number = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+            let result = attempt.await;
+            let rejected = matches!(&result, Err(err) if is_rejection(err));
+            client.record_attempt(result.is_ok() || rejected);
+            result.map_err(|source| {
+                if rejected {
+                    return backoff::Error::Permanent(RetryFailure::Rejected { source });
+                }
+                let failure = RetryFailure::BudgetExhausted {
+                    attempts: attempt_number,
+                    elapsed: started.elapsed(),
+                    source,
+                };
+                if attempt_number >= client.retry_policy.max_attempts {
+                    backoff::Error::Permanent(failure)
+                } else {
+                    backoff::Error::Transient(failure)
+                }
+            })
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests;
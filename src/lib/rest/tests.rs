@@ -0,0 +1,110 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Which failures [`retry`] retries, against a local server that answers every request with the
+//! same status.
+use super::{new, retry, Client, RetryFailure, RetryPolicy, Secret, TokenSource};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use url::Url;
+
+/// The url of a server answering with `status`, and the number of requests it has had
+fn serve(status: StatusCode) -> (Url, Arc<AtomicU32>) {
+    let requests = Arc::new(AtomicU32::new(0));
+    let counter = requests.clone();
+    let make_service = make_service_fn(move |_| {
+        let counter = counter.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move { Response::builder().status(status).body(Body::empty()) }
+            }))
+        }
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let url = Url::parse(&format!("http://{}", server.local_addr())).expect("server url");
+    tokio::spawn(server);
+    (url, requests)
+}
+
+async fn client(url: &Url, retry_policy: &RetryPolicy) -> Client {
+    let token = TokenSource::Token(Secret("token".to_owned()));
+    new(url, "user", &token, retry_policy)
+        .await
+        .expect("client")
+}
+
+async fn request(client: &Client) -> Result<(), RetryFailure<reqwest::Error>> {
+    retry(client, || async {
+        super::get(client, "/")
+            .expect("request")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map(drop)
+    })
+    .await
+}
+
+#[tokio::test]
+async fn does_not_retry_rejected_requests() {
+    for status in &[StatusCode::BAD_REQUEST, StatusCode::UNAUTHORIZED] {
+        let (url, requests) = serve(*status);
+        let client = client(&url, &RetryPolicy::default()).await;
+
+        let result = request(&client).await;
+        assert!(matches!(result, Err(RetryFailure::Rejected { .. })));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[tokio::test]
+async fn retries_too_many_requests() {
+    let (url, requests) = serve(StatusCode::TOO_MANY_REQUESTS);
+    let policy = RetryPolicy {
+        max_attempts: 2,
+        ..RetryPolicy::default()
+    };
+    let client = client(&url, &policy).await;
+
+    let result = request(&client).await;
+    assert!(matches!(
+        result,
+        Err(RetryFailure::BudgetExhausted { attempts: 2, .. })
+    ));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn rejections_do_not_open_the_circuit() {
+    let (url, requests) = serve(StatusCode::NOT_FOUND);
+    let policy = RetryPolicy {
+        circuit_breaker_failures: 1,
+        ..RetryPolicy::default()
+    };
+    let client = client(&url, &policy).await;
+
+    assert!(matches!(
+        request(&client).await,
+        Err(RetryFailure::Rejected { .. })
+    ));
+    assert!(matches!(
+        request(&client).await,
+        Err(RetryFailure::Rejected { .. })
+    ));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
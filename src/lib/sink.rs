@@ -0,0 +1,422 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Report Output
+//!
+//! Where a report ends up. Besides local paths the output path may be an object storage uri,
+//! `s3://bucket/key` or `gs://bucket/key`. The report, and the files written next to it such as
+//! the group averages, are written to a staging directory and uploaded once the report is
+//! finished. Each file is uploaded next to `key` under the name it has in the staging directory.
+//!
+//! Credentials come from the standard environment variables. For S3 those are
+//! `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, the optional `AWS_SESSION_TOKEN` and
+//! `AWS_REGION` or `AWS_DEFAULT_REGION`, `us-east-1` when neither is set. `AWS_ENDPOINT_URL`
+//! points the upload at an S3 compatible store. For GCS it is an oauth access token in
+//! `GOOGLE_OAUTH_ACCESS_TOKEN`, e.g. from `gcloud auth print-access-token`.
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+use tracing::{info, instrument, warn};
+use url::Url;
+use uuid::Uuid;
+
+/// The ways preparing or uploading an output can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The uri names no bucket or key
+    #[snafu(display(
+        "{} is not a valid object storage uri, expected <scheme>://bucket/key",
+        uri
+    ))]
+    InvalidUri {
+        /// The uri
+        uri: String,
+    },
+    /// An environment variable the upload needs is not set
+    #[snafu(display("{} must be set to upload to {}", variable, uri))]
+    MissingCredentials {
+        /// The name of the variable
+        variable: String,
+        /// The uri being uploaded to
+        uri: String,
+    },
+    /// The endpoint of the object store is not a url
+    #[snafu(display("Invalid endpoint {}: {}", endpoint, source))]
+    InvalidEndpoint {
+        /// The endpoint
+        endpoint: String,
+        /// Why it is not a url
+        source: url::ParseError,
+    },
+    /// The staging directory could not be created or read
+    #[snafu(display("Could not use staging directory {}: {}", path.display(), source))]
+    CouldNotStage {
+        /// The path of the directory
+        path: PathBuf,
+        /// The error of using it
+        source: std::io::Error,
+    },
+    /// The upload could not be signed
+    #[snafu(display("Could not sign the upload of {}: {}", key, source))]
+    CouldNotSign {
+        /// The key being uploaded
+        key: String,
+        /// The error of signing it
+        source: openssl::error::ErrorStack,
+    },
+    /// A file could not be uploaded
+    #[snafu(display("Could not upload {}: {}", key, source))]
+    CouldNotUpload {
+        /// The key being uploaded
+        key: String,
+        /// The error of the request
+        source: reqwest::Error,
+    },
+}
+
+/// The object stores a report can be uploaded to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Store {
+    S3,
+    Gcs,
+}
+
+/// A report that is staged locally and uploaded to `bucket` when it is finished
+#[derive(Debug)]
+pub struct Remote {
+    uri: String,
+    store: Store,
+    bucket: String,
+    key: String,
+    staging_dir: PathBuf,
+}
+
+impl Drop for Remote {
+    /// Remove the staging directory, whether the upload happened, failed or was never tried
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.staging_dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => warn!(
+                "Could not remove staging directory {}: {}",
+                self.staging_dir.display(),
+                err
+            ),
+            _ => (),
+        }
+    }
+}
+
+/// Where a report is written
+#[derive(Debug)]
+pub enum Output {
+    /// A path on the local file system
+    Local(PathBuf),
+    /// An object in a bucket, staged locally until it is finished
+    Remote(Remote),
+}
+
+/// Split `s3://bucket/key` into the store, bucket and key. Local paths are `None`.
+fn parse_uri(uri: &str) -> Option<Result<(Store, String, String), Error>> {
+    let (store, rest) = if let Some(rest) = uri.strip_prefix("s3://") {
+        (Store::S3, rest)
+    } else if let Some(rest) = uri.strip_prefix("gs://") {
+        (Store::Gcs, rest)
+    } else {
+        return None;
+    };
+    Some(match rest.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() && !key.ends_with('/') => {
+            Ok((store, bucket.to_owned(), key.to_owned()))
+        }
+        _ => InvalidUri { uri }.fail(),
+    })
+}
+
+/// True when the path is an object storage uri rather than a local path
+#[must_use]
+pub fn is_uri(path: &Path) -> bool {
+    parse_uri(&path.to_string_lossy()).is_some()
+}
+
+impl Output {
+    /// The output for `output_path`. For object storage this creates the staging directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::InvalidUri`] when the uri names no bucket or key, or when the staging
+    /// directory can't be created.
+    pub async fn prepare(output_path: &Path) -> Result<Output, Error> {
+        let uri = output_path.to_string_lossy();
+        match parse_uri(&uri) {
+            None => Ok(Output::Local(output_path.to_path_buf())),
+            Some(parsed) => {
+                let (store, bucket, key) = parsed?;
+                let staging_dir = std::env::temp_dir().join(format!("lectev-{}", Uuid::new_v4()));
+                tokio::fs::create_dir_all(&staging_dir)
+                    .await
+                    .context(CouldNotStage { path: &staging_dir })?;
+                Ok(Output::Remote(Remote {
+                    uri: uri.into_owned(),
+                    store,
+                    bucket,
+                    key,
+                    staging_dir,
+                }))
+            }
+        }
+    }
+
+    /// The local path the report is written to
+    #[must_use]
+    pub fn local_path(&self) -> PathBuf {
+        match self {
+            Output::Local(path) => path.clone(),
+            Output::Remote(remote) => {
+                let file_name = remote.key.rsplit('/').next().unwrap_or(&remote.key);
+                remote.staging_dir.join(file_name)
+            }
+        }
+    }
+
+    /// Upload everything written to the staging directory and remove it. Local output is
+    /// already where it belongs. An output that is dropped without being finished removes its
+    /// staging directory without uploading it.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the credentials aren't set, a file can't be read or signed, or the upload fails.
+    pub async fn finish(self) -> Result<(), Error> {
+        match self {
+            Output::Local(_) => Ok(()),
+            Output::Remote(remote) => remote.upload_staged().await,
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Result<Vec<u8>, openssl::error::ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data.as_bytes())?;
+    signer.sign_to_vec()
+}
+
+/// Percent encode everything but the unreserved characters, and `/` when it separates the
+/// segments of a path, as S3 expects in a canonical request
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b'/' if keep_slash => "/".to_owned(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// The keys and region a request to S3 is signed with
+#[derive(Debug)]
+struct SigningKey {
+    access_key: String,
+    secret_key: String,
+    region: String,
+}
+
+impl SigningKey {
+    /// The authorization header of a request signed with AWS signature version 4. The headers
+    /// are lower case and sorted by name, and the request has no query string.
+    fn authorization(
+        &self,
+        method: &str,
+        canonical_uri: &str,
+        headers: &[(&str, String)],
+        payload_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<String, openssl::error::ErrorStack> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, canonical_uri, canonical_headers, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&openssl::sha::sha256(canonical_request.as_bytes()))
+        );
+        let signature = hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date)
+            .and_then(|date_key| hmac(&date_key, &self.region))
+            .and_then(|region_key| hmac(&region_key, "s3"))
+            .and_then(|service_key| hmac(&service_key, "aws4_request"))
+            .and_then(|signing_key| hmac(&signing_key, &string_to_sign))?;
+        Ok(format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            scope,
+            signed_headers,
+            hex(&signature)
+        ))
+    }
+}
+
+fn env(variable: &str, uri: &str) -> Result<String, Error> {
+    std::env::var(variable)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .context(MissingCredentials { variable, uri })
+}
+
+impl Remote {
+    /// The key a staged file is uploaded to, next to the key of the report
+    fn key_for(&self, file_name: &str) -> String {
+        match self.key.rsplit_once('/') {
+            Some((prefix, _)) => format!("{}/{}", prefix, file_name),
+            None => file_name.to_owned(),
+        }
+    }
+
+    /// Upload every staged file. The staging directory is removed when the remote is dropped.
+    #[instrument]
+    async fn upload_staged(&self) -> Result<(), Error> {
+        let client = reqwest::Client::new();
+        let mut entries = tokio::fs::read_dir(&self.staging_dir)
+            .await
+            .context(CouldNotStage {
+                path: &self.staging_dir,
+            })?;
+        while let Some(entry) = entries.next_entry().await.context(CouldNotStage {
+            path: &self.staging_dir,
+        })? {
+            let path = entry.path();
+            let body = tokio::fs::read(&path)
+                .await
+                .context(CouldNotStage { path: &path })?;
+            let key = self.key_for(&entry.file_name().to_string_lossy());
+            info!("uploading {} to {}", path.display(), key);
+            let request = match self.store {
+                Store::S3 => self.s3_put(&client, &key, body)?,
+                Store::Gcs => self.gcs_upload(&client, &key, body)?,
+            };
+            request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .context(CouldNotUpload { key: &key })?;
+        }
+        Ok(())
+    }
+
+    /// A put of the object signed with AWS signature version 4
+    fn s3_put(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, Error> {
+        let signing_key = SigningKey {
+            access_key: env("AWS_ACCESS_KEY_ID", &self.uri)?,
+            secret_key: env("AWS_SECRET_ACCESS_KEY", &self.uri)?,
+            region: std::env::var("AWS_REGION")
+                .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|_| "us-east-1".to_owned()),
+        };
+        let session_token = std::env::var("AWS_SESSION_TOKEN").ok();
+
+        // Custom endpoints are addressed by path, AWS itself by the bucket's host name
+        let (endpoint, canonical_uri) = if let Ok(endpoint) = std::env::var("AWS_ENDPOINT_URL") {
+            let path = format!("/{}/{}", self.bucket, uri_encode(key, true));
+            (endpoint, path)
+        } else {
+            let endpoint = format!(
+                "https://{}.s3.{}.amazonaws.com",
+                self.bucket, signing_key.region
+            );
+            (endpoint, format!("/{}", uri_encode(key, true)))
+        };
+        let url = Url::parse(&endpoint)
+            .and_then(|url| url.join(&canonical_uri))
+            .context(InvalidEndpoint { endpoint })?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => return InvalidUri { uri: &self.uri }.fail(),
+        };
+
+        let now = Utc::now();
+        let payload_hash = hex(&openssl::sha::sha256(&body));
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string()),
+        ];
+        if let Some(session_token) = session_token {
+            headers.push(("x-amz-security-token", session_token));
+        }
+        let authorization = signing_key
+            .authorization("PUT", &canonical_uri, &headers, &payload_hash, now)
+            .context(CouldNotSign { key })?;
+
+        let mut request = client
+            .put(url)
+            .body(body)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        for (name, value) in headers {
+            if name != "host" {
+                request = request.header(name, value);
+            }
+        }
+        Ok(request)
+    }
+
+    /// A simple media upload to the GCS json api
+    fn gcs_upload(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, Error> {
+        let token = env("GOOGLE_OAUTH_ACCESS_TOKEN", &self.uri)?;
+        let endpoint = format!(
+            "https://storage.googleapis.com/upload/storage/v1/b/{}/o",
+            uri_encode(&self.bucket, false)
+        );
+        let url = Url::parse(&endpoint).context(InvalidEndpoint { endpoint })?;
+        Ok(client
+            .post(url)
+            .query(&[("uploadType", "media"), ("name", key)])
+            .bearer_auth(token)
+            .body(body))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,208 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Local Issue Store
+//!
+//! A persistent store of [`core::Item`]s backed by sqlite. Items, including their timelines, are
+//! keyed by their native id, so storing an item that is already present replaces it. The store
+//! also remembers when each query was last synced so that later syncs only need to pull the
+//! issues that changed since then.
+//!
+//! Items are stored as json rather than being broken out into columns. The core model is still
+//! changing and this avoids a migration for every change to it.
+use crate::lib::jira::core;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use snafu::{ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+
+/// The ways reading and writing the store can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The database could not be opened
+    #[snafu(display("Could not open store {}: {}", path.display(), source))]
+    CouldNotOpenStore {
+        /// The path of the database
+        path: PathBuf,
+        /// The error of opening it
+        source: rusqlite::Error,
+    },
+    /// The tables of the store could not be created
+    #[snafu(display("Could not initialize store {}: {}", path.display(), source))]
+    CouldNotInitializeStore {
+        /// The path of the database
+        path: PathBuf,
+        /// The error of creating them
+        source: rusqlite::Error,
+    },
+    /// Items could not be written
+    #[snafu(display("Could not write items to store: {}", source))]
+    CouldNotWriteItems {
+        /// The error of the database
+        source: rusqlite::Error,
+    },
+    /// Items could not be read
+    #[snafu(display("Could not read items from store: {}", source))]
+    CouldNotReadItems {
+        /// The error of the database
+        source: rusqlite::Error,
+    },
+    /// An item could not be serialized
+    #[snafu(display("Could not serialize item {}: {}", native_id, source))]
+    UnableToSerializeItem {
+        /// The item
+        native_id: core::NativeId,
+        /// The error of serializing it
+        source: serde_json::Error,
+    },
+    /// A stored item could not be read back
+    #[snafu(display("Could not deserialize item {}: {}", native_id, source))]
+    UnableToDeserializeItem {
+        /// The native id of the item
+        native_id: String,
+        /// The error of reading it
+        source: serde_json::Error,
+    },
+    /// When a query was last synced could not be read
+    #[snafu(display("Could not read sync state for {}: {}", query, source))]
+    CouldNotReadSyncState {
+        /// The query
+        query: String,
+        /// The error of the database
+        source: rusqlite::Error,
+    },
+    /// When a query was last synced could not be written
+    #[snafu(display("Could not write sync state for {}: {}", query, source))]
+    CouldNotWriteSyncState {
+        /// The query
+        query: String,
+        /// The error of the database
+        source: rusqlite::Error,
+    },
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS items (
+    native_id TEXT PRIMARY KEY,
+    item TEXT NOT NULL,
+    stored_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sync_state (
+    query TEXT PRIMARY KEY,
+    last_sync TEXT NOT NULL
+);
+";
+
+/// The items synced from a tracker, kept in a sqlite database between runs
+#[derive(Debug)]
+pub struct Store {
+    connection: Connection,
+}
+
+/// Open the store at `path`, creating it if it does not exist
+///
+/// # Errors
+///
+/// Fails when the database can't be opened or its tables can't be created.
+pub fn open(path: &Path) -> Result<Store, Error> {
+    let connection = Connection::open(path).context(CouldNotOpenStore { path })?;
+    connection
+        .execute_batch(SCHEMA)
+        .context(CouldNotInitializeStore { path })?;
+    Ok(Store { connection })
+}
+
+impl Store {
+    /// Insert the items, replacing any items with the same native id
+    ///
+    /// # Errors
+    ///
+    /// Fails when an item can't be serialized or written. Nothing is written when any item fails.
+    pub fn upsert_items(&mut self, items: &[core::Item]) -> Result<(), Error> {
+        let now = Utc::now().to_rfc3339();
+        let transaction = self
+            .connection
+            .transaction()
+            .context(CouldNotWriteItems {})?;
+        for item in items {
+            let serialized = serde_json::to_string(item).context(UnableToSerializeItem {
+                native_id: item.native_id.clone(),
+            })?;
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO items (native_id, item, stored_at) VALUES (?1, ?2, ?3)",
+                    params![item.native_id.0, serialized, now],
+                )
+                .context(CouldNotWriteItems {})?;
+        }
+        transaction.commit().context(CouldNotWriteItems {})
+    }
+
+    /// Every item in the store, ordered by native id
+    ///
+    /// # Errors
+    ///
+    /// Fails when the items can't be read or one of them can't be deserialized.
+    pub fn items(&self) -> Result<Vec<core::Item>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT native_id, item FROM items ORDER BY native_id")
+            .context(CouldNotReadItems {})?;
+        let rows = statement
+            .query_map(params![], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context(CouldNotReadItems {})?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let (native_id, serialized) = row.context(CouldNotReadItems {})?;
+            items.push(
+                serde_json::from_str(&serialized).context(UnableToDeserializeItem { native_id })?,
+            );
+        }
+        Ok(items)
+    }
+
+    /// When `query` was last synced into the store, if ever
+    ///
+    /// # Errors
+    ///
+    /// Fails when the sync state can't be read.
+    pub fn last_sync(&self, query: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        self.connection
+            .query_row(
+                "SELECT last_sync FROM sync_state WHERE query = ?1",
+                params![query],
+                |row| row.get::<_, DateTime<Utc>>(0),
+            )
+            .optional()
+            .context(CouldNotReadSyncState { query })
+    }
+
+    /// Record that `query` was synced at `synced_at`
+    ///
+    /// # Errors
+    ///
+    /// Fails when the sync state can't be written.
+    pub fn set_last_sync(&self, query: &str, synced_at: &DateTime<Utc>) -> Result<(), Error> {
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO sync_state (query, last_sync) VALUES (?1, ?2)",
+                params![query, synced_at],
+            )
+            .map(|_| ())
+            .context(CouldNotWriteSyncState { query })
+    }
+}
@@ -0,0 +1,161 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Reporting Timezone
+//!
+//! Jira records every change in UTC, which puts the day boundaries of a team far from UTC in the
+//! middle of its working day. A [`Timezone`] is the timezone days are counted in. It is written
+//! as the name of a timezone in the IANA database, e.g. `Europe/Berlin`, so that the changes to
+//! and from daylight saving time are followed, or as a fixed offset from UTC such as `+05:30`.
+use chrono::{
+    DateTime, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A timezone of the IANA database or a fixed offset from UTC
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Timezone {
+    /// A timezone of the IANA database, e.g. `America/New_York`
+    Named(Tz),
+    /// A fixed offset from UTC that never changes, e.g. `-05:00`
+    Fixed(FixedOffset),
+}
+
+impl Default for Timezone {
+    fn default() -> Self {
+        Timezone::Named(Tz::UTC)
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timezone::Named(tz) => write!(f, "{}", tz.name()),
+            Timezone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl From<Timezone> for String {
+    fn from(timezone: Timezone) -> Self {
+        timezone.to_string()
+    }
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Parses an offset from UTC of the form `+HH:MM` or `-HH:MM`
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = value.get(1..)?.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    /// Parses the name of a timezone in the IANA database or a fixed offset from UTC
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let spec = value.trim();
+        parse_offset(spec)
+            .map(Timezone::Fixed)
+            .or_else(|| spec.parse().ok().map(Timezone::Named))
+            .ok_or_else(|| {
+                format!(
+                    "{} is neither a timezone such as Europe/Berlin nor an offset such as +05:30",
+                    spec
+                )
+            })
+    }
+}
+
+impl Timezone {
+    /// A timezone that is always `hours` east of UTC
+    pub fn fixed(hours: i32) -> Self {
+        hours
+            .checked_mul(3600)
+            .and_then(FixedOffset::east_opt)
+            .map_or_else(Timezone::default, Timezone::Fixed)
+    }
+
+    /// The offset from UTC in effect at `instant`
+    #[must_use]
+    pub fn offset_at(self, instant: &DateTime<Utc>) -> FixedOffset {
+        match self {
+            Timezone::Named(tz) => tz.offset_from_utc_datetime(&instant.naive_utc()).fix(),
+            Timezone::Fixed(offset) => offset,
+        }
+    }
+
+    /// `instant` in local time
+    #[must_use]
+    pub fn local(self, instant: &DateTime<Utc>) -> DateTime<FixedOffset> {
+        instant.with_timezone(&self.offset_at(instant))
+    }
+
+    /// The local date `instant` falls on
+    #[must_use]
+    pub fn date(self, instant: &DateTime<Utc>) -> NaiveDate {
+        self.local(instant).naive_local().date()
+    }
+
+    /// The instant of a local time. A time that happens twice, as the clocks go back, is the
+    /// first of them and a time that is skipped, as the clocks go forward, is taken to be in
+    /// the offset in effect before the clocks went forward.
+    #[must_use]
+    pub fn instant(self, local: &NaiveDateTime) -> DateTime<Utc> {
+        let tz = match self {
+            Timezone::Named(tz) => tz,
+            Timezone::Fixed(offset) => return Utc.from_utc_datetime(&(*local - offset)),
+        };
+        match tz.from_local_datetime(local) {
+            LocalResult::Single(instant) | LocalResult::Ambiguous(instant, _) => {
+                instant.with_timezone(&Utc)
+            }
+            LocalResult::None => {
+                // No change of offset is more than a day long, so a day earlier is before it
+                let before = tz
+                    .offset_from_utc_datetime(&(*local - Duration::days(1)))
+                    .fix();
+                Utc.from_utc_datetime(&(*local - before))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
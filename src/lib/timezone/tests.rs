@@ -0,0 +1,145 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! Offsets, dates and instants on both sides of the changes to and from daylight saving time in
+//! Berlin in 2021, at 01:00 UTC on March 28th and October 31st.
+use super::Timezone;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+fn berlin() -> Timezone {
+    "Europe/Berlin".parse().expect("a timezone")
+}
+
+fn utc(month: u32, day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+    Utc.ymd(2021, month, day).and_hms(hour, min, 0)
+}
+
+fn local(month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(2021, month, day).and_hms(hour, min, 0)
+}
+
+fn hours(hours: i32) -> FixedOffset {
+    FixedOffset::east(hours * 3600)
+}
+
+#[test]
+fn parses_names_and_offsets() {
+    assert_eq!(berlin().to_string(), "Europe/Berlin");
+    assert_eq!(
+        "+05:30".parse::<Timezone>(),
+        Ok(Timezone::Fixed(FixedOffset::east(5 * 3600 + 30 * 60)))
+    );
+    assert_eq!("-05:00".parse::<Timezone>(), Ok(Timezone::fixed(-5)));
+    assert_eq!(Timezone::fixed(-5).to_string(), "-05:00");
+    assert_eq!("UTC".parse::<Timezone>(), Ok(Timezone::default()));
+}
+
+#[test]
+fn rejects_what_is_not_a_timezone() {
+    for spec in &[
+        "Europe/Atlantis",
+        "CET-1CEST,M3.5.0,M10.5.0/3",
+        "+5",
+        "+05:60",
+        "05:00",
+        "",
+    ] {
+        assert!(spec.parse::<Timezone>().is_err(), "{} was accepted", spec);
+    }
+}
+
+#[test]
+fn follows_the_clocks_forward() {
+    let tz = berlin();
+    assert_eq!(tz.offset_at(&utc(3, 28, 0, 59)), hours(1));
+    assert_eq!(tz.offset_at(&utc(3, 28, 1, 0)), hours(2));
+    assert_eq!(
+        tz.local(&utc(3, 28, 0, 59)).naive_local(),
+        local(3, 28, 1, 59)
+    );
+    assert_eq!(
+        tz.local(&utc(3, 28, 1, 0)).naive_local(),
+        local(3, 28, 3, 0)
+    );
+}
+
+#[test]
+fn follows_the_clocks_back() {
+    let tz = berlin();
+    assert_eq!(tz.offset_at(&utc(10, 31, 0, 59)), hours(2));
+    assert_eq!(tz.offset_at(&utc(10, 31, 1, 0)), hours(1));
+    assert_eq!(
+        tz.local(&utc(10, 31, 0, 59)).naive_local(),
+        local(10, 31, 2, 59)
+    );
+    assert_eq!(
+        tz.local(&utc(10, 31, 1, 0)).naive_local(),
+        local(10, 31, 2, 0)
+    );
+}
+
+#[test]
+fn dates_start_at_local_midnight() {
+    let tz = berlin();
+    // Standard time, an hour ahead of UTC
+    assert_eq!(
+        tz.date(&utc(3, 27, 22, 59)),
+        NaiveDate::from_ymd(2021, 3, 27)
+    );
+    assert_eq!(
+        tz.date(&utc(3, 27, 23, 0)),
+        NaiveDate::from_ymd(2021, 3, 28)
+    );
+    // Summer time, two hours ahead of UTC
+    assert_eq!(
+        tz.date(&utc(10, 30, 21, 59)),
+        NaiveDate::from_ymd(2021, 10, 30)
+    );
+    assert_eq!(
+        tz.date(&utc(10, 30, 22, 0)),
+        NaiveDate::from_ymd(2021, 10, 31)
+    );
+    assert_eq!(
+        tz.date(&utc(10, 31, 22, 59)),
+        NaiveDate::from_ymd(2021, 10, 31)
+    );
+    assert_eq!(
+        tz.date(&utc(10, 31, 23, 0)),
+        NaiveDate::from_ymd(2021, 11, 1)
+    );
+}
+
+#[test]
+fn skipped_times_are_before_the_clocks_go_forward() {
+    let tz = berlin();
+    assert_eq!(tz.instant(&local(3, 28, 1, 30)), utc(3, 28, 0, 30));
+    assert_eq!(tz.instant(&local(3, 28, 2, 30)), utc(3, 28, 1, 30));
+    assert_eq!(tz.instant(&local(3, 28, 3, 30)), utc(3, 28, 1, 30));
+}
+
+#[test]
+fn repeated_times_are_the_first_of_them() {
+    let tz = berlin();
+    assert_eq!(tz.instant(&local(10, 31, 1, 30)), utc(10, 30, 23, 30));
+    assert_eq!(tz.instant(&local(10, 31, 2, 30)), utc(10, 31, 0, 30));
+    assert_eq!(tz.instant(&local(10, 31, 3, 30)), utc(10, 31, 2, 30));
+}
+
+#[test]
+fn fixed_offsets_never_change() {
+    let tz = Timezone::fixed(-5);
+    assert_eq!(tz.offset_at(&utc(3, 14, 6, 59)), hours(-5));
+    assert_eq!(tz.offset_at(&utc(3, 14, 7, 0)), hours(-5));
+    assert_eq!(tz.instant(&local(3, 14, 2, 30)), utc(3, 14, 7, 30));
+}
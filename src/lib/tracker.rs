@@ -0,0 +1,174 @@
+// This file is part of Lectev.
+//
+//  Lectev is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Lectev is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Issue Trackers
+//!
+//! The reports work on [`core::Item`]s and don't care where they came from. A [`Tracker`] is
+//! what pulls the items out of an issue tracking system, translating them from the native model
+//! of that system into the core model on the way. Adding a backend for another tracker means
+//! implementing this trait, the reports don't change. Items a program already has can be
+//! reported on with an [`InMemory`] tracker.
+use crate::lib::github;
+use crate::lib::jira::{api, checkpoint, core, dump, nativetocore};
+use futures::future::{self, BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt};
+use snafu::Snafu;
+
+/// The ways pulling items from a tracker can fail
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The issues could not be pulled from jira
+    #[snafu(display("Could not get issues from jira: {}", source))]
+    Jira {
+        /// The error of pulling them
+        source: api::Error,
+    },
+    /// The issues from jira could not be translated into items
+    #[snafu(display("Could not translate jira issues to items: {}", source))]
+    JiraTranslate {
+        /// The error of translating them
+        source: nativetocore::Error,
+    },
+    /// The dump could not be read or written
+    #[snafu(display("Could not use the jira dump: {}", source))]
+    JiraDump {
+        /// The error of the dump
+        source: dump::Error,
+    },
+    /// The spool of the pull could not be used
+    #[snafu(display("Could not use the checkpoint of the pull: {}", source))]
+    JiraCheckpoint {
+        /// The error of the spool
+        source: checkpoint::Error,
+    },
+    /// Some of the issues were left out because their changelogs could not be pulled
+    #[snafu(display(
+        "The changelogs of {} issues could not be pulled: {}",
+        count,
+        issue_keys
+    ))]
+    Incomplete {
+        /// The number of issues left out
+        count: usize,
+        /// Their keys, separated by `, `
+        issue_keys: String,
+    },
+    /// The issues could not be pulled from github
+    #[snafu(display("Could not get issues from github: {}", source))]
+    Github {
+        /// The error of pulling them
+        source: github::api::Error,
+    },
+    /// The tracker has no item with the id
+    #[snafu(display("There is no item {}", native_id))]
+    NoSuchItem {
+        /// The id that was looked for
+        native_id: core::NativeId,
+    },
+    /// The cancellation token was cancelled while items were being pulled
+    #[snafu(display("Interrupted before all of the items were pulled"))]
+    Cancelled,
+}
+
+/// An issue tracking system that items can be pulled from
+pub trait Tracker {
+    /// Page through the items matching `query`, which is in the query language of the tracker,
+    /// e.g. jql for jira. Only a single page is held at a time.
+    fn items<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<Vec<core::Item>, Error>>;
+
+    /// The status entries of the timeline of a single item, oldest first
+    fn status_history<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::ItemTimeLineEntry>, Error>>;
+
+    /// The items whose parent is the item with the id `native_id`, e.g. the issues of an epic.
+    /// Only their current status is needed, so their timelines may be left empty.
+    fn children<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::Item>, Error>>;
+}
+
+/// The status entries of the timeline of the item with the id `native_id` among `items`
+///
+/// # Errors
+///
+/// Fails with [`Error::NoSuchItem`] when no item has the id.
+pub fn status_history(
+    items: Vec<core::Item>,
+    native_id: &core::NativeId,
+) -> Result<Vec<core::ItemTimeLineEntry>, Error> {
+    let item = items
+        .into_iter()
+        .find(|item| item.native_id == *native_id)
+        .ok_or_else(|| Error::NoSuchItem {
+            native_id: native_id.clone(),
+        })?;
+    Ok(item
+        .timeline
+        .into_iter()
+        .filter(|entry| {
+            matches!(
+                entry,
+                core::ItemTimeLineEntry::ClosedStatus { .. }
+                    | core::ItemTimeLineEntry::OpenStatus { .. }
+            )
+        })
+        .collect())
+}
+
+/// The items among `items` whose parent is the item with the id `native_id`
+#[must_use]
+pub fn children(items: Vec<core::Item>, native_id: &core::NativeId) -> Vec<core::Item> {
+    items
+        .into_iter()
+        .filter(|item| item.parent.as_ref() == Some(native_id))
+        .collect()
+}
+
+/// Items that were already pulled, e.g. from the issue store. They are returned as a single
+/// page whatever the query is.
+#[derive(Debug)]
+pub struct InMemory {
+    items: Vec<core::Item>,
+}
+
+impl InMemory {
+    /// Hold the `items`
+    #[must_use]
+    pub fn new(items: Vec<core::Item>) -> Self {
+        InMemory { items }
+    }
+}
+
+impl Tracker for InMemory {
+    fn items<'a>(&'a self, _query: &'a str) -> BoxStream<'a, Result<Vec<core::Item>, Error>> {
+        stream::once(future::ready(Ok(self.items.clone()))).boxed()
+    }
+
+    fn status_history<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::ItemTimeLineEntry>, Error>> {
+        future::ready(status_history(self.items.clone(), native_id)).boxed()
+    }
+
+    fn children<'a>(
+        &'a self,
+        native_id: &'a core::NativeId,
+    ) -> BoxFuture<'a, Result<Vec<core::Item>, Error>> {
+        future::ready(Ok(children(self.items.clone(), native_id))).boxed()
+    }
+}
@@ -34,11 +34,16 @@
     unused_qualifications
 )]
 
+use error_output::{Category, Coded};
+use lectev::{configs, lib, utils};
 use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
 use std::path::PathBuf;
+use std::time::Instant;
+use structopt::clap::{ArgMatches, Shell};
 use structopt::StructOpt;
 use tracing::{error, info, Level};
+use tracing_subscriber::EnvFilter;
 
 #[macro_use]
 extern crate bitflags;
@@ -46,28 +51,30 @@ extern crate bitflags;
 extern crate features;
 
 mod commands {
+    pub mod config;
+    pub mod generate;
+    pub mod github;
     pub mod jira;
+    pub mod serve;
 }
 mod command;
-mod configs {
-    pub mod jira;
-}
-mod config;
-mod utils;
-mod lib {
-    pub mod jira {
-        pub mod api;
-        pub mod core;
-        pub mod native;
-        pub mod nativetocore;
-        pub mod times_in_flight;
-    }
-    pub mod rest;
-}
+mod error_output;
 
 features! {
     mod feature_flags {
-        const TimeInStatus = 0b0000_0010
+        const TimeInStatus = 0b0000_0010,
+        const Serve = 0b0000_0100,
+        const Store = 0b0000_1000,
+        const EpicReport = 0b0001_0000,
+        const EstimateAccuracy = 0b0010_0000,
+        const FieldHistory = 0b0100_0000,
+        const GithubTimeInStatus = 0b1000_0000,
+        const Rollup = 0b1_0000_0000,
+        const Transitions = 0b10_0000_0000,
+        const DiffDumps = 0b100_0000_0000,
+        const SuggestConfig = 0b1000_0000_0000,
+        const FlowMetrics = 0b1_0000_0000_0000,
+        const Responsiveness = 0b10_0000_0000_0000
     }
 }
 
@@ -88,6 +95,34 @@ pub enum Error {
         /// The unknown flag
         flag: String,
     },
+    /// Produced when the log filter can not be parsed
+    #[snafu(display("Invalid log filter {}: {}", filter, source))]
+    InvalidLogFilter {
+        /// The filter as given
+        filter: String,
+        /// The underlying source of the error
+        source: tracing_subscriber::filter::ParseError,
+    },
+    /// Produced when the log file can not be created
+    #[snafu(display("Could not create log file {}: {}", path.display(), source))]
+    CouldNotCreateLogFile {
+        /// The path of the log file
+        path: PathBuf,
+        /// The underlying source of the error
+        source: std::io::Error,
+    },
+    /// Produced when the notification config can not be read
+    #[snafu(display("Could not read notify config: {}", source))]
+    CouldNotReadNotifyConfig {
+        /// The underlying source of the error
+        source: configs::notify::Error,
+    },
+    /// Produced when the workspace config can not be read
+    #[snafu(display("Could not read workspace config: {}", source))]
+    CouldNotReadWorkspaceConfig {
+        /// The underlying source of the error
+        source: configs::workspace::Error,
+    },
     /// Produced when data can't be extracted from the environment
     #[snafu(display("Couldn't read from environment: {}", source))]
     InvalidEnvironment {
@@ -100,28 +135,173 @@ pub enum Error {
         /// The underlying source of the problem in running the command
         source: commands::jira::Error,
     },
+    /// Produced when the sync command fails
+    #[snafu(display("Failed to run jira sync command: {}", source))]
+    FailedToRunJiraSync {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the epic report command fails
+    #[snafu(display("Failed to run jira epic-report command: {}", source))]
+    FailedToRunJiraEpicReport {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the estimate accuracy command fails
+    #[snafu(display("Failed to run jira estimate-accuracy command: {}", source))]
+    FailedToRunJiraEstimateAccuracy {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the field history command fails
+    #[snafu(display("Failed to run jira field-history command: {}", source))]
+    FailedToRunJiraFieldHistory {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the rollup command fails
+    #[snafu(display("Failed to run jira rollup command: {}", source))]
+    FailedToRunJiraRollup {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the transitions command fails
+    #[snafu(display("Failed to run jira transitions command: {}", source))]
+    FailedToRunJiraTransitions {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the diff dumps command fails
+    #[snafu(display("Failed to run jira diff-dumps command: {}", source))]
+    FailedToRunJiraDiffDumps {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the suggest config command fails
+    #[snafu(display("Failed to run jira suggest-config command: {}", source))]
+    FailedToRunJiraSuggestConfig {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the flow metrics command fails
+    #[snafu(display("Failed to run jira flow-metrics command: {}", source))]
+    FailedToRunJiraFlowMetrics {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the responsiveness command fails
+    #[snafu(display("Failed to run jira responsiveness command: {}", source))]
+    FailedToRunJiraResponsiveness {
+        /// The underlying source of the problem in running the command
+        source: commands::jira::Error,
+    },
+    /// Produced when the github time in status command fails
+    #[snafu(display("Failed to run github time-in-status command: {}", source))]
+    FailedToRunGithubTimeInStatus {
+        /// The underlying source of the problem in running the command
+        source: commands::github::Error,
+    },
+    /// Produced when the config validation fails or finds errors
+    #[snafu(display("Failed to run config validate command: {}", source))]
+    FailedToRunConfigValidate {
+        /// The underlying source of the problem in running the command
+        source: commands::config::Error,
+    },
+    /// Produced when generating sample data fails
+    #[snafu(display("Failed to run generate command: {}", source))]
+    FailedToRunGenerate {
+        /// The underlying source of the problem in running the command
+        source: commands::generate::Error,
+    },
+    /// Produced when the webhook listener fails
+    #[snafu(display("Failed to run serve command: {}", source))]
+    FailedToRunServe {
+        /// The underlying source of the problem in running the command
+        source: commands::serve::Error,
+    },
 }
 
+impl Coded for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidFeatureFlag { .. } => "invalid-feature-flag",
+            Error::InvalidEnvironment { .. } => "invalid-environment",
+            Error::InvalidLogFilter { .. } => "invalid-log-filter",
+            Error::CouldNotCreateLogFile { .. } => "create-log-file",
+            Error::CouldNotReadNotifyConfig { .. } => "notify-read-config",
+            Error::CouldNotReadWorkspaceConfig { .. } => "workspace-read-config",
+            Error::FailedToRunJiraTimeInStatus { source }
+            | Error::FailedToRunJiraSync { source }
+            | Error::FailedToRunJiraEpicReport { source }
+            | Error::FailedToRunJiraEstimateAccuracy { source }
+            | Error::FailedToRunJiraFieldHistory { source }
+            | Error::FailedToRunJiraRollup { source }
+            | Error::FailedToRunJiraTransitions { source }
+            | Error::FailedToRunJiraDiffDumps { source }
+            | Error::FailedToRunJiraSuggestConfig { source }
+            | Error::FailedToRunJiraFlowMetrics { source }
+            | Error::FailedToRunJiraResponsiveness { source } => source.code(),
+            Error::FailedToRunGithubTimeInStatus { source } => source.code(),
+            Error::FailedToRunConfigValidate { source } => source.code(),
+            Error::FailedToRunGenerate { source } => source.code(),
+            Error::FailedToRunServe { source } => source.code(),
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            Error::InvalidFeatureFlag { .. }
+            | Error::InvalidEnvironment { .. }
+            | Error::InvalidLogFilter { .. } => Category::Usage,
+            Error::CouldNotCreateLogFile { .. } => Category::Io,
+            Error::CouldNotReadNotifyConfig { .. } | Error::CouldNotReadWorkspaceConfig { .. } => {
+                Category::Config
+            }
+            Error::FailedToRunJiraTimeInStatus { source }
+            | Error::FailedToRunJiraSync { source }
+            | Error::FailedToRunJiraEpicReport { source }
+            | Error::FailedToRunJiraEstimateAccuracy { source }
+            | Error::FailedToRunJiraFieldHistory { source }
+            | Error::FailedToRunJiraRollup { source }
+            | Error::FailedToRunJiraTransitions { source }
+            | Error::FailedToRunJiraDiffDumps { source }
+            | Error::FailedToRunJiraSuggestConfig { source }
+            | Error::FailedToRunJiraFlowMetrics { source }
+            | Error::FailedToRunJiraResponsiveness { source } => source.category(),
+            Error::FailedToRunGithubTimeInStatus { source } => source.category(),
+            Error::FailedToRunConfigValidate { source } => source.category(),
+            Error::FailedToRunGenerate { source } => source.category(),
+            Error::FailedToRunServe { source } => source.category(),
+        }
+    }
+}
+
+// The `-wip` suffix marks commands that are still in development
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, StructOpt)]
 enum JiraCommand {
-    TimeInStatusWip {
-        /// Raw api dump file. This dumps the response from jira
-        #[structopt(long, parse(from_os_str))]
-        debug_jira_file: Option<PathBuf>,
-
-        /// If specified will load from the jira data file specified in the 'debug-jira-file' argument,
-        /// and *will not* pull from jira.
-        #[structopt(long)]
-        load_from_jira_file: bool,
-        /// Controls the output of the report. It is *always* in csv format, but you can provide the
-        /// path and filename + extension here
-        #[structopt(short, long, parse(from_os_str))]
-        output_path: PathBuf,
-        /// Provides the JQL query that the command uses to gather the Issues which are analyzed
-        /// for the Time in Status report.
-        #[structopt(short, long)]
-        jql_query: String,
-    },
+    TimeInStatusWip(Box<commands::jira::TimeInStatusArgs>),
+    /// Incrementally pull the issues for a query into a local issue store
+    SyncWip(commands::jira::SyncArgs),
+    /// Summarize the status of the children of each epic
+    EpicReportWip(commands::jira::EpicReportArgs),
+    /// Compare the time logged against each resolved issue with its first estimate
+    EstimateAccuracyWip(commands::jira::EstimateAccuracyArgs),
+    /// Export every change to a set of fields, one row per change
+    FieldHistoryWip(commands::jira::FieldHistoryArgs),
+    /// Sum the time in status of the issues of each epic, including the sub-tasks of its stories
+    RollupWip(commands::jira::RollupArgs),
+    /// List every status transition with who made it, one row per transition
+    TransitionsWip(commands::jira::TransitionsArgs),
+    /// List what changed between two debug jira files of the same query
+    DiffDumpsWip(commands::jira::DiffDumpsArgs),
+    /// Print a starting status mapping, resolution mapping and issue types for a query
+    SuggestConfigWip(commands::jira::SuggestConfigArgs),
+    /// Measure the work in progress, throughput and cycle time of each week, and check them
+    /// against Little's Law
+    FlowMetricsWip(commands::jira::FlowMetricsArgs),
+    /// Measure how long issues wait for their first comment, and how many they get
+    ResponsivenessWip(commands::jira::ResponsivenessArgs),
 }
 
 #[derive(Debug, StructOpt)]
@@ -131,13 +311,82 @@ struct Jira {
     #[structopt(short, long, parse(from_os_str))]
     config_path: Option<PathBuf>,
 
+    /// The named profile, in the config file, to use. Each profile describes a jira instance.
+    /// Defaults to the `profile` of the workspace config, or `default`.
+    #[structopt(short, long)]
+    profile: Option<String>,
+
     #[structopt(subcommand)]
     cmd: JiraCommand,
 }
 
+// The `-wip` suffix marks commands that are still in development
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, StructOpt)]
+enum GithubCommand {
+    /// Report the time each issue spent in each status, from the labels of the issues
+    TimeInStatusWip(commands::github::TimeInStatusArgs),
+}
+
+#[derive(Debug, StructOpt)]
+struct Github {
+    // Optional config path for the github functionality. If not provided the default
+    // configuration will be used.
+    #[structopt(short, long, parse(from_os_str))]
+    config_path: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    cmd: GithubCommand,
+}
+
+#[derive(Debug, StructOpt)]
+struct Serve {
+    // Optional config path for the jira functionality. If not provided the default configuration
+    // will be used.
+    #[structopt(short, long, parse(from_os_str))]
+    config_path: Option<PathBuf>,
+
+    /// The named profile, in the config file, to use. Each profile describes a jira instance.
+    /// Defaults to the `profile` of the workspace config, or `default`.
+    #[structopt(short, long)]
+    profile: Option<String>,
+
+    #[structopt(flatten)]
+    args: commands::serve::ServeArgs,
+}
+
+#[derive(Debug, StructOpt)]
+enum ConfigCommand {
+    /// Check the config for mistakes, such as misspelled keys or statuses nothing maps to
+    Validate(commands::config::ValidateArgs),
+}
+
+#[derive(Debug, StructOpt)]
+enum GenerateCommand {
+    /// Write sample jira issues, and optionally a config for them, to try the reports with
+    SampleJiraDump(commands::generate::SampleJiraDumpArgs),
+}
+
+#[derive(Debug, Clone, Copy, StructOpt)]
+struct Completions {
+    /// The shell to complete in
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    shell: Shell,
+}
+
 #[derive(Debug, StructOpt)]
 enum Command {
     Jira(Jira),
+    Github(Github),
+    /// Work with the jira config
+    Config(ConfigCommand),
+    /// Generate sample data to try lectev without a jira instance
+    Generate(GenerateCommand),
+    /// Listen for jira webhooks and store the updated issues
+    ServeWip(Serve),
+    /// Print the completions of the commands and their options for a shell, e.g.
+    /// `lectev completions bash > /etc/bash_completion.d/lectev`
+    Completions(Completions),
 }
 
 #[derive(Debug, StructOpt)]
@@ -153,6 +402,29 @@ struct Opt {
     #[structopt(short, long)]
     verbose: Option<u64>,
 
+    /// How the error that ends a failed run is written to stderr, `text` or `json`. The exit code
+    /// reflects the category of the error, see the README for the list.
+    #[structopt(long, default_value = "text", possible_values = &error_output::FORMATS)]
+    error_format: error_output::Format,
+
+    /// Set the log level of individual modules, e.g. `lectev::lib::jira=debug,reqwest=warn`. The
+    /// syntax is that of `RUST_LOG`. Modules that are not named log at the level set by -v.
+    #[structopt(long)]
+    log_filter: Option<String>,
+
+    /// Write the logs to this file as json, one event per line, rather than to the terminal
+    #[structopt(long, parse(from_os_str))]
+    log_file: Option<PathBuf>,
+
+    /// Send a summary of the run to the Slack webhook or mail relay of this config once the command
+    /// finishes, see the README
+    #[structopt(long, parse(from_os_str))]
+    notify_config: Option<PathBuf>,
+
+    /// Ignore the `.lectev.yml` workspace config of the current directory, or of its parents
+    #[structopt(long)]
+    no_workspace: bool,
+
     #[structopt(subcommand)]
     command: Command,
 }
@@ -172,6 +444,18 @@ fn enable_feature(feature: &str) -> Result<(), Error> {
         "ALL" => {
             info!("Enabled the all feature flags");
             feature_flags::enable(feature_flags::TimeInStatus);
+            feature_flags::enable(feature_flags::Serve);
+            feature_flags::enable(feature_flags::Store);
+            feature_flags::enable(feature_flags::EpicReport);
+            feature_flags::enable(feature_flags::EstimateAccuracy);
+            feature_flags::enable(feature_flags::FieldHistory);
+            feature_flags::enable(feature_flags::GithubTimeInStatus);
+            feature_flags::enable(feature_flags::Rollup);
+            feature_flags::enable(feature_flags::Transitions);
+            feature_flags::enable(feature_flags::DiffDumps);
+            feature_flags::enable(feature_flags::SuggestConfig);
+            feature_flags::enable(feature_flags::FlowMetrics);
+            feature_flags::enable(feature_flags::Responsiveness);
             Ok(())
         }
         "jira-time-in-status" => {
@@ -179,6 +463,66 @@ fn enable_feature(feature: &str) -> Result<(), Error> {
             feature_flags::enable(feature_flags::TimeInStatus);
             Ok(())
         }
+        "jira-epic-report" => {
+            info!("Enabled the `jira-epic-report` flag");
+            feature_flags::enable(feature_flags::EpicReport);
+            Ok(())
+        }
+        "jira-estimate-accuracy" => {
+            info!("Enabled the `jira-estimate-accuracy` flag");
+            feature_flags::enable(feature_flags::EstimateAccuracy);
+            Ok(())
+        }
+        "jira-field-history" => {
+            info!("Enabled the `jira-field-history` flag");
+            feature_flags::enable(feature_flags::FieldHistory);
+            Ok(())
+        }
+        "jira-rollup" => {
+            info!("Enabled the `jira-rollup` flag");
+            feature_flags::enable(feature_flags::Rollup);
+            Ok(())
+        }
+        "jira-transitions" => {
+            info!("Enabled the `jira-transitions` flag");
+            feature_flags::enable(feature_flags::Transitions);
+            Ok(())
+        }
+        "jira-diff-dumps" => {
+            info!("Enabled the `jira-diff-dumps` flag");
+            feature_flags::enable(feature_flags::DiffDumps);
+            Ok(())
+        }
+        "jira-suggest-config" => {
+            info!("Enabled the `jira-suggest-config` flag");
+            feature_flags::enable(feature_flags::SuggestConfig);
+            Ok(())
+        }
+        "jira-flow-metrics" => {
+            info!("Enabled the `jira-flow-metrics` flag");
+            feature_flags::enable(feature_flags::FlowMetrics);
+            Ok(())
+        }
+        "jira-responsiveness" => {
+            info!("Enabled the `jira-responsiveness` flag");
+            feature_flags::enable(feature_flags::Responsiveness);
+            Ok(())
+        }
+        "github-time-in-status" => {
+            info!("Enabled the `github-time-in-status` flag");
+            feature_flags::enable(feature_flags::GithubTimeInStatus);
+            Ok(())
+        }
+        "jira-store" => {
+            info!("Enabled the `jira-store` flag");
+            feature_flags::enable(feature_flags::Store);
+            Ok(())
+        }
+        "serve" => {
+            info!("Enabled the `serve` flag");
+            feature_flags::enable(feature_flags::Serve);
+            Ok(())
+        }
         _ => {
             error!("Unknown feature flag `{}` specified", feature);
             InvalidFeatureFlag { flag: feature }.fail()
@@ -196,35 +540,121 @@ fn resolve_features(features_opts: &Option<Vec<String>>) -> Result<(), Error> {
     Ok(())
 }
 
-async fn do_jira_reports(config_path: &Option<PathBuf>, cmd: &JiraCommand) -> Result<(), Error> {
+/// Run the jira command, returning the headline numbers of the commands that have them
+async fn do_jira_reports(
+    config_path: &Option<PathBuf>,
+    profile: &str,
+    cmd: &JiraCommand,
+    workspace: &configs::workspace::Config,
+) -> Result<Option<lib::notify::Headline>, Error> {
     match cmd {
-        JiraCommand::TimeInStatusWip {
-            debug_jira_file,
-            load_from_jira_file,
-            output_path,
-            jql_query,
-        } => commands::jira::do_time_in_status(
-            config_path,
-            output_path,
-            *load_from_jira_file,
-            debug_jira_file,
-            jql_query,
-        )
-        .await
-        .context(FailedToRunJiraTimeInStatus {}),
+        JiraCommand::TimeInStatusWip(args) => {
+            return commands::jira::do_time_in_status(
+                config_path,
+                profile,
+                args,
+                workspace,
+                &lib::cancel::on_ctrl_c(),
+            )
+            .await
+            .map(Some)
+            .context(FailedToRunJiraTimeInStatus {})
+        }
+        JiraCommand::SyncWip(args) => {
+            commands::jira::do_sync(config_path, profile, args, &lib::cancel::on_ctrl_c())
+                .await
+                .context(FailedToRunJiraSync {})?;
+        }
+        JiraCommand::EpicReportWip(args) => {
+            commands::jira::do_epic_report(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraEpicReport {})?;
+        }
+        JiraCommand::EstimateAccuracyWip(args) => {
+            commands::jira::do_estimate_accuracy(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraEstimateAccuracy {})?;
+        }
+        JiraCommand::FieldHistoryWip(args) => {
+            commands::jira::do_field_history(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraFieldHistory {})?;
+        }
+        JiraCommand::RollupWip(args) => commands::jira::do_rollup(config_path, profile, args)
+            .await
+            .context(FailedToRunJiraRollup {})?,
+        JiraCommand::TransitionsWip(args) => {
+            commands::jira::do_transitions(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraTransitions {})?;
+        }
+        JiraCommand::DiffDumpsWip(args) => commands::jira::do_diff_dumps(args)
+            .await
+            .context(FailedToRunJiraDiffDumps {})?,
+        JiraCommand::SuggestConfigWip(args) => {
+            commands::jira::do_suggest_config(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraSuggestConfig {})?;
+        }
+        JiraCommand::FlowMetricsWip(args) => {
+            commands::jira::do_flow_metrics(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraFlowMetrics {})?;
+        }
+        JiraCommand::ResponsivenessWip(args) => {
+            commands::jira::do_responsiveness(config_path, profile, args)
+                .await
+                .context(FailedToRunJiraResponsiveness {})?;
+        }
     }
+    Ok(None)
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    let opt = Opt::from_args();
+/// The level set by -v applies to everything the log filter does not name
+fn log_filter(verbosity: &Option<u64>, log_filter: &Option<String>) -> Result<EnvFilter, Error> {
+    let level = opt_int_to_level(verbosity);
+    log_filter.as_ref().map_or_else(
+        || Ok(EnvFilter::new(level.to_string())),
+        |directives| {
+            EnvFilter::try_new(format!("{},{}", level, directives))
+                .context(InvalidLogFilter { filter: directives })
+        },
+    )
+}
 
-    let (non_blocking, _guard) = tracing_appender::non_blocking(std::io::stdout());
-    tracing_subscriber::fmt()
-        .with_writer(non_blocking)
-        .pretty()
-        .with_max_level(opt_int_to_level(&opt.verbose))
-        .init();
+/// The names of the subcommands that were run, such as `jira time-in-status-wip`, without any of
+/// their arguments
+fn subcommand_name(matches: &ArgMatches<'_>) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let (name, Some(sub_matches)) = matches.subcommand() {
+        names.push(name);
+        matches = sub_matches;
+    }
+    names.join(" ")
+}
+
+async fn run(opt: Opt, command_name: String) -> Result<(), Error> {
+    let filter = log_filter(&opt.verbose, &opt.log_filter)?;
+    let _guard = if let Some(path) = &opt.log_file {
+        let (non_blocking, guard) = tracing_appender::non_blocking(
+            std::fs::File::create(path).context(CouldNotCreateLogFile { path })?,
+        );
+        tracing_subscriber::fmt()
+            .with_writer(non_blocking)
+            .json()
+            .with_env_filter(filter)
+            .init();
+        guard
+    } else {
+        let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stdout());
+        tracing_subscriber::fmt()
+            .with_writer(non_blocking)
+            .pretty()
+            .with_env_filter(filter)
+            .init();
+        guard
+    };
 
     let env_config = envy::prefixed("LECTEV_")
         .from_env::<Environment>()
@@ -232,8 +662,156 @@ async fn main() -> Result<(), Error> {
 
     resolve_features(&env_config.feature_flags)?;
 
-    match opt.command {
-        Command::Jira(Jira { config_path, cmd }) => do_jira_reports(&config_path, &cmd).await?,
+    let notify_conf = match &opt.notify_config {
+        Some(path) => Some(
+            configs::notify::read(path)
+                .await
+                .context(CouldNotReadNotifyConfig {})?,
+        ),
+        None => None,
+    };
+    let workspace = if opt.no_workspace {
+        configs::workspace::Config::default()
+    } else {
+        configs::workspace::read()
+            .await
+            .context(CouldNotReadWorkspaceConfig {})?
+    };
+    let started = Instant::now();
+    let result = run_command(opt.command, &workspace).await;
+    if let Some(notify_conf) = &notify_conf {
+        let summary = lib::notify::Summary {
+            command: command_name,
+            error: result
+                .as_ref()
+                .err()
+                .map(|err| format!("[{}] {}", err.code(), err)),
+            headline: result.as_ref().ok().copied().flatten(),
+            elapsed_seconds: started.elapsed().as_secs(),
+        };
+        if let Err(err) = lib::notify::send(notify_conf, &summary).await {
+            error!("Could not send the notification: {}", err);
+        }
+    }
+    result.map(|_| ())
+}
+
+/// Apply the defaults of the workspace config to the options of a jira command
+fn apply_workspace(workspace: &configs::workspace::Config, cmd: &mut JiraCommand) {
+    match cmd {
+        JiraCommand::TimeInStatusWip(args) => {
+            args.jql_query = args
+                .jql_query
+                .as_deref()
+                .map(|query| workspace.query(query));
+            args.output_path = args
+                .output_path
+                .as_deref()
+                .map(|path| workspace.output_path(path));
+            args.timeline_output = args
+                .timeline_output
+                .as_deref()
+                .map(|path| workspace.output_path(path));
+            args.metrics_output = args
+                .metrics_output
+                .as_deref()
+                .map(|path| workspace.output_path(path));
+        }
+        JiraCommand::SyncWip(args) => args.jql_query = workspace.query(&args.jql_query),
+        JiraCommand::EpicReportWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::EstimateAccuracyWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::FieldHistoryWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::RollupWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::TransitionsWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::DiffDumpsWip(args) => {
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::SuggestConfigWip(args) => args.jql_query = workspace.query(&args.jql_query),
+        JiraCommand::FlowMetricsWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+        JiraCommand::ResponsivenessWip(args) => {
+            args.jql_query = workspace.query(&args.jql_query);
+            args.output_path = workspace.output_path(&args.output_path);
+        }
+    }
+}
+
+/// Run the command, returning the headline numbers of the commands that have them
+async fn run_command(
+    command: Command,
+    workspace: &configs::workspace::Config,
+) -> Result<Option<lib::notify::Headline>, Error> {
+    match command {
+        Command::Jira(Jira {
+            config_path,
+            profile,
+            mut cmd,
+        }) => {
+            apply_workspace(workspace, &mut cmd);
+            let profile = workspace.profile(profile, configs::jira::DEFAULT_PROFILE);
+            return do_jira_reports(&config_path, &profile, &cmd, workspace).await;
+        }
+        Command::Github(Github {
+            config_path,
+            cmd: GithubCommand::TimeInStatusWip(mut args),
+        }) => {
+            args.output_path = workspace.output_path(&args.output_path);
+            commands::github::do_time_in_status(&config_path, &args, &lib::cancel::on_ctrl_c())
+                .await
+                .context(FailedToRunGithubTimeInStatus {})?;
+        }
+        Command::Config(ConfigCommand::Validate(args)) => commands::config::do_validate(&args)
+            .await
+            .context(FailedToRunConfigValidate {})?,
+        Command::Generate(GenerateCommand::SampleJiraDump(args)) => {
+            commands::generate::do_sample_jira_dump(&args)
+                .await
+                .context(FailedToRunGenerate {})?;
+        }
+        Command::ServeWip(Serve {
+            config_path,
+            profile,
+            args,
+        }) => commands::serve::do_serve(
+            &config_path,
+            &workspace.profile(profile, configs::jira::DEFAULT_PROFILE),
+            &args,
+        )
+        .await
+        .context(FailedToRunServe {})?,
+        Command::Completions(Completions { shell }) => {
+            Opt::clap().gen_completions_to("lectev", shell, &mut std::io::stdout());
+        }
+    }
+    Ok(None)
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = Opt::clap().get_matches();
+    let command_name = subcommand_name(&matches);
+    let opt = Opt::from_clap(&matches);
+    let error_format = opt.error_format;
+
+    if let Err(err) = run(opt, command_name).await {
+        error_output::report(&err, error_format);
+        std::process::exit(err.category().exit_code());
     }
-    Ok(())
 }
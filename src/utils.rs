@@ -12,17 +12,34 @@
 //
 //  You should have received a copy of the GNU General Public License
 //  along with Lectev.  If not, see <https://www.gnu.org/licenses/>.
+//! # Utilities
+//!
+//! Helpers for the file system that don't belong to any one command.
 use snafu::{ResultExt, Snafu};
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+/// The ways setting the permissions of a directory can fail
 #[derive(Debug, Snafu)]
 pub enum Error {
+    /// The permissions of the directory could not be read
     #[snafu(display("Could not get metadata for config directory: {}", source))]
-    CouldNotGetMetadata { source: std::io::Error },
+    CouldNotGetMetadata {
+        /// The error of reading them
+        source: std::io::Error,
+    },
+    /// The permissions of the directory could not be set
     #[snafu(display("Could not set permissions for config directory: {}", source))]
-    CouldNotSetPermisions { source: std::io::Error },
+    CouldNotSetPermisions {
+        /// The error of setting them
+        source: std::io::Error,
+    },
 }
+/// Set the permissions of the directory at `config_dir_path` to the unix mode `octal_perms`
+///
+/// # Errors
+///
+/// Fails when the permissions can't be read or set.
 pub async fn set_permissions(config_dir_path: &Path, octal_perms: u32) -> Result<(), Error> {
     let mut perms = tokio::fs::metadata(&config_dir_path)
         .await
@@ -36,6 +53,25 @@ pub async fn set_permissions(config_dir_path: &Path, octal_perms: u32) -> Result
     Ok(())
 }
 
+/// Make the directory at `config_dir_path` readable, writable and searchable only by its owner
+///
+/// # Errors
+///
+/// Fails when the permissions can't be read or set.
 pub async fn set_to_read_write_execute_only_owner(config_dir_path: &Path) -> Result<(), Error> {
     set_permissions(config_dir_path, 0o700).await
 }
+
+/// The path of a report written next to the main report, so `report.csv` with the suffix
+/// `by-assignee` is `report-by-assignee.csv`
+pub fn sibling_path(out_file: &Path, suffix: &str) -> PathBuf {
+    let stem = out_file
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let mut file_name = format!("{}-{}", stem, suffix);
+    if let Some(extension) = out_file.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    out_file.with_file_name(file_name)
+}